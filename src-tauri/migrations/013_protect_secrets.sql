@@ -0,0 +1,6 @@
+-- Gateway tokens are stored as salted hashes in key_value; key_prefix keeps
+-- the leading characters in clear for lookup and display.
+-- Existing plaintext rows are converted at startup (see secrets::migrate_plaintext_secrets).
+ALTER TABLE tokens ADD COLUMN key_prefix TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_tokens_key_prefix ON tokens(key_prefix);
@@ -0,0 +1,7 @@
+-- Timing for streamed responses. latency_ms only covers the time until
+-- upstream headers arrive, so streams record their own timing once they end.
+ALTER TABLE request_logs ADD COLUMN ttft_ms INTEGER;
+ALTER TABLE request_logs ADD COLUMN stream_duration_ms INTEGER;
+ALTER TABLE request_logs ADD COLUMN tokens_per_second REAL;
+-- 'completed', 'client_closed' or 'upstream_error'; NULL for non-streamed requests.
+ALTER TABLE request_logs ADD COLUMN stream_outcome TEXT;
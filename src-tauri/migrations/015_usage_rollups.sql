@@ -0,0 +1,116 @@
+-- Hourly usage rollup behind the usage analytics, so queries don't scan
+-- request_logs. Maintained by triggers as logs are written; retention pruning
+-- and clearing the logs delete from it as well (services::request_logs).
+--
+-- One row per hour, route, target, token, model, status class and latency
+-- bucket. Missing ids are stored as '' so they take part in the primary key.
+-- status_class is '2xx'..'5xx', or 'upstream' when no response was received
+-- or a stream broke after its headers.
+-- latency_bucket is the bucket's upper bound in ms (see services::analytics).
+CREATE TABLE IF NOT EXISTS usage_rollups (
+    hour TEXT NOT NULL,
+    route_id TEXT NOT NULL,
+    target_id TEXT NOT NULL,
+    token_id TEXT NOT NULL,
+    model TEXT NOT NULL,
+    status_class TEXT NOT NULL,
+    latency_bucket INTEGER NOT NULL,
+    requests INTEGER NOT NULL DEFAULT 0,
+    prompt_tokens INTEGER NOT NULL DEFAULT 0,
+    completion_tokens INTEGER NOT NULL DEFAULT 0,
+    latency_ms_sum INTEGER NOT NULL DEFAULT 0,
+    latency_ms_max INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (hour, route_id, target_id, token_id, model, status_class, latency_bucket)
+);
+
+-- Each log row's rollup key and contribution; the only place they are computed.
+CREATE VIEW IF NOT EXISTS usage_rollup_entries AS
+SELECT
+    id,
+    strftime('%Y-%m-%dT%H:00:00Z', created_at) AS hour,
+    COALESCE(route_id, '') AS route_id,
+    COALESCE(target_id, '') AS target_id,
+    COALESCE(token_id, '') AS token_id,
+    COALESCE(model, '') AS model,
+    CASE
+        WHEN status IS NULL OR stream_outcome = 'upstream_error' THEN 'upstream'
+        ELSE (status / 100) || 'xx'
+    END AS status_class,
+    CASE
+        WHEN COALESCE(latency_ms, 0) <= 50 THEN 50
+        WHEN latency_ms <= 100 THEN 100
+        WHEN latency_ms <= 250 THEN 250
+        WHEN latency_ms <= 500 THEN 500
+        WHEN latency_ms <= 1000 THEN 1000
+        WHEN latency_ms <= 2500 THEN 2500
+        WHEN latency_ms <= 5000 THEN 5000
+        WHEN latency_ms <= 10000 THEN 10000
+        WHEN latency_ms <= 30000 THEN 30000
+        WHEN latency_ms <= 60000 THEN 60000
+        WHEN latency_ms <= 120000 THEN 120000
+        ELSE 86400000
+    END AS latency_bucket,
+    COALESCE(prompt_tokens, 0) AS prompt_tokens,
+    COALESCE(completion_tokens, 0) AS completion_tokens,
+    COALESCE(latency_ms, 0) AS latency_ms
+FROM request_logs
+WHERE created_at IS NOT NULL;
+
+-- Backfill from existing logs.
+INSERT INTO usage_rollups
+SELECT hour, route_id, target_id, token_id, model, status_class, latency_bucket,
+    COUNT(*), SUM(prompt_tokens), SUM(completion_tokens), SUM(latency_ms), MAX(latency_ms)
+FROM usage_rollup_entries
+GROUP BY 1, 2, 3, 4, 5, 6, 7;
+
+CREATE TRIGGER IF NOT EXISTS usage_rollups_log_insert
+AFTER INSERT ON request_logs
+BEGIN
+    INSERT INTO usage_rollups
+    SELECT hour, route_id, target_id, token_id, model, status_class, latency_bucket,
+        1, prompt_tokens, completion_tokens, latency_ms, latency_ms
+    FROM usage_rollup_entries WHERE id = NEW.id
+    ON CONFLICT DO UPDATE SET
+        requests = requests + 1,
+        prompt_tokens = prompt_tokens + excluded.prompt_tokens,
+        completion_tokens = completion_tokens + excluded.completion_tokens,
+        latency_ms_sum = latency_ms_sum + excluded.latency_ms_sum,
+        latency_ms_max = MAX(latency_ms_max, excluded.latency_ms_max);
+END;
+
+-- Streams report usage and their outcome after the row is inserted: take the
+-- row out of the rollup before the update and add it back afterwards, so both
+-- its tokens and its status class follow. latency_ms_max keeps the old maximum.
+CREATE TRIGGER IF NOT EXISTS usage_rollups_log_update_before
+BEFORE UPDATE OF prompt_tokens, completion_tokens, stream_outcome ON request_logs
+BEGIN
+    UPDATE usage_rollups SET
+        requests = usage_rollups.requests - 1,
+        prompt_tokens = usage_rollups.prompt_tokens - entry.prompt_tokens,
+        completion_tokens = usage_rollups.completion_tokens - entry.completion_tokens,
+        latency_ms_sum = usage_rollups.latency_ms_sum - entry.latency_ms
+    FROM (SELECT * FROM usage_rollup_entries WHERE id = OLD.id) AS entry
+    WHERE usage_rollups.hour = entry.hour
+        AND usage_rollups.route_id = entry.route_id
+        AND usage_rollups.target_id = entry.target_id
+        AND usage_rollups.token_id = entry.token_id
+        AND usage_rollups.model = entry.model
+        AND usage_rollups.status_class = entry.status_class
+        AND usage_rollups.latency_bucket = entry.latency_bucket;
+END;
+
+CREATE TRIGGER IF NOT EXISTS usage_rollups_log_update_after
+AFTER UPDATE OF prompt_tokens, completion_tokens, stream_outcome ON request_logs
+BEGIN
+    INSERT INTO usage_rollups
+    SELECT hour, route_id, target_id, token_id, model, status_class, latency_bucket,
+        1, prompt_tokens, completion_tokens, latency_ms, latency_ms
+    FROM usage_rollup_entries WHERE id = NEW.id
+    ON CONFLICT DO UPDATE SET
+        requests = requests + 1,
+        prompt_tokens = prompt_tokens + excluded.prompt_tokens,
+        completion_tokens = completion_tokens + excluded.completion_tokens,
+        latency_ms_sum = latency_ms_sum + excluded.latency_ms_sum,
+        latency_ms_max = MAX(latency_ms_max, excluded.latency_ms_max);
+    DELETE FROM usage_rollups WHERE requests = 0;
+END;
@@ -0,0 +1,23 @@
+-- Alert rules for gateway incidents, delivered to a webhook and/or as a
+-- desktop notification (see server::alerts).
+--
+-- kind is 'circuit_open', 'error_rate', 'quota' or 'key_rejected'.
+-- route_id scopes circuit_open, error_rate and key_rejected rules; token_id
+-- scopes quota rules. NULL matches every route or token.
+-- threshold is a percentage: the error rate over window_secs (once at least
+-- min_requests were seen), or the share of a token's quota used.
+CREATE TABLE IF NOT EXISTS alert_rules (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    route_id TEXT REFERENCES routes(id) ON DELETE CASCADE,
+    token_id TEXT REFERENCES tokens(id) ON DELETE CASCADE,
+    threshold REAL,
+    window_secs INTEGER NOT NULL DEFAULT 300,
+    min_requests INTEGER NOT NULL DEFAULT 10,
+    cooldown_secs INTEGER NOT NULL DEFAULT 900,
+    webhook_url TEXT,
+    notify_desktop INTEGER NOT NULL DEFAULT 1,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL
+);
@@ -0,0 +1,3 @@
+-- Outbound proxy for a target: a proxy URL, 'direct', or NULL to use the
+-- global proxy (see outbound.rs).
+ALTER TABLE route_targets ADD COLUMN proxy_url TEXT;
@@ -0,0 +1,5 @@
+-- Upstream timeouts for a target, in seconds. NULL uses the defaults in
+-- outbound.rs.
+ALTER TABLE route_targets ADD COLUMN connect_timeout_secs INTEGER;
+ALTER TABLE route_targets ADD COLUMN first_byte_timeout_secs INTEGER;
+ALTER TABLE route_targets ADD COLUMN idle_timeout_secs INTEGER;
@@ -0,0 +1,3 @@
+-- How a target's upstream is called: 'stream' or 'non_stream' regardless of
+-- the client, bridging between the two. NULL follows the client.
+ALTER TABLE route_targets ADD COLUMN stream_mode TEXT;
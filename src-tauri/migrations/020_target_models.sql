@@ -0,0 +1,11 @@
+-- Models a target serves. `model` is the name clients use and list;
+-- `upstream_model`, when set, is sent upstream in its place.
+CREATE TABLE IF NOT EXISTS route_target_models (
+    id             TEXT PRIMARY KEY NOT NULL,
+    target_id      TEXT NOT NULL REFERENCES route_targets(id) ON DELETE CASCADE,
+    model          TEXT NOT NULL,
+    upstream_model TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_rtm_target_id
+    ON route_target_models(target_id);
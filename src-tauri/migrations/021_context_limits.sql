@@ -0,0 +1,5 @@
+-- Limits of a target model, checked before forwarding when the route has a
+-- context_policy ('reject', 'clamp' or 'trim'). NULL limits aren't checked.
+ALTER TABLE route_target_models ADD COLUMN context_window INTEGER;
+ALTER TABLE route_target_models ADD COLUMN max_output_tokens INTEGER;
+ALTER TABLE routes ADD COLUMN context_policy TEXT;
@@ -0,0 +1,5 @@
+-- Whether requests converted for a target are normalized first: system
+-- messages coalesced, empty content dropped, tool results paired with their
+-- calls and same-role messages merged. The fixes applied are logged.
+ALTER TABLE route_targets ADD COLUMN normalize_messages INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE request_logs ADD COLUMN normalization_notes TEXT;
@@ -0,0 +1,3 @@
+-- Which image URLs are downloaded and sent to a target as base64: 'auto'
+-- those its format can't take, 'always' all of them. NULL sends URLs as is.
+ALTER TABLE route_targets ADD COLUMN inline_images TEXT;
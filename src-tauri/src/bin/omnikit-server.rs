@@ -0,0 +1,3 @@
+fn main() {
+    omnikit_lib::headless::run()
+}
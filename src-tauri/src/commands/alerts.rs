@@ -0,0 +1,37 @@
+use crate::db::models::AlertRule;
+use crate::error::IpcError;
+use crate::services::alerts::{self as service, AlertRuleInput};
+use crate::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub async fn list_alert_rules(state: State<'_, AppState>) -> Result<Vec<AlertRule>, IpcError> {
+    service::list_alert_rules(&state.db).await
+}
+
+#[tauri::command]
+pub async fn create_alert_rule(
+    state: State<'_, AppState>,
+    rule: AlertRuleInput,
+) -> Result<AlertRule, IpcError> {
+    service::create_alert_rule(&state.db, &state.alerts, rule).await
+}
+
+#[tauri::command]
+pub async fn update_alert_rule(
+    state: State<'_, AppState>,
+    id: String,
+    rule: AlertRuleInput,
+) -> Result<AlertRule, IpcError> {
+    service::update_alert_rule(&state.db, &state.alerts, &id, rule).await
+}
+
+#[tauri::command]
+pub async fn delete_alert_rule(state: State<'_, AppState>, id: String) -> Result<(), IpcError> {
+    service::delete_alert_rule(&state.db, &state.alerts, &id).await
+}
+
+#[tauri::command]
+pub async fn test_alert_rule(state: State<'_, AppState>, id: String) -> Result<(), IpcError> {
+    service::test_alert_rule(&state.db, &state.alerts, &id).await
+}
@@ -1,6 +1,8 @@
+use crate::config::{AppConfig, ConfigWatch};
 use crate::error::IpcError;
+use crate::services::config::{self as service, ConfigUpdate};
+use crate::services::declarative::{self, ExportOptions, ImportOptions, ImportPlan, ImportResult};
 use crate::AppState;
-use crate::config::AppConfig;
 use tauri::State;
 
 #[tauri::command]
@@ -10,10 +12,9 @@ pub async fn get_config(state: State<'_, AppState>) -> Result<AppConfig, IpcErro
 
 #[tauri::command]
 pub async fn get_server_status(state: State<'_, AppState>) -> Result<serde_json::Value, IpcError> {
-    let port = state.config.read().await.server_port;
-    let url = format!("http://127.0.0.1:{}/health", port);
+    let url = format!("{}/health", state.config.read().await.local_base_url());
 
-    match reqwest::get(&url).await {
+    match crate::server::local_client().get(&url).send().await {
         Ok(resp) => {
             let body: serde_json::Value = resp.json().await?;
             Ok(body)
@@ -28,28 +29,51 @@ pub async fn get_server_status(state: State<'_, AppState>) -> Result<serde_json:
 #[tauri::command]
 pub async fn update_config(
     state: State<'_, AppState>,
-    server_port: u16,
-    log_retention_days: u32,
+    update: ConfigUpdate,
 ) -> Result<AppConfig, IpcError> {
-    // UPSERT into app_config table
-    sqlx::query(
-        "INSERT INTO app_config (key, value) VALUES ('server_port', ?1) ON CONFLICT(key) DO UPDATE SET value = ?1",
-    )
-    .bind(server_port.to_string())
-    .execute(&state.db)
-    .await?;
-
-    sqlx::query(
-        "INSERT INTO app_config (key, value) VALUES ('log_retention_days', ?1) ON CONFLICT(key) DO UPDATE SET value = ?1",
-    )
-    .bind(log_retention_days.to_string())
-    .execute(&state.db)
-    .await?;
-
-    // Update in-memory config
-    let mut config = state.config.write().await;
-    config.server_port = server_port;
-    config.log_retention_days = log_retention_days;
-
-    Ok(config.clone())
+    service::update_config(&state.db, &state.config, &state.server, update).await
+}
+
+#[tauri::command]
+pub async fn set_config_watch(
+    state: State<'_, AppState>,
+    watch: Option<ConfigWatch>,
+) -> Result<AppConfig, IpcError> {
+    service::set_config_watch(&state.db, &state.config, &state.watcher, watch).await
+}
+
+#[tauri::command]
+pub async fn export_gateway_config(
+    state: State<'_, AppState>,
+    options: ExportOptions,
+) -> Result<String, IpcError> {
+    declarative::export_config(&state.db, &state.vault, options).await
+}
+
+#[tauri::command]
+pub async fn preview_gateway_import(
+    state: State<'_, AppState>,
+    content: String,
+    options: ImportOptions,
+) -> Result<ImportPlan, IpcError> {
+    declarative::preview_import(&state.db, &state.vault, &content, options).await
+}
+
+#[tauri::command]
+pub async fn import_gateway_config(
+    state: State<'_, AppState>,
+    content: String,
+    options: ImportOptions,
+) -> Result<ImportResult, IpcError> {
+    declarative::apply_import(&state.db, &state.vault, &content, options).await
+}
+
+#[tauri::command]
+pub async fn rotate_admin_key(state: State<'_, AppState>) -> Result<String, IpcError> {
+    service::rotate_admin_key(&state.db, &state.config).await
+}
+
+#[tauri::command]
+pub async fn clear_admin_key(state: State<'_, AppState>) -> Result<(), IpcError> {
+    service::clear_admin_key(&state.db, &state.config).await
 }
@@ -1,11 +1,6 @@
 pub mod config;
 pub mod routes;
 pub mod tokens;
+pub mod alerts;
 pub mod request_logs;
 pub mod video;
-
-#[derive(serde::Serialize)]
-pub struct PaginatedResult<T: serde::Serialize> {
-    pub items: Vec<T>,
-    pub total: i64,
-}
@@ -1,13 +1,12 @@
 use crate::db::models::Token;
 use crate::error::IpcError;
+use crate::services::tokens::{self as service, CreatedToken, TokenInput};
 use crate::AppState;
 use tauri::State;
 
 #[tauri::command]
 pub async fn list_tokens(state: State<'_, AppState>) -> Result<Vec<Token>, IpcError> {
-    Ok(sqlx::query_as::<_, Token>("SELECT * FROM tokens ORDER BY created_at DESC")
-        .fetch_all(&state.db)
-        .await?)
+    service::list_tokens(&state.db).await
 }
 
 #[tauri::command]
@@ -17,23 +16,9 @@ pub async fn create_token(
     quota_limit: Option<i64>,
     expires_at: Option<String>,
     allowed_models: Option<String>,
-) -> Result<Token, IpcError> {
-    let id = uuid::Uuid::new_v4().to_string();
-    let key_value = format!("sk-{}", uuid::Uuid::new_v4().to_string().replace("-", ""));
-    let now = chrono::Utc::now().to_rfc3339();
-
-    sqlx::query(
-        "INSERT INTO tokens (id, name, key_value, quota_limit, quota_used, expires_at, allowed_models, enabled, created_at) VALUES (?, ?, ?, ?, 0, ?, ?, 1, ?)"
-    )
-    .bind(&id).bind(&name).bind(&key_value)
-    .bind(quota_limit).bind(&expires_at).bind(&allowed_models).bind(&now)
-    .execute(&state.db)
-    .await?;
-
-    Ok(sqlx::query_as::<_, Token>("SELECT * FROM tokens WHERE id = ?")
-        .bind(&id)
-        .fetch_one(&state.db)
-        .await?)
+) -> Result<CreatedToken, IpcError> {
+    let input = TokenInput { name, quota_limit, expires_at, allowed_models, enabled: true };
+    service::create_token(&state.db, input).await
 }
 
 #[tauri::command]
@@ -46,30 +31,27 @@ pub async fn update_token(
     allowed_models: Option<String>,
     enabled: bool,
 ) -> Result<(), IpcError> {
-    sqlx::query(
-        "UPDATE tokens SET name = ?, quota_limit = ?, expires_at = ?, allowed_models = ?, enabled = ? WHERE id = ?"
-    )
-    .bind(&name).bind(quota_limit).bind(&expires_at)
-    .bind(&allowed_models).bind(enabled).bind(&id)
-    .execute(&state.db)
-    .await?;
-    Ok(())
+    let input = TokenInput { name, quota_limit, expires_at, allowed_models, enabled };
+    service::update_token(&state.db, &id, input).await
 }
 
 #[tauri::command]
 pub async fn delete_token(state: State<'_, AppState>, id: String) -> Result<(), IpcError> {
-    sqlx::query("DELETE FROM tokens WHERE id = ?")
-        .bind(&id)
-        .execute(&state.db)
-        .await?;
-    Ok(())
+    service::delete_token(&state.db, &id).await
+}
+
+#[tauri::command]
+pub async fn regenerate_token(state: State<'_, AppState>, id: String) -> Result<CreatedToken, IpcError> {
+    service::regenerate_token(&state.db, &id).await
+}
+
+/// The default token's key if this run created it, returned only once.
+#[tauri::command]
+pub async fn take_default_token(state: State<'_, AppState>) -> Result<Option<String>, IpcError> {
+    Ok(state.default_token.lock().unwrap().take())
 }
 
 #[tauri::command]
 pub async fn reset_token_quota(state: State<'_, AppState>, id: String) -> Result<(), IpcError> {
-    sqlx::query("UPDATE tokens SET quota_used = 0 WHERE id = ?")
-        .bind(&id)
-        .execute(&state.db)
-        .await?;
-    Ok(())
+    service::reset_token_quota(&state.db, &id).await
 }
@@ -1,30 +1,28 @@
 use crate::db::models::VideoRecord;
 use crate::error::IpcError;
-use crate::video::{self, VideoInfo};
-use crate::video::downloader::DownloadManager;
+use crate::video::{self, DownloadRequest, VideoInfo};
+use crate::video::downloader::{DownloadManager, DownloadOptions};
 use crate::AppState;
 use std::path::PathBuf;
 use tauri::{AppHandle, State};
 
 #[tauri::command]
-pub async fn parse_video_url(url: String) -> Result<VideoInfo, IpcError> {
-    video::parse_video_url(&url).await
+pub async fn parse_video_url(
+    state: State<'_, AppState>,
+    url: String,
+) -> Result<VideoInfo, IpcError> {
+    let proxy = state.config.read().await.proxy_url.clone();
+    video::parse_video_url(&url, proxy.as_deref()).await
 }
 
 #[tauri::command]
 pub async fn download_video(
     app: AppHandle,
+    state: State<'_, AppState>,
     manager: State<'_, DownloadManager>,
-    task_id: String,
-    title: String,
-    video_url: String,
-    audio_url: Option<String>,
-    quality: String,
-    save_dir: Option<String>,
-    audio_only: Option<bool>,
+    request: DownloadRequest,
 ) -> Result<String, IpcError> {
-    let audio_only = audio_only.unwrap_or(false);
-    let save_dir = if let Some(dir) = save_dir {
+    let save_dir = if let Some(dir) = request.save_dir {
         PathBuf::from(dir)
     } else {
         dirs::download_dir()
@@ -33,14 +31,19 @@ pub async fn download_video(
     };
 
     let format = video::VideoFormat {
-        quality,
-        url: video_url,
-        audio_url,
+        quality: request.quality,
+        url: request.video_url,
+        audio_url: request.audio_url,
         size: None,
     };
 
+    let options = DownloadOptions {
+        save_dir,
+        audio_only: request.audio_only,
+        proxy: state.config.read().await.proxy_url.clone(),
+    };
     let save_path = manager
-        .start_download(app, task_id, title, format, save_dir, audio_only)
+        .start_download(app, request.task_id, request.title, format, options)
         .await?;
 
     Ok(save_path.to_string_lossy().to_string())
@@ -1,22 +1,176 @@
-use serde::{Deserialize, Serialize};
-use sqlx::SqlitePool;
+use serde::{Deserialize, Serialize, Serializer};
+use sqlx::{SqliteExecutor, SqlitePool};
+use std::net::IpAddr;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
     pub server_port: u16,
+    /// Address the gateway listens on; `0.0.0.0` exposes it to the LAN.
+    #[serde(default = "default_bind_address")]
+    pub bind_address: String,
+    #[serde(default)]
+    pub tls: TlsConfig,
+    /// Client IPs or CIDR ranges allowed to connect. Empty allows everyone;
+    /// loopback is always allowed so the app can reach its own gateway.
+    #[serde(default)]
+    pub allowed_ips: Vec<String>,
     pub log_retention_days: u32,
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+    /// Serve the admin API on its own port instead of under `/admin` on `server_port`.
+    #[serde(default)]
+    pub admin_port: Option<u16>,
+    /// Salted hash of the admin API key; the admin API rejects every request while unset.
+    /// Only whether a key is configured is exposed, as `admin_key_set`.
+    #[serde(
+        rename = "admin_key_set",
+        serialize_with = "serialize_is_some",
+        skip_deserializing
+    )]
+    pub admin_key_hash: Option<String>,
+    /// Declarative config file applied on startup and whenever it changes.
+    #[serde(default)]
+    pub config_watch: Option<ConfigWatch>,
+    /// OTLP/HTTP collector that receives request traces, e.g. `http://localhost:4318`.
+    #[serde(default)]
+    pub otlp_endpoint: Option<String>,
+    /// Outbound proxy for upstream and video requests, e.g.
+    /// `http://proxy:8080` or `socks5h://127.0.0.1:1080`. Targets can
+    /// override it (see `outbound`).
+    #[serde(default)]
+    pub proxy_url: Option<String>,
+    #[serde(default)]
+    pub stream: StreamConfig,
+}
+
+fn default_bind_address() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn serialize_is_some<S: Serializer>(value: &Option<String>, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_bool(value.is_some())
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
             server_port: 9000,
+            bind_address: default_bind_address(),
+            tls: TlsConfig::default(),
+            allowed_ips: Vec::new(),
             log_retention_days: 30,
+            redaction: RedactionConfig::default(),
+            admin_port: None,
+            admin_key_hash: None,
+            config_watch: None,
+            otlp_endpoint: None,
+            proxy_url: None,
+            stream: StreamConfig::default(),
+        }
+    }
+}
+
+/// HTTPS for the gateway listener. Without a cert/key pair a self-signed
+/// certificate is generated in the data directory.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TlsConfig {
+    pub enabled: bool,
+    pub cert_path: Option<String>,
+    pub key_path: Option<String>,
+}
+
+impl TlsConfig {
+    /// Check that a cert/key pair is given together and both files exist.
+    pub fn validate(&self) -> Result<(), String> {
+        match (&self.cert_path, &self.key_path) {
+            (Some(cert), Some(key)) => {
+                for path in [cert, key] {
+                    if !std::path::Path::new(path).is_file() {
+                        return Err(format!("File not found: {}", path));
+                    }
+                }
+                Ok(())
+            }
+            (None, None) => Ok(()),
+            _ => Err("TLS needs both cert_path and key_path, or neither".into()),
         }
     }
 }
 
+/// A routes/tokens file (see `services::declarative`) kept in sync with the database.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigWatch {
+    pub path: String,
+    /// Delete routes and named tokens that are missing from the file.
+    #[serde(default)]
+    pub prune: bool,
+}
+
+/// Controls what gets masked before a request is written to `request_logs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactionConfig {
+    /// Mask credential headers (`authorization`, `x-api-key`, cookies, ...).
+    pub mask_auth_headers: bool,
+    /// Additional header names (case-insensitive) to mask.
+    pub extra_headers: Vec<String>,
+    /// Mask emails, phone numbers and API-key-like strings in logged bodies.
+    pub mask_body_pii: bool,
+    /// Additional regex patterns applied to logged bodies.
+    pub body_patterns: Vec<String>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            mask_auth_headers: true,
+            extra_headers: Vec::new(),
+            mask_body_pii: false,
+            body_patterns: Vec::new(),
+        }
+    }
+}
+
+/// How converted chat streams are relayed to clients.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StreamConfig {
+    /// Send a keep-alive after this many seconds without data for the
+    /// client, so idle connections survive client and proxy timeouts.
+    /// `0` disables keep-alives.
+    pub keepalive_secs: u64,
+    /// Start the response to the client before the upstream has replied.
+    /// Upstream errors are then reported as an error event in the stream.
+    pub early_start: bool,
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        Self { keepalive_secs: 15, early_start: false }
+    }
+}
+
+impl StreamConfig {
+    pub fn keepalive(&self) -> Option<Duration> {
+        Some(Duration::from_secs(self.keepalive_secs)).filter(|d| !d.is_zero())
+    }
+}
+
 impl AppConfig {
+    /// Base URL the app itself uses to reach the gateway.
+    pub fn local_base_url(&self) -> String {
+        let scheme = if self.tls.enabled { "https" } else { "http" };
+        let host = match self.bind_address.parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) if ip.is_unspecified() || ip.is_loopback() => "127.0.0.1".to_string(),
+            Ok(IpAddr::V6(ip)) if ip.is_unspecified() || ip.is_loopback() => "[::1]".to_string(),
+            Ok(IpAddr::V6(ip)) => format!("[{}]", ip),
+            _ => self.bind_address.clone(),
+        };
+        format!("{}://{}:{}", scheme, host, self.server_port)
+    }
+
     pub async fn load_from_db(pool: &SqlitePool) -> Result<Self, sqlx::Error> {
         let rows: Vec<(String, String)> =
             sqlx::query_as("SELECT key, value FROM app_config")
@@ -37,6 +191,44 @@ impl AppConfig {
                         config.log_retention_days = days;
                     }
                 }
+                "redaction" => {
+                    if let Ok(redaction) = serde_json::from_str(value) {
+                        config.redaction = redaction;
+                    }
+                }
+                "bind_address" => {
+                    config.bind_address = value.clone();
+                }
+                "tls" => {
+                    if let Ok(tls) = serde_json::from_str(value) {
+                        config.tls = tls;
+                    }
+                }
+                "allowed_ips" => {
+                    if let Ok(allowed_ips) = serde_json::from_str(value) {
+                        config.allowed_ips = allowed_ips;
+                    }
+                }
+                "admin_port" => {
+                    config.admin_port = value.parse::<u16>().ok();
+                }
+                "config_watch" => {
+                    config.config_watch = serde_json::from_str(value).ok();
+                }
+                "otlp_endpoint" => {
+                    config.otlp_endpoint = Some(value.clone());
+                }
+                "proxy_url" => {
+                    config.proxy_url = Some(value.clone());
+                }
+                "stream" => {
+                    if let Ok(stream) = serde_json::from_str(value) {
+                        config.stream = stream;
+                    }
+                }
+                "admin_key_hash" => {
+                    config.admin_key_hash = Some(value.clone());
+                }
                 _ => {}
             }
         }
@@ -44,3 +236,24 @@ impl AppConfig {
         Ok(config)
     }
 }
+
+/// Insert or update a single `app_config` row.
+pub async fn save_value(db: impl SqliteExecutor<'_>, key: &str, value: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO app_config (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = ?2",
+    )
+    .bind(key)
+    .bind(value)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Remove an `app_config` row, reverting the key to its default.
+pub async fn delete_value(db: impl SqliteExecutor<'_>, key: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM app_config WHERE key = ?")
+        .bind(key)
+        .execute(db)
+        .await?;
+    Ok(())
+}
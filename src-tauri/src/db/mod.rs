@@ -1,11 +1,12 @@
 pub mod models;
 
+use crate::secrets::{self, KeyVault};
 use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
 use sqlx::SqlitePool;
 use std::path::Path;
 use std::str::FromStr;
 
-pub async fn init_pool(db_path: &Path) -> Result<SqlitePool, sqlx::Error> {
+pub async fn init_pool(db_path: &Path, vault: &KeyVault) -> Result<SqlitePool, sqlx::Error> {
     if let Some(parent) = db_path.parent() {
         std::fs::create_dir_all(parent).ok();
     }
@@ -21,35 +22,38 @@ pub async fn init_pool(db_path: &Path) -> Result<SqlitePool, sqlx::Error> {
         .await?;
 
     sqlx::migrate!("./migrations").run(&pool).await?;
-
-    seed_default_token(&pool).await?;
+    secrets::migrate_plaintext_secrets(&pool, vault).await?;
 
     Ok(pool)
 }
 
 /// If the `tokens` table is empty, insert a default token so users can start
 /// using the API gateway immediately without manually creating one.
-async fn seed_default_token(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+///
+/// Returns the new key, of which only a hash is stored. The caller shows it
+/// once; it must never reach the log.
+pub async fn seed_default_token(pool: &SqlitePool) -> Result<Option<String>, sqlx::Error> {
     let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tokens")
         .fetch_one(pool)
         .await?;
 
-    if count == 0 {
-        let id = uuid::Uuid::new_v4().to_string();
-        let key_value = format!("sk-{}", uuid::Uuid::new_v4().to_string().replace("-", ""));
-        let now = chrono::Utc::now().to_rfc3339();
-
-        sqlx::query(
-            "INSERT INTO tokens (id, name, key_value, quota_limit, quota_used, expires_at, allowed_models, enabled, created_at) VALUES (?, 'Default', ?, NULL, 0, NULL, NULL, 1, ?)"
-        )
-        .bind(&id)
-        .bind(&key_value)
-        .bind(&now)
-        .execute(pool)
-        .await?;
-
-        log::info!("Created default token: {}", key_value);
+    if count > 0 {
+        return Ok(None);
     }
 
-    Ok(())
+    let id = uuid::Uuid::new_v4().to_string();
+    let key_value = secrets::generate_token();
+    let now = chrono::Utc::now().to_rfc3339();
+
+    sqlx::query(
+        "INSERT INTO tokens (id, name, key_value, key_prefix, quota_limit, quota_used, expires_at, allowed_models, enabled, created_at) VALUES (?, 'Default', ?, ?, NULL, 0, NULL, NULL, 1, ?)"
+    )
+    .bind(&id)
+    .bind(secrets::hash_token(&key_value))
+    .bind(secrets::token_prefix(&key_value))
+    .bind(&now)
+    .execute(pool)
+    .await?;
+
+    Ok(Some(key_value))
 }
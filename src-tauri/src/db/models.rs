@@ -9,6 +9,9 @@ pub struct Route {
     pub enabled: bool,
     pub created_at: String,
     pub updated_at: String,
+    /// `reject`, `clamp` or `trim` for requests over the target model's
+    /// limits; `None` forwards them as they are.
+    pub context_policy: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
@@ -21,6 +24,20 @@ pub struct RouteTarget {
     pub enabled: bool,
     pub key_rotation: bool,
     pub created_at: String,
+    /// Outbound proxy URL or `direct`; `None` uses the global proxy.
+    pub proxy_url: Option<String>,
+    /// Upstream timeouts in seconds; `None` uses the defaults.
+    pub connect_timeout_secs: Option<i64>,
+    pub first_byte_timeout_secs: Option<i64>,
+    pub idle_timeout_secs: Option<i64>,
+    /// `stream` or `non_stream` to call the upstream that way whatever the
+    /// client asked for; `None` follows the client.
+    pub stream_mode: Option<String>,
+    /// Normalize converted requests for upstreams strict about message order.
+    pub normalize_messages: bool,
+    /// `auto` or `always` to send image URLs as base64; `None` sends them
+    /// as they are.
+    pub inline_images: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
@@ -40,11 +57,25 @@ pub struct RouteTargetOverride {
     pub value: String,
 }
 
+/// A model a target serves, optionally under another name upstream.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RouteTargetModel {
+    pub id: String,
+    pub target_id: String,
+    pub model: String,
+    pub upstream_model: Option<String>,
+    pub context_window: Option<i64>,
+    pub max_output_tokens: Option<i64>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Token {
     pub id: String,
     pub name: Option<String>,
+    /// Salted hash of the token; never sent to the frontend.
+    #[serde(skip_serializing)]
     pub key_value: String,
+    pub key_prefix: Option<String>,
     pub quota_limit: Option<i64>,
     pub quota_used: i64,
     pub expires_at: Option<String>,
@@ -75,8 +106,15 @@ pub struct RequestLog {
     pub request_url: Option<String>,
     pub upstream_url: Option<String>,
     pub created_at: String,
+    pub ttft_ms: Option<i64>,
+    pub stream_duration_ms: Option<i64>,
+    pub tokens_per_second: Option<f64>,
+    pub stream_outcome: Option<String>,
+    /// Fixes normalization applied to the request, `; `-separated.
+    pub normalization_notes: Option<String>,
 }
 
+#[cfg(feature = "desktop")]
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct VideoRecord {
     pub id: String,
@@ -90,3 +128,22 @@ pub struct VideoRecord {
     pub save_path: Option<String>,
     pub created_at: String,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AlertRule {
+    pub id: String,
+    pub name: String,
+    /// `circuit_open`, `error_rate`, `quota` or `key_rejected`.
+    pub kind: String,
+    pub route_id: Option<String>,
+    pub token_id: Option<String>,
+    /// Percentage: error rate for `error_rate`, quota used for `quota`.
+    pub threshold: Option<f64>,
+    pub window_secs: i64,
+    pub min_requests: i64,
+    pub cooldown_secs: i64,
+    pub webhook_url: Option<String>,
+    pub notify_desktop: bool,
+    pub enabled: bool,
+    pub created_at: String,
+}
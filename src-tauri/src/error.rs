@@ -1,4 +1,6 @@
-use axum::http::StatusCode;
+use crate::modality::chat::ir::{IrError, IrErrorKind};
+use crate::modality::chat::{get_encoder, ChatFormat};
+use axum::http::{header, StatusCode};
 use axum::response::{IntoResponse, Json, Response};
 use serde::Serialize;
 use serde_json::json;
@@ -24,6 +26,14 @@ impl IpcError {
     pub fn internal(msg: impl Into<String>) -> Self {
         Self { code: "INTERNAL".into(), message: msg.into() }
     }
+
+    pub fn bind_failed(msg: impl Into<String>) -> Self {
+        Self { code: "BIND_FAILED".into(), message: msg.into() }
+    }
+
+    pub fn delivery_failed(msg: impl Into<String>) -> Self {
+        Self { code: "DELIVERY_FAILED".into(), message: msg.into() }
+    }
 }
 
 impl From<sqlx::Error> for IpcError {
@@ -49,6 +59,26 @@ impl From<serde_json::Error> for IpcError {
     }
 }
 
+/// Lets the admin HTTP API return the same errors as the Tauri commands.
+impl IntoResponse for IpcError {
+    fn into_response(self) -> Response {
+        let status = match self.code.as_str() {
+            "NOT_FOUND" => StatusCode::NOT_FOUND,
+            "VALIDATION" => StatusCode::BAD_REQUEST,
+            "CONFLICT" | "BIND_FAILED" => StatusCode::CONFLICT,
+            "DELIVERY_FAILED" => StatusCode::BAD_GATEWAY,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = Json(json!({
+            "error": {
+                "code": self.code,
+                "message": self.message,
+            }
+        }));
+        (status, body).into_response()
+    }
+}
+
 // === HTTP AppError for Axum handlers ===
 
 #[derive(Error, Debug)]
@@ -59,14 +89,23 @@ pub enum AppError {
     #[error("Authentication failed: {0}")]
     Unauthorized(String),
 
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
     #[error("No route found for path: {0}")]
     NoRoute(String),
 
     #[error("No available targets for route: {0}")]
     NoTarget(String),
 
-    #[error("Upstream error: {status} {body}")]
-    Upstream { status: u16, body: String },
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+
+    #[error("Context too long: {0}")]
+    ContextTooLong(String),
+
+    #[error("Upstream error: {} {}", .0.status, .0.message)]
+    Upstream(IrError),
 
     #[error("Codec error: {0}")]
     Codec(String),
@@ -77,6 +116,9 @@ pub enum AppError {
     #[error("HTTP client error: {0}")]
     HttpClient(#[from] reqwest::Error),
 
+    #[error("Upstream timed out: {0}")]
+    UpstreamTimeout(String),
+
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
 
@@ -84,31 +126,128 @@ pub enum AppError {
     Internal(String),
 }
 
+impl AppError {
+    /// The error as the gateway reports it to clients. Upstream errors keep
+    /// the provider's status and message; the rest map to a gateway status.
+    pub fn to_ir(&self) -> IrError {
+        let status = match self {
+            AppError::Upstream(error) => return error.clone(),
+            AppError::BadRequest(_) | AppError::Codec(_) | AppError::Json(_) | AppError::ContextTooLong(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::QuotaExceeded(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::NoRoute(_) => StatusCode::NOT_FOUND,
+            AppError::NoTarget(_) | AppError::HttpClient(_) => StatusCode::BAD_GATEWAY,
+            AppError::UpstreamTimeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            AppError::Database(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let message = match self {
+            AppError::Database(_) => "Database error".to_string(),
+            _ => self.to_string(),
+        };
+        let mut error = IrError::new(status.as_u16(), message);
+        match self {
+            // Every target is cooling down or disabled; a later retry may land.
+            AppError::NoTarget(_) => error.kind = IrErrorKind::Overloaded,
+            AppError::QuotaExceeded(_) => error.code = Some("insufficient_quota".into()),
+            AppError::ContextTooLong(_) => error.code = Some("context_length_exceeded".into()),
+            _ => {}
+        }
+        error
+    }
+
+    /// Render the error in the wire format the client spoke.
+    pub fn into_response_as(self, format: ChatFormat) -> Response {
+        let error = self.to_ir();
+        let status = StatusCode::from_u16(error.status).unwrap_or(StatusCode::BAD_GATEWAY);
+        let body = get_encoder(format).encode_error(&error);
+        let retry = if error.retryable() { "true" } else { "false" };
+        (
+            status,
+            [(header::CONTENT_TYPE, "application/json"), (header::HeaderName::from_static("x-should-retry"), retry)],
+            body,
+        )
+            .into_response()
+    }
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, message) = match &self {
-            AppError::BadRequest(_) => (StatusCode::BAD_REQUEST, self.to_string()),
-            AppError::Unauthorized(_) => (StatusCode::UNAUTHORIZED, self.to_string()),
-            AppError::NoRoute(_) => (StatusCode::NOT_FOUND, self.to_string()),
-            AppError::NoTarget(_) => (StatusCode::BAD_GATEWAY, self.to_string()),
-            AppError::Upstream { status, .. } => (
-                StatusCode::from_u16(*status).unwrap_or(StatusCode::BAD_GATEWAY),
-                self.to_string(),
-            ),
-            AppError::Codec(_) => (StatusCode::BAD_REQUEST, self.to_string()),
-            AppError::Database(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database error".into()),
-            AppError::HttpClient(_) => (StatusCode::BAD_GATEWAY, self.to_string()),
-            AppError::Json(_) => (StatusCode::BAD_REQUEST, self.to_string()),
-            AppError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
-        };
+        self.into_response_as(ChatFormat::OpenaiChat)
+    }
+}
 
-        let body = Json(json!({
-            "error": {
-                "message": message,
-                "type": format!("{:?}", status),
-            }
-        }));
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        (status, body).into_response()
+    fn should_retry(response: &Response) -> &str {
+        response.headers()["x-should-retry"].to_str().unwrap()
+    }
+
+    #[test]
+    fn upstream_timeouts_are_retryable_504s() {
+        let response = AppError::UpstreamTimeout("no response within 30s".into()).into_response();
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(should_retry(&response), "true");
+    }
+
+    #[test]
+    fn errors_map_to_gateway_statuses() {
+        let cases = [
+            (AppError::BadRequest("x".into()), 400),
+            (AppError::Codec("x".into()), 400),
+            (AppError::Unauthorized("x".into()), 401),
+            (AppError::Forbidden("x".into()), 403),
+            (AppError::NoRoute("x".into()), 404),
+            (AppError::QuotaExceeded("x".into()), 429),
+            (AppError::NoTarget("x".into()), 502),
+            (AppError::UpstreamTimeout("x".into()), 504),
+            (AppError::Internal("x".into()), 500),
+        ];
+        for (error, status) in cases {
+            assert_eq!(error.to_ir().status, status, "{error}");
+        }
+
+        let upstream = IrError::new(529, "Overloaded");
+        assert_eq!(AppError::Upstream(upstream.clone()).to_ir(), upstream);
+
+        let error = AppError::Database(sqlx::Error::RowNotFound).to_ir();
+        assert_eq!((error.status, error.message.as_str()), (500, "Database error"));
+    }
+
+    #[test]
+    fn errors_carry_openai_codes() {
+        let error = AppError::QuotaExceeded("token budget spent".into()).to_ir();
+        assert_eq!(error.code.as_deref(), Some("insufficient_quota"));
+        assert!(!error.retryable());
+
+        let error = AppError::ContextTooLong("too many tokens".into()).to_ir();
+        assert_eq!((error.status, error.code.as_deref()), (400, Some("context_length_exceeded")));
+
+        // Every target cooling down is worth retrying, unlike a plain 502.
+        let error = AppError::NoTarget("r".into()).to_ir();
+        assert_eq!(error.kind, IrErrorKind::Overloaded);
+        assert!(error.retryable());
+    }
+
+    #[tokio::test]
+    async fn responses_use_the_client_format() {
+        let response = AppError::QuotaExceeded("token budget spent".into()).into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(should_retry(&response), "false");
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["code"], "insufficient_quota");
+
+        let response = AppError::NoTarget("r".into()).into_response_as(ChatFormat::Anthropic);
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        assert_eq!(should_retry(&response), "true");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!((body["type"].as_str(), body["error"]["type"].as_str()), (Some("error"), Some("overloaded_error")));
     }
 }
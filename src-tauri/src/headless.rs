@@ -0,0 +1,304 @@
+//! Standalone gateway without the Tauri shell, used by the `omnikit-server` binary.
+//!
+//! Opens the same `omnikit.db` (schema, migrations and master key) as the desktop
+//! app and runs the Axum server. Settings come from, in increasing priority:
+//! the `app_config` table, a TOML file passed with `--config <path>` (or
+//! `OMNIKIT_CONFIG`), and `OMNIKIT_*` environment variables.
+//!
+//! `--rotate-admin-key` stores a new admin API key, prints it and exits.
+
+use crate::config::{AppConfig, ConfigWatch, TlsConfig};
+use crate::services::config_watch::ConfigWatcher;
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Same directory the desktop app resolves via `app_data_dir()`.
+const APP_IDENTIFIER: &str = "com.omnikit.desktop";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct HeadlessConfig {
+    /// Directory holding `omnikit.db` and the master key.
+    pub data_dir: Option<PathBuf>,
+    pub server_port: Option<u16>,
+    pub log_retention_days: Option<u32>,
+    /// Listen address, e.g. `0.0.0.0` inside a container.
+    pub bind_address: Option<String>,
+    /// Serve the admin API on this port instead of under `/admin` on `server_port`.
+    pub admin_port: Option<u16>,
+    /// Admin API key. Replaces any key generated from the desktop app for this run.
+    pub admin_key: Option<String>,
+    /// Declarative routes/tokens file to apply on startup and on every change.
+    pub watch_file: Option<String>,
+    /// Delete routes and named tokens missing from `watch_file`.
+    pub watch_prune: Option<bool>,
+    /// OTLP/HTTP collector for request traces, e.g. `http://localhost:4318`.
+    pub otlp_endpoint: Option<String>,
+    /// Outbound proxy for upstream requests, e.g. `socks5h://127.0.0.1:1080`.
+    pub proxy_url: Option<String>,
+    /// HTTPS for the gateway listener, as `[tls]` with `enabled`, `cert_path`
+    /// and `key_path`.
+    pub tls: Option<TlsConfig>,
+    /// Client IPs or CIDRs allowed to use the gateway.
+    pub allowed_ips: Option<Vec<String>>,
+}
+
+impl HeadlessConfig {
+    /// Load from the optional config file, then apply environment overrides.
+    pub fn load() -> Result<Self, String> {
+        let path = config_path_from_args(std::env::args().skip(1))?
+            .or_else(|| std::env::var_os("OMNIKIT_CONFIG").map(PathBuf::from));
+
+        let mut config = match path {
+            Some(path) => {
+                let text = std::fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                toml::from_str(&text)
+                    .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?
+            }
+            None => Self::default(),
+        };
+
+        if let Some(dir) = std::env::var_os("OMNIKIT_DATA_DIR") {
+            config.data_dir = Some(PathBuf::from(dir));
+        }
+        if let Some(port) = env_parse("OMNIKIT_PORT")? {
+            config.server_port = Some(port);
+        }
+        if let Some(days) = env_parse("OMNIKIT_LOG_RETENTION_DAYS")? {
+            config.log_retention_days = Some(days);
+        }
+        if let Ok(addr) = std::env::var("OMNIKIT_BIND_ADDRESS") {
+            config.bind_address = Some(addr);
+        }
+        if let Some(port) = env_parse("OMNIKIT_ADMIN_PORT")? {
+            config.admin_port = Some(port);
+        }
+        if let Ok(key) = std::env::var("OMNIKIT_ADMIN_KEY") {
+            config.admin_key = Some(key);
+        }
+        if let Ok(path) = std::env::var("OMNIKIT_WATCH_FILE") {
+            config.watch_file = Some(path);
+        }
+        if let Some(prune) = env_parse("OMNIKIT_WATCH_PRUNE")? {
+            config.watch_prune = Some(prune);
+        }
+        if let Ok(endpoint) = std::env::var("OMNIKIT_OTLP_ENDPOINT") {
+            config.otlp_endpoint = Some(endpoint);
+        }
+        if let Ok(proxy_url) = std::env::var("OMNIKIT_PROXY_URL") {
+            config.proxy_url = Some(proxy_url);
+        }
+        if let Some(enabled) = env_parse("OMNIKIT_TLS")? {
+            config.tls.get_or_insert_with(TlsConfig::default).enabled = enabled;
+        }
+        if let Ok(path) = std::env::var("OMNIKIT_TLS_CERT") {
+            config.tls.get_or_insert_with(TlsConfig::default).cert_path = Some(path);
+        }
+        if let Ok(path) = std::env::var("OMNIKIT_TLS_KEY") {
+            config.tls.get_or_insert_with(TlsConfig::default).key_path = Some(path);
+        }
+        if let Ok(ips) = std::env::var("OMNIKIT_ALLOWED_IPS") {
+            config.allowed_ips = Some(ips.split(',').map(|ip| ip.trim().to_string()).collect());
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if let Some(endpoint) = self.otlp_endpoint.as_deref().filter(|e| !e.is_empty()) {
+            crate::telemetry::validate_endpoint(endpoint)?;
+        }
+        if let Some(proxy_url) = self.proxy_url.as_deref().filter(|p| !p.is_empty()) {
+            crate::outbound::validate_proxy_url(proxy_url)?;
+        }
+        if let Some(tls) = &self.tls {
+            tls.validate()?;
+        }
+        if let Some(allowed_ips) = &self.allowed_ips {
+            crate::server::middleware::parse_allow_list(allowed_ips)?;
+        }
+        Ok(())
+    }
+
+    pub fn data_dir(&self) -> Result<PathBuf, String> {
+        match &self.data_dir {
+            Some(dir) => Ok(dir.clone()),
+            None => dirs::data_dir()
+                .map(|d| d.join(APP_IDENTIFIER))
+                .ok_or_else(|| "Cannot determine data directory; set OMNIKIT_DATA_DIR".to_string()),
+        }
+    }
+
+    /// Overlay file/env settings onto the config loaded from the database.
+    /// Overrides only live in memory; `app_config` is left untouched.
+    fn apply(&self, config: &mut AppConfig) {
+        if let Some(port) = self.server_port {
+            config.server_port = port;
+        }
+        if let Some(days) = self.log_retention_days {
+            config.log_retention_days = days;
+        }
+        if let Some(addr) = &self.bind_address {
+            config.bind_address = addr.clone();
+        }
+        if let Some(port) = self.admin_port {
+            config.admin_port = Some(port);
+        }
+        if let Some(key) = self.admin_key.as_deref().filter(|k| !k.is_empty()) {
+            config.admin_key_hash = Some(crate::secrets::hash_token(key));
+        }
+        if let Some(path) = self.watch_file.as_deref().filter(|p| !p.is_empty()) {
+            config.config_watch = Some(ConfigWatch {
+                path: path.to_string(),
+                prune: self.watch_prune.unwrap_or(false),
+            });
+        }
+        if let Some(endpoint) = self.otlp_endpoint.as_deref().filter(|e| !e.is_empty()) {
+            config.otlp_endpoint = Some(endpoint.to_string());
+        }
+        if let Some(proxy_url) = self.proxy_url.as_deref().filter(|p| !p.is_empty()) {
+            config.proxy_url = Some(proxy_url.to_string());
+        }
+        if let Some(tls) = &self.tls {
+            config.tls = tls.clone();
+        }
+        if let Some(allowed_ips) = &self.allowed_ips {
+            config.allowed_ips = allowed_ips.clone();
+        }
+    }
+}
+
+fn config_path_from_args(
+    mut args: impl Iterator<Item = String>,
+) -> Result<Option<PathBuf>, String> {
+    while let Some(arg) = args.next() {
+        if arg == "--config" || arg == "-c" {
+            return args
+                .next()
+                .map(|p| Some(PathBuf::from(p)))
+                .ok_or_else(|| format!("{} requires a path", arg));
+        }
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Ok(Some(PathBuf::from(path)));
+        }
+    }
+    Ok(None)
+}
+
+fn env_parse<T: std::str::FromStr>(name: &str) -> Result<Option<T>, String> {
+    match std::env::var(name) {
+        Ok(v) => v
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("Invalid value for {}: {}", name, v)),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Entry point of the `omnikit-server` binary.
+pub fn run() {
+    env_logger::init();
+    crate::telemetry::init();
+
+    if let Err(e) = run_inner() {
+        log::error!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run_inner() -> Result<(), Box<dyn std::error::Error>> {
+    let headless = HeadlessConfig::load()?;
+    let data_dir = headless.data_dir()?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async move {
+        let crate::Bootstrapped { pool, mut config, vault, default_token } =
+            crate::bootstrap(&data_dir).await?;
+        if std::env::args().skip(1).any(|arg| arg == "--rotate-admin-key") {
+            return rotate_admin_key(&pool, config, &headless).await;
+        }
+        headless.apply(&mut config);
+        crate::server::middleware::check_exposure(&config.bind_address, &config.allowed_ips)?;
+        log::info!("Using data directory {}", data_dir.display());
+        if let Some(key) = default_token {
+            // stdout rather than the log, which may be kept or shipped elsewhere.
+            println!("Created the default gateway token; it is shown only once:\n\n    {}\n", key);
+        }
+
+        let snapshot = config.clone();
+        crate::telemetry::set_endpoint(snapshot.otlp_endpoint.as_deref())?;
+        let config = Arc::new(RwLock::new(config));
+        let watcher = ConfigWatcher::new(pool.clone(), vault.clone());
+        watcher.set(snapshot.config_watch.as_ref())?;
+        let traffic = crate::server::traffic::TrafficBus::new();
+        let alerts = crate::server::alerts::Alerter::new(pool.clone(), &traffic);
+        let server = crate::server::start(pool, config, vault, watcher, traffic, alerts, data_dir);
+        server.apply(&snapshot).await?;
+
+        shutdown_signal().await?;
+        log::info!("Shutting down; draining in-flight requests");
+        server.shutdown().await;
+        tokio::task::spawn_blocking(crate::telemetry::shutdown).await?;
+        Ok(())
+    })
+}
+
+/// Store a new admin key in the database and print it once.
+async fn rotate_admin_key(
+    pool: &sqlx::SqlitePool,
+    config: AppConfig,
+    headless: &HeadlessConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let key = crate::services::config::rotate_admin_key(pool, &RwLock::new(config))
+        .await
+        .map_err(|e| e.message)?;
+    println!("New admin API key; it is shown only once:\n\n    {}\n", key);
+    if headless.admin_key.as_deref().is_some_and(|k| !k.is_empty()) {
+        log::warn!("admin_key is set in the config file or OMNIKIT_ADMIN_KEY and overrides the rotated key; remove it to use this one");
+    }
+    Ok(())
+}
+
+/// Wait for Ctrl-C, or SIGTERM as sent by containers and service managers.
+async fn shutdown_signal() -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut terminate = signal(SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result,
+            _ = terminate.recv() => Ok(()),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<AppConfig, String> {
+        let headless: HeadlessConfig = toml::from_str(text).map_err(|e| e.to_string())?;
+        headless.validate()?;
+        let mut config = AppConfig::default();
+        headless.apply(&mut config);
+        Ok(config)
+    }
+
+    #[test]
+    fn otlp_endpoint_from_the_config_file() {
+        assert_eq!(parse("").unwrap().otlp_endpoint, None);
+        assert_eq!(parse("otlp_endpoint = \"\"").unwrap().otlp_endpoint, None);
+        assert_eq!(
+            parse("otlp_endpoint = \"http://localhost:4318\"").unwrap().otlp_endpoint.as_deref(),
+            Some("http://localhost:4318")
+        );
+        assert!(parse("otlp_endpoint = \"localhost:4318\"").is_err());
+    }
+}
@@ -1,34 +1,92 @@
+
+#[cfg(feature = "desktop")]
 mod commands;
 mod config;
 mod db;
 mod error;
+pub mod headless;
 mod modality;
+mod outbound;
 mod routing;
 mod rules;
+mod secrets;
 mod server;
+mod services;
+mod telemetry;
+#[cfg(feature = "desktop")]
 mod video;
 
 use sqlx::SqlitePool;
+use std::path::Path;
+use std::sync::Arc;
+#[cfg(feature = "desktop")]
 use tauri::Manager;
 use tokio::sync::RwLock;
 
 pub struct AppState {
     pub db: SqlitePool,
-    pub config: RwLock<config::AppConfig>,
+    /// Shared with the Axum server so config changes apply without a restart.
+    pub config: Arc<RwLock<config::AppConfig>>,
+    pub vault: Arc<secrets::KeyVault>,
+    /// Rebinds the gateway listeners when listener settings change.
+    pub server: server::ServerControl,
+    pub watcher: services::config_watch::ConfigWatcher,
+    /// Live feed of proxied requests.
+    pub traffic: server::traffic::TrafficBus,
+    pub alerts: server::alerts::Alerter,
+    /// Key of the token seeded on first run, until the UI has shown it.
+    pub default_token: std::sync::Mutex<Option<String>>,
+}
+
+/// What [`bootstrap`] opened.
+struct Bootstrapped {
+    pool: SqlitePool,
+    config: config::AppConfig,
+    vault: Arc<secrets::KeyVault>,
+    /// Key of the default token if this run created it; shown once, never logged.
+    default_token: Option<String>,
+}
+
+/// Open `omnikit.db` under `data_dir`, run migrations and load the gateway config.
+/// Shared by the desktop app and the headless `omnikit-server` binary.
+async fn bootstrap(data_dir: &Path) -> Result<Bootstrapped, Box<dyn std::error::Error>> {
+    let vault = Arc::new(secrets::KeyVault::load_or_create(data_dir)?);
+    let pool = db::init_pool(&data_dir.join("omnikit.db"), &vault).await?;
+    let default_token = db::seed_default_token(&pool).await?;
+
+    // Seed built-in system rules on first startup
+    if let Err(e) = rules::seed_system_rules(&pool).await {
+        log::error!("Failed to seed system rules: {}", e);
+    }
+
+    let config = config::AppConfig::load_from_db(&pool)
+        .await
+        .unwrap_or_default();
+
+    Ok(Bootstrapped { pool, config, vault, default_token })
 }
 
+#[cfg(feature = "desktop")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     env_logger::init();
+    telemetry::init();
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_process::init())
+        .plugin(tauri_plugin_notification::init())
         .invoke_handler(tauri::generate_handler![
             commands::config::get_config,
             commands::config::get_server_status,
             commands::config::update_config,
+            commands::config::rotate_admin_key,
+            commands::config::clear_admin_key,
+            commands::config::set_config_watch,
+            commands::config::export_gateway_config,
+            commands::config::preview_gateway_import,
+            commands::config::import_gateway_config,
             commands::routes::list_routes,
             commands::routes::create_route,
             commands::routes::update_route,
@@ -38,12 +96,22 @@ pub fn run() {
             commands::tokens::create_token,
             commands::tokens::update_token,
             commands::tokens::delete_token,
+            commands::tokens::regenerate_token,
+            commands::tokens::take_default_token,
             commands::tokens::reset_token_quota,
+            commands::alerts::list_alert_rules,
+            commands::alerts::create_alert_rule,
+            commands::alerts::update_alert_rule,
+            commands::alerts::delete_alert_rule,
+            commands::alerts::test_alert_rule,
             commands::request_logs::list_request_logs,
             commands::request_logs::get_request_log,
             commands::request_logs::clear_request_logs,
             commands::request_logs::get_usage_stats,
+            commands::request_logs::get_usage_analytics,
             commands::request_logs::retry_request_log,
+            commands::request_logs::subscribe_request_events,
+            commands::request_logs::unsubscribe_request_events,
             commands::video::parse_video_url,
             commands::video::download_video,
             commands::video::cancel_video_download,
@@ -61,35 +129,65 @@ pub fn run() {
                     .path()
                     .app_data_dir()
                     .expect("failed to resolve app data dir");
-                let db_path = app_dir.join("omnikit.db");
 
-                let pool = db::init_pool(&db_path)
+                let Bootstrapped { pool, config, vault, default_token } = bootstrap(&app_dir)
                     .await
                     .expect("failed to initialize database");
 
-                // Seed built-in system rules on first startup
-                if let Err(e) = rules::seed_system_rules(&pool).await {
-                    log::error!("Failed to seed system rules: {}", e);
+                let snapshot = config.clone();
+                if let Err(e) = server::middleware::check_exposure(&snapshot.bind_address, &snapshot.allowed_ips) {
+                    log::warn!("{}; until then only local clients can connect", e);
+                }
+                if let Err(e) = telemetry::set_endpoint(snapshot.otlp_endpoint.as_deref()) {
+                    log::error!("Tracing error: {}", e);
+                }
+                let config = Arc::new(RwLock::new(config));
+                let watcher = services::config_watch::ConfigWatcher::new(pool.clone(), vault.clone());
+                if let Err(e) = watcher.set(snapshot.config_watch.as_ref()) {
+                    log::error!("Config watch error: {}", e);
+                }
+                let traffic = server::traffic::TrafficBus::new();
+                let alerts = server::alerts::Alerter::new(pool.clone(), &traffic);
+                let notify_handle = app_handle.clone();
+                alerts.set_notifier(Arc::new(move |alert: &server::alerts::Alert| {
+                    use tauri_plugin_notification::NotificationExt;
+                    if let Err(e) = notify_handle
+                        .notification()
+                        .builder()
+                        .title(&alert.title)
+                        .body(&alert.message)
+                        .show()
+                    {
+                        log::error!("Failed to show alert notification: {}", e);
+                    }
+                }));
+                let server = server::start(
+                    pool.clone(),
+                    config.clone(),
+                    vault.clone(),
+                    watcher.clone(),
+                    traffic.clone(),
+                    alerts.clone(),
+                    app_dir,
+                );
+                // A bind failure leaves the app usable so the port can be changed in Settings.
+                if let Err(e) = server.apply(&snapshot).await {
+                    log::error!("Axum server error: {}", e);
                 }
-
-                let config = config::AppConfig::load_from_db(&pool)
-                    .await
-                    .unwrap_or_default();
-                let server_port = config.server_port;
 
                 let state = AppState {
-                    db: pool.clone(),
-                    config: RwLock::new(config),
+                    db: pool,
+                    config,
+                    vault,
+                    server,
+                    watcher,
+                    traffic,
+                    alerts,
+                    default_token: std::sync::Mutex::new(default_token),
                 };
                 app_handle.manage(state);
+                app_handle.manage(commands::request_logs::EventSubscriptions::default());
                 app_handle.manage(video::downloader::DownloadManager::new());
-
-                // Start Axum HTTP server in background
-                tauri::async_runtime::spawn(async move {
-                    if let Err(e) = server::start(pool, server_port).await {
-                        log::error!("Axum server error: {}", e);
-                    }
-                });
             });
             Ok(())
         })
@@ -1,7 +1,13 @@
-use super::helpers::{from_json, from_json_str, from_json_value, to_json, to_json_str};
+use super::helpers::{
+    error_message, from_json, from_json_str, from_json_value, stream_error_value, to_json, to_json_str,
+    unsupported_part,
+};
 use super::ir::*;
+use super::sse::SseEvent;
 use super::{Decoder, Encoder};
 use crate::error::AppError;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 use serde::{Deserialize, Serialize};
 
 pub struct AnthropicCodec;
@@ -182,16 +188,28 @@ fn anthropic_content_to_ir(
                     }
                     "image" => {
                         if let Some(source) = block.get("source") {
+                            let field = |key: &str| source.get(key).and_then(|v| v.as_str()).map(String::from);
                             parts.push(IrContentPart::Image {
-                                url: None,
-                                media_type: source
-                                    .get("media_type")
-                                    .and_then(|m| m.as_str())
-                                    .map(String::from),
-                                data: source
-                                    .get("data")
-                                    .and_then(|d| d.as_str())
-                                    .map(String::from),
+                                url: field("url"),
+                                media_type: field("media_type"),
+                                data: field("data"),
+                            });
+                        }
+                    }
+                    "document" => {
+                        if let Some(source) = block.get("source") {
+                            let field = |key: &str| source.get(key).and_then(|v| v.as_str()).map(String::from);
+                            // Plain text sources carry the text itself.
+                            let data = match source.get("type").and_then(|t| t.as_str()) {
+                                Some("text") => field("data").map(|text| BASE64.encode(text)),
+                                _ => field("data"),
+                            };
+                            parts.push(IrContentPart::Document {
+                                url: field("url"),
+                                media_type: field("media_type"),
+                                data,
+                                file_id: field("file_id"),
+                                filename: block.get("title").and_then(|t| t.as_str()).map(String::from),
                             });
                         }
                     }
@@ -248,27 +266,27 @@ fn anthropic_content_to_ir(
 }
 
 /// Convert IR content to Anthropic content blocks array.
-fn ir_content_to_anthropic(content: &IrContent) -> Vec<serde_json::Value> {
+fn ir_content_to_anthropic(content: &IrContent) -> Result<Vec<serde_json::Value>, AppError> {
     match content {
         IrContent::Text(s) => {
             if s.is_empty() {
-                vec![]
+                Ok(vec![])
             } else {
-                vec![serde_json::json!({"type": "text", "text": s})]
+                Ok(vec![serde_json::json!({"type": "text", "text": s})])
             }
         }
         IrContent::Parts(parts) => parts
             .iter()
             .map(|p| match p {
                 IrContentPart::Text { text } => {
-                    serde_json::json!({"type": "text", "text": text})
+                    Ok(serde_json::json!({"type": "text", "text": text}))
                 }
                 IrContentPart::Image {
                     url,
                     media_type,
                     data,
                 } => {
-                    if let Some(data) = data {
+                    Ok(if let Some(data) = data {
                         serde_json::json!({
                             "type": "image",
                             "source": {
@@ -287,8 +305,37 @@ fn ir_content_to_anthropic(content: &IrContent) -> Vec<serde_json::Value> {
                         })
                     } else {
                         serde_json::json!({"type": "text", "text": "[image]"})
+                    })
+                }
+                IrContentPart::Document { url, media_type, data, file_id, filename } => {
+                    let media_type = media_type.as_deref().unwrap_or("application/pdf");
+                    let source = match (file_id, data, url) {
+                        (Some(file_id), _, _) => serde_json::json!({"type": "file", "file_id": file_id}),
+                        // Plain text goes as text; base64 sources take PDFs only.
+                        (None, Some(data), _) if media_type == "text/plain" => {
+                            let text = BASE64
+                                .decode(data)
+                                .ok()
+                                .and_then(|bytes| String::from_utf8(bytes).ok())
+                                .ok_or_else(|| AppError::BadRequest("Text document is not base64 UTF-8".into()))?;
+                            serde_json::json!({"type": "text", "media_type": "text/plain", "data": text})
+                        }
+                        (None, Some(_), _) if media_type != "application/pdf" => {
+                            return Err(unsupported_part("Anthropic", &format!("{} documents, only PDF and plain text", media_type)));
+                        }
+                        (None, Some(data), _) => {
+                            serde_json::json!({"type": "base64", "media_type": media_type, "data": data})
+                        }
+                        (None, None, Some(url)) => serde_json::json!({"type": "url", "url": url}),
+                        (None, None, None) => return Err(unsupported_part("Anthropic", "documents without data")),
+                    };
+                    let mut block = serde_json::json!({"type": "document", "source": source});
+                    if let Some(filename) = filename {
+                        block["title"] = serde_json::Value::String(filename.clone());
                     }
+                    Ok(block)
                 }
+                IrContentPart::Audio { .. } => Err(unsupported_part("Anthropic", "audio")),
             })
             .collect(),
     }
@@ -582,6 +629,63 @@ impl Decoder for AnthropicCodec {
         // Anthropic signals end with message_stop event
         data.contains("\"type\":\"message_stop\"") || data.contains("\"type\": \"message_stop\"")
     }
+
+    fn decode_error(&self, status: u16, body: &[u8]) -> IrError {
+        serde_json::from_slice::<serde_json::Value>(body)
+            .ok()
+            .and_then(|value| error_from_value(Some(status), &value))
+            .unwrap_or_else(|| IrError::new(status, error_message(status, body)))
+    }
+
+    fn decode_stream_error(&self, data: &str) -> Option<IrError> {
+        error_from_value(None, &stream_error_value(data)?)
+    }
+}
+
+// --- Errors ---
+
+/// Decode `{"type": "error", "error": {"type", "message"}}`, the body of
+/// both error responses and `error` stream events.
+fn error_from_value(status: Option<u16>, value: &serde_json::Value) -> Option<IrError> {
+    let error = &value["error"];
+    let message = error.get("message")?.as_str()?;
+    let kind = match error["type"].as_str() {
+        Some("invalid_request_error") => Some(IrErrorKind::InvalidRequest),
+        Some("authentication_error") => Some(IrErrorKind::Authentication),
+        Some("permission_error") => Some(IrErrorKind::PermissionDenied),
+        Some("not_found_error") => Some(IrErrorKind::NotFound),
+        Some("request_too_large") => Some(IrErrorKind::RequestTooLarge),
+        Some("rate_limit_error") => Some(IrErrorKind::RateLimit),
+        Some("timeout_error") => Some(IrErrorKind::Timeout),
+        Some("overloaded_error") => Some(IrErrorKind::Overloaded),
+        Some("api_error") => Some(IrErrorKind::Server),
+        _ => None,
+    };
+    let kind = kind.unwrap_or_else(|| status.map_or(IrErrorKind::Server, IrErrorKind::from_status));
+    Some(IrError {
+        status: status.unwrap_or_else(|| kind.status()),
+        kind,
+        code: error["type"].as_str().map(str::to_string),
+        message: message.to_string(),
+    })
+}
+
+fn encode_error_value(error: &IrError) -> serde_json::Value {
+    let error_type = match error.kind {
+        IrErrorKind::InvalidRequest => "invalid_request_error",
+        IrErrorKind::Authentication => "authentication_error",
+        IrErrorKind::PermissionDenied => "permission_error",
+        IrErrorKind::NotFound => "not_found_error",
+        IrErrorKind::RequestTooLarge => "request_too_large",
+        IrErrorKind::RateLimit => "rate_limit_error",
+        IrErrorKind::Timeout => "timeout_error",
+        IrErrorKind::Overloaded => "overloaded_error",
+        IrErrorKind::Server => "api_error",
+    };
+    serde_json::json!({
+        "type": "error",
+        "error": { "type": error_type, "message": error.message }
+    })
 }
 
 // --- Encoder impl ---
@@ -597,14 +701,14 @@ impl Encoder for AnthropicCodec {
                     continue;
                 }
                 IrRole::User => {
-                    let content_blocks = ir_content_to_anthropic(&msg.content);
+                    let content_blocks = ir_content_to_anthropic(&msg.content)?;
                     messages.push(AnthropicMessage {
                         role: "user".to_string(),
                         content: serde_json::Value::Array(content_blocks),
                     });
                 }
                 IrRole::Assistant => {
-                    let mut content_blocks = ir_content_to_anthropic(&msg.content);
+                    let mut content_blocks = ir_content_to_anthropic(&msg.content)?;
 
                     // Add tool_use blocks
                     if let Some(tcs) = &msg.tool_calls {
@@ -758,7 +862,7 @@ impl Encoder for AnthropicCodec {
         to_json(&resp)
     }
 
-    fn encode_stream_chunk(&mut self, chunk: &IrStreamChunk) -> Result<Option<String>, AppError> {
+    fn encode_stream_chunk(&mut self, chunk: &IrStreamChunk) -> Result<Vec<SseEvent>, AppError> {
         let mut events = Vec::new();
 
         // message_start event (when we have role + id)
@@ -783,10 +887,7 @@ impl Encoder for AnthropicCodec {
                     }
                 }
             });
-            events.push(format!(
-                "event: message_start\ndata: {}",
-                to_json_str(&msg_start)?
-            ));
+            events.push(SseEvent::named("message_start", to_json_str(&msg_start)?));
         }
 
         // content_block_delta for text
@@ -799,10 +900,7 @@ impl Encoder for AnthropicCodec {
                     "text": text,
                 }
             });
-            events.push(format!(
-                "event: content_block_delta\ndata: {}",
-                to_json_str(&delta)?
-            ));
+            events.push(SseEvent::named("content_block_delta", to_json_str(&delta)?));
         }
 
         // tool call deltas
@@ -820,10 +918,7 @@ impl Encoder for AnthropicCodec {
                             "input": {},
                         }
                     });
-                    events.push(format!(
-                        "event: content_block_start\ndata: {}",
-                        to_json_str(&block_start)?
-                    ));
+                    events.push(SseEvent::named("content_block_start", to_json_str(&block_start)?));
                 }
                 if let Some(args) = &tc.arguments {
                     let delta = serde_json::json!({
@@ -834,10 +929,7 @@ impl Encoder for AnthropicCodec {
                             "partial_json": args,
                         }
                     });
-                    events.push(format!(
-                        "event: content_block_delta\ndata: {}",
-                        to_json_str(&delta)?
-                    ));
+                    events.push(SseEvent::named("content_block_delta", to_json_str(&delta)?));
                 }
             }
         }
@@ -864,25 +956,87 @@ impl Encoder for AnthropicCodec {
                     "output_tokens": output_tokens,
                 }
             });
-            events.push(format!(
-                "event: message_delta\ndata: {}",
-                to_json_str(&msg_delta)?
-            ));
+            events.push(SseEvent::named("message_delta", to_json_str(&msg_delta)?));
         }
 
-        if events.is_empty() {
-            Ok(None)
-        } else {
-            // Join multiple events — each will be sent as a separate SSE block
-            // The proxy SSE layer wraps each line in "data: ...\n\n" so we need
-            // to return the raw content. Since we handle event: lines ourselves,
-            // return as a single string (the proxy will wrap it).
-            Ok(Some(events.join("\n\n")))
-        }
+        Ok(events)
+    }
+
+    fn stream_done_signal(&mut self) -> Vec<SseEvent> {
+        vec![SseEvent::named("message_stop", r#"{"type":"message_stop"}"#)]
+    }
+
+    fn keep_alive(&self) -> Option<SseEvent> {
+        Some(SseEvent::named("ping", r#"{"type":"ping"}"#))
+    }
+
+    fn encode_error(&self, error: &IrError) -> Vec<u8> {
+        encode_error_value(error).to_string().into_bytes()
     }
 
-    fn stream_done_signal(&mut self) -> Option<String> {
-        Some(r#"event: message_stop
-data: {"type":"message_stop"}"#.to_string())
+    fn stream_error(&self, error: &IrError) -> Vec<SseEvent> {
+        vec![SseEvent::named("error", encode_error_value(error).to_string())]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_error_responses_and_events() {
+        let body = br#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        let error = AnthropicCodec.decode_error(529, body);
+        assert_eq!((error.status, error.kind), (529, IrErrorKind::Overloaded));
+        assert_eq!(error.code.as_deref(), Some("overloaded_error"));
+        assert!(error.retryable());
+
+        let event = r#"{"type":"error","error":{"type":"rate_limit_error","message":"slow down"}}"#;
+        let error = AnthropicCodec.decode_stream_error(event).unwrap();
+        assert_eq!((error.status, error.kind), (429, IrErrorKind::RateLimit));
+        assert_eq!(error.message, "slow down");
+        assert!(AnthropicCodec.decode_stream_error(r#"{"type":"ping"}"#).is_none());
+
+        // Not an Anthropic envelope, e.g. from a proxy in between.
+        let error = AnthropicCodec.decode_error(502, b"Bad Gateway");
+        assert_eq!((error.status, error.kind), (502, IrErrorKind::Server));
+    }
+
+    #[test]
+    fn encodes_errors_in_its_envelope() {
+        let error = IrError::new(400, "API key not valid");
+        let body: serde_json::Value = serde_json::from_slice(&AnthropicCodec.encode_error(&error)).unwrap();
+        assert_eq!(body["type"], "error");
+        assert_eq!(body["error"]["type"], "invalid_request_error");
+        assert_eq!(body["error"]["message"], "API key not valid");
+        assert!(!error.retryable());
+
+        let events = AnthropicCodec.stream_error(&error);
+        assert_eq!(events[0].event.as_deref(), Some("error"));
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&events[0].data).unwrap(), body);
+    }
+
+    #[test]
+    fn encodes_documents_and_rejects_audio() {
+        let request = |part: serde_json::Value| -> IrChatRequest {
+            serde_json::from_value(serde_json::json!({
+                "model": "claude", "stream": false,
+                "messages": [{"role": "user", "content": [part]}]
+            }))
+            .unwrap()
+        };
+        let pdf = request(serde_json::json!({
+            "type": "document", "media_type": "application/pdf", "data": "JVBERi0=", "filename": "q3.pdf"
+        }));
+        let body: serde_json::Value =
+            serde_json::from_slice(&AnthropicCodec.encode_request(&pdf, "claude").unwrap()).unwrap();
+        let document = &body["messages"][0]["content"][0];
+        assert_eq!(document["type"], "document");
+        assert_eq!(document["source"]["media_type"], "application/pdf");
+        assert_eq!(document["source"]["data"], "JVBERi0=");
+        assert_eq!(document["title"], "q3.pdf");
+
+        let audio = request(serde_json::json!({"type": "audio", "data": "UklGRg==", "media_type": "audio/wav"}));
+        assert!(AnthropicCodec.encode_request(&audio, "claude").is_err());
     }
 }
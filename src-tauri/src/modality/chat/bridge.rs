@@ -0,0 +1,265 @@
+//! Bridging between streaming and non-streaming exchanges, for targets whose
+//! upstream is always called one way regardless of what the client asked for.
+//!
+//! [`StreamAggregator`] folds the chunks of an upstream stream into the
+//! response a non-streaming client expects; [`synthesize_stream`] splits a
+//! complete response into the chunks a streaming client expects.
+
+use super::ir::{
+    IrChatResponse, IrContent, IrFinishReason, IrMessage, IrRole, IrStreamChunk, IrToolCall,
+    IrToolCallDelta, IrUsage,
+};
+use super::sse::SseParser;
+use super::Decoder;
+use crate::error::AppError;
+use std::collections::BTreeMap;
+
+/// Accumulates stream chunks into a single response.
+#[derive(Debug, Default)]
+pub struct StreamAggregator {
+    id: String,
+    model: Option<String>,
+    text: String,
+    /// Tool calls by their index in the stream.
+    tool_calls: BTreeMap<u32, IrToolCall>,
+    finish_reason: Option<IrFinishReason>,
+    usage: Option<IrUsage>,
+}
+
+impl StreamAggregator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, chunk: &IrStreamChunk) {
+        if self.id.is_empty() {
+            self.id = chunk.id.clone();
+        }
+        if self.model.is_none() {
+            self.model = chunk.model.clone();
+        }
+        if let Some(text) = &chunk.delta_content {
+            self.text.push_str(text);
+        }
+        for delta in chunk.delta_tool_calls.iter().flatten() {
+            let call = self.tool_calls.entry(delta.index).or_insert_with(|| IrToolCall {
+                id: String::new(),
+                name: String::new(),
+                arguments: String::new(),
+            });
+            if let Some(id) = &delta.id {
+                call.id = id.clone();
+            }
+            if let Some(name) = &delta.name {
+                call.name = name.clone();
+            }
+            if let Some(arguments) = &delta.arguments {
+                call.arguments.push_str(arguments);
+            }
+        }
+        if chunk.finish_reason.is_some() {
+            self.finish_reason = chunk.finish_reason.clone();
+        }
+        // Usage is cumulative but may be split across chunks (Anthropic
+        // reports input tokens first and output tokens at the end).
+        if let Some(usage) = &chunk.usage {
+            let merged = self.usage.get_or_insert_with(IrUsage::default);
+            merged.prompt_tokens = merged.prompt_tokens.max(usage.prompt_tokens);
+            merged.completion_tokens = merged.completion_tokens.max(usage.completion_tokens);
+            merged.total_tokens = Some(merged.prompt_tokens + merged.completion_tokens);
+        }
+    }
+
+    /// The aggregated response. `model` is used when no chunk named one.
+    pub fn finish(self, model: &str) -> IrChatResponse {
+        let tool_calls: Vec<IrToolCall> = self.tool_calls.into_values().collect();
+        IrChatResponse {
+            id: self.id,
+            model: self.model.unwrap_or_else(|| model.to_string()),
+            message: IrMessage {
+                role: IrRole::Assistant,
+                content: IrContent::Text(self.text),
+                tool_calls: Some(tool_calls).filter(|calls| !calls.is_empty()),
+                tool_call_id: None,
+                name: None,
+            },
+            finish_reason: self.finish_reason,
+            usage: self.usage,
+        }
+    }
+}
+
+/// Decode a whole upstream event stream into a single response.
+pub fn aggregate_stream(
+    decoder: &dyn Decoder,
+    body: &[u8],
+    model: &str,
+) -> Result<IrChatResponse, AppError> {
+    let mut parser = SseParser::new();
+    let mut events = parser.push(body);
+    events.extend(parser.finish());
+
+    let mut aggregator = StreamAggregator::new();
+    for event in &events {
+        if decoder.is_stream_done(&event.data) {
+            break;
+        }
+        // A stream that fails midway must not pass as a truncated success.
+        if let Some(error) = decoder.decode_stream_error(&event.data) {
+            return Err(AppError::Upstream(error));
+        }
+        if let Some(chunk) = decoder.decode_stream_chunk(&event.data)? {
+            aggregator.push(&chunk);
+        }
+    }
+    Ok(aggregator.finish(model))
+}
+
+/// Split a complete response into stream chunks: the role, the text, one
+/// chunk per tool call, then the finish reason with usage.
+pub fn synthesize_stream(response: &IrChatResponse) -> Vec<IrStreamChunk> {
+    let chunk = || IrStreamChunk {
+        id: response.id.clone(),
+        model: Some(response.model.clone()),
+        delta_role: None,
+        delta_content: None,
+        delta_tool_calls: None,
+        finish_reason: None,
+        usage: None,
+    };
+
+    let mut chunks = vec![IrStreamChunk { delta_role: Some(IrRole::Assistant), ..chunk() }];
+    let text = response.message.content.to_text();
+    if !text.is_empty() {
+        chunks.push(IrStreamChunk { delta_content: Some(text), ..chunk() });
+    }
+    for (index, call) in response.message.tool_calls.iter().flatten().enumerate() {
+        chunks.push(IrStreamChunk {
+            delta_tool_calls: Some(vec![IrToolCallDelta {
+                index: index as u32,
+                id: Some(call.id.clone()),
+                name: Some(call.name.clone()),
+                arguments: Some(call.arguments.clone()),
+            }]),
+            ..chunk()
+        });
+    }
+    chunks.push(IrStreamChunk {
+        finish_reason: Some(response.finish_reason.clone().unwrap_or(IrFinishReason::Stop)),
+        usage: response.usage.clone(),
+        ..chunk()
+    });
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delta(index: u32, id: Option<&str>, name: Option<&str>, args: &str) -> IrToolCallDelta {
+        IrToolCallDelta {
+            index,
+            id: id.map(String::from),
+            name: name.map(String::from),
+            arguments: Some(args.to_string()),
+        }
+    }
+
+    #[test]
+    fn aggregates_text_tool_calls_and_usage() {
+        let base = IrStreamChunk {
+            id: "resp-1".into(),
+            model: Some("m".into()),
+            delta_role: None,
+            delta_content: None,
+            delta_tool_calls: None,
+            finish_reason: None,
+            usage: None,
+        };
+        let chunks = [
+            IrStreamChunk { delta_role: Some(IrRole::Assistant), delta_content: Some("Hel".into()), ..base.clone() },
+            IrStreamChunk { delta_content: Some("lo".into()), ..base.clone() },
+            IrStreamChunk {
+                delta_tool_calls: Some(vec![delta(0, Some("call_a"), Some("lookup"), "{\"q\":")]),
+                ..base.clone()
+            },
+            IrStreamChunk {
+                delta_tool_calls: Some(vec![delta(1, Some("call_b"), Some("time"), "{}"), delta(0, None, None, "1}")]),
+                ..base.clone()
+            },
+            IrStreamChunk {
+                finish_reason: Some(IrFinishReason::ToolCalls),
+                usage: Some(IrUsage { prompt_tokens: 5, completion_tokens: 7, total_tokens: Some(12) }),
+                ..base.clone()
+            },
+        ];
+
+        let mut aggregator = StreamAggregator::new();
+        for chunk in &chunks {
+            aggregator.push(chunk);
+        }
+        let response = aggregator.finish("fallback");
+
+        assert_eq!(response.id, "resp-1");
+        assert_eq!(response.model, "m");
+        assert_eq!(response.message.content.to_text(), "Hello");
+        let calls = response.message.tool_calls.unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!((calls[0].id.as_str(), calls[0].name.as_str(), calls[0].arguments.as_str()), ("call_a", "lookup", "{\"q\":1}"));
+        assert_eq!((calls[1].id.as_str(), calls[1].arguments.as_str()), ("call_b", "{}"));
+        assert_eq!(response.finish_reason, Some(IrFinishReason::ToolCalls));
+        assert_eq!(response.usage.unwrap().completion_tokens, 7);
+    }
+
+    #[test]
+    fn synthesized_stream_aggregates_back() {
+        let response = IrChatResponse {
+            id: "resp-2".into(),
+            model: "m".into(),
+            message: IrMessage {
+                role: IrRole::Assistant,
+                content: IrContent::Text("Checking.".into()),
+                tool_calls: Some(vec![IrToolCall {
+                    id: "call_a".into(),
+                    name: "lookup".into(),
+                    arguments: "{\"q\":1}".into(),
+                }]),
+                tool_call_id: None,
+                name: None,
+            },
+            finish_reason: Some(IrFinishReason::ToolCalls),
+            usage: Some(IrUsage { prompt_tokens: 3, completion_tokens: 4, total_tokens: None }),
+        };
+
+        let chunks = synthesize_stream(&response);
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(chunks[0].delta_role, Some(IrRole::Assistant));
+        assert!(chunks[..3].iter().all(|c| c.finish_reason.is_none() && c.usage.is_none()));
+
+        let mut aggregator = StreamAggregator::new();
+        for chunk in &chunks {
+            aggregator.push(chunk);
+        }
+        let roundtrip = aggregator.finish("unused");
+        assert_eq!(roundtrip.message.content.to_text(), "Checking.");
+        assert_eq!(roundtrip.message.tool_calls.unwrap()[0].arguments, "{\"q\":1}");
+        assert_eq!(roundtrip.finish_reason, Some(IrFinishReason::ToolCalls));
+        assert_eq!(roundtrip.usage.unwrap().prompt_tokens, 3);
+    }
+
+    #[test]
+    fn aggregate_stream_surfaces_mid_stream_errors() {
+        let body = concat!(
+            "data: {\"id\":\"c1\",\"object\":\"chat.completion.chunk\",\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+            "data: {\"error\":{\"message\":\"overloaded\",\"type\":\"server_error\"}}\n\n",
+        );
+        let codec = super::super::openai_chat::OpenAiChatCodec;
+        match aggregate_stream(&codec, body.as_bytes(), "m") {
+            Err(AppError::Upstream(error)) => assert_eq!(error.message, "overloaded"),
+            other => panic!("expected an upstream error, got {:?}", other.map(|r| r.message.content.to_text())),
+        }
+
+        let ok = aggregate_stream(&codec, &body.as_bytes()[..body.find("data: {\"error").unwrap()], "m").unwrap();
+        assert_eq!(ok.message.content.to_text(), "Hel");
+    }
+}
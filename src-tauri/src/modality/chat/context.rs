@@ -0,0 +1,204 @@
+//! Fitting a request into the target model's context window before it is
+//! forwarded, instead of letting the upstream answer with a 400.
+//!
+//! Sizes are the local estimates of [`super::tokens`], so a request that
+//! fits here can still be slightly over at the upstream.
+
+use super::ir::{IrChatRequest, IrRole};
+use super::tokens;
+
+/// Limits of a target model; unset limits aren't checked.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ModelLimits {
+    pub context_window: Option<u32>,
+    pub max_output_tokens: Option<u32>,
+}
+
+/// What a route does with a request over its model's limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextPolicy {
+    /// Reject the request before it is forwarded.
+    Reject,
+    /// Lower `max_tokens` to what the window leaves after the input.
+    Clamp,
+    /// Drop the oldest turns, then clamp if still needed.
+    Trim,
+}
+
+impl ContextPolicy {
+    /// The values [`ContextPolicy::parse`] accepts.
+    pub const ALL: &'static [&'static str] = &["reject", "clamp", "trim"];
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "reject" => Some(Self::Reject),
+            "clamp" => Some(Self::Clamp),
+            "trim" => Some(Self::Trim),
+            _ => None,
+        }
+    }
+}
+
+/// What [`fit`] changed.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Fitted {
+    pub dropped_messages: usize,
+    /// The new `max_tokens`, when it was lowered.
+    pub max_tokens: Option<u32>,
+}
+
+/// Make `ir` fit `limits` under `policy`. The error explains why it can't.
+pub fn fit(ir: &mut IrChatRequest, limits: ModelLimits, policy: ContextPolicy) -> Result<Fitted, String> {
+    let mut fitted = Fitted::default();
+
+    if let (Some(limit), Some(requested)) = (limits.max_output_tokens, ir.max_tokens) {
+        if requested > limit {
+            if policy == ContextPolicy::Reject {
+                return Err(format!("max_tokens {} exceeds the model's output limit of {}", requested, limit));
+            }
+            ir.max_tokens = Some(limit);
+            fitted.max_tokens = Some(limit);
+        }
+    }
+
+    let Some(window) = limits.context_window else {
+        return Ok(fitted);
+    };
+    let output = ir.max_tokens.unwrap_or(0);
+    let mut input = tokens::estimate_request(ir);
+    if input + output <= window {
+        return Ok(fitted);
+    }
+
+    match policy {
+        ContextPolicy::Reject => {
+            return Err(format!(
+                "about {} input tokens plus max_tokens {} exceed the context window of {}",
+                input, output, window
+            ));
+        }
+        ContextPolicy::Trim => {
+            let (dropped, trimmed) = trim(ir, input, window.saturating_sub(output));
+            fitted.dropped_messages = dropped;
+            input = trimmed;
+        }
+        ContextPolicy::Clamp => {}
+    }
+
+    if input + output > window {
+        if input >= window {
+            return Err(format!("about {} input tokens exceed the context window of {}", input, window));
+        }
+        ir.max_tokens = Some(window - input);
+        fitted.max_tokens = ir.max_tokens;
+    }
+    Ok(fitted)
+}
+
+/// Drop the oldest turns until the input fits `budget`, keeping system
+/// messages and the latest turn. A turn runs from a user message to the
+/// next one, so tool calls stay with their results. Returns the number of
+/// messages dropped and the new input size.
+fn trim(ir: &mut IrChatRequest, mut input: u32, budget: u32) -> (usize, u32) {
+    let mut turns: Vec<(Vec<usize>, u32)> = Vec::new();
+    for (i, message) in ir.messages.iter().enumerate() {
+        if message.role == IrRole::System {
+            continue;
+        }
+        if message.role == IrRole::User || turns.is_empty() {
+            turns.push((Vec::new(), 0));
+        }
+        let turn = turns.last_mut().unwrap();
+        turn.0.push(i);
+        turn.1 += tokens::estimate_message(message);
+    }
+
+    let mut dropped = Vec::new();
+    for (indices, size) in &turns[..turns.len().saturating_sub(1)] {
+        if input <= budget {
+            break;
+        }
+        dropped.extend(indices);
+        input -= size;
+    }
+
+    let mut index = 0;
+    ir.messages.retain(|_| {
+        index += 1;
+        !dropped.contains(&(index - 1))
+    });
+    (dropped.len(), input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modality::chat::ir::{IrContent, IrMessage, IrToolCall};
+
+    fn message(role: IrRole, text: &str) -> IrMessage {
+        IrMessage { role, content: IrContent::Text(text.into()), tool_calls: None, tool_call_id: None, name: None }
+    }
+
+    fn request(messages: Vec<IrMessage>, max_tokens: Option<u32>) -> IrChatRequest {
+        IrChatRequest {
+            model: "m".into(),
+            messages,
+            system: Some("Be brief.".into()),
+            temperature: None,
+            top_p: None,
+            max_tokens,
+            stream: false,
+            stop: None,
+            tools: None,
+            tool_choice: None,
+            extra: None,
+        }
+    }
+
+    fn conversation() -> Vec<IrMessage> {
+        let long = "word ".repeat(200);
+        let mut call = message(IrRole::Assistant, "");
+        call.tool_calls = Some(vec![IrToolCall { id: "c1".into(), name: "lookup".into(), arguments: "{}".into() }]);
+        let mut result = message(IrRole::Tool, &long);
+        result.tool_call_id = Some("c1".into());
+        vec![
+            message(IrRole::User, &long),
+            call,
+            result,
+            message(IrRole::Assistant, &long),
+            message(IrRole::User, "And now?"),
+        ]
+    }
+
+    #[test]
+    fn trims_whole_turns_and_keeps_the_latest() {
+        let mut ir = request(conversation(), Some(100));
+        let limits = ModelLimits { context_window: Some(300), max_output_tokens: None };
+        let fitted = fit(&mut ir, limits, ContextPolicy::Trim).unwrap();
+
+        // The first turn holds the tool call and its result; it goes as a whole.
+        assert_eq!(fitted.dropped_messages, 4);
+        assert_eq!(ir.messages.len(), 1);
+        assert_eq!(ir.messages[0].content.to_text(), "And now?");
+        assert_eq!(ir.system.as_deref(), Some("Be brief."));
+        assert_eq!(ir.max_tokens, Some(100));
+    }
+
+    #[test]
+    fn clamps_or_rejects() {
+        let limits = ModelLimits { context_window: Some(1000), max_output_tokens: Some(500) };
+
+        let mut ir = request(conversation(), Some(800));
+        let fitted = fit(&mut ir, limits, ContextPolicy::Clamp).unwrap();
+        let input = tokens::estimate_request(&ir);
+        assert_eq!(fitted.max_tokens, Some(1000 - input));
+        assert_eq!(ir.messages.len(), 5);
+
+        let mut ir = request(conversation(), Some(800));
+        assert!(fit(&mut ir, limits, ContextPolicy::Reject).unwrap_err().contains("output limit"));
+
+        let mut ir = request(conversation(), None);
+        let tight = ModelLimits { context_window: Some(100), max_output_tokens: None };
+        assert!(fit(&mut ir, tight, ContextPolicy::Clamp).is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use super::ir::*;
+use super::sse::SseEvent;
 use super::openai_chat::OpenAiChatCodec;
 use super::{Decoder, Encoder};
 use crate::error::AppError;
@@ -33,11 +34,11 @@ impl Encoder for DeepSeekCodec {
         OpenAiChatCodec.encode_response(ir)
     }
 
-    fn encode_stream_chunk(&mut self, chunk: &IrStreamChunk) -> Result<Option<String>, AppError> {
+    fn encode_stream_chunk(&mut self, chunk: &IrStreamChunk) -> Result<Vec<SseEvent>, AppError> {
         OpenAiChatCodec.encode_stream_chunk(chunk)
     }
 
-    fn stream_done_signal(&mut self) -> Option<String> {
-        Some("[DONE]".to_string())
+    fn stream_done_signal(&mut self) -> Vec<SseEvent> {
+        OpenAiChatCodec.stream_done_signal()
     }
 }
@@ -1,5 +1,8 @@
-use super::helpers::{from_json, from_json_str, to_json, to_json_str};
+use super::helpers::{
+    error_message, from_json, from_json_str, stream_error_value, to_json, to_json_str, unsupported_part,
+};
 use super::ir::*;
+use super::sse::SseEvent;
 use super::{Decoder, Encoder};
 use crate::error::AppError;
 use serde::{Deserialize, Serialize};
@@ -42,6 +45,8 @@ pub struct GeminiPart {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub inline_data: Option<GeminiInlineData>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_data: Option<GeminiFileData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub function_call: Option<GeminiFunctionCall>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub function_response: Option<GeminiFunctionResponse>,
@@ -54,6 +59,15 @@ pub struct GeminiInlineData {
     pub data: String,
 }
 
+/// A file referenced by URI, such as one uploaded to the Files API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiFileData {
+    #[serde(default)]
+    pub mime_type: Option<String>,
+    pub file_uri: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GeminiFunctionCall {
     pub name: String,
@@ -180,11 +194,19 @@ fn ir_finish_to_gemini(reason: &Option<IrFinishReason>) -> Option<String> {
 /// Convert Gemini parts into IR content + optional tool_calls.
 fn gemini_parts_to_ir(parts: &[GeminiPart]) -> (IrContent, Option<Vec<IrToolCall>>) {
     let mut text_parts = Vec::new();
+    let mut media = Vec::new();
     let mut tool_calls = Vec::new();
 
     for (i, part) in parts.iter().enumerate() {
         if let Some(text) = &part.text {
             text_parts.push(text.clone());
+            media.push(IrContentPart::Text { text: text.clone() });
+        }
+        if let Some(inline) = &part.inline_data {
+            media.push(inline_data_to_ir(inline));
+        }
+        if let Some(file) = &part.file_data {
+            media.push(file_data_to_ir(file));
         }
         if let Some(fc) = &part.function_call {
             tool_calls.push(IrToolCall {
@@ -195,7 +217,10 @@ fn gemini_parts_to_ir(parts: &[GeminiPart]) -> (IrContent, Option<Vec<IrToolCall
         }
     }
 
-    let content = if text_parts.len() == 1 {
+    // Text alone stays plain; with files, every part keeps its place.
+    let content = if media.len() > text_parts.len() {
+        IrContent::Parts(media)
+    } else if text_parts.len() == 1 {
         IrContent::Text(text_parts.into_iter().next().unwrap())
     } else if text_parts.is_empty() {
         IrContent::Text(String::new())
@@ -212,57 +237,90 @@ fn gemini_parts_to_ir(parts: &[GeminiPart]) -> (IrContent, Option<Vec<IrToolCall
     (content, tc)
 }
 
+fn inline_data_to_ir(inline: &GeminiInlineData) -> IrContentPart {
+    let (media_type, data) = (inline.mime_type.clone(), inline.data.clone());
+    if media_type.starts_with("image/") {
+        IrContentPart::Image { url: None, media_type: Some(media_type), data: Some(data) }
+    } else if media_type.starts_with("audio/") {
+        IrContentPart::Audio { data, media_type }
+    } else {
+        IrContentPart::Document { url: None, media_type: Some(media_type), data: Some(data), file_id: None, filename: None }
+    }
+}
+
+fn file_data_to_ir(file: &GeminiFileData) -> IrContentPart {
+    let (url, media_type) = (Some(file.file_uri.clone()), file.mime_type.clone());
+    if media_type.as_deref().is_some_and(|m| m.starts_with("image/")) {
+        IrContentPart::Image { url, media_type, data: None }
+    } else {
+        IrContentPart::Document { url, media_type, data: None, file_id: None, filename: None }
+    }
+}
+
+fn text_part(text: String) -> GeminiPart {
+    GeminiPart {
+        text: Some(text),
+        inline_data: None,
+        file_data: None,
+        function_call: None,
+        function_response: None,
+    }
+}
+
+fn inline_part(mime_type: &str, data: &str) -> GeminiPart {
+    GeminiPart {
+        text: None,
+        inline_data: Some(GeminiInlineData { mime_type: mime_type.to_string(), data: data.to_string() }),
+        file_data: None,
+        function_call: None,
+        function_response: None,
+    }
+}
+
+fn file_part(mime_type: &str, uri: &str) -> GeminiPart {
+    GeminiPart {
+        text: None,
+        inline_data: None,
+        file_data: Some(GeminiFileData { mime_type: Some(mime_type.to_string()), file_uri: uri.to_string() }),
+        function_call: None,
+        function_response: None,
+    }
+}
+
 /// Convert IR content into Gemini parts.
-fn ir_content_to_gemini_parts(content: &IrContent) -> Vec<GeminiPart> {
+fn ir_content_to_gemini_parts(content: &IrContent) -> Result<Vec<GeminiPart>, AppError> {
     match content {
         IrContent::Text(s) => {
             if s.is_empty() {
-                vec![]
+                Ok(vec![])
             } else {
-                vec![GeminiPart {
-                    text: Some(s.clone()),
-                    inline_data: None,
-                    function_call: None,
-                    function_response: None,
-                }]
+                Ok(vec![text_part(s.clone())])
             }
         }
         IrContent::Parts(parts) => parts
             .iter()
-            .filter_map(|p| match p {
-                IrContentPart::Text { text } => Some(GeminiPart {
-                    text: Some(text.clone()),
-                    inline_data: None,
-                    function_call: None,
-                    function_response: None,
+            .map(|p| match p {
+                IrContentPart::Text { text } => Ok(text_part(text.clone())),
+                IrContentPart::Image { url, data, media_type } => Ok(match (data, url, media_type) {
+                    (Some(data), _, _) => inline_part(media_type.as_deref().unwrap_or("image/png"), data),
+                    // fileData needs the type, which only Gemini's own requests carry.
+                    (None, Some(url), Some(media_type)) => file_part(media_type, url),
+                    // Gemini can't fetch image URLs; emit a text placeholder
+                    // unless the target inlines them.
+                    _ => text_part("[image]".to_string()),
                 }),
-                IrContentPart::Image {
-                    data, media_type, ..
-                } => {
-                    if let Some(data) = data {
-                        Some(GeminiPart {
-                            text: None,
-                            inline_data: Some(GeminiInlineData {
-                                mime_type: media_type
-                                    .as_deref()
-                                    .unwrap_or("image/png")
-                                    .to_string(),
-                                data: data.clone(),
-                            }),
-                            function_call: None,
-                            function_response: None,
-                        })
-                    } else {
-                        // Gemini doesn't support URL-based images directly;
-                        // emit a text placeholder.
-                        Some(GeminiPart {
-                            text: Some("[image]".to_string()),
-                            inline_data: None,
-                            function_call: None,
-                            function_response: None,
-                        })
+                IrContentPart::Document { url, media_type, data, file_id, .. } => {
+                    let media_type = media_type.as_deref().unwrap_or("application/pdf");
+                    match (data, url) {
+                        (Some(data), _) => Ok(inline_part(media_type, data)),
+                        (None, Some(url)) => Ok(file_part(media_type, url)),
+                        (None, None) if file_id.is_some() => {
+                            Err(unsupported_part("Gemini", "files uploaded to another provider"))
+                        }
+                        (None, None) => Err(unsupported_part("Gemini", "documents without data")),
                     }
                 }
+                IrContentPart::Audio { data, media_type } => Ok(inline_part(media_type, data)),
             })
             .collect(),
     }
@@ -509,6 +567,67 @@ impl Decoder for GeminiCodec {
         // Gemini streams end when the connection closes; there is no [DONE] signal.
         false
     }
+
+    fn decode_error(&self, status: u16, body: &[u8]) -> IrError {
+        serde_json::from_slice::<serde_json::Value>(body)
+            .ok()
+            .and_then(|value| error_from_value(Some(status), &value))
+            .unwrap_or_else(|| IrError::new(status, error_message(status, body)))
+    }
+
+    fn decode_stream_error(&self, data: &str) -> Option<IrError> {
+        error_from_value(None, &stream_error_value(data)?)
+    }
+}
+
+// --- Errors ---
+
+/// Decode `{"error": {"code", "message", "status"}}`. Some endpoints wrap the
+/// body in a one-element array.
+fn error_from_value(status: Option<u16>, value: &serde_json::Value) -> Option<IrError> {
+    let value = value.get(0).unwrap_or(value);
+    let error = &value["error"];
+    let message = error.get("message")?.as_str()?;
+    let code = error["status"].as_str();
+    let kind = match code {
+        Some("INVALID_ARGUMENT") | Some("FAILED_PRECONDITION") | Some("OUT_OF_RANGE") => {
+            Some(IrErrorKind::InvalidRequest)
+        }
+        Some("UNAUTHENTICATED") => Some(IrErrorKind::Authentication),
+        Some("PERMISSION_DENIED") => Some(IrErrorKind::PermissionDenied),
+        Some("NOT_FOUND") => Some(IrErrorKind::NotFound),
+        Some("RESOURCE_EXHAUSTED") => Some(IrErrorKind::RateLimit),
+        Some("UNAVAILABLE") => Some(IrErrorKind::Overloaded),
+        Some("DEADLINE_EXCEEDED") => Some(IrErrorKind::Timeout),
+        Some("INTERNAL") => Some(IrErrorKind::Server),
+        _ => None,
+    };
+    let status = status
+        .or_else(|| error["code"].as_u64().and_then(|code| u16::try_from(code).ok()))
+        .or_else(|| kind.map(IrErrorKind::status))
+        .unwrap_or(500);
+    Some(IrError {
+        status,
+        kind: kind.unwrap_or_else(|| IrErrorKind::from_status(status)),
+        code: code.map(str::to_string),
+        message: message.to_string(),
+    })
+}
+
+fn encode_error_value(error: &IrError) -> serde_json::Value {
+    let status = match error.kind {
+        IrErrorKind::InvalidRequest | IrErrorKind::RequestTooLarge => "INVALID_ARGUMENT",
+        IrErrorKind::Authentication => "UNAUTHENTICATED",
+        IrErrorKind::PermissionDenied => "PERMISSION_DENIED",
+        IrErrorKind::NotFound => "NOT_FOUND",
+        IrErrorKind::RateLimit => "RESOURCE_EXHAUSTED",
+        IrErrorKind::Overloaded => "UNAVAILABLE",
+        IrErrorKind::Timeout => "DEADLINE_EXCEEDED",
+        IrErrorKind::Server => "INTERNAL",
+    };
+    serde_json::json!({
+        "error": { "code": error.status, "message": error.message, "status": status }
+    })
 }
 
 // --- Encoder impl ---
@@ -524,7 +643,7 @@ impl Encoder for GeminiCodec {
                     continue;
                 }
                 IrRole::User => {
-                    let parts = ir_content_to_gemini_parts(&msg.content);
+                    let parts = ir_content_to_gemini_parts(&msg.content)?;
                     if !parts.is_empty() {
                         contents.push(GeminiContent {
                             role: Some("user".to_string()),
@@ -533,7 +652,7 @@ impl Encoder for GeminiCodec {
                     }
                 }
                 IrRole::Assistant => {
-                    let mut parts = ir_content_to_gemini_parts(&msg.content);
+                    let mut parts = ir_content_to_gemini_parts(&msg.content)?;
 
                     // Add functionCall parts for tool calls
                     if let Some(tcs) = &msg.tool_calls {
@@ -544,6 +663,7 @@ impl Encoder for GeminiCodec {
                             parts.push(GeminiPart {
                                 text: None,
                                 inline_data: None,
+                                file_data: None,
                                 function_call: Some(GeminiFunctionCall {
                                     name: tc.name.clone(),
                                     args,
@@ -577,6 +697,7 @@ impl Encoder for GeminiCodec {
                         parts: vec![GeminiPart {
                             text: None,
                             inline_data: None,
+                            file_data: None,
                             function_call: None,
                             function_response: Some(GeminiFunctionResponse {
                                 name: func_name,
@@ -593,6 +714,7 @@ impl Encoder for GeminiCodec {
             parts: vec![GeminiPart {
                 text: Some(s.clone()),
                 inline_data: None,
+                file_data: None,
                 function_call: None,
                 function_response: None,
             }],
@@ -658,7 +780,7 @@ impl Encoder for GeminiCodec {
     }
 
     fn encode_response(&self, ir: &IrChatResponse) -> Result<Vec<u8>, AppError> {
-        let mut parts = ir_content_to_gemini_parts(&ir.message.content);
+        let mut parts = ir_content_to_gemini_parts(&ir.message.content)?;
 
         // Add functionCall parts for tool calls
         if let Some(tcs) = &ir.message.tool_calls {
@@ -668,6 +790,7 @@ impl Encoder for GeminiCodec {
                 parts.push(GeminiPart {
                     text: None,
                     inline_data: None,
+                    file_data: None,
                     function_call: Some(GeminiFunctionCall {
                         name: tc.name.clone(),
                         args,
@@ -681,6 +804,7 @@ impl Encoder for GeminiCodec {
             parts.push(GeminiPart {
                 text: Some(String::new()),
                 inline_data: None,
+                file_data: None,
                 function_call: None,
                 function_response: None,
             });
@@ -708,7 +832,7 @@ impl Encoder for GeminiCodec {
         to_json(&resp)
     }
 
-    fn encode_stream_chunk(&mut self, chunk: &IrStreamChunk) -> Result<Option<String>, AppError> {
+    fn encode_stream_chunk(&mut self, chunk: &IrStreamChunk) -> Result<Vec<SseEvent>, AppError> {
         let mut parts = Vec::new();
 
         // Text delta
@@ -716,6 +840,7 @@ impl Encoder for GeminiCodec {
             parts.push(GeminiPart {
                 text: Some(text.clone()),
                 inline_data: None,
+                file_data: None,
                 function_call: None,
                 function_response: None,
             });
@@ -730,6 +855,7 @@ impl Encoder for GeminiCodec {
                     parts.push(GeminiPart {
                         text: None,
                         inline_data: None,
+                        file_data: None,
                         function_call: Some(GeminiFunctionCall {
                             name: name.clone(),
                             args,
@@ -742,7 +868,7 @@ impl Encoder for GeminiCodec {
 
         // If no content parts, still emit chunk with empty parts for finish_reason / usage
         if parts.is_empty() && chunk.finish_reason.is_none() && chunk.usage.is_none() {
-            return Ok(None);
+            return Ok(Vec::new());
         }
 
         let role = chunk
@@ -767,11 +893,75 @@ impl Encoder for GeminiCodec {
 
         let json = to_json_str(&gemini_chunk)?;
 
-        Ok(Some(json))
+        Ok(vec![SseEvent::data(json)])
     }
 
-    fn stream_done_signal(&mut self) -> Option<String> {
+    fn stream_done_signal(&mut self) -> Vec<SseEvent> {
         // Gemini streams end when the connection closes; no explicit done signal.
-        None
+        Vec::new()
+    }
+
+    fn encode_error(&self, error: &IrError) -> Vec<u8> {
+        encode_error_value(error).to_string().into_bytes()
+    }
+
+    fn stream_error(&self, error: &IrError) -> Vec<SseEvent> {
+        vec![SseEvent::data(encode_error_value(error).to_string())]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_error_responses_and_events() {
+        let body = br#"[{"error":{"code":400,"message":"API key not valid","status":"INVALID_ARGUMENT"}}]"#;
+        let error = GeminiCodec.decode_error(400, body);
+        assert_eq!((error.kind, error.code.as_deref()), (IrErrorKind::InvalidRequest, Some("INVALID_ARGUMENT")));
+        assert_eq!(error.message, "API key not valid");
+        assert!(!error.retryable());
+
+        // Mid-stream errors carry their status in `code`.
+        let event = r#"{"error":{"code":503,"message":"The model is overloaded","status":"UNAVAILABLE"}}"#;
+        let error = GeminiCodec.decode_stream_error(event).unwrap();
+        assert_eq!((error.status, error.kind), (503, IrErrorKind::Overloaded));
+        assert!(GeminiCodec.decode_stream_error(r#"{"candidates":[]}"#).is_none());
+    }
+
+    #[test]
+    fn encodes_errors_in_its_envelope() {
+        let mut error = IrError::new(529, "Overloaded");
+        error.kind = IrErrorKind::Overloaded;
+        let body: serde_json::Value = serde_json::from_slice(&GeminiCodec.encode_error(&error)).unwrap();
+        assert_eq!(body["error"]["status"], "UNAVAILABLE");
+        assert_eq!(body["error"]["code"], 529);
+        assert_eq!(body["error"]["message"], "Overloaded");
+
+        let events = GeminiCodec.stream_error(&error);
+        assert_eq!(events[0].event, None);
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&events[0].data).unwrap(), body);
+    }
+
+    #[test]
+    fn encodes_documents_and_audio_inline() {
+        let request = |part: serde_json::Value| -> IrChatRequest {
+            serde_json::from_value(serde_json::json!({
+                "model": "gemini", "stream": false,
+                "messages": [{"role": "user", "content": [part]}]
+            }))
+            .unwrap()
+        };
+        let parts = [
+            serde_json::json!({"type": "document", "media_type": "application/pdf", "data": "JVBERi0="}),
+            serde_json::json!({"type": "audio", "media_type": "audio/wav", "data": "UklGRg=="}),
+        ];
+        for part in parts {
+            let body: serde_json::Value =
+                serde_json::from_slice(&GeminiCodec.encode_request(&request(part.clone()), "gemini").unwrap()).unwrap();
+            let inline = &body["contents"][0]["parts"][0]["inlineData"];
+            assert_eq!(inline["mimeType"], part["media_type"]);
+            assert_eq!(inline["data"], part["data"]);
+        }
     }
 }
@@ -25,3 +25,56 @@ pub fn to_json<T: Serialize>(value: &T) -> Result<Vec<u8>, AppError> {
 pub fn to_json_str<T: Serialize>(value: &T) -> Result<String, AppError> {
     serde_json::to_string(value).map_err(|e| AppError::Codec(e.to_string()))
 }
+
+/// Message for an error body no decoder understood: the body text, or the
+/// status when the body is empty.
+pub fn error_message(status: u16, body: &[u8]) -> String {
+    let text = String::from_utf8_lossy(body).trim().to_string();
+    if text.is_empty() {
+        format!("Upstream returned HTTP {}", status)
+    } else {
+        text
+    }
+}
+
+/// Parse an SSE data line that may carry an error object. Most data lines
+/// are chunks, so those without an `"error"` key are skipped unparsed.
+pub fn stream_error_value(data: &str) -> Option<serde_json::Value> {
+    if !data.contains("\"error\"") {
+        return None;
+    }
+    serde_json::from_str(data).ok()
+}
+
+/// The media type and base64 data of a `data:` URL.
+pub fn parse_data_url(url: &str) -> Option<(&str, &str)> {
+    let (meta, data) = url.strip_prefix("data:")?.split_once(',')?;
+    Some((meta.strip_suffix(";base64")?, data))
+}
+
+pub fn data_url(media_type: &str, data: &str) -> String {
+    format!("data:{};base64,{}", media_type, data)
+}
+
+/// OpenAI's `input_audio.format` for an audio media type; it takes WAV and
+/// MP3 only.
+pub fn audio_format(media_type: &str) -> Option<&'static str> {
+    match media_type {
+        "audio/wav" | "audio/x-wav" | "audio/wave" => Some("wav"),
+        "audio/mpeg" | "audio/mp3" => Some("mp3"),
+        _ => None,
+    }
+}
+
+/// The media type of an OpenAI `input_audio.format`.
+pub fn audio_media_type(format: &str) -> String {
+    match format {
+        "mp3" => "audio/mpeg".to_string(),
+        other => format!("audio/{}", other),
+    }
+}
+
+/// Error for a content part an upstream format has no way to carry.
+pub fn unsupported_part(format: &str, what: &str) -> AppError {
+    AppError::BadRequest(format!("{} upstreams can't take {}", format, what))
+}
@@ -97,6 +97,25 @@ pub enum IrContentPart {
         #[serde(skip_serializing_if = "Option::is_none")]
         data: Option<String>,
     },
+    /// A file such as a PDF: base64 `data`, a `url`, or the id of a file
+    /// uploaded to the provider beforehand.
+    Document {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        media_type: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        data: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        file_id: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        filename: Option<String>,
+    },
+    /// Base64 audio, such as `audio/wav` or `audio/mpeg`.
+    Audio {
+        data: String,
+        media_type: String,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -182,3 +201,77 @@ pub struct IrToolCallDelta {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub arguments: Option<String>,
 }
+
+// --- Error IR ---
+
+/// A failed request, decoded from a provider's error body or raised by the
+/// gateway, and encoded into the client's error envelope.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrError {
+    /// HTTP status the client is answered with.
+    pub status: u16,
+    pub kind: IrErrorKind,
+    /// Provider-specific code, e.g. OpenAI's `insufficient_quota` or
+    /// Gemini's `RESOURCE_EXHAUSTED`.
+    pub code: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IrErrorKind {
+    InvalidRequest,
+    Authentication,
+    PermissionDenied,
+    NotFound,
+    RequestTooLarge,
+    RateLimit,
+    Overloaded,
+    Timeout,
+    Server,
+}
+
+impl IrErrorKind {
+    /// The HTTP status for errors that arrive without one, mid-stream.
+    pub fn status(self) -> u16 {
+        match self {
+            Self::InvalidRequest => 400,
+            Self::Authentication => 401,
+            Self::PermissionDenied => 403,
+            Self::NotFound => 404,
+            Self::RequestTooLarge => 413,
+            Self::RateLimit => 429,
+            Self::Overloaded => 503,
+            Self::Timeout => 504,
+            Self::Server => 500,
+        }
+    }
+
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            401 => Self::Authentication,
+            403 => Self::PermissionDenied,
+            404 => Self::NotFound,
+            408 | 504 => Self::Timeout,
+            413 => Self::RequestTooLarge,
+            429 => Self::RateLimit,
+            503 | 529 => Self::Overloaded,
+            500..=599 => Self::Server,
+            _ => Self::InvalidRequest,
+        }
+    }
+}
+
+impl IrError {
+    pub fn new(status: u16, message: impl Into<String>) -> Self {
+        Self { status, kind: IrErrorKind::from_status(status), code: None, message: message.into() }
+    }
+
+    /// Whether sending the same request again may succeed. An exhausted
+    /// quota is reported as a rate limit but doesn't recover by waiting.
+    pub fn retryable(&self) -> bool {
+        matches!(
+            self.kind,
+            IrErrorKind::RateLimit | IrErrorKind::Overloaded | IrErrorKind::Timeout | IrErrorKind::Server
+        ) && self.code.as_deref() != Some("insufficient_quota")
+    }
+}
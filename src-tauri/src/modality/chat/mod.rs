@@ -1,5 +1,10 @@
+pub mod bridge;
+pub mod context;
 pub mod helpers;
 pub mod ir;
+pub mod normalize;
+pub mod sse;
+pub mod tokens;
 pub mod openai_chat;
 pub mod anthropic;
 pub mod openai_responses;
@@ -11,7 +16,8 @@ pub mod mistral;
 pub mod azure_openai;
 
 use crate::error::AppError;
-use ir::{IrChatRequest, IrChatResponse, IrStreamChunk};
+use ir::{IrChatRequest, IrChatResponse, IrError, IrStreamChunk};
+use sse::SseEvent;
 
 /// Identifies the wire format of a request/response.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
@@ -44,36 +50,6 @@ impl ChatFormat {
             _ => None,
         }
     }
-
-    /// Return a string identifier for logging/display.
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            Self::OpenaiChat => "openai-chat",
-            Self::OpenaiResponses => "openai-responses",
-            Self::Anthropic => "anthropic",
-            Self::Gemini => "gemini",
-            Self::Moonshot => "moonshot",
-            Self::DeepSeek => "deepseek",
-            Self::Qwen => "qwen",
-            Self::Mistral => "mistral",
-            Self::AzureOpenAi => "azure-openai",
-        }
-    }
-
-    /// Map from provider name stored in database channel.
-    pub fn from_provider(provider: &str) -> Option<Self> {
-        match provider {
-            "openai" => Some(Self::OpenaiChat),
-            "anthropic" => Some(Self::Anthropic),
-            "gemini" => Some(Self::Gemini),
-            "moonshot" => Some(Self::Moonshot),
-            "deepseek" => Some(Self::DeepSeek),
-            "qwen" => Some(Self::Qwen),
-            "mistral" => Some(Self::Mistral),
-            "azure-openai" | "azure" => Some(Self::AzureOpenAi),
-            _ => None,
-        }
-    }
 }
 
 /// Decodes a provider-specific format into IR.
@@ -90,6 +66,17 @@ pub trait Decoder: Send + Sync {
 
     /// Returns true if the given SSE data line signals end-of-stream.
     fn is_stream_done(&self, data: &str) -> bool;
+
+    /// Decode an upstream error response. Bodies that don't parse keep
+    /// their text as the message. Defaults to the OpenAI envelope.
+    fn decode_error(&self, status: u16, body: &[u8]) -> IrError {
+        openai_chat::decode_error(status, body)
+    }
+
+    /// Decode an SSE data line that reports an error mid-stream.
+    fn decode_stream_error(&self, data: &str) -> Option<IrError> {
+        openai_chat::decode_stream_error(data)
+    }
 }
 
 /// Encodes IR into a provider-specific format.
@@ -100,14 +87,31 @@ pub trait Encoder: Send + Sync {
     /// Encode IR response into bytes to send downstream.
     fn encode_response(&self, ir: &IrChatResponse) -> Result<Vec<u8>, AppError>;
 
-    /// Encode an IR stream chunk into one or more SSE data lines to send downstream.
+    /// Encode an IR stream chunk into zero or more SSE events to send downstream.
     /// May accumulate state across calls for formats that require it (e.g. Responses API).
-    fn encode_stream_chunk(&mut self, chunk: &IrStreamChunk) -> Result<Option<String>, AppError>;
+    fn encode_stream_chunk(&mut self, chunk: &IrStreamChunk) -> Result<Vec<SseEvent>, AppError>;
 
-    /// Return the SSE termination signal(s) for this format.
+    /// Return the SSE termination event(s) for this format.
     /// Called once after the upstream stream ends (after [DONE] or equivalent).
-    /// May emit multiple events joined by '\n' (e.g. Responses API closing events).
-    fn stream_done_signal(&mut self) -> Option<String>;
+    /// May emit several events (e.g. Responses API closing events).
+    fn stream_done_signal(&mut self) -> Vec<SseEvent>;
+
+    /// Event sent while the stream is quiet so the connection stays open.
+    /// `None` sends an SSE comment, which clients ignore.
+    fn keep_alive(&self) -> Option<SseEvent> {
+        None
+    }
+
+    /// Encode an error response body. Defaults to the OpenAI envelope.
+    fn encode_error(&self, error: &IrError) -> Vec<u8> {
+        openai_chat::encode_error(error).to_string().into_bytes()
+    }
+
+    /// Event(s) reporting a failure after the response to the client has
+    /// already started.
+    fn stream_error(&self, error: &IrError) -> Vec<SseEvent> {
+        vec![SseEvent::data(openai_chat::encode_error(error).to_string())]
+    }
 }
 
 /// Get a decoder for a given format.
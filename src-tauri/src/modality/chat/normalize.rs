@@ -0,0 +1,289 @@
+//! Reshaping a conversation for upstreams stricter than OpenAI about it.
+//!
+//! Anthropic and Gemini reject consecutive messages of the same role, empty
+//! content, tool results that don't directly follow their call, and
+//! conversations opening with an assistant turn. [`normalize`] fixes these
+//! in the IR, between decoding the client's request and encoding the
+//! upstream's, and reports what it changed.
+
+use super::ir::{IrChatRequest, IrContent, IrContentPart, IrMessage, IrRole};
+
+/// Text of a user turn the upstream requires but the client didn't send.
+const PLACEHOLDER_TURN: &str = "(continued)";
+/// Result given to a tool call the client sent no result for.
+const PLACEHOLDER_RESULT: &str = "(no result)";
+
+/// Normalize `ir` in place. Returns a note for each kind of fix applied,
+/// empty when the conversation was already fine.
+pub fn normalize(ir: &mut IrChatRequest) -> Vec<String> {
+    let mut notes = Vec::new();
+    coalesce_system(ir, &mut notes);
+    drop_empty(ir, &mut notes);
+    pair_tool_results(ir, &mut notes);
+    merge_adjacent(ir, &mut notes);
+    open_with_user(ir, &mut notes);
+    notes
+}
+
+fn note(notes: &mut Vec<String>, what: &str, count: usize) {
+    if count > 0 {
+        notes.push(format!("{}: {}", what, count));
+    }
+}
+
+/// Move system messages into the system prompt, in order.
+fn coalesce_system(ir: &mut IrChatRequest, notes: &mut Vec<String>) {
+    let (system, rest): (Vec<_>, Vec<_>) =
+        std::mem::take(&mut ir.messages).into_iter().partition(|m| m.role == IrRole::System);
+    ir.messages = rest;
+    if system.is_empty() {
+        return;
+    }
+    let texts: Vec<String> = ir
+        .system
+        .take()
+        .into_iter()
+        .chain(system.iter().map(|m| m.content.to_text()))
+        .filter(|text| !text.is_empty())
+        .collect();
+    ir.system = (!texts.is_empty()).then(|| texts.join("\n\n"));
+    note(notes, "moved system messages into the system prompt", system.len());
+}
+
+/// Drop empty text parts, then messages left with nothing to send. Tool
+/// results are kept even when empty, as their call needs one.
+fn drop_empty(ir: &mut IrChatRequest, notes: &mut Vec<String>) {
+    let mut parts = 0;
+    for message in &mut ir.messages {
+        if let IrContent::Parts(list) = &mut message.content {
+            let before = list.len();
+            list.retain(|part| !matches!(part, IrContentPart::Text { text } if text.is_empty()));
+            parts += before - list.len();
+        }
+    }
+    let before = ir.messages.len();
+    ir.messages.retain(|m| {
+        m.role == IrRole::Tool || !m.content.is_empty() || m.tool_calls.as_ref().is_some_and(|c| !c.is_empty())
+    });
+    note(notes, "dropped empty parts", parts);
+    note(notes, "dropped empty messages", before - ir.messages.len());
+}
+
+/// Put each tool result right after the assistant message calling it. Calls
+/// without a result get a placeholder; results without a call become user
+/// text where they were.
+fn pair_tool_results(ir: &mut IrChatRequest, notes: &mut Vec<String>) {
+    let messages = std::mem::take(&mut ir.messages);
+
+    // The result of each call: the first unclaimed tool message with its id.
+    let mut claimed = vec![false; messages.len()];
+    let mut results: Vec<Vec<Option<usize>>> = Vec::with_capacity(messages.len());
+    for message in &messages {
+        let calls = message.tool_calls.as_deref().unwrap_or_default();
+        let found = calls
+            .iter()
+            .map(|call| {
+                let index = messages.iter().enumerate().position(|(j, m)| {
+                    !claimed[j] && m.role == IrRole::Tool && m.tool_call_id.as_deref() == Some(call.id.as_str())
+                })?;
+                claimed[index] = true;
+                Some(index)
+            })
+            .collect();
+        results.push(found);
+    }
+
+    let (mut moved, mut inserted, mut orphaned) = (0, 0, 0);
+    let mut slots: Vec<Option<IrMessage>> = messages.into_iter().map(Some).collect();
+    for i in 0..slots.len() {
+        // Claimed results go out with their call.
+        if claimed[i] {
+            continue;
+        }
+        let Some(message) = slots[i].take() else {
+            continue;
+        };
+        if message.role == IrRole::Tool {
+            orphaned += 1;
+            ir.messages.push(IrMessage {
+                role: IrRole::User,
+                content: IrContent::Text(format!("Tool result: {}", message.content.to_text())),
+                tool_calls: None,
+                tool_call_id: None,
+                name: None,
+            });
+            continue;
+        }
+        let calls = message.tool_calls.clone().unwrap_or_default();
+        ir.messages.push(message);
+        for (k, (call, result)) in calls.iter().zip(&results[i]).enumerate() {
+            match result.and_then(|j| slots[j].take().map(|m| (j, m))) {
+                Some((j, result)) => {
+                    // Results already following the call in order stay put.
+                    if j != i + 1 + k {
+                        moved += 1;
+                    }
+                    ir.messages.push(result);
+                }
+                None => {
+                    inserted += 1;
+                    ir.messages.push(IrMessage {
+                        role: IrRole::Tool,
+                        content: IrContent::Text(PLACEHOLDER_RESULT.into()),
+                        tool_calls: None,
+                        tool_call_id: Some(call.id.clone()),
+                        name: Some(call.name.clone()),
+                    });
+                }
+            }
+        }
+    }
+    note(notes, "moved tool results after their calls", moved);
+    note(notes, "inserted placeholder tool results", inserted);
+    note(notes, "turned tool results without a call into user text", orphaned);
+}
+
+/// Merge consecutive user or assistant messages. Tool results stay separate;
+/// encoders group them into one turn themselves.
+fn merge_adjacent(ir: &mut IrChatRequest, notes: &mut Vec<String>) {
+    let mut merged = 0;
+    let mut messages: Vec<IrMessage> = Vec::with_capacity(ir.messages.len());
+    for message in std::mem::take(&mut ir.messages) {
+        match messages.last_mut() {
+            Some(last) if last.role == message.role && last.role != IrRole::Tool => {
+                merged += 1;
+                last.content = join(std::mem::replace(&mut last.content, IrContent::Text(String::new())), message.content);
+                if let Some(calls) = message.tool_calls {
+                    last.tool_calls.get_or_insert_with(Vec::new).extend(calls);
+                }
+            }
+            _ => messages.push(message),
+        }
+    }
+    ir.messages = messages;
+    note(notes, "merged adjacent messages of the same role", merged);
+}
+
+fn join(a: IrContent, b: IrContent) -> IrContent {
+    match (a, b) {
+        (a, b) if b.is_empty() => a,
+        (a, b) if a.is_empty() => b,
+        (IrContent::Text(a), IrContent::Text(b)) => IrContent::Text(format!("{}\n\n{}", a, b)),
+        (a, b) => IrContent::Parts([parts(a), parts(b)].concat()),
+    }
+}
+
+fn parts(content: IrContent) -> Vec<IrContentPart> {
+    match content {
+        IrContent::Text(text) => vec![IrContentPart::Text { text }],
+        IrContent::Parts(parts) => parts,
+    }
+}
+
+/// Open the conversation with a user turn.
+fn open_with_user(ir: &mut IrChatRequest, notes: &mut Vec<String>) {
+    if ir.messages.first().is_some_and(|m| m.role != IrRole::User) {
+        ir.messages.insert(
+            0,
+            IrMessage {
+                role: IrRole::User,
+                content: IrContent::Text(PLACEHOLDER_TURN.into()),
+                tool_calls: None,
+                tool_call_id: None,
+                name: None,
+            },
+        );
+        notes.push("inserted a user turn before the first message".into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modality::chat::ir::IrToolCall;
+
+    fn message(role: IrRole, text: &str) -> IrMessage {
+        IrMessage { role, content: IrContent::Text(text.into()), tool_calls: None, tool_call_id: None, name: None }
+    }
+
+    fn request(messages: Vec<IrMessage>) -> IrChatRequest {
+        IrChatRequest {
+            model: "m".into(),
+            messages,
+            system: Some("Be brief.".into()),
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            stream: false,
+            stop: None,
+            tools: None,
+            tool_choice: None,
+            extra: None,
+        }
+    }
+
+    fn roles(ir: &IrChatRequest) -> Vec<IrRole> {
+        ir.messages.iter().map(|m| m.role.clone()).collect()
+    }
+
+    #[test]
+    fn leaves_valid_conversations_alone() {
+        let mut ir = request(vec![message(IrRole::User, "Hi"), message(IrRole::Assistant, "Hello")]);
+        assert!(normalize(&mut ir).is_empty());
+        assert_eq!(ir.messages.len(), 2);
+    }
+
+    #[test]
+    fn fixes_roles_system_and_empty_content() {
+        let mut ir = request(vec![
+            message(IrRole::Assistant, "Earlier answer"),
+            message(IrRole::User, "One"),
+            message(IrRole::System, "Use metric units."),
+            message(IrRole::User, ""),
+            message(IrRole::User, "Two"),
+        ]);
+        let notes = normalize(&mut ir);
+
+        assert_eq!(ir.system.as_deref(), Some("Be brief.\n\nUse metric units."));
+        assert_eq!(roles(&ir), [IrRole::User, IrRole::Assistant, IrRole::User]);
+        assert_eq!(ir.messages[0].content.to_text(), PLACEHOLDER_TURN);
+        assert_eq!(ir.messages[2].content.to_text(), "One\n\nTwo");
+        assert_eq!(notes.len(), 4);
+    }
+
+    #[test]
+    fn pairs_tool_results_with_their_calls() {
+        let mut call = message(IrRole::Assistant, "");
+        call.tool_calls = Some(vec![
+            IrToolCall { id: "a".into(), name: "lookup".into(), arguments: "{}".into() },
+            IrToolCall { id: "b".into(), name: "fetch".into(), arguments: "{}".into() },
+        ]);
+        let mut result_a = message(IrRole::Tool, "42");
+        result_a.tool_call_id = Some("a".into());
+        let mut stray = message(IrRole::Tool, "stale");
+        stray.tool_call_id = Some("zz".into());
+        let mut ir = request(vec![
+            message(IrRole::User, "Look it up"),
+            call,
+            message(IrRole::User, "Well?"),
+            result_a,
+            stray,
+        ]);
+        let notes = normalize(&mut ir);
+
+        assert_eq!(roles(&ir), [IrRole::User, IrRole::Assistant, IrRole::Tool, IrRole::Tool, IrRole::User]);
+        assert_eq!(ir.messages[2].tool_call_id.as_deref(), Some("a"));
+        assert_eq!(ir.messages[3].content.to_text(), PLACEHOLDER_RESULT);
+        assert_eq!(ir.messages[3].name.as_deref(), Some("fetch"));
+        assert_eq!(ir.messages[4].content.to_text(), "Well?\n\nTool result: stale");
+        assert_eq!(
+            notes,
+            [
+                "moved tool results after their calls: 1",
+                "inserted placeholder tool results: 1",
+                "turned tool results without a call into user text: 1",
+                "merged adjacent messages of the same role: 1",
+            ]
+        );
+    }
+}
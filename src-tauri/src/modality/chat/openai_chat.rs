@@ -1,5 +1,9 @@
-use super::helpers::{from_json, from_json_str, to_json, to_json_str};
+use super::helpers::{
+    audio_format, audio_media_type, data_url, error_message, from_json, from_json_str, parse_data_url,
+    stream_error_value, to_json, to_json_str, unsupported_part,
+};
 use super::ir::*;
+use super::sse::SseEvent;
 use super::{Decoder, Encoder};
 use crate::error::AppError;
 use serde::{Deserialize, Serialize};
@@ -194,6 +198,25 @@ fn oai_content_to_ir(content: &Option<serde_json::Value>) -> IrContent {
                                 data: None,
                             })
                         }
+                        "file" => {
+                            let file = p.get("file")?;
+                            let field = |key: &str| file.get(key).and_then(|v| v.as_str());
+                            let inline = field("file_data").and_then(parse_data_url);
+                            Some(IrContentPart::Document {
+                                url: None,
+                                media_type: inline.map(|(media_type, _)| media_type.to_string()),
+                                data: inline.map(|(_, data)| data.to_string()),
+                                file_id: field("file_id").map(String::from),
+                                filename: field("filename").map(String::from),
+                            })
+                        }
+                        "input_audio" => {
+                            let audio = p.get("input_audio")?;
+                            Some(IrContentPart::Audio {
+                                data: audio.get("data")?.as_str()?.to_string(),
+                                media_type: audio_media_type(audio.get("format")?.as_str()?),
+                            })
+                        }
                         _ => None,
                     }
                 })
@@ -205,24 +228,57 @@ fn oai_content_to_ir(content: &Option<serde_json::Value>) -> IrContent {
     }
 }
 
-fn ir_content_to_oai(content: &IrContent) -> serde_json::Value {
+fn ir_content_to_oai(content: &IrContent) -> Result<serde_json::Value, AppError> {
     match content {
-        IrContent::Text(s) => serde_json::Value::String(s.clone()),
+        IrContent::Text(s) => Ok(serde_json::Value::String(s.clone())),
         IrContent::Parts(parts) => {
-            let oai_parts: Vec<serde_json::Value> = parts
+            let oai_parts = parts
                 .iter()
                 .map(|p| match p {
-                    IrContentPart::Text { text } => serde_json::json!({
+                    IrContentPart::Text { text } => Ok(serde_json::json!({
                         "type": "text",
                         "text": text,
-                    }),
-                    IrContentPart::Image { url, .. } => serde_json::json!({
-                        "type": "image_url",
-                        "image_url": { "url": url },
-                    }),
+                    })),
+                    IrContentPart::Image { url, media_type, data } => {
+                        // Inline images go as data URLs.
+                        let url = match (url, data) {
+                            (Some(url), _) => url.clone(),
+                            (None, Some(data)) => {
+                                data_url(media_type.as_deref().unwrap_or("image/png"), data)
+                            }
+                            (None, None) => String::new(),
+                        };
+                        Ok(serde_json::json!({
+                            "type": "image_url",
+                            "image_url": { "url": url },
+                        }))
+                    }
+                    IrContentPart::Document { url, media_type, data, file_id, filename } => {
+                        let file = match (file_id, data) {
+                            (Some(file_id), _) => serde_json::json!({ "file_id": file_id }),
+                            (None, Some(data)) => serde_json::json!({
+                                "file_data": data_url(media_type.as_deref().unwrap_or("application/pdf"), data),
+                                "filename": filename.as_deref().unwrap_or("document"),
+                            }),
+                            (None, None) if url.is_some() => {
+                                return Err(unsupported_part("OpenAI Chat", "documents by URL"));
+                            }
+                            (None, None) => return Err(unsupported_part("OpenAI Chat", "documents without data")),
+                        };
+                        Ok(serde_json::json!({ "type": "file", "file": file }))
+                    }
+                    IrContentPart::Audio { data, media_type } => {
+                        let format = audio_format(media_type).ok_or_else(|| {
+                            unsupported_part("OpenAI Chat", &format!("{} audio, only WAV and MP3", media_type))
+                        })?;
+                        Ok(serde_json::json!({
+                            "type": "input_audio",
+                            "input_audio": { "data": data, "format": format },
+                        }))
+                    }
                 })
-                .collect();
-            serde_json::Value::Array(oai_parts)
+                .collect::<Result<Vec<_>, AppError>>()?;
+            Ok(serde_json::Value::Array(oai_parts))
         }
     }
 }
@@ -253,12 +309,13 @@ impl Decoder for OpenAiChatCodec {
         let req: OaiRequest =
             from_json(body)?;
 
-        // Extract system message from messages list
+        // The first system message becomes the system prompt; later ones stay
+        // in place for the upstream, or normalization, to handle.
         let mut system = None;
         let mut messages = Vec::new();
 
         for msg in &req.messages {
-            if msg.role == "system" {
+            if msg.role == "system" && system.is_none() {
                 system = Some(oai_content_to_ir(&msg.content).to_text());
             } else {
                 let mut ir_msg = IrMessage {
@@ -441,6 +498,71 @@ impl Decoder for OpenAiChatCodec {
     }
 }
 
+// --- Errors ---
+
+/// Decode an OpenAI error body, `{"error": {"message", "type", "param", "code"}}`,
+/// which most OpenAI-compatible providers also use.
+pub fn decode_error(status: u16, body: &[u8]) -> IrError {
+    serde_json::from_slice::<serde_json::Value>(body)
+        .ok()
+        .and_then(|value| error_from_value(Some(status), &value["error"]))
+        .unwrap_or_else(|| IrError::new(status, error_message(status, body)))
+}
+
+/// Decode a `data: {"error": {...}}` line sent mid-stream.
+pub fn decode_stream_error(data: &str) -> Option<IrError> {
+    error_from_value(None, &stream_error_value(data)?["error"])
+}
+
+fn error_from_value(status: Option<u16>, error: &serde_json::Value) -> Option<IrError> {
+    // Some compatible servers send `{"error": "message"}`.
+    if let Some(message) = error.as_str() {
+        let status = status.unwrap_or(500);
+        return Some(IrError::new(status, message));
+    }
+    let message = error.get("message")?.as_str()?;
+    let kind = match error["type"].as_str() {
+        Some("invalid_request_error") => Some(IrErrorKind::InvalidRequest),
+        Some("authentication_error") => Some(IrErrorKind::Authentication),
+        Some("permission_error") => Some(IrErrorKind::PermissionDenied),
+        Some("not_found_error") => Some(IrErrorKind::NotFound),
+        Some("rate_limit_error") | Some("insufficient_quota") => Some(IrErrorKind::RateLimit),
+        Some("server_error") => Some(IrErrorKind::Server),
+        _ => None,
+    };
+    let (status, kind) = match (status, kind) {
+        (Some(status), kind) => (status, kind.unwrap_or_else(|| IrErrorKind::from_status(status))),
+        (None, Some(kind)) => (kind.status(), kind),
+        (None, None) => (500, IrErrorKind::Server),
+    };
+    let code = match &error["code"] {
+        serde_json::Value::String(code) => Some(code.clone()),
+        serde_json::Value::Number(code) => Some(code.to_string()),
+        _ => None,
+    };
+    Some(IrError { status, kind, code, message: message.to_string() })
+}
+
+/// Encode an OpenAI error body; also used by the Responses API.
+pub fn encode_error(error: &IrError) -> serde_json::Value {
+    let error_type = match error.kind {
+        IrErrorKind::InvalidRequest | IrErrorKind::RequestTooLarge => "invalid_request_error",
+        IrErrorKind::Authentication => "authentication_error",
+        IrErrorKind::PermissionDenied => "permission_error",
+        IrErrorKind::NotFound => "not_found_error",
+        IrErrorKind::RateLimit => "rate_limit_error",
+        IrErrorKind::Overloaded | IrErrorKind::Timeout | IrErrorKind::Server => "server_error",
+    };
+    serde_json::json!({
+        "error": {
+            "message": error.message,
+            "type": error_type,
+            "param": null,
+            "code": error.code,
+        }
+    })
+}
+
 // --- Encoder impl ---
 
 impl Encoder for OpenAiChatCodec {
@@ -461,7 +583,7 @@ impl Encoder for OpenAiChatCodec {
         for msg in &ir.messages {
             let mut oai_msg = OaiMessage {
                 role: ir_role_to_oai(&msg.role).to_string(),
-                content: Some(ir_content_to_oai(&msg.content)),
+                content: Some(ir_content_to_oai(&msg.content)?),
                 tool_calls: None,
                 tool_call_id: msg.tool_call_id.clone(),
                 name: msg.name.clone(),
@@ -532,7 +654,7 @@ impl Encoder for OpenAiChatCodec {
     fn encode_response(&self, ir: &IrChatResponse) -> Result<Vec<u8>, AppError> {
         let mut oai_msg = OaiMessage {
             role: ir_role_to_oai(&ir.message.role).to_string(),
-            content: Some(ir_content_to_oai(&ir.message.content)),
+            content: Some(ir_content_to_oai(&ir.message.content)?),
             tool_calls: None,
             tool_call_id: None,
             name: None,
@@ -574,7 +696,7 @@ impl Encoder for OpenAiChatCodec {
         to_json(&resp)
     }
 
-    fn encode_stream_chunk(&mut self, chunk: &IrStreamChunk) -> Result<Option<String>, AppError> {
+    fn encode_stream_chunk(&mut self, chunk: &IrStreamChunk) -> Result<Vec<SseEvent>, AppError> {
         let delta_tool_calls = chunk.delta_tool_calls.as_ref().map(|tcs| {
             tcs.iter()
                 .map(|tc| OaiStreamToolCall {
@@ -615,10 +737,56 @@ impl Encoder for OpenAiChatCodec {
 
         let json = to_json_str(&oai_chunk)?;
 
-        Ok(Some(json))
+        Ok(vec![SseEvent::data(json)])
     }
 
-    fn stream_done_signal(&mut self) -> Option<String> {
-        Some("[DONE]".to_string())
+    fn stream_done_signal(&mut self) -> Vec<SseEvent> {
+        vec![SseEvent::data("[DONE]")]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn documents_and_audio_roundtrip() {
+        let body = serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{
+                "role": "user",
+                "content": [
+                    {"type": "file", "file": {"filename": "q3.pdf", "file_data": "data:application/pdf;base64,JVBERi0="}},
+                    {"type": "input_audio", "input_audio": {"data": "UklGRg==", "format": "wav"}}
+                ]
+            }]
+        });
+        let ir = OpenAiChatCodec.decode_request(&serde_json::to_vec(&body).unwrap()).unwrap();
+        let IrContent::Parts(parts) = &ir.messages[0].content else { panic!("expected parts") };
+        assert!(matches!(
+            &parts[0],
+            IrContentPart::Document { media_type: Some(media_type), data: Some(data), filename: Some(filename), .. }
+                if media_type == "application/pdf" && data == "JVBERi0=" && filename == "q3.pdf"
+        ));
+        assert!(matches!(&parts[1], IrContentPart::Audio { media_type, .. } if media_type == "audio/wav"));
+
+        let encoded: serde_json::Value =
+            serde_json::from_slice(&OpenAiChatCodec.encode_request(&ir, "gpt-4o").unwrap()).unwrap();
+        assert_eq!(encoded["messages"][0]["content"], body["messages"][0]["content"]);
+    }
+
+    #[test]
+    fn rejects_parts_chat_cannot_carry() {
+        let request = |part: serde_json::Value| -> IrChatRequest {
+            serde_json::from_value(serde_json::json!({
+                "model": "gpt-4o", "stream": false,
+                "messages": [{"role": "user", "content": [part]}]
+            }))
+            .unwrap()
+        };
+        let linked = request(serde_json::json!({"type": "document", "url": "https://example.com/q3.pdf"}));
+        assert!(OpenAiChatCodec.encode_request(&linked, "gpt-4o").is_err());
+        let ogg = request(serde_json::json!({"type": "audio", "data": "T2dnUw==", "media_type": "audio/ogg"}));
+        assert!(OpenAiChatCodec.encode_request(&ogg, "gpt-4o").is_err());
     }
 }
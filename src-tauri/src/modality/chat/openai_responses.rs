@@ -1,5 +1,8 @@
-use super::helpers::{from_json, from_json_str, to_json, to_json_str};
+use super::helpers::{
+    data_url, from_json, from_json_str, parse_data_url, stream_error_value, to_json, to_json_str, unsupported_part,
+};
 use super::ir::*;
+use super::sse::SseEvent;
 use super::{Decoder, Encoder};
 use crate::error::AppError;
 use serde::{Deserialize, Serialize};
@@ -202,6 +205,17 @@ fn resp_content_to_ir(value: &serde_json::Value) -> IrContent {
                                 data,
                             })
                         }
+                        "input_file" => {
+                            let field = |key: &str| p.get(key).and_then(|v| v.as_str());
+                            let inline = field("file_data").and_then(parse_data_url);
+                            Some(IrContentPart::Document {
+                                url: field("file_url").map(String::from),
+                                media_type: inline.map(|(media_type, _)| media_type.to_string()),
+                                data: inline.map(|(_, data)| data.to_string()),
+                                file_id: field("file_id").map(String::from),
+                                filename: field("filename").map(String::from),
+                            })
+                        }
                         _ => None,
                     }
                 })
@@ -213,33 +227,45 @@ fn resp_content_to_ir(value: &serde_json::Value) -> IrContent {
     }
 }
 
-fn ir_content_to_resp_input(content: &IrContent) -> serde_json::Value {
+fn ir_content_to_resp_input(content: &IrContent) -> Result<serde_json::Value, AppError> {
     match content {
-        IrContent::Text(s) => serde_json::Value::String(s.clone()),
+        IrContent::Text(s) => Ok(serde_json::Value::String(s.clone())),
         IrContent::Parts(parts) => {
-            let resp_parts: Vec<serde_json::Value> = parts
+            let resp_parts = parts
                 .iter()
                 .map(|p| match p {
-                    IrContentPart::Text { text } => serde_json::json!({
+                    IrContentPart::Text { text } => Ok(serde_json::json!({
                         "type": "input_text",
                         "text": text,
-                    }),
+                    })),
                     IrContentPart::Image { url, media_type, data } => {
+                        // Inline images go as data URLs.
                         let mut obj = serde_json::json!({"type": "input_image"});
                         if let Some(u) = url {
                             obj["image_url"] = serde_json::Value::String(u.clone());
+                        } else if let Some(d) = data {
+                            let media_type = media_type.as_deref().unwrap_or("image/png");
+                            obj["image_url"] = serde_json::Value::String(data_url(media_type, d));
                         }
-                        if let Some(d) = data {
-                            obj["data"] = serde_json::Value::String(d.clone());
-                        }
-                        if let Some(m) = media_type {
-                            obj["media_type"] = serde_json::Value::String(m.clone());
+                        Ok(obj)
+                    }
+                    IrContentPart::Document { url, media_type, data, file_id, filename } => {
+                        let mut obj = serde_json::json!({"type": "input_file"});
+                        if let Some(id) = file_id {
+                            obj["file_id"] = serde_json::Value::String(id.clone());
+                        } else if let Some(d) = data {
+                            let media_type = media_type.as_deref().unwrap_or("application/pdf");
+                            obj["file_data"] = serde_json::Value::String(data_url(media_type, d));
+                            obj["filename"] = serde_json::Value::String(filename.clone().unwrap_or_else(|| "document".into()));
+                        } else if let Some(u) = url {
+                            obj["file_url"] = serde_json::Value::String(u.clone());
                         }
-                        obj
+                        Ok(obj)
                     }
+                    IrContentPart::Audio { .. } => Err(unsupported_part("Responses", "audio")),
                 })
-                .collect();
-            serde_json::Value::Array(resp_parts)
+                .collect::<Result<Vec<_>, AppError>>()?;
+            Ok(serde_json::Value::Array(resp_parts))
         }
     }
 }
@@ -638,6 +664,30 @@ impl Decoder for OpenAiResponsesCodec {
         }
         false
     }
+
+    /// Error bodies are OpenAI's; mid-stream the Responses API sends an
+    /// `error` event or a `response.failed` event carrying the error.
+    fn decode_stream_error(&self, data: &str) -> Option<IrError> {
+        let value = stream_error_value(data)?;
+        let error = match value["type"].as_str()? {
+            "error" => &value,
+            "response.failed" => &value["response"]["error"],
+            _ => return None,
+        };
+        let message = error["message"].as_str()?;
+        let code = error["code"].as_str();
+        let kind = match code {
+            Some("rate_limit_exceeded") => IrErrorKind::RateLimit,
+            Some("server_error") | None => IrErrorKind::Server,
+            Some(_) => IrErrorKind::InvalidRequest,
+        };
+        Some(IrError {
+            status: kind.status(),
+            kind,
+            code: code.map(str::to_string),
+            message: message.to_string(),
+        })
+    }
 }
 
 /// Extract a response id from the event.
@@ -673,7 +723,7 @@ impl OpenAiResponsesCodec {
                     // encode it as a regular message item (the API accepts it).
                     items.push(OaiRespApiInputItem::Message {
                         role: "user".to_string(),
-                        content: ir_content_to_resp_input(&msg.content),
+                        content: ir_content_to_resp_input(&msg.content)?,
                     });
                 }
                 IrRole::User | IrRole::Assistant => {
@@ -691,7 +741,7 @@ impl OpenAiResponsesCodec {
                     } else {
                         items.push(OaiRespApiInputItem::Message {
                             role: ir_role_to_resp(&msg.role).to_string(),
-                            content: ir_content_to_resp_input(&msg.content),
+                            content: ir_content_to_resp_input(&msg.content)?,
                         });
                     }
                 }
@@ -843,8 +893,8 @@ impl Encoder for OpenAiResponsesEncoder {
         OpenAiResponsesCodec::encode_response_inner(ir)
     }
 
-    fn encode_stream_chunk(&mut self, chunk: &IrStreamChunk) -> Result<Option<String>, AppError> {
-        let mut events: Vec<String> = Vec::new();
+    fn encode_stream_chunk(&mut self, chunk: &IrStreamChunk) -> Result<Vec<SseEvent>, AppError> {
+        let mut events: Vec<SseEvent> = Vec::new();
 
         // Persist id and model from every chunk that carries them.
         if !chunk.id.is_empty() {
@@ -887,7 +937,7 @@ impl Encoder for OpenAiResponsesEncoder {
                 arguments: None,
                 sequence_number: None,
             };
-            events.push(to_json_str(&created)?);
+            events.push(stream_event(&created)?);
 
             let item_added = OaiRespApiStreamEvent {
                 event_type: "response.output_item.added".to_string(),
@@ -905,7 +955,7 @@ impl Encoder for OpenAiResponsesEncoder {
                 arguments: None,
                 sequence_number: None,
             };
-            events.push(to_json_str(&item_added)?);
+            events.push(stream_event(&item_added)?);
 
             let part_added = OaiRespApiStreamEvent {
                 event_type: "response.content_part.added".to_string(),
@@ -922,7 +972,7 @@ impl Encoder for OpenAiResponsesEncoder {
                 arguments: None,
                 sequence_number: None,
             };
-            events.push(to_json_str(&part_added)?);
+            events.push(stream_event(&part_added)?);
         }
 
         // Text delta.
@@ -941,7 +991,7 @@ impl Encoder for OpenAiResponsesEncoder {
                 arguments: None,
                 sequence_number: None,
             };
-            events.push(to_json_str(&text_delta)?);
+            events.push(stream_event(&text_delta)?);
         }
 
         // Tool call deltas.
@@ -969,7 +1019,7 @@ impl Encoder for OpenAiResponsesEncoder {
                         arguments: None,
                         sequence_number: None,
                     };
-                    events.push(to_json_str(&item_added)?);
+                    events.push(stream_event(&item_added)?);
                 }
 
                 // Argument delta.
@@ -986,7 +1036,7 @@ impl Encoder for OpenAiResponsesEncoder {
                         arguments: None,
                         sequence_number: None,
                     };
-                    events.push(to_json_str(&args_delta)?);
+                    events.push(stream_event(&args_delta)?);
                 }
             }
         }
@@ -995,14 +1045,10 @@ impl Encoder for OpenAiResponsesEncoder {
         // response.completed is intentionally NOT emitted here — it is only
         // emitted from stream_done_signal(), after the upstream [DONE] is received.
 
-        if events.is_empty() {
-            Ok(None)
-        } else {
-            Ok(Some(events.join("\n")))
-        }
+        Ok(events)
     }
 
-    fn stream_done_signal(&mut self) -> Option<String> {
+    fn stream_done_signal(&mut self) -> Vec<SseEvent> {
         // Emit the sequence of closing events required by the Responses API spec:
         //   response.output_text.done
         //   response.content_part.done
@@ -1016,7 +1062,7 @@ impl Encoder for OpenAiResponsesEncoder {
         // the connection upon seeing response.completed, dropping the generator
         // before the log can be persisted.
 
-        let mut events: Vec<String> = Vec::new();
+        let mut events: Vec<SseEvent> = Vec::new();
 
         let has_text = !self.accumulated_text.is_empty();
         let has_tool_calls = !self.tool_call_output_indices.is_empty();
@@ -1034,7 +1080,7 @@ impl Encoder for OpenAiResponsesEncoder {
                 arguments: None,
                 sequence_number: None,
             };
-            if let Ok(s) = serde_json::to_string(&text_done) { events.push(s); }
+            if let Ok(event) = stream_event(&text_done) { events.push(event); }
 
             let part_done = OaiRespApiStreamEvent {
                 event_type: "response.content_part.done".to_string(),
@@ -1051,7 +1097,7 @@ impl Encoder for OpenAiResponsesEncoder {
                 arguments: None,
                 sequence_number: None,
             };
-            if let Ok(s) = serde_json::to_string(&part_done) { events.push(s); }
+            if let Ok(event) = stream_event(&part_done) { events.push(event); }
 
             let item_done = OaiRespApiStreamEvent {
                 event_type: "response.output_item.done".to_string(),
@@ -1072,7 +1118,7 @@ impl Encoder for OpenAiResponsesEncoder {
                 arguments: None,
                 sequence_number: None,
             };
-            if let Ok(s) = serde_json::to_string(&item_done) { events.push(s); }
+            if let Ok(event) = stream_event(&item_done) { events.push(event); }
         }
 
         // Tool call output_item.done events.
@@ -1095,7 +1141,7 @@ impl Encoder for OpenAiResponsesEncoder {
                     arguments: None,
                     sequence_number: None,
                 };
-                if let Ok(s) = serde_json::to_string(&item_done) { events.push(s); }
+                if let Ok(event) = stream_event(&item_done) { events.push(event); }
             }
         }
 
@@ -1127,16 +1173,35 @@ impl Encoder for OpenAiResponsesEncoder {
             arguments: None,
             sequence_number: None,
         };
-        if let Ok(s) = serde_json::to_string(&completed) { events.push(s); }
+        if let Ok(event) = stream_event(&completed) { events.push(event); }
 
-        if events.is_empty() {
-            None
-        } else {
-            Some(events.join("\n"))
-        }
+        events
+    }
+
+    fn stream_error(&self, error: &IrError) -> Vec<SseEvent> {
+        let code = error.code.clone().unwrap_or_else(|| {
+            match error.kind {
+                IrErrorKind::RateLimit => "rate_limit_exceeded",
+                IrErrorKind::InvalidRequest | IrErrorKind::RequestTooLarge => "invalid_request_error",
+                _ => "server_error",
+            }
+            .to_string()
+        });
+        let body = serde_json::json!({
+            "type": "error",
+            "code": code,
+            "message": error.message,
+            "param": null,
+        });
+        vec![SseEvent::named("error", body.to_string())]
     }
 }
 
+/// Responses API stream events are named after their `type`.
+fn stream_event(event: &OaiRespApiStreamEvent) -> Result<SseEvent, AppError> {
+    Ok(SseEvent::named(event.event_type.clone(), to_json_str(event)?))
+}
+
 // =============================================================================
 // Tests
 // =============================================================================
@@ -1341,7 +1406,7 @@ mod tests {
             tool_choice: None,
             extra: None,
         };
-        let mut codec = OpenAiResponsesEncoder::new();
+        let codec = OpenAiResponsesEncoder::new();
         let bytes = codec.encode_request(&ir, "gpt-4o-mini").unwrap();
         let req: OaiRespApiRequest = serde_json::from_slice(&bytes).unwrap();
 
@@ -1399,7 +1464,7 @@ mod tests {
             tool_choice: None,
             extra: None,
         };
-        let mut codec = OpenAiResponsesEncoder::new();
+        let codec = OpenAiResponsesEncoder::new();
         let bytes = codec.encode_request(&ir, "gpt-4o").unwrap();
         let req: OaiRespApiRequest = serde_json::from_slice(&bytes).unwrap();
 
@@ -1447,9 +1512,8 @@ mod tests {
         if let OaiRespApiOutputItem::Message { content, role, .. } = &resp.output[0] {
             assert_eq!(role, "assistant");
             assert_eq!(content.len(), 1);
-            if let OaiRespApiContentPart::OutputText { text, .. } = &content[0] {
-                assert_eq!(text, "Hello there!");
-            }
+            let OaiRespApiContentPart::OutputText { text, .. } = &content[0];
+            assert_eq!(text, "Hello there!");
         } else {
             panic!("Expected Message output");
         }
@@ -1568,6 +1632,48 @@ mod tests {
         assert!(!codec.is_stream_done("{\"type\":\"response.output_text.delta\",\"delta\":\"hi\"}"));
     }
 
+    #[test]
+    fn decode_stream_error_events() {
+        let codec = OpenAiResponsesCodec;
+        let failed = r#"{"type":"response.failed","response":{"id":"resp_1","error":{"code":"rate_limit_exceeded","message":"slow down"}}}"#;
+        let error = codec.decode_stream_error(failed).unwrap();
+        assert_eq!((error.status, error.kind), (429, IrErrorKind::RateLimit));
+        assert_eq!(error.message, "slow down");
+        assert!(codec.decode_stream_error(r#"{"type":"response.output_text.delta","delta":"error"}"#).is_none());
+    }
+
+    #[test]
+    fn stream_errors_are_error_events() {
+        let mut error = IrError::new(529, "Overloaded");
+        error.kind = IrErrorKind::Overloaded;
+        error.code = Some("overloaded_error".into());
+        let events = OpenAiResponsesEncoder::new().stream_error(&error);
+        assert_eq!(events[0].event.as_deref(), Some("error"));
+        let event: serde_json::Value = serde_json::from_str(&events[0].data).unwrap();
+        assert_eq!((event["code"].as_str(), event["message"].as_str()), (Some("overloaded_error"), Some("Overloaded")));
+    }
+
+    #[test]
+    fn decode_input_file_as_document() {
+        let body = serde_json::json!({
+            "model": "gpt-4o",
+            "input": [{
+                "role": "user",
+                "content": [
+                    {"type": "input_text", "text": "Summarize"},
+                    {"type": "input_file", "filename": "q3.pdf", "file_data": "data:application/pdf;base64,JVBERi0="}
+                ]
+            }]
+        });
+        let ir = OpenAiResponsesCodec.decode_request(&serde_json::to_vec(&body).unwrap()).unwrap();
+        let IrContent::Parts(parts) = &ir.messages[0].content else { panic!("expected parts") };
+        assert!(matches!(
+            &parts[1],
+            IrContentPart::Document { media_type: Some(media_type), data: Some(data), filename: Some(filename), .. }
+                if media_type == "application/pdf" && data == "JVBERi0=" && filename == "q3.pdf"
+        ));
+    }
+
     #[test]
     fn encode_stream_chunk_text_delta() {
         let chunk = IrStreamChunk {
@@ -1580,9 +1686,11 @@ mod tests {
             usage: None,
         };
         let mut enc = OpenAiResponsesEncoder::new();
-        let result = enc.encode_stream_chunk(&chunk).unwrap().unwrap();
+        let result = enc.encode_stream_chunk(&chunk).unwrap();
 
-        let event: OaiRespApiStreamEvent = serde_json::from_str(&result).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].event.as_deref(), Some("response.output_text.delta"));
+        let event: OaiRespApiStreamEvent = serde_json::from_str(&result[0].data).unwrap();
         assert_eq!(event.event_type, "response.output_text.delta");
         assert_eq!(event.delta, Some("world".to_string()));
     }
@@ -1599,13 +1707,11 @@ mod tests {
             usage: None,
         };
         let mut enc = OpenAiResponsesEncoder::new();
-        let result = enc.encode_stream_chunk(&chunk).unwrap().unwrap();
+        let result = enc.encode_stream_chunk(&chunk).unwrap();
 
-        // Should contain multiple events separated by newlines.
-        let lines: Vec<&str> = result.split('\n').collect();
-        assert!(lines.len() >= 3); // created, output_item.added, content_part.added
+        assert!(result.len() >= 3); // created, output_item.added, content_part.added
 
-        let first: OaiRespApiStreamEvent = serde_json::from_str(lines[0]).unwrap();
+        let first: OaiRespApiStreamEvent = serde_json::from_str(&result[0].data).unwrap();
         assert_eq!(first.event_type, "response.created");
     }
 
@@ -1616,11 +1722,11 @@ mod tests {
         let mut enc = OpenAiResponsesEncoder::new();
         enc.response_id = "resp_001".to_string();
         enc.model = "gpt-4o".to_string();
-        let signal = enc.stream_done_signal().unwrap();
-        // The signal may contain multiple events joined by '\n'.
-        // The last event must be response.completed.
-        let last_line = signal.split('\n').last().unwrap();
-        let event: OaiRespApiStreamEvent = serde_json::from_str(last_line).unwrap();
+        let signal = enc.stream_done_signal();
+        // The signal may contain several events; the last must be response.completed.
+        let last = signal.last().unwrap();
+        assert_eq!(last.event.as_deref(), Some("response.completed"));
+        let event: OaiRespApiStreamEvent = serde_json::from_str(&last.data).unwrap();
         assert_eq!(event.event_type, "response.completed");
     }
 
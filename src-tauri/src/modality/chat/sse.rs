@@ -0,0 +1,187 @@
+//! Server-sent events framing for chat streams.
+//!
+//! [`SseParser`] reads an upstream `text/event-stream` body as described in
+//! the WHATWG HTML spec: lines may end in CRLF, LF or CR, `data:` fields of
+//! one event are joined with newlines, `event:` names the event, and lines
+//! starting with `:` are comments. [`SseEvent::to_wire`] writes events back
+//! out for the client.
+
+/// One server-sent event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SseEvent {
+    /// The `event:` name; `None` for the default `message` event.
+    pub event: Option<String>,
+    pub data: String,
+}
+
+impl SseEvent {
+    /// An unnamed event, as used by OpenAI Chat and Gemini streams.
+    pub fn data(data: impl Into<String>) -> Self {
+        Self { event: None, data: data.into() }
+    }
+
+    /// A named event, as used by Anthropic and Responses API streams.
+    pub fn named(event: impl Into<String>, data: impl Into<String>) -> Self {
+        Self { event: Some(event.into()), data: data.into() }
+    }
+
+    /// Serialize the event, one `data:` line per line of data, followed by
+    /// the blank line that ends it.
+    pub fn to_wire(&self) -> String {
+        let mut out = String::with_capacity(self.data.len() + 16);
+        if let Some(event) = &self.event {
+            out.push_str("event: ");
+            out.push_str(event);
+            out.push('\n');
+        }
+        for line in self.data.split('\n') {
+            out.push_str("data: ");
+            out.push_str(line.strip_suffix('\r').unwrap_or(line));
+            out.push('\n');
+        }
+        out.push('\n');
+        out
+    }
+}
+
+/// An SSE comment line. Clients ignore it, which makes it a keep-alive
+/// for formats without a ping event.
+pub fn comment(text: &str) -> String {
+    format!(": {}\n\n", text)
+}
+
+/// Incremental parser for an event stream arriving in arbitrary chunks.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    /// Bytes of the current, unterminated line. Kept as bytes so a UTF-8
+    /// sequence split across chunks is decoded whole.
+    line: Vec<u8>,
+    /// The previous chunk ended in CR, so a leading LF completes that
+    /// line ending rather than ending an empty line.
+    after_cr: bool,
+    started: bool,
+    event: Option<String>,
+    data: Option<String>,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a chunk of the stream, returning the events it completes.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+        let mut bytes = chunk;
+        if !self.started && !bytes.is_empty() {
+            self.started = true;
+            bytes = bytes.strip_prefix("\u{feff}".as_bytes()).unwrap_or(bytes);
+        }
+        for &b in bytes {
+            if std::mem::take(&mut self.after_cr) && b == b'\n' {
+                continue;
+            }
+            match b {
+                b'\r' | b'\n' => {
+                    self.after_cr = b == b'\r';
+                    let line = std::mem::take(&mut self.line);
+                    if let Some(event) = self.process_line(&String::from_utf8_lossy(&line)) {
+                        events.push(event);
+                    }
+                }
+                _ => self.line.push(b),
+            }
+        }
+        events
+    }
+
+    /// End of stream. An event missing its closing blank line is still
+    /// returned, since some upstreams omit it on the last event.
+    pub fn finish(&mut self) -> Option<SseEvent> {
+        let line = std::mem::take(&mut self.line);
+        if !line.is_empty() {
+            self.process_line(&String::from_utf8_lossy(&line));
+        }
+        self.dispatch()
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => {
+                let data = self.data.get_or_insert_with(String::new);
+                data.push_str(value);
+                data.push('\n');
+            }
+            // `id` and `retry` only matter to browsers reconnecting.
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = self.event.take();
+        let mut data = self.data.take()?;
+        data.pop();
+        Some(SseEvent { event: event.filter(|e| !e.is_empty()), data })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_events_across_chunks_and_line_endings() {
+        let mut parser = SseParser::new();
+        let mut events = parser.push(b"event: message_start\r\ndata: {\"a\":");
+        events.extend(parser.push(b"1}\r"));
+        events.extend(parser.push(b"\n\r\n: keep-alive\n\ndata: line one\ndata:line two\n\n"));
+        events.extend(parser.push(b"data: [DONE]\r\rdata: tail"));
+        events.extend(parser.finish());
+
+        assert_eq!(
+            events,
+            vec![
+                SseEvent::named("message_start", "{\"a\":1}"),
+                SseEvent::data("line one\nline two"),
+                SseEvent::data("[DONE]"),
+                SseEvent::data("tail"),
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_utf8_and_skips_empty_events() {
+        let mut parser = SseParser::new();
+        let text = "data: 你好\n\n".as_bytes();
+        let mut events = parser.push(&text[..8]);
+        events.extend(parser.push(&text[8..]));
+        // An event name without data dispatches nothing.
+        events.extend(parser.push(b"event: ping\n\ndata\n\n"));
+        assert_eq!(events, vec![SseEvent::data("你好"), SseEvent::data("")]);
+    }
+
+    #[test]
+    fn writes_named_and_multiline_events() {
+        assert_eq!(
+            SseEvent::named("message_stop", "{}").to_wire(),
+            "event: message_stop\ndata: {}\n\n"
+        );
+        assert_eq!(SseEvent::data("a\nb").to_wire(), "data: a\ndata: b\n\n");
+
+        let mut parser = SseParser::new();
+        let event = SseEvent::named("x", "one\ntwo");
+        assert_eq!(parser.push(event.to_wire().as_bytes()), vec![event]);
+    }
+}
@@ -0,0 +1,113 @@
+//! Local token estimates, for counting when the upstream can't and for
+//! checking quotas before a request is forwarded.
+//!
+//! Text is tokenized with OpenAI's `o200k_base` BPE. Other providers use
+//! their own tokenizers, so for them the counts are close estimates.
+
+use super::ir::{IrChatRequest, IrContent, IrContentPart, IrMessage};
+
+/// Tokens added per message for its role and delimiters.
+const PER_MESSAGE: u32 = 3;
+/// Tokens priming the assistant's reply.
+const PER_REQUEST: u32 = 3;
+/// A low-detail image; high-detail images are billed by tile and cost more.
+const PER_IMAGE: u32 = 85;
+/// A document, costed as a single PDF page; longer ones cost more.
+const PER_DOCUMENT: u32 = 1500;
+/// Base64 characters of audio per token: about 32 tokens a second of 16 kHz
+/// 16-bit WAV, as Gemini bills it.
+const AUDIO_CHARS_PER_TOKEN: u32 = 1333;
+
+/// Tokens in `text`.
+pub fn count_text(text: &str) -> u32 {
+    if text.is_empty() {
+        return 0;
+    }
+    tiktoken_rs::o200k_base_singleton().encode_ordinary(text).len() as u32
+}
+
+fn count_content(content: &IrContent) -> u32 {
+    match content {
+        IrContent::Text(text) => count_text(text),
+        IrContent::Parts(parts) => parts
+            .iter()
+            .map(|part| match part {
+                IrContentPart::Text { text } => count_text(text),
+                IrContentPart::Image { .. } => PER_IMAGE,
+                IrContentPart::Document { .. } => PER_DOCUMENT,
+                IrContentPart::Audio { data, .. } => (data.len() as u32).div_ceil(AUDIO_CHARS_PER_TOKEN),
+            })
+            .sum(),
+    }
+}
+
+/// Estimated tokens of one message, including its tool calls.
+pub fn estimate_message(message: &IrMessage) -> u32 {
+    let calls: u32 = message
+        .tool_calls
+        .iter()
+        .flatten()
+        .map(|call| count_text(&call.name) + count_text(&call.arguments))
+        .sum();
+    PER_MESSAGE + count_content(&message.content) + calls
+}
+
+/// Estimated input tokens of a request: system prompt, messages, tool calls
+/// and tool definitions.
+pub fn estimate_request(ir: &IrChatRequest) -> u32 {
+    let mut tokens = PER_REQUEST;
+    if let Some(system) = &ir.system {
+        tokens += PER_MESSAGE + count_text(system);
+    }
+    tokens += ir.messages.iter().map(estimate_message).sum::<u32>();
+    for tool in ir.tools.iter().flatten() {
+        tokens += count_text(&tool.name);
+        tokens += tool.description.as_deref().map_or(0, count_text);
+        tokens += count_text(&tool.parameters.to_string());
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modality::chat::ir::IrRole;
+
+    fn message(role: IrRole, content: IrContent) -> IrMessage {
+        IrMessage { role, content, tool_calls: None, tool_call_id: None, name: None }
+    }
+
+    #[test]
+    fn counts_text_with_o200k() {
+        assert_eq!(count_text(""), 0);
+        assert_eq!(count_text("hello world"), 2);
+    }
+
+    #[test]
+    fn estimates_messages_system_and_images() {
+        let ir = IrChatRequest {
+            model: "m".into(),
+            messages: vec![
+                message(IrRole::User, IrContent::Text("hello world".into())),
+                message(
+                    IrRole::User,
+                    IrContent::Parts(vec![
+                        IrContentPart::Text { text: "hello world".into() },
+                        IrContentPart::Image { url: Some("https://x/y.png".into()), media_type: None, data: None },
+                    ]),
+                ),
+            ],
+            system: Some("hello world".into()),
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            stream: false,
+            stop: None,
+            tools: None,
+            tool_choice: None,
+            extra: None,
+        };
+        // Priming, then system and two messages with their overhead.
+        assert_eq!(estimate_request(&ir), 3 + (3 + 2) + (3 + 2) + (3 + 2 + 85));
+    }
+}
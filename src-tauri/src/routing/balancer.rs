@@ -1,6 +1,7 @@
-use crate::db::models::{RouteTarget, RouteTargetKey, RouteTargetOverride};
+use crate::db::models::{RouteTarget, RouteTargetKey, RouteTargetModel, RouteTargetOverride};
 use crate::error::AppError;
 use crate::routing::circuit::CircuitBreaker;
+use crate::secrets::KeyVault;
 use rand::Rng;
 use sqlx::SqlitePool;
 use std::collections::HashMap;
@@ -35,15 +36,32 @@ impl KeyRotationState {
 pub struct SelectedTarget {
     pub target: RouteTarget,
     pub api_key: String,
+    /// Id of the key in `api_key`; `None` when the target has no keys.
+    pub key_id: Option<String>,
     pub overrides: Vec<RouteTargetOverride>,
+    pub models: Vec<RouteTargetModel>,
+}
+
+impl SelectedTarget {
+    /// The target's mapping for `model`, if any.
+    pub fn mapping(&self, model: &str) -> Option<&RouteTargetModel> {
+        self.models.iter().find(|m| m.model == model)
+    }
+
+    /// The name to send upstream for `model`, when the target maps it.
+    pub fn upstream_model(&self, model: &str) -> Option<&str> {
+        self.mapping(model).and_then(|m| m.upstream_model.as_deref())
+    }
 }
 
 /// Select the best available target for a route.
+#[tracing::instrument(name = "select_target", skip_all, fields(route_id = %route_id, target_id, candidates))]
 pub async fn select_target(
     route_id: &str,
     db: &SqlitePool,
     circuit: &CircuitBreaker,
     rotation: &KeyRotationState,
+    vault: &KeyVault,
 ) -> Result<SelectedTarget, AppError> {
     let targets = sqlx::query_as::<_, RouteTarget>(
         "SELECT * FROM route_targets WHERE route_id = ? AND enabled = 1",
@@ -68,6 +86,9 @@ pub async fn select_target(
 
     // Weighted random selection
     let target = weighted_random_select(&available);
+    let span = tracing::Span::current();
+    span.record("candidates", available.len());
+    span.record("target_id", target.id.as_str());
 
     // Fetch enabled keys for this target
     let keys = sqlx::query_as::<_, RouteTargetKey>(
@@ -78,24 +99,32 @@ pub async fn select_target(
     .await?;
 
     // Pick key; allow empty keys for passthrough targets (upstream_format = "none")
-    let api_key = if keys.is_empty() {
-        String::new()
-    } else if target.key_rotation {
-        let idx = rotation.next_index(&target.id, keys.len());
-        keys[idx].key_value.clone()
+    let (api_key, key_id) = if keys.is_empty() {
+        (String::new(), None)
     } else {
-        keys[0].key_value.clone()
+        let idx = if target.key_rotation {
+            rotation.next_index(&target.id, keys.len())
+        } else {
+            0
+        };
+        // Keys are encrypted at rest; decrypt only for this request.
+        let api_key = vault
+            .decrypt(&keys[idx].key_value)
+            .map_err(|e| AppError::Internal(format!("Upstream key for target {}: {}", target.id, e)))?;
+        (api_key, Some(keys[idx].id.clone()))
     };
 
     Ok(SelectedTarget {
         target: target.clone(),
         api_key,
+        key_id,
         overrides: sqlx::query_as::<_, RouteTargetOverride>(
             "SELECT * FROM route_target_overrides WHERE target_id = ? ORDER BY id ASC",
         )
         .bind(&target.id)
         .fetch_all(db)
         .await?,
+        models: crate::services::routes::load_models(db, &target.id).await?,
     })
 }
 
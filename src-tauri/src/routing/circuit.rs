@@ -15,6 +15,15 @@ struct ChannelCircuit {
     last_failure: Option<Instant>,
 }
 
+/// Point-in-time view of one target's circuit, for metrics.
+#[derive(Debug, Clone)]
+pub struct CircuitSnapshot {
+    pub channel_id: String,
+    /// `"closed"`, `"open"` or `"half_open"`.
+    pub state: &'static str,
+    pub consecutive_failures: u32,
+}
+
 pub struct CircuitBreaker {
     states: Mutex<HashMap<String, ChannelCircuit>>,
     failure_threshold: u32,
@@ -65,7 +74,8 @@ impl CircuitBreaker {
     }
 
     /// Record a failed request — increment failures, possibly open circuit.
-    pub fn record_failure(&self, channel_id: &str) {
+    /// Returns true if this failure opened the circuit.
+    pub fn record_failure(&self, channel_id: &str) -> bool {
         let mut states = self.states.lock().unwrap();
         let circuit = states.entry(channel_id.to_string()).or_insert(ChannelCircuit {
             consecutive_failures: 0,
@@ -76,8 +86,38 @@ impl CircuitBreaker {
         circuit.consecutive_failures += 1;
         circuit.last_failure = Some(Instant::now());
 
-        if circuit.consecutive_failures >= self.failure_threshold {
+        if circuit.consecutive_failures >= self.failure_threshold
+            && circuit.state != CircuitState::Open
+        {
             circuit.state = CircuitState::Open;
+            return true;
         }
+        false
+    }
+
+    /// Current state of every channel that has recorded a failure. An open
+    /// circuit whose cooldown has elapsed is reported as half-open, which is
+    /// what the next `is_available` call would turn it into.
+    pub fn snapshot(&self) -> Vec<CircuitSnapshot> {
+        let states = self.states.lock().unwrap();
+        states
+            .iter()
+            .map(|(id, circuit)| {
+                let cooled_down = circuit
+                    .last_failure
+                    .is_some_and(|t| t.elapsed() >= self.cooldown);
+                let state = match circuit.state {
+                    CircuitState::Closed => "closed",
+                    CircuitState::Open if cooled_down => "half_open",
+                    CircuitState::Open => "open",
+                    CircuitState::HalfOpen => "half_open",
+                };
+                CircuitSnapshot {
+                    channel_id: id.clone(),
+                    state,
+                    consecutive_failures: circuit.consecutive_failures,
+                }
+            })
+            .collect()
     }
 }
@@ -18,12 +18,6 @@ pub const SYSTEM_RULES: [(&str, &str, &str); 5] = [
     ("moonshot", "Moonshot (Kimi)", "Built-in Moonshot codec"),
 ];
 
-pub fn is_system_rule_slug(slug: &str) -> bool {
-    SYSTEM_RULES
-        .iter()
-        .any(|(system_slug, _, _)| *system_slug == slug)
-}
-
 fn system_rule_slugs_sql_list() -> String {
     SYSTEM_RULES
         .iter()
@@ -0,0 +1,244 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use std::path::Path;
+
+/// Prefix marking a value encrypted by [`KeyVault::encrypt`].
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+/// Prefix marking a salted token hash produced by [`hash_token`].
+const HASH_PREFIX: &str = "sha256$";
+/// Number of leading characters of a gateway token kept in clear for lookup/display.
+const TOKEN_PREFIX_LEN: usize = 11;
+
+const KEY_FILE: &str = "omnikit.key";
+const SALT_FILE: &str = "omnikit.salt";
+const PASSPHRASE_ENV: &str = "OMNIKIT_MASTER_PASSPHRASE";
+#[cfg(not(test))]
+pub const PBKDF2_ROUNDS: u32 = 210_000;
+/// Tests derive keys repeatedly; the work factor is irrelevant there.
+#[cfg(test)]
+pub const PBKDF2_ROUNDS: u32 = 1_000;
+
+/// Holds the master key used to encrypt upstream API keys at rest.
+///
+/// The key lives outside the database: either a random key in `omnikit.key`
+/// next to `omnikit.db`, or derived from `OMNIKIT_MASTER_PASSPHRASE`.
+pub struct KeyVault {
+    cipher: Aes256Gcm,
+    /// Random per-process secret that lets in-app callers (e.g. request retry)
+    /// authenticate against the local gateway without a plaintext token.
+    session_key: String,
+}
+
+impl KeyVault {
+    /// Load the master key for the given data directory, creating one on first run.
+    pub fn load_or_create(data_dir: &Path) -> std::io::Result<Self> {
+        std::fs::create_dir_all(data_dir)?;
+
+        if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+            if !passphrase.is_empty() {
+                let salt = read_or_create_file(&data_dir.join(SALT_FILE), 16)?;
+                return Ok(Self::from_passphrase(&passphrase, &salt, PBKDF2_ROUNDS));
+            }
+        }
+
+        let key = read_or_create_file(&data_dir.join(KEY_FILE), 32)?;
+        if key.len() != 32 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{} must contain exactly 32 bytes", KEY_FILE),
+            ));
+        }
+        Ok(Self::from_key(&key))
+    }
+
+    pub fn from_key(key: &[u8]) -> Self {
+        Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)),
+            session_key: generate_token(),
+        }
+    }
+
+    /// Derive the key from a passphrase with PBKDF2-HMAC-SHA256.
+    pub fn from_passphrase(passphrase: &str, salt: &[u8], rounds: u32) -> Self {
+        let mut key = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, rounds, &mut key);
+        Self::from_key(&key)
+    }
+
+    /// Encrypt a secret into the `enc:v1:<base64(nonce || ciphertext)>` form.
+    pub fn encrypt(&self, plaintext: &str) -> String {
+        let mut nonce = [0u8; 12];
+        rand::rng().fill_bytes(&mut nonce);
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+            .expect("AES-GCM encryption cannot fail for in-memory buffers");
+        let mut payload = nonce.to_vec();
+        payload.extend_from_slice(&ciphertext);
+        format!("{}{}", ENCRYPTED_PREFIX, BASE64.encode(payload))
+    }
+
+    /// Decrypt a stored secret. Values without the encryption prefix are
+    /// returned unchanged so rows written before migration keep working.
+    pub fn decrypt(&self, stored: &str) -> Result<String, String> {
+        let Some(encoded) = stored.strip_prefix(ENCRYPTED_PREFIX) else {
+            return Ok(stored.to_string());
+        };
+        let payload = BASE64
+            .decode(encoded)
+            .map_err(|e| format!("Malformed encrypted value: {}", e))?;
+        if payload.len() < 12 {
+            return Err("Malformed encrypted value: too short".into());
+        }
+        let (nonce, ciphertext) = payload.split_at(12);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "Failed to decrypt value; is the master key correct?".to_string())?;
+        String::from_utf8(plaintext).map_err(|e| e.to_string())
+    }
+
+    pub fn session_key(&self) -> &str {
+        &self.session_key
+    }
+
+    /// Whether `value` is this process's session key, compared in constant time.
+    pub fn is_session_key(&self, value: &str) -> bool {
+        constant_time_eq(value, &self.session_key)
+    }
+}
+
+fn read_or_create_file(path: &Path, len: usize) -> std::io::Result<Vec<u8>> {
+    if path.exists() {
+        return std::fs::read(path);
+    }
+    let mut bytes = vec![0u8; len];
+    rand::rng().fill_bytes(&mut bytes);
+    std::fs::write(path, &bytes)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    log::info!("Created {}", path.display());
+    Ok(bytes)
+}
+
+// === Gateway tokens ===
+
+/// Generate a new gateway token (`sk-` + 32 hex chars).
+pub fn generate_token() -> String {
+    format!("sk-{}", uuid::Uuid::new_v4().to_string().replace('-', ""))
+}
+
+/// The visible part of a token, stored in clear for lookup and display.
+pub fn token_prefix(token: &str) -> String {
+    token.chars().take(TOKEN_PREFIX_LEN).collect()
+}
+
+/// Hash a token with a random salt: `sha256$<salt_hex>$<digest_hex>`.
+pub fn hash_token(token: &str) -> String {
+    let mut salt = [0u8; 16];
+    rand::rng().fill_bytes(&mut salt);
+    let salt_hex = hex::encode(salt);
+    format!("{}{}${}", HASH_PREFIX, salt_hex, digest_hex(&salt_hex, token))
+}
+
+/// Check a presented token against a stored `hash_token` value.
+pub fn verify_token(token: &str, stored: &str) -> bool {
+    let Some((salt_hex, digest)) = stored
+        .strip_prefix(HASH_PREFIX)
+        .and_then(|rest| rest.split_once('$'))
+    else {
+        return false;
+    };
+    constant_time_eq(&digest_hex(salt_hex, token), digest)
+}
+
+/// Compare two secrets without an early exit on the first differing byte.
+/// Only the length leaks, which is fixed for digests and session keys.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+fn digest_hex(salt_hex: &str, token: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt_hex.as_bytes());
+    hasher.update(token.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Encrypt plaintext upstream keys and hash plaintext gateway tokens left
+/// over from before at-rest protection. Safe to run on every startup.
+pub async fn migrate_plaintext_secrets(
+    pool: &SqlitePool,
+    vault: &KeyVault,
+) -> Result<(), sqlx::Error> {
+    let keys: Vec<(String, String)> = sqlx::query_as(
+        "SELECT id, key_value FROM route_target_keys WHERE key_value NOT LIKE 'enc:v1:%'",
+    )
+    .fetch_all(pool)
+    .await?;
+    for (id, key_value) in &keys {
+        sqlx::query("UPDATE route_target_keys SET key_value = ? WHERE id = ?")
+            .bind(vault.encrypt(key_value))
+            .bind(id)
+            .execute(pool)
+            .await?;
+    }
+
+    let tokens: Vec<(String, String)> =
+        sqlx::query_as("SELECT id, key_value FROM tokens WHERE key_prefix IS NULL")
+            .fetch_all(pool)
+            .await?;
+    for (id, key_value) in &tokens {
+        sqlx::query("UPDATE tokens SET key_prefix = ?, key_value = ? WHERE id = ?")
+            .bind(token_prefix(key_value))
+            .bind(hash_token(key_value))
+            .bind(id)
+            .execute(pool)
+            .await?;
+    }
+
+    if !keys.is_empty() || !tokens.is_empty() {
+        log::info!(
+            "Protected {} upstream key(s) and {} gateway token(s) at rest",
+            keys.len(),
+            tokens.len()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypt_roundtrip_and_legacy_passthrough() {
+        let vault = KeyVault::from_key(&[7u8; 32]);
+        let stored = vault.encrypt("sk-upstream-secret");
+        assert!(stored.starts_with(ENCRYPTED_PREFIX));
+        assert!(!stored.contains("sk-upstream-secret"));
+        assert_eq!(vault.decrypt(&stored).unwrap(), "sk-upstream-secret");
+        assert_eq!(vault.decrypt("plain-legacy").unwrap(), "plain-legacy");
+
+        let other = KeyVault::from_key(&[8u8; 32]);
+        assert!(other.decrypt(&stored).is_err());
+    }
+
+    #[test]
+    fn token_hash_verifies_only_original() {
+        let token = generate_token();
+        let stored = hash_token(&token);
+        assert!(verify_token(&token, &stored));
+        assert!(!verify_token("sk-wrong", &stored));
+        assert_ne!(stored, hash_token(&token), "salt must differ per hash");
+        assert_eq!(token_prefix(&token).len(), TOKEN_PREFIX_LEN);
+    }
+}
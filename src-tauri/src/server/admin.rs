@@ -0,0 +1,462 @@
+//! Authenticated management API under `/admin`, mirroring the Tauri commands
+//! so a headless instance can be provisioned and inspected over HTTP.
+//!
+//! Requests must present the admin key as `Authorization: Bearer <key>` or
+//! `x-admin-key: <key>`. With no admin key configured every request is rejected.
+
+use super::middleware;
+use super::proxy::ProxyState;
+use crate::config::{AppConfig, ConfigWatch};
+use crate::db::models::{AlertRule, RequestLog, Token};
+use crate::error::{AppError, IpcError};
+use crate::secrets;
+use crate::services::alerts::AlertRuleInput;
+use crate::services::analytics::{AnalyticsQuery, UsageAnalytics};
+use crate::services::config::ConfigUpdate;
+use crate::services::declarative::{ExportFormat, ExportOptions, ImportOptions};
+use crate::services::request_logs::RetryResult;
+use crate::services::routes::{RouteInput, RouteWithTargets, TestRouteResult};
+use crate::services::tokens::{CreatedToken, TokenInput};
+use crate::services::{self, PaginatedResult};
+use super::traffic::{TrafficEventKind, TrafficFilter};
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::Next;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Json, Response};
+use axum::routing::{get, post, put};
+use axum::Router;
+use serde::Deserialize;
+
+pub const ADMIN_KEY_HEADER: &str = "x-admin-key";
+/// Passphrase for a config document whose upstream keys are encrypted.
+pub const CONFIG_PASSPHRASE_HEADER: &str = "x-config-passphrase";
+
+pub fn router(state: ProxyState) -> Router {
+    Router::new()
+        .route("/admin/config", get(get_config).patch(update_config))
+        .route("/admin/config/watch", put(set_config_watch))
+        .route("/admin/config/admin-key", post(rotate_admin_key))
+        .route("/admin/config/export", post(export_config))
+        .route("/admin/config/import", post(import_config))
+        .route("/admin/routes", get(list_routes).post(create_route))
+        .route(
+            "/admin/routes/{id}",
+            get(get_route).put(update_route).delete(delete_route),
+        )
+        .route("/admin/routes/{id}/test", post(test_route))
+        .route("/admin/tokens", get(list_tokens).post(create_token))
+        .route(
+            "/admin/tokens/{id}",
+            get(get_token).put(update_token).delete(delete_token),
+        )
+        .route("/admin/tokens/{id}/reset-quota", post(reset_token_quota))
+        .route("/admin/tokens/{id}/regenerate", post(regenerate_token))
+        .route("/admin/alerts", get(list_alert_rules).post(create_alert_rule))
+        .route(
+            "/admin/alerts/{id}",
+            get(get_alert_rule).put(update_alert_rule).delete(delete_alert_rule),
+        )
+        .route("/admin/alerts/{id}/test", post(test_alert_rule))
+        .route("/admin/logs", get(list_request_logs).delete(clear_request_logs))
+        .route("/admin/logs/{id}", get(get_request_log))
+        .route("/admin/logs/{id}/retry", post(retry_request_log))
+        .route("/admin/stats/usage", get(get_usage_stats))
+        .route("/admin/stats/analytics", get(get_usage_analytics))
+        .route("/admin/events", get(stream_events))
+        .route("/admin/metrics", get(super::router::metrics))
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            require_admin_key,
+        ))
+        .with_state(state)
+}
+
+async fn require_admin_key(
+    State(state): State<ProxyState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let presented = match req
+        .headers()
+        .get(ADMIN_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+    {
+        Some(key) => key.to_string(),
+        None => middleware::extract_bearer_token(req.headers())?,
+    };
+
+    {
+        let config = state.config.read().await;
+        let Some(hash) = config.admin_key_hash.as_deref() else {
+            return Err(AppError::Unauthorized("Admin API is disabled: no admin key configured".into()));
+        };
+        if !secrets::verify_token(&presented, hash) {
+            return Err(AppError::Unauthorized("Invalid admin key".into()));
+        }
+    }
+
+    Ok(next.run(req).await)
+}
+
+// === Config ===
+
+async fn get_config(State(state): State<ProxyState>) -> Json<AppConfig> {
+    Json(state.config.read().await.clone())
+}
+
+async fn update_config(
+    State(state): State<ProxyState>,
+    Json(update): Json<ConfigUpdate>,
+) -> Result<Json<AppConfig>, IpcError> {
+    services::config::update_config(&state.db, &state.config, &state.server, update)
+        .await
+        .map(Json)
+}
+
+async fn set_config_watch(
+    State(state): State<ProxyState>,
+    Json(watch): Json<Option<ConfigWatch>>,
+) -> Result<Json<AppConfig>, IpcError> {
+    services::config::set_config_watch(&state.db, &state.config, &state.watcher, watch)
+        .await
+        .map(Json)
+}
+
+/// Replace the admin key; the one used for this request stops working.
+async fn rotate_admin_key(State(state): State<ProxyState>) -> Result<Json<serde_json::Value>, IpcError> {
+    let key = services::config::rotate_admin_key(&state.db, &state.config).await?;
+    Ok(Json(serde_json::json!({ "admin_key": key })))
+}
+
+async fn export_config(
+    State(state): State<ProxyState>,
+    Json(options): Json<ExportOptions>,
+) -> Result<Response, IpcError> {
+    let content_type = match options.format {
+        ExportFormat::Yaml => "application/yaml",
+        ExportFormat::Json => "application/json",
+    };
+    let body = services::declarative::export_config(&state.db, &state.vault, options).await?;
+    Ok(([(header::CONTENT_TYPE, content_type)], body).into_response())
+}
+
+#[derive(Deserialize)]
+struct ImportQuery {
+    #[serde(default)]
+    dry_run: bool,
+    #[serde(default)]
+    prune: bool,
+}
+
+/// The request body is the YAML or JSON document itself.
+async fn import_config(
+    State(state): State<ProxyState>,
+    Query(query): Query<ImportQuery>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Response, IpcError> {
+    let options = ImportOptions {
+        passphrase: headers
+            .get(CONFIG_PASSPHRASE_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string),
+        prune: query.prune,
+        ..Default::default()
+    };
+    if query.dry_run {
+        let plan = services::declarative::preview_import(&state.db, &state.vault, &body, options).await?;
+        Ok(Json(plan).into_response())
+    } else {
+        let result = services::declarative::apply_import(&state.db, &state.vault, &body, options).await?;
+        Ok(Json(result).into_response())
+    }
+}
+
+// === Routes ===
+
+async fn list_routes(
+    State(state): State<ProxyState>,
+) -> Result<Json<Vec<RouteWithTargets>>, IpcError> {
+    services::routes::list_routes(&state.db, &state.vault).await.map(Json)
+}
+
+async fn get_route(
+    State(state): State<ProxyState>,
+    Path(id): Path<String>,
+) -> Result<Json<RouteWithTargets>, IpcError> {
+    services::routes::get_route(&state.db, &state.vault, &id).await.map(Json)
+}
+
+async fn create_route(
+    State(state): State<ProxyState>,
+    Json(input): Json<RouteInput>,
+) -> Result<(StatusCode, Json<RouteWithTargets>), IpcError> {
+    let route = services::routes::create_route(&state.db, &state.vault, input).await?;
+    Ok((StatusCode::CREATED, Json(route)))
+}
+
+async fn update_route(
+    State(state): State<ProxyState>,
+    Path(id): Path<String>,
+    Json(input): Json<RouteInput>,
+) -> Result<Json<RouteWithTargets>, IpcError> {
+    services::routes::update_route(&state.db, &state.vault, &id, input)
+        .await
+        .map(Json)
+}
+
+async fn delete_route(
+    State(state): State<ProxyState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, IpcError> {
+    services::routes::delete_route(&state.db, &id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct TestRouteBody {
+    token_key: String,
+}
+
+async fn test_route(
+    State(state): State<ProxyState>,
+    Path(id): Path<String>,
+    Json(body): Json<TestRouteBody>,
+) -> Result<Json<TestRouteResult>, IpcError> {
+    let base_url = state.config.read().await.local_base_url();
+    services::routes::test_route(&state.db, &base_url, &id, &body.token_key)
+        .await
+        .map(Json)
+}
+
+// === Tokens ===
+
+async fn list_tokens(State(state): State<ProxyState>) -> Result<Json<Vec<Token>>, IpcError> {
+    services::tokens::list_tokens(&state.db).await.map(Json)
+}
+
+async fn get_token(
+    State(state): State<ProxyState>,
+    Path(id): Path<String>,
+) -> Result<Json<Token>, IpcError> {
+    services::tokens::get_token(&state.db, &id).await.map(Json)
+}
+
+async fn create_token(
+    State(state): State<ProxyState>,
+    Json(input): Json<TokenInput>,
+) -> Result<(StatusCode, Json<CreatedToken>), IpcError> {
+    let token = services::tokens::create_token(&state.db, input).await?;
+    Ok((StatusCode::CREATED, Json(token)))
+}
+
+async fn update_token(
+    State(state): State<ProxyState>,
+    Path(id): Path<String>,
+    Json(input): Json<TokenInput>,
+) -> Result<Json<Token>, IpcError> {
+    services::tokens::update_token(&state.db, &id, input).await?;
+    services::tokens::get_token(&state.db, &id).await.map(Json)
+}
+
+async fn delete_token(
+    State(state): State<ProxyState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, IpcError> {
+    services::tokens::delete_token(&state.db, &id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn reset_token_quota(
+    State(state): State<ProxyState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, IpcError> {
+    services::tokens::reset_token_quota(&state.db, &id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn regenerate_token(
+    State(state): State<ProxyState>,
+    Path(id): Path<String>,
+) -> Result<Json<CreatedToken>, IpcError> {
+    services::tokens::regenerate_token(&state.db, &id).await.map(Json)
+}
+
+// === Alerts ===
+
+async fn list_alert_rules(State(state): State<ProxyState>) -> Result<Json<Vec<AlertRule>>, IpcError> {
+    services::alerts::list_alert_rules(&state.db).await.map(Json)
+}
+
+async fn get_alert_rule(
+    State(state): State<ProxyState>,
+    Path(id): Path<String>,
+) -> Result<Json<AlertRule>, IpcError> {
+    services::alerts::get_alert_rule(&state.db, &id).await.map(Json)
+}
+
+async fn create_alert_rule(
+    State(state): State<ProxyState>,
+    Json(input): Json<AlertRuleInput>,
+) -> Result<(StatusCode, Json<AlertRule>), IpcError> {
+    let rule = services::alerts::create_alert_rule(&state.db, &state.alerts, input).await?;
+    Ok((StatusCode::CREATED, Json(rule)))
+}
+
+async fn update_alert_rule(
+    State(state): State<ProxyState>,
+    Path(id): Path<String>,
+    Json(input): Json<AlertRuleInput>,
+) -> Result<Json<AlertRule>, IpcError> {
+    services::alerts::update_alert_rule(&state.db, &state.alerts, &id, input)
+        .await
+        .map(Json)
+}
+
+async fn delete_alert_rule(
+    State(state): State<ProxyState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, IpcError> {
+    services::alerts::delete_alert_rule(&state.db, &state.alerts, &id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn test_alert_rule(
+    State(state): State<ProxyState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, IpcError> {
+    services::alerts::test_alert_rule(&state.db, &state.alerts, &id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// === Request logs & stats ===
+
+#[derive(Deserialize)]
+struct LogQuery {
+    limit: Option<i64>,
+    offset: Option<i64>,
+    model: Option<String>,
+}
+
+async fn list_request_logs(
+    State(state): State<ProxyState>,
+    Query(query): Query<LogQuery>,
+) -> Result<Json<PaginatedResult<RequestLog>>, IpcError> {
+    services::request_logs::list_request_logs(&state.db, query.limit, query.offset, query.model)
+        .await
+        .map(Json)
+}
+
+async fn get_request_log(
+    State(state): State<ProxyState>,
+    Path(id): Path<String>,
+) -> Result<Json<RequestLog>, IpcError> {
+    services::request_logs::get_request_log(&state.db, &id)
+        .await?
+        .map(Json)
+        .ok_or_else(|| IpcError::not_found("Log not found"))
+}
+
+async fn clear_request_logs(State(state): State<ProxyState>) -> Result<StatusCode, IpcError> {
+    services::request_logs::clear_request_logs(&state.db).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn retry_request_log(
+    State(state): State<ProxyState>,
+    Path(id): Path<String>,
+) -> Result<Json<RetryResult>, IpcError> {
+    let base_url = state.config.read().await.local_base_url();
+    services::request_logs::retry_request_log(&state.db, &state.vault, &base_url, &id)
+        .await
+        .map(Json)
+}
+
+#[derive(Deserialize)]
+struct UsageQuery {
+    days: Option<i32>,
+}
+
+async fn get_usage_stats(
+    State(state): State<ProxyState>,
+    Query(query): Query<UsageQuery>,
+) -> Result<Json<serde_json::Value>, IpcError> {
+    services::request_logs::get_usage_stats(&state.db, query.days)
+        .await
+        .map(Json)
+}
+
+#[derive(Deserialize)]
+struct EventsQuery {
+    route_id: Option<String>,
+    target_id: Option<String>,
+    token_id: Option<String>,
+    model: Option<String>,
+    /// Comma-separated event kinds, e.g. `completed,error`.
+    kinds: Option<String>,
+}
+
+/// Live traffic as server-sent events, starting with the requests already
+/// in flight.
+async fn stream_events(
+    State(state): State<ProxyState>,
+    Query(query): Query<EventsQuery>,
+) -> Result<Sse<impl futures_core::Stream<Item = Result<Event, std::convert::Infallible>>>, IpcError> {
+    let kinds = query
+        .kinds
+        .map(|kinds| {
+            kinds
+                .split(',')
+                .map(str::trim)
+                .filter(|k| !k.is_empty())
+                .map(|k| {
+                    serde_json::from_value::<TrafficEventKind>(serde_json::Value::from(k))
+                        .map_err(|_| IpcError::validation(format!("Unknown event kind: {}", k)))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()?;
+    let filter = TrafficFilter {
+        route_id: query.route_id,
+        target_id: query.target_id,
+        token_id: query.token_id,
+        model: query.model,
+        kinds,
+    };
+
+    // Subscribe before taking the snapshot so nothing falls in between.
+    let mut rx = state.traffic.subscribe();
+    let in_flight = state.traffic.in_flight();
+    let stream = async_stream::stream! {
+        for event in in_flight.into_iter().filter(|e| filter.matches(e)) {
+            if let Ok(event) = Event::default().json_data(&event) {
+                yield Ok(event);
+            }
+        }
+        loop {
+            match rx.recv().await {
+                Ok(event) if filter.matches(&event) => {
+                    if let Ok(event) = Event::default().json_data(&event) {
+                        yield Ok(event);
+                    }
+                }
+                Ok(_) => {}
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    yield Ok(Event::default().event("lagged").data(skipped.to_string()));
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    };
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+async fn get_usage_analytics(
+    State(state): State<ProxyState>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<UsageAnalytics>, IpcError> {
+    services::analytics::get_usage_analytics(&state.db, query)
+        .await
+        .map(Json)
+}
@@ -0,0 +1,449 @@
+//! Alerts for gateway incidents.
+//!
+//! Rules in `alert_rules` fire when a target's circuit opens, a route's
+//! error rate crosses a threshold, a token has used a share of its quota, or
+//! the provider rejects an upstream key. Alerts are POSTed as
+//! JSON to the rule's webhook and, on desktop, shown as notifications.
+//!
+//! An alert is not repeated for the same rule and subject within the rule's
+//! cooldown; the next one carries the number that were held back.
+
+use super::traffic::{TrafficBus, TrafficEvent, TrafficEventKind};
+use crate::db::models::{AlertRule, Route, RouteTarget, Token};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::SqlitePool;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    CircuitOpen,
+    ErrorRate,
+    Quota,
+    KeyRejected,
+}
+
+impl AlertKind {
+    pub const ALL: [AlertKind; 4] = [
+        AlertKind::CircuitOpen,
+        AlertKind::ErrorRate,
+        AlertKind::Quota,
+        AlertKind::KeyRejected,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AlertKind::CircuitOpen => "circuit_open",
+            AlertKind::ErrorRate => "error_rate",
+            AlertKind::Quota => "quota",
+            AlertKind::KeyRejected => "key_rejected",
+        }
+    }
+
+    pub fn parse(kind: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == kind)
+    }
+}
+
+/// Webhook payload; also handed to the desktop notifier.
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub kind: AlertKind,
+    /// Id of the target, route, token or key the alert is about.
+    pub subject: String,
+    pub title: String,
+    pub message: String,
+    pub details: serde_json::Value,
+    /// Alerts for the same rule and subject held back by the cooldown
+    /// since the previous one.
+    pub suppressed: u64,
+    /// Sent from "Send test alert" rather than by an incident.
+    pub test: bool,
+    pub fired_at: String,
+}
+
+/// Shows an alert on the desktop; set by the Tauri app.
+pub type Notifier = Arc<dyn Fn(&Alert) + Send + Sync>;
+
+#[derive(Clone)]
+pub struct Alerter {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    db: SqlitePool,
+    http: reqwest::Client,
+    /// Enabled rules, reloaded whenever rules change.
+    rules: RwLock<Vec<AlertRule>>,
+    cooldowns: Mutex<Cooldowns>,
+    /// Recent request outcomes per error-rate rule and route.
+    windows: Mutex<HashMap<(String, String), ErrorWindow>>,
+    notifier: RwLock<Option<Notifier>>,
+}
+
+impl Alerter {
+    /// Load the rules and start watching `traffic` for error rates.
+    /// Must be called within a Tokio runtime.
+    pub fn new(db: SqlitePool, traffic: &TrafficBus) -> Self {
+        let alerter = Self {
+            inner: Arc::new(Inner {
+                db,
+                http: reqwest::Client::builder()
+                    .timeout(WEBHOOK_TIMEOUT)
+                    .build()
+                    .unwrap_or_default(),
+                rules: RwLock::default(),
+                cooldowns: Mutex::default(),
+                windows: Mutex::default(),
+                notifier: RwLock::default(),
+            }),
+        };
+
+        let mut events = traffic.subscribe();
+        let watcher = alerter.clone();
+        tokio::spawn(async move {
+            if let Err(e) = watcher.reload().await {
+                log::error!("Failed to load alert rules: {}", e);
+            }
+            loop {
+                match events.recv().await {
+                    Ok(event) => watcher.on_traffic(&event),
+                    Err(RecvError::Lagged(n)) => log::warn!("Alerts missed {} traffic events", n),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+        alerter
+    }
+
+    pub fn set_notifier(&self, notifier: Notifier) {
+        *self.inner.notifier.write().unwrap_or_else(|e| e.into_inner()) = Some(notifier);
+    }
+
+    /// Re-read the enabled rules, dropping state kept for removed ones.
+    pub async fn reload(&self) -> Result<(), sqlx::Error> {
+        let rules = sqlx::query_as::<_, AlertRule>(
+            "SELECT * FROM alert_rules WHERE enabled = 1 ORDER BY created_at ASC",
+        )
+        .fetch_all(&self.inner.db)
+        .await?;
+
+        let keep = |key: &(String, String)| rules.iter().any(|r| r.id == key.0);
+        self.inner.windows.lock().unwrap_or_else(|e| e.into_inner()).retain(|k, _| keep(k));
+        self.inner.cooldowns.lock().unwrap_or_else(|e| e.into_inner()).0.retain(|k, _| keep(k));
+        *self.inner.rules.write().unwrap_or_else(|e| e.into_inner()) = rules;
+        Ok(())
+    }
+
+    /// Enabled rules of `kind` for which `applies` holds.
+    fn rules(&self, kind: AlertKind, applies: impl Fn(&AlertRule) -> bool) -> Vec<AlertRule> {
+        self.inner
+            .rules
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|r| r.kind == kind.as_str() && applies(r))
+            .cloned()
+            .collect()
+    }
+
+    /// A target's circuit breaker has just opened.
+    pub fn circuit_opened(&self, route: &Route, target: &RouteTarget) {
+        for rule in self.rules(AlertKind::CircuitOpen, |r| scoped(&r.route_id, &route.id)) {
+            self.fire(
+                &rule,
+                &target.id,
+                format!("Circuit opened: {}", route.name),
+                format!(
+                    "Target {} on route {} ({}) stopped receiving traffic after repeated failures.",
+                    target.base_url, route.name, route.path_prefix
+                ),
+                json!({
+                    "route_id": route.id,
+                    "route": route.path_prefix,
+                    "target_id": target.id,
+                    "base_url": target.base_url,
+                }),
+            );
+        }
+    }
+
+    /// The provider rejected an upstream key. The key stays in rotation, as a
+    /// rejection may be brief or scoped to one model.
+    pub fn key_rejected(&self, route: &Route, target: &RouteTarget, key_id: &str, status: u16) {
+        for rule in self.rules(AlertKind::KeyRejected, |r| scoped(&r.route_id, &route.id)) {
+            self.fire(
+                &rule,
+                key_id,
+                format!("Upstream key rejected: {}", route.name),
+                format!(
+                    "A key for {} on route {} was rejected with HTTP {}; check or replace it in the route's targets.",
+                    target.base_url, route.name, status
+                ),
+                json!({
+                    "route_id": route.id,
+                    "route": route.path_prefix,
+                    "target_id": target.id,
+                    "base_url": target.base_url,
+                    "key_id": key_id,
+                    "status": status,
+                }),
+            );
+        }
+    }
+
+    /// Compare a token's quota usage with the quota rules, after it changed.
+    pub async fn check_quota(&self, token_id: &str) {
+        let rules = self.rules(AlertKind::Quota, |r| scoped(&r.token_id, token_id));
+        if rules.is_empty() {
+            return;
+        }
+        let token = match sqlx::query_as::<_, Token>("SELECT * FROM tokens WHERE id = ?")
+            .bind(token_id)
+            .fetch_optional(&self.inner.db)
+            .await
+        {
+            Ok(Some(token)) => token,
+            Ok(None) => return,
+            Err(e) => {
+                log::error!("Failed to check token quota: {}", e);
+                return;
+            }
+        };
+        let Some(limit) = token.quota_limit.filter(|l| *l > 0) else {
+            return;
+        };
+        let used_pct = token.quota_used as f64 * 100.0 / limit as f64;
+        let name = token.name.clone().unwrap_or_else(|| token.key_prefix.clone().unwrap_or_default());
+
+        for rule in rules {
+            if used_pct < rule.threshold.unwrap_or(100.0) {
+                continue;
+            }
+            self.fire(
+                &rule,
+                &token.id,
+                format!("Token quota {:.0}% used: {}", used_pct, name),
+                format!(
+                    "Token {} has used {} of its {} token quota ({:.1}%).",
+                    name, token.quota_used, limit, used_pct
+                ),
+                json!({
+                    "token_id": token.id,
+                    "token": name,
+                    "quota_used": token.quota_used,
+                    "quota_limit": limit,
+                    "used_percent": used_pct,
+                }),
+            );
+        }
+    }
+
+    fn on_traffic(&self, event: &TrafficEvent) {
+        let failed = match event.kind {
+            TrafficEventKind::Completed => false,
+            TrafficEventKind::Error => !event.client_closed(),
+            _ => return,
+        };
+        let now = Instant::now();
+        for rule in self.rules(AlertKind::ErrorRate, |r| scoped(&r.route_id, &event.route_id)) {
+            let window = Duration::from_secs(rule.window_secs.max(1) as u64);
+            let (requests, failures) = self
+                .inner
+                .windows
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .entry((rule.id.clone(), event.route_id.clone()))
+                .or_default()
+                .record(now, failed, window);
+
+            let rate = failures as f64 * 100.0 / requests as f64;
+            if (requests as i64) < rule.min_requests.max(1) || rate < rule.threshold.unwrap_or(100.0) {
+                continue;
+            }
+            self.fire(
+                &rule,
+                &event.route_id,
+                format!("High error rate: {}", event.route),
+                format!(
+                    "{:.0}% of {} requests to {} failed in the last {}s.",
+                    rate, requests, event.route, window.as_secs()
+                ),
+                json!({
+                    "route_id": event.route_id,
+                    "route": event.route,
+                    "requests": requests,
+                    "failures": failures,
+                    "error_rate": rate,
+                    "window_secs": window.as_secs(),
+                }),
+            );
+        }
+    }
+
+    fn fire(
+        &self,
+        rule: &AlertRule,
+        subject: &str,
+        title: String,
+        message: String,
+        details: serde_json::Value,
+    ) {
+        let cooldown = Duration::from_secs(rule.cooldown_secs.max(0) as u64);
+        let admitted = self
+            .inner
+            .cooldowns
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .admit((rule.id.clone(), subject.to_string()), Instant::now(), cooldown);
+        let Some(suppressed) = admitted else {
+            return;
+        };
+
+        log::warn!("Alert \"{}\": {}", rule.name, message);
+        let alert = Alert {
+            rule_id: rule.id.clone(),
+            rule_name: rule.name.clone(),
+            kind: AlertKind::parse(&rule.kind).unwrap_or(AlertKind::CircuitOpen),
+            subject: subject.to_string(),
+            title,
+            message,
+            details,
+            suppressed,
+            test: false,
+            fired_at: chrono::Utc::now().to_rfc3339(),
+        };
+        let alerter = self.clone();
+        let rule = rule.clone();
+        tokio::spawn(async move {
+            if let Err(e) = alerter.deliver(&rule, &alert).await {
+                log::error!("Alert \"{}\" not delivered: {}", rule.name, e);
+            }
+        });
+    }
+
+    /// Send a sample alert through a rule's channels, ignoring its cooldown.
+    pub async fn send_test(&self, rule: &AlertRule) -> Result<(), String> {
+        let alert = Alert {
+            rule_id: rule.id.clone(),
+            rule_name: rule.name.clone(),
+            kind: AlertKind::parse(&rule.kind).unwrap_or(AlertKind::CircuitOpen),
+            subject: String::new(),
+            title: format!("Test alert: {}", rule.name),
+            message: "This is a test alert from the OmniKit gateway.".to_string(),
+            details: json!({}),
+            suppressed: 0,
+            test: true,
+            fired_at: chrono::Utc::now().to_rfc3339(),
+        };
+        self.deliver(rule, &alert).await
+    }
+
+    async fn deliver(&self, rule: &AlertRule, alert: &Alert) -> Result<(), String> {
+        if rule.notify_desktop {
+            let notifier = self.inner.notifier.read().unwrap_or_else(|e| e.into_inner()).clone();
+            if let Some(notify) = notifier {
+                notify(alert);
+            }
+        }
+        if let Some(url) = rule.webhook_url.as_deref().filter(|u| !u.is_empty()) {
+            self.inner
+                .http
+                .post(url)
+                .json(alert)
+                .send()
+                .await
+                .and_then(|resp| resp.error_for_status())
+                .map_err(|e| format!("Webhook {}: {}", url, e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether a rule scoped to `scope` (none = everything) covers `id`.
+fn scoped(scope: &Option<String>, id: &str) -> bool {
+    scope.as_deref().is_none_or(|s| s.is_empty() || s == id)
+}
+
+/// Last alert time per (rule, subject), with how many were held back since.
+#[derive(Default)]
+struct Cooldowns(HashMap<(String, String), (Instant, u64)>);
+
+impl Cooldowns {
+    /// Whether an alert may fire now; if so, returns the number suppressed
+    /// since the previous one.
+    fn admit(&mut self, key: (String, String), now: Instant, cooldown: Duration) -> Option<u64> {
+        if let Some((last, suppressed)) = self.0.get_mut(&key) {
+            if now.duration_since(*last) < cooldown {
+                *suppressed += 1;
+                return None;
+            }
+        }
+        let suppressed = self.0.insert(key, (now, 0)).map_or(0, |(_, n)| n);
+        Some(suppressed)
+    }
+}
+
+/// Request outcomes within a sliding time window.
+#[derive(Default)]
+struct ErrorWindow {
+    samples: VecDeque<(Instant, bool)>,
+    failures: usize,
+}
+
+impl ErrorWindow {
+    /// Add an outcome and return (requests, failures) within `window`.
+    fn record(&mut self, now: Instant, failed: bool, window: Duration) -> (usize, usize) {
+        self.samples.push_back((now, failed));
+        if failed {
+            self.failures += 1;
+        }
+        while let Some(&(at, failed)) = self.samples.front() {
+            if now.duration_since(at) <= window {
+                break;
+            }
+            self.samples.pop_front();
+            if failed {
+                self.failures -= 1;
+            }
+        }
+        (self.samples.len(), self.failures)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_window_slides_and_cooldown_suppresses_repeats() {
+        let start = Instant::now();
+        let window = Duration::from_secs(60);
+        let mut errors = ErrorWindow::default();
+        assert_eq!(errors.record(start, true, window), (1, 1));
+        assert_eq!(errors.record(start + Duration::from_secs(30), false, window), (2, 1));
+        // The first failure has left the window.
+        assert_eq!(errors.record(start + Duration::from_secs(61), false, window), (2, 0));
+
+        let key = || ("rule".to_string(), "route".to_string());
+        let cooldown = Duration::from_secs(300);
+        let mut cooldowns = Cooldowns::default();
+        assert_eq!(cooldowns.admit(key(), start, cooldown), Some(0));
+        assert_eq!(cooldowns.admit(key(), start + Duration::from_secs(10), cooldown), None);
+        assert_eq!(cooldowns.admit(key(), start + Duration::from_secs(20), cooldown), None);
+        // Another subject is tracked separately.
+        assert_eq!(
+            cooldowns.admit(("rule".to_string(), "other".to_string()), start, cooldown),
+            Some(0)
+        );
+        assert_eq!(cooldowns.admit(key(), start + Duration::from_secs(300), cooldown), Some(2));
+    }
+}
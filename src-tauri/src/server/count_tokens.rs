@@ -0,0 +1,188 @@
+//! Token counting: Anthropic's `POST /v1/messages/count_tokens` and Gemini's
+//! `POST /v1beta/models/{model}:countTokens`.
+//!
+//! The request is decoded into the IR and counted by the selected target's
+//! own counting endpoint when its format has one (Anthropic, Gemini). Other
+//! targets, and upstream failures, fall back to the local estimate in
+//! [`chat::tokens`].
+
+use crate::db::models::Route;
+use crate::error::AppError;
+use crate::modality::chat::anthropic::AnthropicCodec;
+use crate::modality::chat::gemini::GeminiCodec;
+use crate::modality::chat::ir::IrChatRequest;
+use crate::modality::chat::{self, ChatFormat, Decoder, Encoder};
+use crate::outbound::Timeouts;
+use crate::routing::balancer::{self, SelectedTarget};
+use axum::response::{IntoResponse, Json, Response};
+use serde_json::{json, Value};
+
+use super::proxy::{apply_auth, apply_overrides, ProxyState};
+
+/// Fields Anthropic's counting endpoint accepts; it rejects the rest of a
+/// messages request, such as `max_tokens`.
+const ANTHROPIC_COUNT_FIELDS: &[&str] = &["model", "messages", "system", "tools", "tool_choice", "thinking"];
+
+/// The client format of a counting request, from the path after the route
+/// prefix.
+pub fn detect(sub_path: &str) -> Option<ChatFormat> {
+    let path = sub_path.split('?').next().unwrap_or(sub_path);
+    let normalized = path.strip_prefix("/v1").unwrap_or(path);
+    if normalized == "/messages/count_tokens" {
+        Some(ChatFormat::Anthropic)
+    } else if path.ends_with(":countTokens") {
+        Some(ChatFormat::Gemini)
+    } else {
+        None
+    }
+}
+
+pub async fn handle_count_tokens(
+    state: &ProxyState,
+    route: &Route,
+    format: ChatFormat,
+    sub_path: &str,
+    body: &[u8],
+) -> Result<Response, AppError> {
+    let ir = decode(format, sub_path, body)?;
+
+    let selected = balancer::select_target(
+        &route.id,
+        &state.db,
+        &state.circuit,
+        &state.rotation,
+        &state.vault,
+    )
+    .await?;
+    let mut upstream_ir = ir.clone();
+    if let Some(upstream_model) = selected.upstream_model(&ir.model) {
+        upstream_ir.model = upstream_model.to_string();
+    }
+
+    let tokens = match count_upstream(state, &selected, &upstream_ir).await {
+        Ok(Some(tokens)) => tokens,
+        Ok(None) => chat::tokens::estimate_request(&ir) as u64,
+        Err(e) => {
+            log::warn!("Counting tokens on target {} failed, estimating: {}", selected.target.id, e);
+            chat::tokens::estimate_request(&ir) as u64
+        }
+    };
+
+    let body = match format {
+        ChatFormat::Gemini => json!({ "totalTokens": tokens }),
+        _ => json!({ "input_tokens": tokens }),
+    };
+    Ok(Json(body).into_response())
+}
+
+/// Decode a counting request. Anthropic's lacks the `max_tokens` a messages
+/// request requires; Gemini's names the model in the path and may wrap the
+/// request in `generateContentRequest`.
+fn decode(format: ChatFormat, sub_path: &str, body: &[u8]) -> Result<IrChatRequest, AppError> {
+    let mut value: Value = serde_json::from_slice(body)
+        .map_err(|e| AppError::BadRequest(format!("Invalid JSON: {}", e)))?;
+    match format {
+        ChatFormat::Gemini => {
+            if let Some(inner) = value.get_mut("generateContentRequest").map(Value::take) {
+                value = inner;
+            }
+            let mut ir = GeminiCodec.decode_request(&serde_json::to_vec(&value)?)?;
+            ir.model = gemini_model(sub_path).unwrap_or_default().to_string();
+            Ok(ir)
+        }
+        _ => {
+            if let Value::Object(map) = &mut value {
+                map.entry("max_tokens").or_insert(json!(1));
+            }
+            AnthropicCodec.decode_request(&serde_json::to_vec(&value)?)
+        }
+    }
+}
+
+/// The model in `/v1beta/models/{model}:countTokens`.
+fn gemini_model(sub_path: &str) -> Option<&str> {
+    let path = sub_path.split('?').next().unwrap_or(sub_path);
+    let model = path.rsplit_once("/models/")?.1;
+    model.strip_suffix(":countTokens")
+}
+
+/// Count with the target's own endpoint; `None` when its format has none.
+async fn count_upstream(
+    state: &ProxyState,
+    selected: &SelectedTarget,
+    ir: &IrChatRequest,
+) -> Result<Option<u64>, AppError> {
+    let target = &selected.target;
+    let base = target.base_url.trim_end_matches('/');
+    let (format, url, body) = match ChatFormat::from_str_loose(&target.upstream_format) {
+        Some(ChatFormat::Anthropic) => {
+            let mut body: Value = serde_json::from_slice(&AnthropicCodec.encode_request(ir, &ir.model)?)?;
+            if let Value::Object(map) = &mut body {
+                map.retain(|key, _| ANTHROPIC_COUNT_FIELDS.contains(&key.as_str()));
+            }
+            (ChatFormat::Anthropic, format!("{}/messages/count_tokens", base), body)
+        }
+        Some(ChatFormat::Gemini) => {
+            let mut request: Value = serde_json::from_slice(&GeminiCodec.encode_request(ir, &ir.model)?)?;
+            request["model"] = json!(format!("models/{}", ir.model));
+            let body = json!({ "generateContentRequest": request });
+            (ChatFormat::Gemini, format!("{}/models/{}:countTokens", base, ir.model), body)
+        }
+        _ => return Ok(None),
+    };
+
+    let (body, override_headers, url) =
+        apply_overrides(&serde_json::to_vec(&body)?, &url, &selected.overrides);
+    let mut request = state
+        .client_for(target)
+        .await?
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .timeout(Timeouts::for_target(target).first_byte)
+        .body(body);
+    request = apply_auth(request, format, &selected.api_key);
+    for (k, v) in &override_headers {
+        request = request.header(k.as_str(), v.as_str());
+    }
+
+    let resp = request.send().await?;
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.bytes().await.unwrap_or_default();
+        let decoder = chat::get_decoder(format);
+        return Err(AppError::Upstream(decoder.decode_error(status.as_u16(), &body)));
+    }
+    let body: Value = resp.json().await?;
+    let field = match format {
+        ChatFormat::Gemini => "totalTokens",
+        _ => "input_tokens",
+    };
+    body[field]
+        .as_u64()
+        .map(Some)
+        .ok_or_else(|| AppError::Codec(format!("Count response without {}", field)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_counting_paths() {
+        assert_eq!(detect("/v1/messages/count_tokens"), Some(ChatFormat::Anthropic));
+        assert_eq!(detect("/v1beta/models/gemini-2.0-flash:countTokens"), Some(ChatFormat::Gemini));
+        assert_eq!(detect("/v1/messages"), None);
+        assert_eq!(gemini_model("/v1beta/models/gemini-2.0-flash:countTokens"), Some("gemini-2.0-flash"));
+    }
+
+    #[test]
+    fn decodes_requests_without_max_tokens() {
+        let body = br#"{"model":"claude-sonnet","system":"Be brief.","messages":[{"role":"user","content":"Hi"}]}"#;
+        let ir = decode(ChatFormat::Anthropic, "/v1/messages/count_tokens", body).unwrap();
+        assert_eq!((ir.model.as_str(), ir.messages.len()), ("claude-sonnet", 1));
+
+        let body = br#"{"generateContentRequest":{"contents":[{"role":"user","parts":[{"text":"Hi"}]}]}}"#;
+        let ir = decode(ChatFormat::Gemini, "/v1beta/models/gemini-pro:countTokens", body).unwrap();
+        assert_eq!((ir.model.as_str(), ir.messages[0].content.to_text().as_str()), ("gemini-pro", "Hi"));
+    }
+}
@@ -0,0 +1,351 @@
+//! Inlining image URLs as base64 for upstreams that can't fetch them.
+//!
+//! Gemini only takes inline image data, Anthropic takes `http(s)` URLs but
+//! not `data:` URLs, and no provider can reach a URL on the caller's own
+//! network. A target's `inline_images` decides which image URLs are replaced
+//! by their content before the request is encoded: `auto` those its format
+//! can't take, `always` all of them. Other images are left as URLs.
+//!
+//! Downloads go through the target's outbound proxy, are limited to
+//! [`MAX_BYTES`] and to the image types every provider accepts, and are
+//! cached by URL for [`TTL`]. Since any token holder picks the URLs, hosts
+//! resolving to loopback, link-local (cloud metadata) or other non-routable
+//! addresses are refused, redirects are checked hop by hop, a request
+//! downloads at most [`MAX_IMAGES`], and failures are reported to the client
+//! without upstream details.
+
+use crate::db::models::RouteTarget;
+use crate::error::AppError;
+use crate::modality::chat::helpers;
+use crate::modality::chat::ir::{IrChatRequest, IrContent, IrContentPart};
+use crate::modality::chat::ChatFormat;
+use crate::outbound::{self, Timeouts};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use reqwest::header::LOCATION;
+use reqwest::{Client, Url};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::proxy::ProxyState;
+
+/// Largest image inlined; Anthropic's limit per image.
+const MAX_BYTES: usize = 5 * 1024 * 1024;
+/// How long a downloaded image is reused.
+const TTL: Duration = Duration::from_secs(600);
+/// Images kept in the cache at most.
+const CACHE_ENTRIES: usize = 32;
+/// Image URLs downloaded for one request at most.
+const MAX_IMAGES: usize = 8;
+/// Redirects followed per image; each hop is checked like the first URL.
+const MAX_REDIRECTS: usize = 3;
+/// AWS's IPv6 instance metadata address, which is otherwise a private one.
+const AWS_METADATA_V6: Ipv6Addr = Ipv6Addr::new(0xfd00, 0xec2, 0, 0, 0, 0, 0, 0x254);
+
+/// Image types every provider accepts.
+const MEDIA_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+#[derive(Debug, Clone, PartialEq)]
+struct Image {
+    media_type: String,
+    data: String,
+}
+
+/// Downloaded images by URL.
+pub struct ImageCache {
+    entries: Mutex<HashMap<String, (Instant, Image)>>,
+}
+
+impl ImageCache {
+    pub fn new() -> Self {
+        Self { entries: Mutex::new(HashMap::new()) }
+    }
+
+    fn get(&self, url: &str) -> Option<Image> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(url)
+            .filter(|(fetched, _)| fetched.elapsed() < TTL)
+            .map(|(_, image)| image.clone())
+    }
+
+    fn put(&self, url: &str, image: Image) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (fetched, _)| fetched.elapsed() < TTL);
+        if entries.len() >= CACHE_ENTRIES {
+            let oldest = entries.iter().min_by_key(|(_, (fetched, _))| *fetched).map(|(url, _)| url.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(url.to_string(), (Instant::now(), image));
+    }
+}
+
+/// Whether an upstream of `format` takes `url` as an image source.
+fn accepts_url(format: ChatFormat, url: &str) -> bool {
+    match format {
+        ChatFormat::Gemini => false,
+        ChatFormat::Anthropic => !url.starts_with("data:"),
+        _ => true,
+    }
+}
+
+/// Replace image URLs in `ir` with their content as the target's
+/// `inline_images` asks. Returns how many images were inlined; an image that
+/// can't be fetched or isn't accepted fails the request.
+pub async fn inline_images(
+    state: &ProxyState,
+    target: &RouteTarget,
+    format: ChatFormat,
+    ir: &mut IrChatRequest,
+) -> Result<usize, AppError> {
+    let always = match target.inline_images.as_deref() {
+        Some("always") => true,
+        Some("auto") => false,
+        _ => return Ok(0),
+    };
+
+    let downloads = ir
+        .messages
+        .iter()
+        .filter_map(|message| match &message.content {
+            IrContent::Parts(parts) => Some(parts),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|part| match part {
+            IrContentPart::Image { url: Some(source), data: None, .. } => Some(source),
+            _ => None,
+        })
+        .filter(|source| !source.starts_with("data:") && (always || !accepts_url(format, source)))
+        .count();
+    if downloads > MAX_IMAGES {
+        return Err(AppError::BadRequest(format!(
+            "A request may inline at most {} image URLs",
+            MAX_IMAGES
+        )));
+    }
+
+    let mut inlined = 0;
+    for message in &mut ir.messages {
+        let IrContent::Parts(parts) = &mut message.content else {
+            continue;
+        };
+        for part in parts {
+            let IrContentPart::Image { url, media_type, data } = part else {
+                continue;
+            };
+            let Some(source) = url.as_deref() else {
+                continue;
+            };
+            if data.is_some() || (!always && accepts_url(format, source)) {
+                continue;
+            }
+            let image = if source.starts_with("data:") {
+                parse_data_url(source)?
+            } else {
+                fetch(state, target, source).await?
+            };
+            *media_type = Some(image.media_type);
+            *data = Some(image.data);
+            *url = None;
+            inlined += 1;
+        }
+    }
+    Ok(inlined)
+}
+
+/// The image in a `data:` URL.
+fn parse_data_url(url: &str) -> Result<Image, AppError> {
+    let invalid = |why: &str| AppError::BadRequest(format!("Invalid image data URL: {}", why));
+    let (media_type, data) = helpers::parse_data_url(url).ok_or_else(|| invalid("not base64"))?;
+    if !MEDIA_TYPES.contains(&media_type) {
+        return Err(invalid(&format!("unsupported type {}", media_type)));
+    }
+    if data.len() / 4 * 3 > MAX_BYTES {
+        return Err(invalid(&format!("larger than {} bytes", MAX_BYTES)));
+    }
+    Ok(Image { media_type: media_type.to_string(), data: data.to_string() })
+}
+
+async fn fetch(state: &ProxyState, target: &RouteTarget, url: &str) -> Result<Image, AppError> {
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err(AppError::BadRequest(format!("Unsupported image URL: {}", url)));
+    }
+    if let Some(image) = state.images.get(url) {
+        return Ok(image);
+    }
+    // The reason stays in the log: echoing statuses or connection errors
+    // would let clients probe hosts through the gateway.
+    let image = download(state, target, url).await.map_err(|why| {
+        log::warn!("Inlining an image for target {} failed: {}", target.id, why);
+        AppError::BadRequest(format!("Image {} could not be fetched", url))
+    })?;
+    state.images.put(url, image.clone());
+    Ok(image)
+}
+
+async fn download(state: &ProxyState, target: &RouteTarget, url: &str) -> Result<Image, String> {
+    let global = state.config.read().await.proxy_url.clone();
+    let proxy = outbound::effective_proxy(target.proxy_url.as_deref(), global.as_deref());
+    let timeouts = Timeouts::for_target(target);
+
+    let mut url = Url::parse(url).map_err(|e| e.to_string())?;
+    let mut redirects = 0;
+    let mut resp = loop {
+        let resp = client_for_host(&url, proxy, timeouts.connect)
+            .await?
+            .get(url.clone())
+            .timeout(timeouts.first_byte)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !resp.status().is_redirection() {
+            break resp;
+        }
+        if redirects == MAX_REDIRECTS {
+            return Err(format!("more than {} redirects", MAX_REDIRECTS));
+        }
+        redirects += 1;
+        let location = resp
+            .headers()
+            .get(LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .ok_or("redirect without a location")?;
+        url = url.join(location).map_err(|e| e.to_string())?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("redirect to a {} URL", url.scheme()));
+        }
+    };
+    if !resp.status().is_success() {
+        return Err(format!("{} answered HTTP {}", url.host_str().unwrap_or_default(), resp.status().as_u16()));
+    }
+    let too_large = || format!("larger than {} bytes", MAX_BYTES);
+    if resp.content_length().is_some_and(|len| len > MAX_BYTES as u64) {
+        return Err(too_large());
+    }
+    let mut bytes = Vec::new();
+    while let Some(chunk) = resp.chunk().await.map_err(|e| e.to_string())? {
+        bytes.extend_from_slice(&chunk);
+        if bytes.len() > MAX_BYTES {
+            return Err(too_large());
+        }
+    }
+
+    let media_type = sniff(&bytes).ok_or("not a PNG, JPEG, GIF or WebP image")?;
+    Ok(Image { media_type: media_type.to_string(), data: BASE64.encode(&bytes) })
+}
+
+/// A client for one hop to `url` that doesn't follow redirects, after
+/// checking where its host resolves. The checked addresses are pinned so a
+/// second DNS answer can't point the connection elsewhere. Behind a proxy
+/// the proxy resolves the host, so a name it alone can resolve is let through.
+async fn client_for_host(url: &Url, proxy: Option<&str>, connect: Duration) -> Result<Client, String> {
+    let mut builder = Client::builder()
+        .connect_timeout(connect)
+        .redirect(reqwest::redirect::Policy::none());
+    let host = url.host_str().ok_or("URL has no host")?;
+    if let Ok(ip) = host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+        if is_blocked(ip) {
+            return Err(format!("{} is a blocked address", ip));
+        }
+    } else {
+        let port = url.port_or_known_default().unwrap_or(80);
+        let proxied = proxy.is_some_and(|p| p != outbound::DIRECT);
+        match tokio::net::lookup_host((host, port)).await {
+            Ok(addrs) => {
+                let addrs: Vec<SocketAddr> = addrs.collect();
+                if let Some(addr) = addrs.iter().find(|addr| is_blocked(addr.ip())) {
+                    return Err(format!("{} resolves to a blocked address ({})", host, addr.ip()));
+                }
+                builder = builder.resolve_to_addrs(host, &addrs);
+            }
+            Err(e) if !proxied => return Err(format!("resolving {}: {}", host, e)),
+            Err(_) => {}
+        }
+    }
+    outbound::with_proxy(builder, proxy)
+        .and_then(|builder| builder.build())
+        .map_err(|e| e.to_string())
+}
+
+/// Addresses images are never fetched from: this host, link-local ones
+/// (where cloud metadata services live) and others no image is served on.
+/// Private network ranges stay allowed, as inlining them is the point.
+fn is_blocked(ip: IpAddr) -> bool {
+    match ip.to_canonical() {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || a == 0
+                // Shared address space, which holds Alibaba Cloud's metadata service.
+                || (a == 100 && (b & 0xC0) == 64)
+        }
+        IpAddr::V6(ip) => {
+            ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || (ip.segments()[0] & 0xFFC0) == 0xFE80
+                || ip == AWS_METADATA_V6
+        }
+    }
+}
+
+/// The image type of `bytes` from its signature; the `Content-Type` of
+/// arbitrary hosts isn't reliable.
+fn sniff(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_image_types() {
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\0\0"), Some("image/png"));
+        assert_eq!(sniff(&[0xFF, 0xD8, 0xFF, 0xE0]), Some("image/jpeg"));
+        assert_eq!(sniff(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(sniff(b"<html>"), None);
+    }
+
+    #[test]
+    fn parses_data_urls_and_decides_what_to_inline() {
+        let image = parse_data_url("data:image/png;base64,iVBORw0KGgo=").unwrap();
+        assert_eq!(image, Image { media_type: "image/png".into(), data: "iVBORw0KGgo=".into() });
+        assert!(parse_data_url("data:image/svg+xml;base64,PHN2Zz4=").is_err());
+        assert!(parse_data_url("data:text/plain,hello").is_err());
+
+        assert!(!accepts_url(ChatFormat::Gemini, "https://x/y.png"));
+        assert!(accepts_url(ChatFormat::Anthropic, "https://x/y.png"));
+        assert!(!accepts_url(ChatFormat::Anthropic, "data:image/png;base64,AAAA"));
+        assert!(accepts_url(ChatFormat::OpenaiChat, "data:image/png;base64,AAAA"));
+    }
+
+    #[test]
+    fn blocks_local_and_metadata_addresses() {
+        for blocked in ["127.0.0.1", "169.254.169.254", "0.0.0.0", "100.100.100.200", "::1", "fe80::1", "::ffff:127.0.0.1", "fd00:ec2::254"] {
+            assert!(is_blocked(blocked.parse().unwrap()), "{} should be blocked", blocked);
+        }
+        for allowed in ["93.184.216.34", "10.0.0.5", "192.168.1.20", "2606:4700::1111", "fd12::1"] {
+            assert!(!is_blocked(allowed.parse().unwrap()), "{} should be allowed", allowed);
+        }
+    }
+}
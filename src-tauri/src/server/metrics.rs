@@ -0,0 +1,379 @@
+//! Prometheus metrics for proxied requests, served as text on `/metrics`.
+//!
+//! Request series are labeled by route (path prefix), target id, upstream
+//! format and model. The model label is the requested model when the target
+//! maps it and [`OTHER_MODEL`] otherwise, so clients can't create series at
+//! will. Each upstream request is tracked by a
+//! [`RequestObserver`], which keeps the in-flight gauge accurate even when a
+//! stream is dropped because the client went away.
+
+use super::traffic::TrafficTracker;
+use crate::routing::circuit::CircuitBreaker;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use sqlx::SqlitePool;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const REQUEST_LABELS: &[&str] = &["route", "target", "upstream_format", "model"];
+const CIRCUIT_STATES: &[&str] = &["closed", "open", "half_open"];
+/// Model label for models the target doesn't map.
+pub const OTHER_MODEL: &str = "other";
+/// How long the target list behind the circuit gauges is reused between scrapes.
+const TARGETS_TTL: Duration = Duration::from_secs(30);
+
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    errors: IntCounterVec,
+    duration: HistogramVec,
+    ttft: HistogramVec,
+    tokens: IntCounterVec,
+    in_flight: IntGaugeVec,
+    circuit_state: IntGaugeVec,
+    circuit_failures: IntGaugeVec,
+    /// Configured target ids, with when they were read.
+    targets: Mutex<Option<(Instant, Vec<String>)>>,
+}
+
+/// How a proxied request ended.
+#[derive(Debug, Clone, Copy)]
+pub enum Outcome {
+    /// A response with this status was relayed to the client.
+    Status(u16),
+    /// The upstream could not be reached or its stream broke.
+    UpstreamError,
+    /// The client disconnected before the response finished.
+    ClientClosed,
+}
+
+impl Outcome {
+    fn status_label(self) -> String {
+        match self {
+            Outcome::Status(code) => code.to_string(),
+            Outcome::UpstreamError => "error".to_string(),
+            Outcome::ClientClosed => "client_closed".to_string(),
+        }
+    }
+
+    /// Error class, or `None` for a successful request.
+    fn error_class(self) -> Option<&'static str> {
+        match self {
+            Outcome::Status(code) if code < 400 => None,
+            Outcome::Status(code) if code < 500 => Some("4xx"),
+            Outcome::Status(_) => Some("5xx"),
+            Outcome::UpstreamError => Some("upstream"),
+            Outcome::ClientClosed => Some("client_closed"),
+        }
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let labels_with = |extra: &'static str| {
+            let mut labels = REQUEST_LABELS.to_vec();
+            labels.push(extra);
+            labels
+        };
+
+        let requests = IntCounterVec::new(
+            Opts::new("omnikit_requests_total", "Proxied requests by final status"),
+            &labels_with("status"),
+        )
+        .unwrap();
+        let errors = IntCounterVec::new(
+            Opts::new(
+                "omnikit_request_errors_total",
+                "Failed requests by class (4xx, 5xx, upstream, client_closed)",
+            ),
+            &labels_with("class"),
+        )
+        .unwrap();
+        let duration = HistogramVec::new(
+            HistogramOpts::new(
+                "omnikit_request_duration_seconds",
+                "Time from upstream request to the end of the response, including streaming",
+            )
+            .buckets(vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0]),
+            REQUEST_LABELS,
+        )
+        .unwrap();
+        let ttft = HistogramVec::new(
+            HistogramOpts::new(
+                "omnikit_time_to_first_token_seconds",
+                "Time from upstream request to the first streamed chunk",
+            )
+            .buckets(vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.0, 5.0, 10.0, 30.0]),
+            REQUEST_LABELS,
+        )
+        .unwrap();
+        let tokens = IntCounterVec::new(
+            Opts::new("omnikit_tokens_total", "Tokens reported by upstream usage"),
+            &labels_with("kind"),
+        )
+        .unwrap();
+        let in_flight = IntGaugeVec::new(
+            Opts::new("omnikit_requests_in_flight", "Upstream requests currently in progress"),
+            REQUEST_LABELS,
+        )
+        .unwrap();
+        let circuit_state = IntGaugeVec::new(
+            Opts::new(
+                "omnikit_circuit_state",
+                "Circuit breaker state per target (1 for the current state)",
+            ),
+            &["target", "state"],
+        )
+        .unwrap();
+        let circuit_failures = IntGaugeVec::new(
+            Opts::new(
+                "omnikit_circuit_consecutive_failures",
+                "Consecutive failures recorded by the circuit breaker per target",
+            ),
+            &["target"],
+        )
+        .unwrap();
+
+        let registry = Registry::new();
+        for collector in [
+            Box::new(requests.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(errors.clone()),
+            Box::new(duration.clone()),
+            Box::new(ttft.clone()),
+            Box::new(tokens.clone()),
+            Box::new(in_flight.clone()),
+            Box::new(circuit_state.clone()),
+            Box::new(circuit_failures.clone()),
+        ] {
+            registry.register(collector).unwrap();
+        }
+
+        Self {
+            registry,
+            requests,
+            errors,
+            duration,
+            ttft,
+            tokens,
+            in_flight,
+            circuit_state,
+            circuit_failures,
+            targets: Mutex::new(None),
+        }
+    }
+
+    /// Start tracking an upstream request.
+    pub fn observe(
+        self: &Arc<Self>,
+        route: &str,
+        target: &str,
+        upstream_format: &str,
+        model: &str,
+    ) -> RequestObserver {
+        let labels = [
+            route.to_string(),
+            target.to_string(),
+            upstream_format.to_string(),
+            model.to_string(),
+        ];
+        self.in_flight.with_label_values(&labels).inc();
+        RequestObserver {
+            metrics: self.clone(),
+            labels,
+            start: Instant::now(),
+            saw_first_token: false,
+            finished: false,
+            traffic: None,
+        }
+    }
+
+    /// Render all metrics in the Prometheus text format. Circuit breaker
+    /// gauges are refreshed first, covering every configured target.
+    pub async fn render(&self, db: &SqlitePool, circuit: &CircuitBreaker) -> String {
+        let target_ids = self.target_ids(db).await;
+        let snapshots = circuit.snapshot();
+
+        self.circuit_state.reset();
+        self.circuit_failures.reset();
+        for target_id in &target_ids {
+            let snapshot = snapshots.iter().find(|s| &s.channel_id == target_id);
+            let current = snapshot.map_or("closed", |s| s.state);
+            for state in CIRCUIT_STATES {
+                self.circuit_state
+                    .with_label_values(&[target_id.as_str(), state])
+                    .set(i64::from(*state == current));
+            }
+            self.circuit_failures
+                .with_label_values(&[target_id.as_str()])
+                .set(snapshot.map_or(0, |s| i64::from(s.consecutive_failures)));
+        }
+
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            log::error!("Failed to encode metrics: {}", e);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+
+    /// Configured target ids, read from the database at most once per
+    /// [`TARGETS_TTL`] however often the endpoint is scraped.
+    async fn target_ids(&self, db: &SqlitePool) -> Vec<String> {
+        if let Some((read, ids)) = self.targets.lock().unwrap().as_ref() {
+            if read.elapsed() < TARGETS_TTL {
+                return ids.clone();
+            }
+        }
+        let ids: Vec<String> = match sqlx::query_scalar("SELECT id FROM route_targets")
+            .fetch_all(db)
+            .await
+        {
+            Ok(ids) => ids,
+            Err(e) => {
+                log::warn!("Failed to list targets for metrics: {}", e);
+                return Vec::new();
+            }
+        };
+        *self.targets.lock().unwrap() = Some((Instant::now(), ids.clone()));
+        ids
+    }
+}
+
+/// Tracks one upstream request from send to the end of its response.
+/// Dropping it without [`RequestObserver::finish`] records a client disconnect.
+pub struct RequestObserver {
+    metrics: Arc<Metrics>,
+    labels: [String; 4],
+    start: Instant,
+    saw_first_token: bool,
+    finished: bool,
+    /// Also report the request on the live traffic feed.
+    traffic: Option<TrafficTracker>,
+}
+
+impl RequestObserver {
+    pub fn with_traffic(mut self, traffic: TrafficTracker) -> Self {
+        self.traffic = Some(traffic);
+        self
+    }
+
+    /// Record time to first token; only the first call counts.
+    pub fn first_token(&mut self) {
+        if !self.saw_first_token {
+            self.saw_first_token = true;
+            self.metrics
+                .ttft
+                .with_label_values(&self.labels)
+                .observe(self.start.elapsed().as_secs_f64());
+            let elapsed_ms = self.elapsed_ms();
+            if let Some(traffic) = &mut self.traffic {
+                traffic.first_token(elapsed_ms);
+            }
+        }
+    }
+
+    /// Time since the upstream request was sent.
+    pub fn elapsed_ms(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+
+    /// Count reported usage. Ignored once the request has finished.
+    pub fn tokens(&mut self, prompt: Option<i64>, completion: Option<i64>) {
+        if self.finished {
+            return;
+        }
+        if let Some(traffic) = &mut self.traffic {
+            traffic.tokens(prompt, completion);
+        }
+        for (kind, count) in [("prompt", prompt), ("completion", completion)] {
+            if let Some(count) = count.filter(|c| *c > 0) {
+                self.metrics
+                    .tokens
+                    .with_label_values(&self.label_refs_with(kind))
+                    .inc_by(count as u64);
+            }
+        }
+    }
+
+    /// Describe an upstream failure for the traffic feed.
+    pub fn error(&mut self, error: &dyn std::fmt::Display) {
+        if let Some(traffic) = &mut self.traffic {
+            traffic.error(error.to_string());
+        }
+    }
+
+    /// Record how the request ended. Later calls are ignored.
+    pub fn finish(&mut self, outcome: Outcome) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+        let metrics = &self.metrics;
+        metrics
+            .requests
+            .with_label_values(&self.label_refs_with(&outcome.status_label()))
+            .inc();
+        if let Some(class) = outcome.error_class() {
+            metrics
+                .errors
+                .with_label_values(&self.label_refs_with(class))
+                .inc();
+        }
+        metrics
+            .duration
+            .with_label_values(&self.labels)
+            .observe(self.start.elapsed().as_secs_f64());
+        metrics.in_flight.with_label_values(&self.labels).dec();
+        let elapsed_ms = self.elapsed_ms();
+        if let Some(traffic) = &mut self.traffic {
+            traffic.finish(outcome, elapsed_ms);
+        }
+    }
+
+    fn label_refs_with<'a>(&'a self, extra: &'a str) -> [&'a str; 5] {
+        [
+            &self.labels[0],
+            &self.labels[1],
+            &self.labels[2],
+            &self.labels[3],
+            extra,
+        ]
+    }
+}
+
+impl Drop for RequestObserver {
+    fn drop(&mut self) {
+        self.finish(Outcome::ClientClosed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn observer_tracks_in_flight_and_outcomes() {
+        let metrics = Arc::new(Metrics::new());
+        let labels = ["/openai", "t1", "openai-chat", "gpt-4o"];
+
+        let mut observer = metrics.observe("/openai", "t1", "openai-chat", "gpt-4o");
+        assert_eq!(metrics.in_flight.with_label_values(&labels).get(), 1);
+        observer.first_token();
+        observer.first_token();
+        observer.tokens(Some(10), Some(5));
+        observer.finish(Outcome::Status(200));
+        assert_eq!(metrics.in_flight.with_label_values(&labels).get(), 0);
+        assert_eq!(metrics.ttft.with_label_values(&labels).get_sample_count(), 1);
+
+        // Dropped mid-stream: counted as a client disconnect.
+        drop(metrics.observe("/openai", "t1", "openai-chat", "gpt-4o"));
+        assert_eq!(metrics.in_flight.with_label_values(&labels).get(), 0);
+        let closed = ["/openai", "t1", "openai-chat", "gpt-4o", "client_closed"];
+        assert_eq!(metrics.errors.with_label_values(&closed).get(), 1);
+
+        let ok = ["/openai", "t1", "openai-chat", "gpt-4o", "200"];
+        assert_eq!(metrics.requests.with_label_values(&ok).get(), 1);
+        let prompt = ["/openai", "t1", "openai-chat", "gpt-4o", "prompt"];
+        assert_eq!(metrics.tokens.with_label_values(&prompt).get(), 10);
+    }
+}
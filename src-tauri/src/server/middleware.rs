@@ -1,5 +1,19 @@
+use crate::db::models::Token;
 use crate::error::AppError;
+use crate::secrets::{self, KeyVault};
+use axum::extract::{ConnectInfo, Request, State};
 use axum::http::HeaderMap;
+use axum::middleware::Next;
+use axum::response::Response;
+use ipnet::IpNet;
+use sqlx::SqlitePool;
+use std::net::{IpAddr, SocketAddr};
+
+use super::proxy::ProxyState;
+
+/// Header naming the token to act as when authenticating with the vault's
+/// per-process session key (used by in-app request retry).
+pub const INTERNAL_TOKEN_ID_HEADER: &str = "x-omnikit-token-id";
 
 /// Extract the client's API token from request headers.
 ///
@@ -15,8 +29,8 @@ use axum::http::HeaderMap;
 pub fn extract_bearer_token(headers: &HeaderMap) -> Result<String, AppError> {
     // Priority 1: Authorization: Bearer (standard / OpenAI-compatible)
     if let Some(auth) = headers.get("authorization").and_then(|v| v.to_str().ok()) {
-        if auth.starts_with("Bearer ") {
-            return Ok(auth[7..].to_string());
+        if let Some(token) = auth.strip_prefix("Bearer ") {
+            return Ok(token.to_string());
         }
         return Err(AppError::Unauthorized("Invalid Authorization format".into()));
     }
@@ -45,22 +59,154 @@ pub fn extract_bearer_token(headers: &HeaderMap) -> Result<String, AppError> {
     Err(AppError::Unauthorized("Missing Authorization header".into()))
 }
 
-/// Determine desired output format from headers or query params.
-/// Returns None if not specified (meaning: same as input format).
-pub fn extract_output_format(headers: &HeaderMap, query: Option<&str>) -> Option<String> {
-    // Priority 1: X-Output-Format header
-    if let Some(v) = headers.get("x-output-format").and_then(|v| v.to_str().ok()) {
-        return Some(v.to_string());
+/// Resolve the presented token to an enabled `tokens` row.
+///
+/// Tokens are looked up by their clear-text prefix and then checked against
+/// the stored salted hash.
+pub async fn authenticate_token(
+    db: &SqlitePool,
+    vault: &KeyVault,
+    headers: &HeaderMap,
+    token_value: &str,
+) -> Result<Token, AppError> {
+    if vault.is_session_key(token_value) {
+        let token_id = headers
+            .get(INTERNAL_TOKEN_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| AppError::Unauthorized("Invalid API key".into()))?;
+        return sqlx::query_as::<_, Token>("SELECT * FROM tokens WHERE id = ? AND enabled = 1")
+            .bind(token_id)
+            .fetch_optional(db)
+            .await?
+            .ok_or_else(|| AppError::Unauthorized("Invalid API key".into()));
+    }
+
+    let candidates = sqlx::query_as::<_, Token>(
+        "SELECT * FROM tokens WHERE key_prefix = ? AND enabled = 1",
+    )
+    .bind(secrets::token_prefix(token_value))
+    .fetch_all(db)
+    .await?;
+
+    candidates
+        .into_iter()
+        .find(|t| secrets::verify_token(token_value, &t.key_value))
+        .ok_or_else(|| AppError::Unauthorized("Invalid API key".into()))
+}
+
+/// Reject a request that would take the token past its quota. `estimated`
+/// is the request's input tokens when known before forwarding, else 0, so a
+/// spent quota is still caught.
+pub fn check_quota(token: &Token, estimated: i64) -> Result<(), AppError> {
+    match token.quota_limit {
+        Some(limit) if token.quota_used + estimated > limit || token.quota_used >= limit => {
+            Err(AppError::QuotaExceeded(format!(
+                "{} of {} tokens used, request needs about {}",
+                token.quota_used, limit, estimated
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Parse `allowed_ips` entries: single addresses or CIDR ranges.
+pub fn parse_allow_list(entries: &[String]) -> Result<Vec<IpNet>, String> {
+    entries
+        .iter()
+        .map(|e| e.trim())
+        .filter(|e| !e.is_empty())
+        .map(|e| {
+            e.parse::<IpNet>()
+                .or_else(|_| e.parse::<IpAddr>().map(IpNet::from))
+                .map_err(|_| format!("Invalid IP or CIDR: {}", e))
+        })
+        .collect()
+}
+
+/// Check that a listener on `bind_address` lets someone in: other machines
+/// are only admitted through `allowed_ips`, so a non-loopback bind needs it.
+pub fn check_exposure(bind_address: &str, allowed_ips: &[String]) -> Result<(), String> {
+    let ip: IpAddr = bind_address
+        .parse()
+        .map_err(|_| format!("Invalid bind address: {}", bind_address))?;
+    if !ip.is_loopback() && parse_allow_list(allowed_ips)?.is_empty() {
+        return Err(format!(
+            "Binding to {} needs allowed_ips listing the clients to let in, e.g. 192.168.1.0/24 (0.0.0.0/0 and ::/0 admit everyone)",
+            ip
+        ));
     }
+    Ok(())
+}
 
-    // Priority 2: output_format query param
-    if let Some(q) = query {
-        for pair in q.split('&') {
-            if let Some(val) = pair.strip_prefix("output_format=") {
-                return Some(val.to_string());
-            }
+/// Loopback clients are always allowed; others only when listed.
+fn is_allowed(ip: IpAddr, allow_list: &[IpNet]) -> bool {
+    // IPv4 clients on a dual-stack listener show up as IPv4-mapped IPv6.
+    let ip = match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+        v4 => v4,
+    };
+    ip.is_loopback() || allow_list.iter().any(|net| net.contains(&ip))
+}
+
+/// Reject clients outside the configured `allowed_ips`.
+pub async fn ip_allow_list(
+    State(state): State<ProxyState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let allowed = {
+        let config = state.config.read().await;
+        match parse_allow_list(&config.allowed_ips) {
+            Ok(allow_list) => is_allowed(peer.ip(), &allow_list),
+            // Entries are validated when saved; fail closed if one slipped through.
+            Err(_) => peer.ip().is_loopback(),
         }
+    };
+    if !allowed {
+        log::warn!("Rejected connection from {} (not in allowed_ips)", peer.ip());
+        return Err(AppError::Forbidden(format!("{} is not allowed", peer.ip())));
+    }
+    Ok(next.run(req).await)
+}
+
+/// Reject clients on other machines, for endpoints that are only meant for
+/// the desktop app or a local scraper whatever the listener is bound to.
+pub async fn local_only(
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    if !peer.ip().to_canonical().is_loopback() {
+        log::warn!("Rejected {} from {}: local clients only", req.uri().path(), peer.ip());
+        return Err(AppError::Forbidden(format!(
+            "{} only accepts clients on this machine",
+            req.uri().path()
+        )));
     }
+    Ok(next.run(req).await)
+}
 
-    None
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allow_list_matches_ips_and_ranges() {
+        let list = parse_allow_list(&["10.0.0.0/8".into(), " 192.168.1.7 ".into()]).unwrap();
+        assert!(is_allowed("10.2.3.4".parse().unwrap(), &list));
+        assert!(is_allowed("192.168.1.7".parse().unwrap(), &list));
+        assert!(is_allowed("::ffff:10.0.0.1".parse().unwrap(), &list));
+        assert!(is_allowed("127.0.0.1".parse().unwrap(), &list));
+        assert!(!is_allowed("192.168.1.8".parse().unwrap(), &list));
+        assert!(!is_allowed("203.0.113.9".parse().unwrap(), &[]));
+        assert!(is_allowed("203.0.113.9".parse().unwrap(), &parse_allow_list(&["0.0.0.0/0".into()]).unwrap()));
+        assert!(parse_allow_list(&["not-an-ip".into()]).is_err());
+
+        assert!(check_exposure("127.0.0.1", &[]).is_ok());
+        assert!(check_exposure("::1", &[]).is_ok());
+        assert!(check_exposure("0.0.0.0", &[]).is_err());
+        assert!(check_exposure("0.0.0.0", &[" ".into()]).is_err());
+        assert!(check_exposure("0.0.0.0", &["192.168.1.0/24".into()]).is_ok());
+    }
 }
@@ -1,20 +1,53 @@
+pub mod admin;
+pub mod alerts;
+pub mod count_tokens;
+pub mod images;
+pub mod metrics;
 pub mod middleware;
+pub mod model_list;
 pub mod proxy;
+pub mod redact;
 pub mod router;
+pub mod stream_log;
+pub mod supervisor;
+pub mod tls;
+pub mod traffic;
 
+use crate::config::AppConfig;
+use crate::secrets::KeyVault;
+use crate::services::config_watch::ConfigWatcher;
 use sqlx::SqlitePool;
-use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
 
-pub async fn start(
-    pool: SqlitePool,
-    port: u16,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let app = router::create_router(pool).await;
-    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+pub use supervisor::ServerControl;
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    log::info!("Axum server listening on {}", addr);
+/// Spawn the listener supervisor and return its handle. Nothing is bound
+/// until [`ServerControl::apply`] is called with the config to serve.
+/// Also starts pruning request logs past their retention.
+pub fn start(
+    pool: SqlitePool,
+    config: Arc<RwLock<AppConfig>>,
+    vault: Arc<KeyVault>,
+    watcher: ConfigWatcher,
+    traffic: traffic::TrafficBus,
+    alerts: alerts::Alerter,
+    data_dir: PathBuf,
+) -> ServerControl {
+    let (control, supervisor) = supervisor::channel();
+    crate::services::request_logs::spawn_retention(pool.clone(), config.clone());
+    let state =
+        router::create_state(pool, config, vault, control.clone(), watcher, traffic, alerts);
+    supervisor.spawn(state, data_dir);
+    control
+}
 
-    axum::serve(listener, app).await?;
-    Ok(())
+/// HTTP client for the app's own requests to the gateway (status probes,
+/// retries, route tests). Accepts the self-signed certificate when TLS is on.
+pub fn local_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()
+        .unwrap_or_default()
 }
@@ -0,0 +1,256 @@
+//! The model list a route serves at `GET /{prefix}/v1/models`.
+//!
+//! It combines the models mapped on the route's targets with those each
+//! target's upstream lists, and is rendered in the caller's format: OpenAI,
+//! Anthropic or Gemini. Upstream lists are cached per target for [`TTL`];
+//! targets whose upstream can't be listed contribute their mappings only.
+
+use crate::db::models::{Route, RouteTarget, RouteTargetKey};
+use crate::error::AppError;
+use crate::modality::chat::ChatFormat;
+use crate::outbound::Timeouts;
+use crate::services::routes::load_models;
+use axum::http::HeaderMap;
+use axum::response::{IntoResponse, Json, Response};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::proxy::{apply_auth, ProxyState};
+
+/// How long an upstream's model list is reused before it is fetched again.
+const TTL: Duration = Duration::from_secs(300);
+
+/// Upstream model lists by target id.
+pub struct ModelCache {
+    entries: Mutex<HashMap<String, (Instant, Vec<String>)>>,
+}
+
+impl ModelCache {
+    pub fn new() -> Self {
+        Self { entries: Mutex::new(HashMap::new()) }
+    }
+
+    fn get(&self, target_id: &str) -> Option<Vec<String>> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(target_id)
+            .filter(|(fetched, _)| fetched.elapsed() < TTL)
+            .map(|(_, models)| models.clone())
+    }
+
+    fn put(&self, target_id: &str, models: Vec<String>) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (fetched, _)| fetched.elapsed() < TTL);
+        entries.insert(target_id.to_string(), (Instant::now(), models));
+    }
+}
+
+/// Whether `sub_path` (the path after the route prefix) asks for the model list.
+pub fn is_models_path(sub_path: &str) -> bool {
+    let path = sub_path.split('?').next().unwrap_or(sub_path);
+    let normalized = path
+        .strip_prefix("/v1beta")
+        .or_else(|| path.strip_prefix("/v1"))
+        .unwrap_or(path);
+    normalized == "/models"
+}
+
+/// The format to answer in: Gemini for its `/v1beta` path, Anthropic when the
+/// route speaks it or the client sent `anthropic-version`, OpenAI otherwise.
+fn list_format(route: &Route, sub_path: &str, headers: &HeaderMap) -> ChatFormat {
+    if sub_path.starts_with("/v1beta") || route.input_format == "gemini" {
+        ChatFormat::Gemini
+    } else if route.input_format == "anthropic" || headers.contains_key("anthropic-version") {
+        ChatFormat::Anthropic
+    } else {
+        ChatFormat::OpenaiChat
+    }
+}
+
+pub async fn handle_models(
+    state: &ProxyState,
+    route: &Route,
+    headers: &HeaderMap,
+    sub_path: &str,
+) -> Result<Response, AppError> {
+    let models = list_models(state, route).await?;
+    let format = list_format(route, sub_path, headers);
+    Ok(Json(render(format, &models)).into_response())
+}
+
+/// Mapped models first, in configuration order, then discovered ones.
+async fn list_models(state: &ProxyState, route: &Route) -> Result<Vec<String>, AppError> {
+    let targets = sqlx::query_as::<_, RouteTarget>(
+        "SELECT * FROM route_targets WHERE route_id = ? AND enabled = 1 ORDER BY created_at ASC",
+    )
+    .bind(&route.id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut models = Vec::new();
+    for target in &targets {
+        models.extend(load_models(&state.db, &target.id).await?.into_iter().map(|m| m.model));
+    }
+    let discovered = futures_util::future::join_all(
+        targets
+            .iter()
+            .filter(|t| state.circuit.is_available(&t.id))
+            .map(|t| discover(state, t)),
+    )
+    .await;
+    models.extend(discovered.into_iter().flatten());
+
+    let mut seen = std::collections::HashSet::new();
+    models.retain(|m| seen.insert(m.clone()));
+    Ok(models)
+}
+
+/// The target's upstream model list, from the cache when fresh. Failures are
+/// logged and not cached, so the next request tries again.
+async fn discover(state: &ProxyState, target: &RouteTarget) -> Vec<String> {
+    if let Some(models) = state.models.get(&target.id) {
+        return models;
+    }
+    match fetch_upstream_models(state, target).await {
+        Ok(Some(models)) => {
+            state.models.put(&target.id, models.clone());
+            models
+        }
+        Ok(None) => Vec::new(),
+        Err(e) => {
+            log::warn!("Listing models of target {} failed: {}", target.id, e);
+            Vec::new()
+        }
+    }
+}
+
+/// `None` when the target's format has no model list endpoint.
+async fn fetch_upstream_models(
+    state: &ProxyState,
+    target: &RouteTarget,
+) -> Result<Option<Vec<String>>, AppError> {
+    let Some(format) = ChatFormat::from_str_loose(&target.upstream_format) else {
+        return Ok(None);
+    };
+    // Azure lists deployments, not models, under another API.
+    if format == ChatFormat::AzureOpenAi {
+        return Ok(None);
+    }
+    let key = sqlx::query_as::<_, RouteTargetKey>(
+        "SELECT * FROM route_target_keys WHERE target_id = ? AND enabled = 1 ORDER BY id ASC LIMIT 1",
+    )
+    .bind(&target.id)
+    .fetch_optional(&state.db)
+    .await?;
+    let api_key = match key {
+        Some(key) => state
+            .vault
+            .decrypt(&key.key_value)
+            .map_err(|e| AppError::Internal(format!("Upstream key for target {}: {}", target.id, e)))?,
+        None => String::new(),
+    };
+
+    let url = format!("{}/models", target.base_url.trim_end_matches('/'));
+    let request = state
+        .client_for(target)
+        .await?
+        .get(&url)
+        .timeout(Timeouts::for_target(target).first_byte);
+    let resp = apply_auth(request, format, &api_key).send().await?;
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(AppError::Internal(format!("{} returned HTTP {}", url, status.as_u16())));
+    }
+    let body: Value = resp.json().await?;
+    Ok(Some(parse_model_ids(format, &body)))
+}
+
+/// Model ids from an upstream list: `data[].id` for OpenAI-compatible and
+/// Anthropic lists, `models[].name` without its `models/` prefix for Gemini.
+fn parse_model_ids(format: ChatFormat, body: &Value) -> Vec<String> {
+    let (items, field) = match format {
+        ChatFormat::Gemini => (&body["models"], "name"),
+        _ => (&body["data"], "id"),
+    };
+    items
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|item| item[field].as_str())
+        .map(|id| id.strip_prefix("models/").unwrap_or(id).to_string())
+        .collect()
+}
+
+fn render(format: ChatFormat, models: &[String]) -> Value {
+    match format {
+        ChatFormat::Anthropic => json!({
+            "data": models
+                .iter()
+                .map(|id| json!({
+                    "type": "model",
+                    "id": id,
+                    "display_name": id,
+                    "created_at": "1970-01-01T00:00:00Z",
+                }))
+                .collect::<Vec<_>>(),
+            "has_more": false,
+            "first_id": models.first(),
+            "last_id": models.last(),
+        }),
+        ChatFormat::Gemini => json!({
+            "models": models
+                .iter()
+                .map(|id| json!({
+                    "name": format!("models/{}", id),
+                    "displayName": id,
+                    "supportedGenerationMethods": ["generateContent", "streamGenerateContent"],
+                }))
+                .collect::<Vec<_>>(),
+        }),
+        _ => json!({
+            "object": "list",
+            "data": models
+                .iter()
+                .map(|id| json!({
+                    "id": id,
+                    "object": "model",
+                    "created": 0,
+                    "owned_by": "omnikit",
+                }))
+                .collect::<Vec<_>>(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_models_paths() {
+        assert!(is_models_path("/v1/models"));
+        assert!(is_models_path("/models"));
+        assert!(is_models_path("/v1beta/models?pageSize=50"));
+        assert!(!is_models_path("/v1/models/gpt-4o"));
+        assert!(!is_models_path("/v1/chat/completions"));
+    }
+
+    #[test]
+    fn parses_and_renders_each_format() {
+        let openai = json!({"object": "list", "data": [{"id": "gpt-4o", "object": "model"}]});
+        assert_eq!(parse_model_ids(ChatFormat::OpenaiChat, &openai), ["gpt-4o"]);
+        let gemini = json!({"models": [{"name": "models/gemini-2.0-flash"}], "nextPageToken": "x"});
+        assert_eq!(parse_model_ids(ChatFormat::Gemini, &gemini), ["gemini-2.0-flash"]);
+
+        let models = ["claude-sonnet".to_string(), "gpt-4o".to_string()];
+        let anthropic = render(ChatFormat::Anthropic, &models);
+        assert_eq!(parse_model_ids(ChatFormat::Anthropic, &anthropic), models);
+        assert_eq!(anthropic["last_id"], "gpt-4o");
+        let gemini = render(ChatFormat::Gemini, &models);
+        assert_eq!(gemini["models"][0]["name"], "models/claude-sonnet");
+        assert_eq!(parse_model_ids(ChatFormat::Gemini, &gemini), models);
+        assert_eq!(render(ChatFormat::OpenaiChat, &models)["data"][1]["id"], "gpt-4o");
+    }
+}
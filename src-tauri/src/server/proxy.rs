@@ -1,23 +1,88 @@
-use crate::db::models::{Route, Token};
+use crate::config::AppConfig;
+use crate::db::models::{Route, RouteTarget, Token};
 use crate::error::AppError;
-use crate::modality::chat::{self, ChatFormat};
+use crate::modality::chat::ir::IrError;
+use crate::modality::chat::sse::{self, SseParser};
+use crate::modality::chat::context::{self, ContextPolicy, ModelLimits};
+use crate::modality::chat::normalize;
+use crate::modality::chat::{self, bridge, ChatFormat};
+use crate::outbound::{self, ClientPool, Timeouts};
 use crate::routing::balancer::{self, KeyRotationState};
 use crate::routing::circuit::CircuitBreaker;
+use crate::server::alerts::Alerter;
+use crate::server::count_tokens;
+use crate::server::images::{self, ImageCache};
+use crate::server::metrics::{Metrics, Outcome, RequestObserver, OTHER_MODEL};
 use crate::server::middleware;
+use crate::server::model_list::{self, ModelCache};
+use crate::secrets::KeyVault;
+use crate::server::redact::Redactor;
+use crate::server::stream_log::{StreamLog, StreamOutcome};
+use crate::server::traffic::{TrafficBus, TrafficRequest};
+use crate::telemetry;
 use axum::body::Body;
 use axum::extract::{Request, State};
 use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
 use axum::response::Response;
 use sqlx::SqlitePool;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
 use tokio_stream::StreamExt;
+use tracing::field::Empty;
+use tracing::Instrument;
 
 #[derive(Clone)]
 pub struct ProxyState {
     pub db: SqlitePool,
-    pub http_client: reqwest::Client,
+    /// Upstream clients, one per outbound proxy.
+    pub clients: Arc<ClientPool>,
     pub circuit: Arc<CircuitBreaker>,
     pub rotation: Arc<KeyRotationState>,
+    pub config: Arc<RwLock<AppConfig>>,
+    pub vault: Arc<KeyVault>,
+    /// Lets the admin API rebind the listeners after a config change.
+    pub server: super::ServerControl,
+    pub watcher: crate::services::config_watch::ConfigWatcher,
+    pub metrics: Arc<Metrics>,
+    pub traffic: TrafficBus,
+    pub alerts: Alerter,
+    /// Upstream model lists for `/v1/models`.
+    pub models: Arc<ModelCache>,
+    /// Images downloaded for targets that take them inline.
+    pub images: Arc<ImageCache>,
+}
+
+impl ProxyState {
+    /// HTTP client for `target`, going through its outbound proxy with its
+    /// connect timeout.
+    pub async fn client_for(&self, target: &RouteTarget) -> Result<reqwest::Client, AppError> {
+        let global = self.config.read().await.proxy_url.clone();
+        let proxy = outbound::effective_proxy(target.proxy_url.as_deref(), global.as_deref());
+        self.clients
+            .get(Some(&target.id), proxy, Timeouts::for_target(target).connect)
+            .map_err(|e| AppError::Internal(format!("Outbound proxy for target {}: {}", target.id, e)))
+    }
+
+    /// HTTP client going through the global outbound proxy.
+    pub async fn default_client(&self) -> Result<reqwest::Client, AppError> {
+        let global = self.config.read().await.proxy_url.clone();
+        self.clients
+            .get(None, global.as_deref().filter(|p| !p.is_empty()), Timeouts::default().connect)
+            .map_err(|e| AppError::Internal(format!("Outbound proxy: {}", e)))
+    }
+
+    /// Build a redactor from the current config, also masking the given
+    /// override header names since they may carry upstream secrets.
+    async fn redactor(&self, overrides: &[crate::db::models::RouteTargetOverride]) -> Redactor {
+        let config = self.config.read().await;
+        Redactor::new(&config.redaction).with_extra_headers(
+            overrides
+                .iter()
+                .filter(|o| o.scope == "header")
+                .map(|o| o.key.as_str()),
+        )
+    }
 }
 
 fn detect_chat_format_from_path(path: &str) -> Option<&'static str> {
@@ -72,7 +137,7 @@ fn build_upstream_url(base_url: &str, format: ChatFormat, model: &str, stream: b
     }
 }
 
-fn apply_auth(
+pub(crate) fn apply_auth(
     builder: reqwest::RequestBuilder,
     format: ChatFormat,
     api_key: &str,
@@ -92,7 +157,7 @@ fn apply_auth(
 
 /// 将覆盖规则应用到上游请求。
 /// 返回 (修改后的请求体, 额外请求头列表, 修改后的URL)。
-fn apply_overrides(
+pub(crate) fn apply_overrides(
     body_bytes: &[u8],
     upstream_url: &str,
     overrides: &[crate::db::models::RouteTargetOverride],
@@ -152,27 +217,52 @@ const HOP_BY_HOP: &[&str] = &[
     "api-key",
     "anthropic-version",
     "x-goog-api-key",
+    middleware::INTERNAL_TOKEN_ID_HEADER,
 ];
 
-fn headers_to_json(headers: &HeaderMap) -> Option<String> {
-    let mut map = serde_json::Map::new();
-    for (k, v) in headers.iter() {
-        if let Ok(v_str) = v.to_str() {
-            map.insert(k.as_str().to_string(), serde_json::Value::String(v_str.to_string()));
-        }
-    }
-    if map.is_empty() {
-        None
-    } else {
-        serde_json::to_string(&map).ok()
-    }
-}
-
 /// Main handler for all route-based proxy requests.
 pub async fn handle_route_proxy(
     State(state): State<ProxyState>,
     req: Request,
 ) -> Result<Response, AppError> {
+    let span = tracing::info_span!(
+        "proxy_request",
+        http.method = %req.method(),
+        url.path = %req.uri().path(),
+        route = Empty,
+        http.status_code = Empty,
+        error = Empty,
+    );
+    telemetry::set_parent_from(&span, req.headers());
+
+    let error_format = client_error_format(req.uri().path());
+    let result = route_proxy(state, req).instrument(span.clone()).await;
+    match result {
+        Ok(resp) => {
+            span.record("http.status_code", resp.status().as_u16());
+            Ok(resp)
+        }
+        Err(e) => {
+            span.record("error", tracing::field::display(&e));
+            Ok(e.into_response_as(error_format))
+        }
+    }
+}
+
+/// The wire format errors are rendered in, judged from the request path so
+/// that even requests rejected before their route is known get an envelope
+/// the client's SDK can parse.
+fn client_error_format(path: &str) -> ChatFormat {
+    let sub_path = strip_prefix(path, &extract_prefix(path));
+    if sub_path.contains(":generateContent") || sub_path.contains(":streamGenerateContent") {
+        return ChatFormat::Gemini;
+    }
+    detect_chat_format_from_path(&sub_path)
+        .and_then(ChatFormat::from_str_loose)
+        .unwrap_or(ChatFormat::OpenaiChat)
+}
+
+async fn route_proxy(state: ProxyState, req: Request) -> Result<Response, AppError> {
     let full_path = req.uri().path().to_string();
     let query = req.uri().query().map(|q| q.to_string());
     let method = req.method().clone();
@@ -190,16 +280,12 @@ pub async fn handle_route_proxy(
     .fetch_optional(&state.db)
     .await?
     .ok_or_else(|| AppError::NoRoute(path_prefix.clone()))?;
+    tracing::Span::current().record("route", route.path_prefix.as_str());
 
     // Authenticate token
     let token_value = middleware::extract_bearer_token(&headers)?;
-    let token = sqlx::query_as::<_, Token>(
-        "SELECT * FROM tokens WHERE key_value = ? AND enabled = 1",
-    )
-    .bind(&token_value)
-    .fetch_optional(&state.db)
-    .await?
-    .ok_or_else(|| AppError::Unauthorized("Invalid API key".into()))?;
+    let token =
+        middleware::authenticate_token(&state.db, &state.vault, &headers, &token_value).await?;
 
     if let Some(expires) = &token.expires_at {
         let now = chrono::Utc::now().naive_utc().to_string();
@@ -210,15 +296,26 @@ pub async fn handle_route_proxy(
 
     // Strip prefix to get the sub-path
     let sub_path = strip_prefix(&full_path, &path_prefix);
+    let is_passthrough = route.input_format == "none" || route.input_format.is_empty();
+
+    // Passthrough routes leave the model list to their upstream.
+    if method == axum::http::Method::GET && !is_passthrough && model_list::is_models_path(&sub_path) {
+        return model_list::handle_models(&state, &route, &headers, &sub_path).await;
+    }
 
     // Read body bytes
     let body_bytes = axum::body::to_bytes(body, 32 * 1024 * 1024)
         .await
         .map_err(|e| AppError::BadRequest(format!("Failed to read body: {}", e)))?;
 
+    if method == axum::http::Method::POST && !is_passthrough {
+        if let Some(format) = count_tokens::detect(&sub_path) {
+            return count_tokens::handle_count_tokens(&state, &route, format, &sub_path, &body_bytes).await;
+        }
+    }
+
     // Check if this path needs format conversion
     let path_format_hint = detect_chat_format_from_path(&sub_path);
-    let is_passthrough = route.input_format == "none" || route.input_format.is_empty();
 
     let request_url = match &query {
         Some(q) => format!("{}?{}", full_path, q),
@@ -227,59 +324,119 @@ pub async fn handle_route_proxy(
 
     if path_format_hint.is_some() && !is_passthrough {
         handle_format_conversion(
-            &state, &route, &token.id, &headers, &body_bytes, &sub_path, &query, &request_url,
+            &state, &route, &token, &headers, &body_bytes, &sub_path, &query, &request_url,
         )
         .await
     } else {
+        // The body isn't decoded here, so only a spent quota is caught.
+        middleware::check_quota(&token, 0)?;
         handle_passthrough(&state, &route, &token.id, &headers, &body_bytes, &sub_path, &query, method, &request_url)
             .await
     }
 }
 
 /// Handle requests that need format conversion (known codec paths).
+#[allow(clippy::too_many_arguments)]
 async fn handle_format_conversion(
     state: &ProxyState,
     route: &Route,
-    token_id: &str,
+    token: &Token,
     headers: &HeaderMap,
     body_bytes: &[u8],
     _sub_path: &str,
     _query: &Option<String>,
     request_url: &str,
 ) -> Result<Response, AppError> {
-    let start = std::time::Instant::now();
+    let start = Instant::now();
+    let token_id = token.id.as_str();
 
     let decoder = resolve_decoder(&route.input_format)?;
-    let ir = decoder.decode_request(body_bytes)?;
+    let mut ir = tracing::info_span!("decode", format = %route.input_format)
+        .in_scope(|| decoder.decode_request(body_bytes))?;
+
+    if token.quota_limit.is_some() {
+        middleware::check_quota(token, chat::tokens::estimate_request(&ir) as i64)?;
+    }
 
     let model = ir.model.clone();
-    let input_fmt_str = route.input_format.clone();
 
     let selected = balancer::select_target(
         &route.id,
         &state.db,
         &state.circuit,
         &state.rotation,
+        &state.vault,
     )
     .await?;
 
     let target = &selected.target;
     let api_key = &selected.api_key;
+    let timeouts = Timeouts::for_target(target);
     let upstream_slug = target.upstream_format.clone();
-    let output_fmt_str = upstream_slug.clone();
+    let upstream_format = ChatFormat::from_str_loose(&upstream_slug)
+        .ok_or_else(|| AppError::Codec(format!("Unknown upstream format: {}", upstream_slug)))?;
+
+    let normalization_notes = if target.normalize_messages {
+        let notes = normalize::normalize(&mut ir);
+        if !notes.is_empty() {
+            log::info!("Normalized request for target {}: {}", target.id, notes.join("; "));
+        }
+        (!notes.is_empty()).then(|| notes.join("; "))
+    } else {
+        None
+    };
+
+    if let Some(policy) = route.context_policy.as_deref().and_then(ContextPolicy::parse) {
+        if let Some(mapping) = selected.mapping(&ir.model) {
+            let limit = |l: Option<i64>| l.map(|l| l.min(u32::MAX as i64) as u32);
+            let limits = ModelLimits {
+                context_window: limit(mapping.context_window),
+                max_output_tokens: limit(mapping.max_output_tokens),
+            };
+            let fitted = context::fit(&mut ir, limits, policy).map_err(AppError::ContextTooLong)?;
+            if fitted.dropped_messages > 0 || fitted.max_tokens.is_some() {
+                log::info!(
+                    "Fitted request for {} into its limits: dropped {} messages, max_tokens {:?}",
+                    ir.model, fitted.dropped_messages, fitted.max_tokens
+                );
+            }
+        }
+    }
+
+    let inlined = images::inline_images(state, target, upstream_format, &mut ir).await?;
+    if inlined > 0 {
+        log::info!("Inlined {} images for target {}", inlined, target.id);
+    }
+
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let mut observer = observe(state, &request_id, route, &selected, &upstream_slug, &model, token_id);
+
+    // A mapped model goes upstream under its upstream name; logs keep the
+    // name the client asked for.
+    if let Some(upstream_model) = selected.upstream_model(&ir.model) {
+        ir.model = upstream_model.to_string();
+    }
+
+    // The target may call its upstream the other way than the client asked.
+    let client_stream = ir.stream;
+    ir.stream = match target.stream_mode.as_deref() {
+        Some("stream") => true,
+        Some("non_stream") => false,
+        _ => client_stream,
+    };
 
     let upstream_encoder = resolve_encoder(&upstream_slug)?;
-    let upstream_body = upstream_encoder.encode_request(&ir, &ir.model)?;
+    let upstream_body = tracing::info_span!("encode", format = %upstream_slug)
+        .in_scope(|| upstream_encoder.encode_request(&ir, &ir.model))?;
 
-    let upstream_format = ChatFormat::from_str_loose(&upstream_slug)
-        .ok_or_else(|| AppError::Codec(format!("Unknown upstream format: {}", upstream_slug)))?;
     let upstream_url = build_upstream_url(&target.base_url, upstream_format, &ir.model, ir.stream);
 
     let (upstream_body, override_headers, upstream_url) =
         apply_overrides(&upstream_body, &upstream_url, &selected.overrides);
 
     let mut req_builder = state
-        .http_client
+        .client_for(target)
+        .await?
         .post(&upstream_url)
         .header("Content-Type", "application/json")
         .body(upstream_body);
@@ -287,95 +444,95 @@ async fn handle_format_conversion(
     for (k, v) in &override_headers {
         req_builder = req_builder.header(k.as_str(), v.as_str());
     }
-
-    let request_body_str = String::from_utf8_lossy(body_bytes).to_string();
-    let req_headers_json = headers_to_json(headers);
-    let target_id = target.id.clone();
-    let route_id = route.id.clone();
-
-    let upstream_resp = req_builder.send().await;
-    let upstream_resp = match upstream_resp {
-        Ok(r) => r,
-        Err(e) => {
-            state.circuit.record_failure(&target.id);
-            let latency = start.elapsed().as_millis() as i64;
-            log_request(
-                &state.db, token_id, &route_id, &target_id, &model, "chat",
-                &input_fmt_str, &output_fmt_str, None, latency, None, None,
-                Some(&request_body_str), Some(&e.to_string()), None,
-                req_headers_json.as_deref(), None,
-                Some(request_url), Some(&upstream_url),
-            ).await;
-            return Err(AppError::HttpClient(e));
-        }
-    };
-
-    let status = upstream_resp.status();
-    if !status.is_success() {
-        state.circuit.record_failure(&target.id);
-        let resp_headers_json = headers_to_json(upstream_resp.headers());
-        let error_body = upstream_resp.text().await.unwrap_or_default();
-        let latency = start.elapsed().as_millis() as i64;
-        log_request(
-            &state.db, token_id, &route_id, &target_id, &model, "chat",
-            &input_fmt_str, &output_fmt_str, Some(status.as_u16() as i32),
-            latency, None, None, Some(&request_body_str), Some(&error_body), None,
-            req_headers_json.as_deref(), resp_headers_json.as_deref(),
-            Some(request_url), Some(&upstream_url),
-        ).await;
-        return Err(AppError::Upstream { status: status.as_u16(), body: error_body });
+    let redactor = state.redactor(&selected.overrides).await;
+    let upstream_span = upstream_span(&redactor.url(&upstream_url));
+    for (k, v) in upstream_span.in_scope(|| telemetry::upstream_headers(headers)) {
+        req_builder = req_builder.header(k, v);
     }
 
-    state.circuit.record_success(&target.id);
+    let call = UpstreamCall {
+        state: state.clone(),
+        route: route.clone(),
+        request_id,
+        token_id: token_id.to_string(),
+        model,
+        input_format: route.input_format.clone(),
+        output_format: upstream_slug.clone(),
+        request_body: redactor.body(&String::from_utf8_lossy(body_bytes)),
+        request_headers: redactor.headers_to_json(headers),
+        request_url: redactor.url(request_url),
+        upstream_url: redactor.url(&upstream_url),
+        redactor,
+        timeouts,
+        start,
+        upstream_stream: ir.stream,
+        normalization_notes,
+        selected,
+    };
 
-    if ir.stream {
-        let resp_headers_json = headers_to_json(upstream_resp.headers());
-        let latency = start.elapsed().as_millis() as i64;
-        let log_id = log_request(
-            &state.db, token_id, &route_id, &target_id, &model, "chat",
-            &input_fmt_str, &output_fmt_str, Some(200), latency, None, None,
-            Some(&request_body_str), None, None,
-            req_headers_json.as_deref(), resp_headers_json.as_deref(),
-            Some(request_url), Some(&upstream_url),
-        ).await;
-        return proxy_stream(
-            upstream_resp,
-            upstream_slug.clone(),
-            route.input_format.clone(),
-            state.db.clone(),
-            log_id,
-        ).await;
+    if client_stream {
+        let config = state.config.read().await.stream.clone();
+        let stream_span = stream_span();
+        let upstream = if config.early_start {
+            Upstream::Pending(req_builder, upstream_span)
+        } else {
+            Upstream::Sent(call.send(req_builder, &upstream_span, &mut observer).await?)
+        };
+        return proxy_stream(call, upstream, config.keepalive(), observer, stream_span);
     }
 
-    let resp_headers_json = headers_to_json(upstream_resp.headers());
-    let resp_bytes = upstream_resp.bytes().await?;
-    let upstream_decoder = resolve_decoder(&upstream_slug)?;
-    let ir_response = upstream_decoder.decode_response(&resp_bytes)?;
-    let output_encoder = resolve_encoder(&route.input_format)?;
-    let output_bytes = output_encoder.encode_response(&ir_response)?;
+    let upstream_resp = call.send(req_builder, &upstream_span, &mut observer).await?;
+    let resp_headers_json = call.redactor.headers_to_json(upstream_resp.headers());
+    let resp_bytes = match read_body(upstream_resp, timeouts.idle).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            observer.error(&e);
+            observer.finish(Outcome::UpstreamError);
+            return Err(e);
+        }
+    };
+    let converted = resolve_decoder(&upstream_slug)
+        .and_then(|decoder| {
+            tracing::info_span!("decode", format = %upstream_slug).in_scope(|| {
+                if call.upstream_stream {
+                    bridge::aggregate_stream(&*decoder, &resp_bytes, &call.model)
+                } else {
+                    decoder.decode_response(&resp_bytes)
+                }
+            })
+        })
+        .and_then(|ir_response| {
+            let encoder = resolve_encoder(&route.input_format)?;
+            let output_bytes = tracing::info_span!("encode", format = %route.input_format)
+                .in_scope(|| encoder.encode_response(&ir_response))?;
+            Ok((ir_response, output_bytes))
+        });
+    let (ir_response, output_bytes) = match converted {
+        Ok(converted) => converted,
+        Err(e) => {
+            observer.error(&e);
+            observer.finish(Outcome::UpstreamError);
+            return Err(e);
+        }
+    };
 
-    let latency = start.elapsed().as_millis() as i64;
     let prompt_tokens = ir_response.usage.as_ref().map(|u| u.prompt_tokens as i64);
     let completion_tokens = ir_response.usage.as_ref().map(|u| u.completion_tokens as i64);
-    let raw_resp_body_str = String::from_utf8_lossy(&resp_bytes).to_string();
-    let converted_body_str = String::from_utf8_lossy(&output_bytes).to_string();
-
-    log_request(
-        &state.db, token_id, &route_id, &target_id, &model, "chat",
-        &input_fmt_str, &output_fmt_str, Some(200), latency,
-        prompt_tokens, completion_tokens,
-        Some(&request_body_str), Some(&raw_resp_body_str), Some(&converted_body_str),
-        req_headers_json.as_deref(), resp_headers_json.as_deref(),
-        Some(request_url), Some(&upstream_url),
-    ).await;
+    let raw_resp_body_str = call.redactor.body(&String::from_utf8_lossy(&resp_bytes));
+    let converted_body_str = call.redactor.body(&String::from_utf8_lossy(&output_bytes));
+    observer.tokens(prompt_tokens, completion_tokens);
+    observer.finish(Outcome::Status(200));
+
+    call.log(
+        Some(200),
+        (prompt_tokens, completion_tokens),
+        Some(&raw_resp_body_str),
+        Some(&converted_body_str),
+        resp_headers_json.as_deref(),
+    )
+    .await;
 
-    if let (Some(pt), Some(ct)) = (prompt_tokens, completion_tokens) {
-        let _ = sqlx::query("UPDATE tokens SET quota_used = quota_used + ? WHERE id = ?")
-            .bind(pt + ct)
-            .bind(token_id)
-            .execute(&state.db)
-            .await;
-    }
+    charge_quota(&state.db, &state.alerts, token_id, prompt_tokens, completion_tokens).await;
 
     Ok(Response::builder()
         .status(StatusCode::OK)
@@ -384,7 +541,133 @@ async fn handle_format_conversion(
         .unwrap())
 }
 
+/// A converted chat request on its way upstream, with what is needed to log
+/// and account for it wherever the exchange completes: in the handler, or
+/// inside the response stream when it was started early.
+struct UpstreamCall {
+    state: ProxyState,
+    route: Route,
+    selected: balancer::SelectedTarget,
+    request_id: String,
+    token_id: String,
+    model: String,
+    /// The client's format.
+    input_format: String,
+    /// The upstream's format.
+    output_format: String,
+    /// Redacted, like everything below that ends up in the log.
+    request_body: String,
+    request_headers: Option<String>,
+    request_url: String,
+    upstream_url: String,
+    redactor: Redactor,
+    timeouts: Timeouts,
+    start: Instant,
+    /// Whether the upstream was asked to stream, which differs from the
+    /// client's request when the target bridges the two.
+    upstream_stream: bool,
+    normalization_notes: Option<String>,
+}
+
+impl UpstreamCall {
+    /// Send the request and check its status. Failures are recorded against
+    /// the target and logged before being returned.
+    async fn send(
+        &self,
+        req_builder: reqwest::RequestBuilder,
+        span: &tracing::Span,
+        observer: &mut RequestObserver,
+    ) -> Result<reqwest::Response, AppError> {
+        let target = &self.selected.target;
+        let mut abandoned = AbandonedLog {
+            db: self.state.db.clone(),
+            id: self.request_id.clone(),
+            token_id: self.token_id.clone(),
+            route_id: self.route.id.clone(),
+            target_id: target.id.clone(),
+            model: self.model.clone(),
+            modality: "chat",
+            input_format: self.input_format.clone(),
+            output_format: self.output_format.clone(),
+            request_body: self.request_body.clone(),
+            request_headers: self.request_headers.clone(),
+            request_url: self.request_url.clone(),
+            upstream_url: self.upstream_url.clone(),
+            normalization_notes: self.normalization_notes.clone(),
+            start: self.start,
+            armed: true,
+        };
+        let upstream_resp = send_upstream(req_builder, self.timeouts.first_byte, span).await;
+        abandoned.disarm();
+        let upstream_resp = match upstream_resp {
+            Ok(r) => {
+                span.record("http.status_code", r.status().as_u16());
+                r
+            }
+            Err(e) => {
+                record_failure(&self.state, &self.route, target);
+                observer.error(&e);
+                observer.finish(Outcome::UpstreamError);
+                self.log(None, (None, None), Some(&e.to_string()), None, None).await;
+                return Err(e);
+            }
+        };
+
+        let status = upstream_resp.status();
+        if !status.is_success() {
+            record_failure(&self.state, &self.route, target);
+            if status == StatusCode::UNAUTHORIZED {
+                report_rejected_key(&self.state, &self.route, &self.selected, status.as_u16());
+            }
+            observer.finish(Outcome::Status(status.as_u16()));
+            let resp_headers_json = self.redactor.headers_to_json(upstream_resp.headers());
+            let error_body = upstream_resp.text().await.unwrap_or_default();
+            self.log(
+                Some(status.as_u16()),
+                (None, None),
+                Some(&self.redactor.body(&error_body)),
+                None,
+                resp_headers_json.as_deref(),
+            )
+            .await;
+            let error = resolve_decoder(&self.output_format)?.decode_error(status.as_u16(), error_body.as_bytes());
+            return Err(AppError::Upstream(error));
+        }
+
+        self.state.circuit.record_success(&target.id);
+        Ok(upstream_resp)
+    }
+
+    async fn log(
+        &self,
+        status: Option<u16>,
+        (prompt_tokens, completion_tokens): (Option<i64>, Option<i64>),
+        response_body: Option<&str>,
+        response_body_converted: Option<&str>,
+        response_headers: Option<&str>,
+    ) {
+        log_request(
+            &self.state.db, &self.request_id, &self.token_id, &self.route.id,
+            &self.selected.target.id, &self.model, "chat",
+            &self.input_format, &self.output_format, status.map(i32::from),
+            self.start.elapsed().as_millis() as i64, prompt_tokens, completion_tokens,
+            Some(&self.request_body), response_body, response_body_converted,
+            self.request_headers.as_deref(), response_headers,
+            Some(&self.request_url), Some(&self.upstream_url),
+            self.normalization_notes.as_deref(),
+        ).await;
+    }
+}
+
+/// The upstream side of a stream: already answered, or still to be sent
+/// once the response to the client has started.
+enum Upstream {
+    Sent(reqwest::Response),
+    Pending(reqwest::RequestBuilder, tracing::Span),
+}
+
 /// Passthrough: strip prefix, replace auth, forward as-is.
+#[allow(clippy::too_many_arguments)]
 async fn handle_passthrough(
     state: &ProxyState,
     route: &Route,
@@ -396,18 +679,27 @@ async fn handle_passthrough(
     method: axum::http::Method,
     request_url: &str,
 ) -> Result<Response, AppError> {
-    let start = std::time::Instant::now();
+    let start = Instant::now();
 
     let selected = balancer::select_target(
         &route.id,
         &state.db,
         &state.circuit,
         &state.rotation,
+        &state.vault,
     )
     .await?;
 
     let target = &selected.target;
     let api_key = &selected.api_key;
+    let timeouts = Timeouts::for_target(target);
+
+    let model = serde_json::from_slice::<serde_json::Value>(body_bytes)
+        .ok()
+        .and_then(|v| v.get("model").and_then(|m| m.as_str()).map(str::to_string))
+        .unwrap_or_default();
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let mut observer = observe(state, &request_id, route, &selected, &target.upstream_format, &model, token_id);
 
     let base = target.base_url.trim_end_matches('/');
     let target_url = match query {
@@ -420,15 +712,17 @@ async fn handle_passthrough(
     let body_bytes = body_owned.as_slice();
 
     let upstream_format = ChatFormat::from_str_loose(&target.upstream_format);
+    // Reads usage from responses for the token's quota, where the format is known.
+    let usage_decoder = resolve_decoder(&target.upstream_format).ok();
 
     let reqwest_method = reqwest::Method::from_bytes(method.as_str().as_bytes())
         .map_err(|_| AppError::BadRequest(format!("Unsupported method: {}", method)))?;
 
-    let mut req_builder = state.http_client.request(reqwest_method, &target_url);
+    let mut req_builder = state.client_for(target).await?.request(reqwest_method, &target_url);
 
     for (name, value) in headers.iter() {
         let name_lower = name.as_str().to_lowercase();
-        if HOP_BY_HOP.contains(&name_lower.as_str()) {
+        if HOP_BY_HOP.contains(&name_lower.as_str()) || telemetry::is_trace_header(&name_lower) {
             continue;
         }
         if let Ok(v) = value.to_str() {
@@ -447,7 +741,7 @@ async fn handle_passthrough(
             let scheme = headers
                 .get("authorization")
                 .and_then(|v| v.to_str().ok())
-                .and_then(|s| s.splitn(2, ' ').next().filter(|p| p.len() < 20))
+                .and_then(|s| s.split(' ').next().filter(|p| p.len() < 20))
                 .unwrap_or("Bearer");
             req_builder = req_builder.header("authorization", format!("{} {}", scheme, api_key));
         }
@@ -455,35 +749,71 @@ async fn handle_passthrough(
     for (k, v) in &override_headers {
         req_builder = req_builder.header(k.as_str(), v.as_str());
     }
+    let redactor = state.redactor(&selected.overrides).await;
+    let upstream_span = upstream_span(&redactor.url(&target_url));
+    for (k, v) in upstream_span.in_scope(|| telemetry::upstream_headers(headers)) {
+        req_builder = req_builder.header(k, v);
+    }
 
     if !body_bytes.is_empty() {
         req_builder = req_builder.body(body_bytes.to_vec());
     }
 
-    let request_body_str = String::from_utf8_lossy(body_bytes).to_string();
-    let req_headers_json = headers_to_json(headers);
+    let request_body_str = redactor.body(&String::from_utf8_lossy(body_bytes));
+    let req_headers_json = redactor.headers_to_json(headers);
+    let request_url: &str = &redactor.url(request_url);
+    let target_url = redactor.url(&target_url);
     let target_id = target.id.clone();
     let route_id = route.id.clone();
     let upstream_format_str = target.upstream_format.clone();
 
-    let upstream_resp = match req_builder.send().await {
-        Ok(r) => r,
+    let mut abandoned = AbandonedLog {
+        db: state.db.clone(),
+        id: request_id.clone(),
+        token_id: token_id.to_string(),
+        route_id: route_id.clone(),
+        target_id: target_id.clone(),
+        model: String::new(),
+        modality: "passthrough",
+        input_format: route.input_format.clone(),
+        output_format: upstream_format_str.clone(),
+        request_body: request_body_str.clone(),
+        request_headers: req_headers_json.clone(),
+        request_url: request_url.to_string(),
+        upstream_url: target_url.clone(),
+        normalization_notes: None,
+        start,
+        armed: true,
+    };
+    let upstream_resp = send_upstream(req_builder, timeouts.first_byte, &upstream_span).await;
+    abandoned.disarm();
+    let upstream_resp = match upstream_resp {
+        Ok(r) => {
+            upstream_span.record("http.status_code", r.status().as_u16());
+            r
+        }
         Err(e) => {
+            observer.error(&e);
+            observer.finish(Outcome::UpstreamError);
             let latency = start.elapsed().as_millis() as i64;
             log_request(
-                &state.db, token_id, &route_id, &target_id, "", "passthrough",
+                &state.db, &request_id, token_id, &route_id, &target_id, "", "passthrough",
                 &route.input_format, &upstream_format_str, None, latency, None, None,
                 Some(&request_body_str), Some(&e.to_string()), None,
                 req_headers_json.as_deref(), None,
-                Some(request_url), Some(&target_url),
+                Some(request_url), Some(&target_url), None,
             ).await;
-            return Err(AppError::HttpClient(e));
+            return Err(e);
         }
     };
 
     let status = upstream_resp.status();
+    // Only keys the gateway sent itself can be blamed for a rejection.
+    if status == StatusCode::UNAUTHORIZED && upstream_format.is_some() {
+        report_rejected_key(state, route, &selected, status.as_u16());
+    }
     let resp_headers = upstream_resp.headers().clone();
-    let resp_headers_json = headers_to_json(&resp_headers);
+    let resp_headers_json = redactor.headers_to_json(&resp_headers);
 
     let content_type = resp_headers
         .get("content-type")
@@ -493,41 +823,58 @@ async fn handle_passthrough(
 
     if is_streaming {
         let latency = start.elapsed().as_millis() as i64;
-        let log_id = log_request(
-            &state.db, token_id, &route_id, &target_id, "", "passthrough",
+        log_request(
+            &state.db, &request_id, token_id, &route_id, &target_id, "", "passthrough",
             &route.input_format, &upstream_format_str, Some(status.as_u16() as i32),
             latency, None, None, Some(&request_body_str), None, None,
             req_headers_json.as_deref(), resp_headers_json.as_deref(),
-            Some(request_url), Some(&target_url),
+            Some(request_url), Some(&target_url), None,
         ).await;
 
         let byte_stream = upstream_resp.bytes_stream();
-        let db_for_stream = state.db.clone();
+        let mut stream_log = StreamLog::new(state.db.clone(), request_id, redactor, stream_span(), start)
+            .charge_to(token_id, state.alerts.clone());
+        // Events are only parsed for usage, and only in a known format.
+        let mut usage_parser = usage_decoder.map(|decoder| (decoder, SseParser::new()));
 
         let capturing_stream = async_stream::stream! {
-            let mut full_body: Vec<u8> = Vec::new();
+            let mut outcome = StreamOutcome::Completed;
             let mut byte_stream = Box::pin(byte_stream);
             loop {
-                match byte_stream.next().await {
-                    Some(Ok(chunk)) => {
-                        full_body.extend_from_slice(&chunk);
+                match tokio::time::timeout(timeouts.idle, byte_stream.next()).await {
+                    Ok(Some(Ok(chunk))) => {
+                        stream_log.chunk();
+                        observer.first_token();
+                        stream_log.push_raw(&chunk);
+                        if let Some((decoder, parser)) = &mut usage_parser {
+                            for event in parser.push(&chunk) {
+                                let usage = decoder.decode_stream_chunk(&event.data).ok().flatten().and_then(|c| c.usage);
+                                if let Some(usage) = usage {
+                                    stream_log.usage(usage.prompt_tokens, usage.completion_tokens);
+                                }
+                            }
+                        }
                         yield Ok::<_, std::convert::Infallible>(chunk);
                     }
-                    Some(Err(e)) => {
+                    Ok(Some(Err(e))) => {
                         log::error!("Passthrough stream error: {}", e);
+                        stream_log.error(&e);
+                        observer.error(&e);
+                        outcome = StreamOutcome::UpstreamError;
+                        break;
+                    }
+                    Ok(None) => break,
+                    Err(_) => {
+                        let e = idle_timeout(timeouts.idle);
+                        log::error!("Passthrough stream error: {}", e);
+                        stream_log.error(&e);
+                        observer.error(&e);
+                        outcome = StreamOutcome::UpstreamError;
                         break;
                     }
-                    None => break,
                 }
             }
-            if !full_body.is_empty() {
-                let body_str = String::from_utf8_lossy(&full_body).to_string();
-                let _ = sqlx::query("UPDATE request_logs SET response_body = ? WHERE id = ?")
-                    .bind(&body_str)
-                    .bind(&log_id)
-                    .execute(&db_for_stream)
-                    .await;
-            }
+            finish_stream(&mut stream_log, &mut observer, status.as_u16(), outcome);
         };
 
         let mut resp = Response::builder().status(status);
@@ -545,16 +892,32 @@ async fn handle_passthrough(
         return Ok(resp.body(Body::from_stream(capturing_stream)).unwrap());
     }
 
-    let resp_bytes = upstream_resp.bytes().await.unwrap_or_default();
+    let resp_bytes = match read_body(upstream_resp, timeouts.idle).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            observer.error(&e);
+            observer.finish(Outcome::UpstreamError);
+            return Err(e);
+        }
+    };
+    let usage = usage_decoder
+        .filter(|_| status.is_success())
+        .and_then(|decoder| decoder.decode_response(&resp_bytes).ok())
+        .and_then(|response| response.usage);
+    let prompt_tokens = usage.as_ref().map(|u| u.prompt_tokens as i64);
+    let completion_tokens = usage.as_ref().map(|u| u.completion_tokens as i64);
+    observer.tokens(prompt_tokens, completion_tokens);
+    observer.finish(Outcome::Status(status.as_u16()));
     let latency = start.elapsed().as_millis() as i64;
-    let resp_body_str = String::from_utf8_lossy(&resp_bytes).to_string();
+    let resp_body_str = redactor.body(&String::from_utf8_lossy(&resp_bytes));
     log_request(
-        &state.db, token_id, &route_id, &target_id, "", "passthrough",
+        &state.db, &request_id, token_id, &route_id, &target_id, "", "passthrough",
         &route.input_format, &upstream_format_str, Some(status.as_u16() as i32),
-        latency, None, None, Some(&request_body_str), Some(&resp_body_str), None,
+        latency, prompt_tokens, completion_tokens, Some(&request_body_str), Some(&resp_body_str), None,
         req_headers_json.as_deref(), resp_headers_json.as_deref(),
-        Some(request_url), Some(&target_url),
+        Some(request_url), Some(&target_url), None,
     ).await;
+    charge_quota(&state.db, &state.alerts, token_id, prompt_tokens, completion_tokens).await;
 
     let mut resp = Response::builder().status(status);
     for (name, value) in resp_headers.iter() {
@@ -586,148 +949,189 @@ fn strip_prefix(path: &str, prefix: &str) -> String {
     }
 }
 
-async fn proxy_stream(
-    upstream_resp: reqwest::Response,
-    upstream_slug: String,
-    output_slug: String,
-    db: SqlitePool,
-    log_id: String,
+/// Relay an upstream stream to the client in its own format. When the
+/// stream goes quiet for `keepalive`, the client is sent a keep-alive; with
+/// an [`Upstream::Pending`] request these start before the upstream answers.
+/// A non-streaming upstream's response is replayed as a stream once read.
+fn proxy_stream(
+    call: UpstreamCall,
+    upstream: Upstream,
+    keepalive: Option<Duration>,
+    mut observer: RequestObserver,
+    stream_span: tracing::Span,
 ) -> Result<Response, AppError> {
-    let upstream_decoder = resolve_decoder(&upstream_slug)?;
-    let mut output_encoder = resolve_encoder(&output_slug)?;
-
-    let byte_stream = upstream_resp.bytes_stream();
+    let upstream_decoder = resolve_decoder(&call.output_format)?;
+    let mut output_encoder = resolve_encoder(&call.input_format)?;
+    let idle = call.timeouts.idle;
 
     let sse_stream = async_stream::stream! {
-        let mut buffer = String::new();
-        let mut byte_stream = Box::pin(byte_stream);
-        // Accumulate raw upstream data lines for logging (newline-delimited JSON).
-        let mut raw_response_body = String::new();
-        // Accumulate converted events (what the client actually receives).
-        let mut converted_response_body = String::new();
-        let mut stream_done = false;
-
-        while !stream_done {
-            let chunk_result = match byte_stream.next().await {
-                Some(c) => c,
-                None => break,
-            };
-            let chunk = match chunk_result {
-                Ok(c) => c,
-                Err(e) => {
-                    log::error!("Upstream stream error: {}", e);
-                    break;
+        let keep_alive = output_encoder
+            .keep_alive()
+            .map(|event| event.to_wire())
+            .unwrap_or_else(|| sse::comment("keep-alive"));
+
+        let upstream_resp = match upstream {
+            Upstream::Sent(resp) => resp,
+            Upstream::Pending(req_builder, span) => {
+                let send = call.send(req_builder, &span, &mut observer);
+                tokio::pin!(send);
+                let result = loop {
+                    match keepalive {
+                        Some(every) => match tokio::time::timeout(every, &mut send).await {
+                            Ok(result) => break result,
+                            Err(_) => yield Ok::<_, std::convert::Infallible>(keep_alive.clone()),
+                        },
+                        None => break (&mut send).await,
+                    }
+                };
+                match result {
+                    Ok(resp) => resp,
+                    Err(e) => {
+                        // Already logged by `send`; the client has a 200
+                        // response by now, so the error goes in the stream.
+                        for event in output_encoder.stream_error(&e.to_ir()) {
+                            yield Ok(event.to_wire());
+                        }
+                        return;
+                    }
                 }
-            };
-
-            match std::str::from_utf8(&chunk) {
-                Ok(text) => buffer.push_str(text),
-                Err(_) => buffer.push_str(&String::from_utf8_lossy(&chunk)),
             }
+        };
 
-            while let Some(pos) = buffer.find("\n\n") {
-                let event_block = buffer[..pos].to_owned();
-                buffer.drain(..pos + 2);
-
-                for line in event_block.lines() {
-                    let data = if let Some(d) = line.strip_prefix("data: ") {
-                        d.trim()
-                    } else if let Some(d) = line.strip_prefix("data:") {
-                        d.trim()
+        let resp_headers_json = call.redactor.headers_to_json(upstream_resp.headers());
+        call.log(Some(200), (None, None), None, None, resp_headers_json.as_deref()).await;
+        let mut stream_log = StreamLog::new(
+            call.state.db.clone(),
+            call.request_id.clone(),
+            call.redactor.clone(),
+            stream_span,
+            call.start,
+        )
+        .charge_to(&call.token_id, call.state.alerts.clone());
+
+        let mut parser = SseParser::new();
+        let mut body = Vec::new();
+        let mut byte_stream = Box::pin(upstream_resp.bytes_stream());
+        let mut outcome = StreamOutcome::Completed;
+        // Sent to the client in its own error format once the stream ends.
+        let mut failure: Option<IrError> = None;
+        let mut upstream_ended = false;
+        let mut last_received = tokio::time::Instant::now();
+        let mut last_sent = last_received;
+
+        'read: while !upstream_ended {
+            let idle_deadline = last_received + idle;
+            let deadline = match keepalive {
+                Some(every) => idle_deadline.min(last_sent + every),
+                None => idle_deadline,
+            };
+            let events = match tokio::time::timeout_at(deadline, byte_stream.next()).await {
+                Ok(Some(Ok(chunk))) => {
+                    last_received = tokio::time::Instant::now();
+                    stream_log.chunk();
+                    observer.first_token();
+                    if call.upstream_stream {
+                        parser.push(&chunk)
                     } else {
-                        continue;
-                    };
-
-                    if upstream_decoder.is_stream_done(data) {
-                        // Persist logs BEFORE yielding the done signal.
-                        // The client closes the connection upon receiving the done event,
-                        // which drops this stream generator before any trailing code runs.
-                        if !raw_response_body.is_empty() {
-                            let converted = if converted_response_body.is_empty() {
-                                None::<String>
-                            } else {
-                                Some(converted_response_body.clone())
-                            };
-                            let _ = sqlx::query(
-                                "UPDATE request_logs SET response_body = ?, response_body_converted = ? WHERE id = ?"
-                            )
-                            .bind(&raw_response_body)
-                            .bind(converted.as_deref())
-                            .bind(&log_id)
-                            .execute(&db)
-                            .await;
-                        }
-                        if let Some(done) = output_encoder.stream_done_signal() {
-                            let events: Vec<String> = done
-                                .split('\n')
-                                .map(|s| s.trim().to_string())
-                                .filter(|s| !s.is_empty())
-                                .collect();
-                            for event in events {
-                                yield Ok::<_, std::convert::Infallible>(format!("data: {}\n\n", event));
+                        body.extend_from_slice(&chunk);
+                        Vec::new()
+                    }
+                }
+                Ok(Some(Err(e))) => {
+                    log::error!("Upstream stream error: {}", e);
+                    stream_log.error(&e);
+                    observer.error(&e);
+                    outcome = StreamOutcome::UpstreamError;
+                    failure = Some(IrError::new(502, e.to_string()));
+                    break;
+                }
+                Ok(None) if !call.upstream_stream => {
+                    stream_log.push_raw(&body);
+                    match upstream_decoder.decode_response(&body) {
+                        Ok(response) => {
+                            for ir_chunk in bridge::synthesize_stream(&response) {
+                                for wire in encode_chunk(&mut *output_encoder, &ir_chunk, &mut stream_log) {
+                                    yield Ok(wire);
+                                }
+                            }
+                            finish_stream(&mut stream_log, &mut observer, 200, StreamOutcome::Completed);
+                            for done in output_encoder.stream_done_signal() {
+                                yield Ok(done.to_wire());
                             }
                         }
-                        stream_done = true;
-                        break;
+                        Err(e) => {
+                            log::error!("Decode response error: {}", e);
+                            stream_log.error(&e);
+                            observer.error(&e);
+                            outcome = StreamOutcome::UpstreamError;
+                            failure = Some(IrError::new(502, e.to_string()));
+                        }
                     }
+                    break;
+                }
+                Ok(None) => {
+                    upstream_ended = true;
+                    parser.finish().into_iter().collect()
+                }
+                Err(_) if deadline < idle_deadline => {
+                    yield Ok(keep_alive.clone());
+                    last_sent = tokio::time::Instant::now();
+                    continue;
+                }
+                Err(_) => {
+                    let e = idle_timeout(idle);
+                    log::error!("Upstream stream error: {}", e);
+                    stream_log.error(&e);
+                    observer.error(&e);
+                    outcome = StreamOutcome::UpstreamError;
+                    failure = Some(e.to_ir());
+                    break;
+                }
+            };
 
-                    // Accumulate raw upstream chunk for logging.
-                    if !raw_response_body.is_empty() {
-                        raw_response_body.push('\n');
+            for event in events {
+                let data = event.data.as_str();
+                if upstream_decoder.is_stream_done(data) {
+                    // Finalize BEFORE yielding the done signal: the client
+                    // closes the connection upon receiving it, which drops
+                    // this generator before any trailing code runs.
+                    finish_stream(&mut stream_log, &mut observer, 200, StreamOutcome::Completed);
+                    for done in output_encoder.stream_done_signal() {
+                        yield Ok(done.to_wire());
                     }
-                    raw_response_body.push_str(data);
-
-                    match upstream_decoder.decode_stream_chunk(data) {
-                        Ok(Some(ir_chunk)) => {
-                            match output_encoder.encode_stream_chunk(&ir_chunk) {
-                                Ok(Some(encoded)) => {
-                                    // An encoder may return multiple newline-separated events.
-                                    // Collect into owned Strings first to avoid borrow-across-yield
-                                    // issues, then emit each as its own SSE message.
-                                    let events: Vec<String> = encoded
-                                        .split('\n')
-                                        .map(|s| s.trim().to_string())
-                                        .filter(|s| !s.is_empty())
-                                        .collect();
-                                    for event in &events {
-                                        if !converted_response_body.is_empty() {
-                                            converted_response_body.push('\n');
-                                        }
-                                        converted_response_body.push_str(event);
-                                    }
-                                    for event in events {
-                                        yield Ok(format!("data: {}\n\n", event));
-                                    }
-                                }
-                                Ok(None) => {}
-                                Err(e) => { log::error!("Encode stream chunk error: {}", e); }
-                            }
+                    break 'read;
+                }
+
+                stream_log.push_raw_line(data);
+
+                if let Some(error) = upstream_decoder.decode_stream_error(data) {
+                    log::error!("Upstream stream error: {}", error.message);
+                    stream_log.error(&error.message);
+                    observer.error(&error.message);
+                    outcome = StreamOutcome::UpstreamError;
+                    failure = Some(error);
+                    break 'read;
+                }
+
+                match upstream_decoder.decode_stream_chunk(data) {
+                    Ok(Some(ir_chunk)) => {
+                        for wire in encode_chunk(&mut *output_encoder, &ir_chunk, &mut stream_log) {
+                            yield Ok(wire);
+                            last_sent = tokio::time::Instant::now();
                         }
-                        Ok(None) => {}
-                        Err(e) => { log::error!("Decode stream chunk error: {}", e); }
                     }
+                    Ok(None) => {}
+                    Err(e) => { log::error!("Decode stream chunk error: {}", e); }
                 }
-                if stream_done { break; }
             }
         }
 
-        // Fallback: persist logs if stream ended without a proper done signal
-        // (e.g., network drop). May not run if the client already closed the
-        // connection, but is best-effort for abnormal terminations.
-        if !stream_done && !raw_response_body.is_empty() {
-            let converted = if converted_response_body.is_empty() {
-                None::<String>
-            } else {
-                Some(converted_response_body)
-            };
-            let _ = sqlx::query(
-                "UPDATE request_logs SET response_body = ?, response_body_converted = ? WHERE id = ?"
-            )
-            .bind(&raw_response_body)
-            .bind(converted.as_deref())
-            .bind(&log_id)
-            .execute(&db)
-            .await;
+        // Upstreams such as Gemini end the stream without a done event.
+        finish_stream(&mut stream_log, &mut observer, 200, outcome);
+        if let Some(error) = failure {
+            for event in output_encoder.stream_error(&error) {
+                yield Ok(event.to_wire());
+            }
         }
     };
 
@@ -740,9 +1144,244 @@ async fn proxy_stream(
         .unwrap())
 }
 
+/// Encode a chunk for the client, recording its usage and the events sent.
+fn encode_chunk(
+    encoder: &mut dyn chat::Encoder,
+    chunk: &chat::ir::IrStreamChunk,
+    stream_log: &mut StreamLog,
+) -> Vec<String> {
+    if let Some(usage) = &chunk.usage {
+        stream_log.usage(usage.prompt_tokens, usage.completion_tokens);
+    }
+    match encoder.encode_stream_chunk(chunk) {
+        Ok(events) => events
+            .iter()
+            .map(|event| {
+                let wire = event.to_wire();
+                stream_log.push_converted(wire.trim_end());
+                wire
+            })
+            .collect(),
+        Err(e) => {
+            log::error!("Encode stream chunk error: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Start tracking an upstream request in metrics and on the traffic feed.
+/// Metrics only name models the target maps.
+fn observe(
+    state: &ProxyState,
+    request_id: &str,
+    route: &Route,
+    selected: &balancer::SelectedTarget,
+    upstream_format: &str,
+    model: &str,
+    token_id: &str,
+) -> RequestObserver {
+    let target_id = &selected.target.id;
+    let model_label = match selected.mapping(model) {
+        Some(mapping) => mapping.model.as_str(),
+        None => OTHER_MODEL,
+    };
+    let traffic = state.traffic.start(TrafficRequest {
+        id: request_id.to_string(),
+        route_id: route.id.clone(),
+        route: route.path_prefix.clone(),
+        target_id: target_id.to_string(),
+        upstream_format: upstream_format.to_string(),
+        model: model.to_string(),
+        token_id: Some(token_id.to_string()).filter(|t| !t.is_empty()),
+    });
+    state
+        .metrics
+        .observe(&route.path_prefix, target_id, upstream_format, model_label)
+        .with_traffic(traffic)
+}
+
+/// Count a failed upstream request against the target's circuit breaker,
+/// alerting if it opens the circuit.
+fn record_failure(state: &ProxyState, route: &Route, target: &RouteTarget) {
+    if state.circuit.record_failure(&target.id) {
+        state.alerts.circuit_opened(route, target);
+    }
+}
+
+/// Alert about an upstream key the provider rejected. The key is left
+/// enabled; whether to replace it is up to the user.
+fn report_rejected_key(state: &ProxyState, route: &Route, selected: &balancer::SelectedTarget, status: u16) {
+    let Some(key_id) = &selected.key_id else {
+        return;
+    };
+    log::warn!("Upstream key {} of target {} was rejected with HTTP {}", key_id, selected.target.id, status);
+    state.alerts.key_rejected(route, &selected.target, key_id, status);
+}
+
+/// Record the end of a stream in metrics and the request log. `status` is
+/// the response status already sent to the client.
+fn finish_stream(
+    stream_log: &mut StreamLog,
+    observer: &mut RequestObserver,
+    status: u16,
+    outcome: StreamOutcome,
+) {
+    observer.tokens(
+        stream_log.prompt_tokens().map(i64::from),
+        stream_log.completion_tokens().map(i64::from),
+    );
+    observer.finish(match outcome {
+        StreamOutcome::Completed => Outcome::Status(status),
+        StreamOutcome::UpstreamError => Outcome::UpstreamError,
+        StreamOutcome::ClientClosed => Outcome::ClientClosed,
+    });
+    stream_log.finish(outcome);
+}
+
+/// Add a request's usage to its API token's quota and check the quota alerts.
+pub(super) async fn charge_quota(
+    db: &SqlitePool,
+    alerts: &Alerter,
+    token_id: &str,
+    prompt_tokens: Option<i64>,
+    completion_tokens: Option<i64>,
+) {
+    let used = prompt_tokens.unwrap_or(0) + completion_tokens.unwrap_or(0);
+    if token_id.is_empty() || used <= 0 {
+        return;
+    }
+    let result = sqlx::query("UPDATE tokens SET quota_used = quota_used + ? WHERE id = ?")
+        .bind(used)
+        .bind(token_id)
+        .execute(db)
+        .await;
+    match result {
+        Ok(_) => alerts.check_quota(token_id).await,
+        Err(e) => log::error!("Failed to record token usage: {}", e),
+    }
+}
+
+/// Send an upstream request, giving up if its response headers do not
+/// arrive within `first_byte`.
+async fn send_upstream(
+    req_builder: reqwest::RequestBuilder,
+    first_byte: Duration,
+    span: &tracing::Span,
+) -> Result<reqwest::Response, AppError> {
+    match tokio::time::timeout(first_byte, req_builder.send().instrument(span.clone())).await {
+        Ok(Ok(resp)) => Ok(resp),
+        // The connect timeout set on the client.
+        Ok(Err(e)) if e.is_timeout() => Err(AppError::UpstreamTimeout(e.to_string())),
+        Ok(Err(e)) => Err(AppError::HttpClient(e)),
+        Err(_) => Err(AppError::UpstreamTimeout(format!(
+            "no response within {}s",
+            first_byte.as_secs()
+        ))),
+    }
+}
+
+/// Read a whole response body, giving up if the upstream sends nothing for
+/// `idle`.
+async fn read_body(upstream_resp: reqwest::Response, idle: Duration) -> Result<Vec<u8>, AppError> {
+    let mut byte_stream = Box::pin(upstream_resp.bytes_stream());
+    let mut body = Vec::new();
+    loop {
+        match tokio::time::timeout(idle, byte_stream.next()).await {
+            Ok(Some(chunk)) => body.extend_from_slice(&chunk?),
+            Ok(None) => return Ok(body),
+            Err(_) => return Err(idle_timeout(idle)),
+        }
+    }
+}
+
+fn idle_timeout(idle: Duration) -> AppError {
+    AppError::UpstreamTimeout(format!("no data from upstream for {}s", idle.as_secs()))
+}
+
+/// Log entry for a request the client abandoned while the gateway waited
+/// for the upstream to respond. A disconnect drops the handler mid-way,
+/// which aborts the upstream request; dropping this then writes the entry
+/// the handler never got to.
+struct AbandonedLog {
+    db: SqlitePool,
+    id: String,
+    token_id: String,
+    route_id: String,
+    target_id: String,
+    model: String,
+    modality: &'static str,
+    input_format: String,
+    output_format: String,
+    request_body: String,
+    request_headers: Option<String>,
+    request_url: String,
+    upstream_url: String,
+    normalization_notes: Option<String>,
+    start: Instant,
+    armed: bool,
+}
+
+impl AbandonedLog {
+    /// The handler got a response and writes its own log entry.
+    fn disarm(&mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for AbandonedLog {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            log::warn!("No runtime to log abandoned request {}", self.id);
+            return;
+        };
+        let latency = self.start.elapsed().as_millis() as i64;
+        let db = self.db.clone();
+        let id = std::mem::take(&mut self.id);
+        let token_id = std::mem::take(&mut self.token_id);
+        let route_id = std::mem::take(&mut self.route_id);
+        let target_id = std::mem::take(&mut self.target_id);
+        let model = std::mem::take(&mut self.model);
+        let modality = self.modality;
+        let input_format = std::mem::take(&mut self.input_format);
+        let output_format = std::mem::take(&mut self.output_format);
+        let request_body = std::mem::take(&mut self.request_body);
+        let request_headers = self.request_headers.take();
+        let request_url = std::mem::take(&mut self.request_url);
+        let upstream_url = std::mem::take(&mut self.upstream_url);
+        let normalization_notes = self.normalization_notes.take();
+        handle.spawn(async move {
+            log_request(
+                &db, &id, &token_id, &route_id, &target_id, &model, modality,
+                &input_format, &output_format, None, latency, None, None,
+                Some(&request_body), Some("Client closed the connection before the upstream responded"), None,
+                request_headers.as_deref(), None,
+                Some(&request_url), Some(&upstream_url),
+                normalization_notes.as_deref(),
+            ).await;
+        });
+    }
+}
+
+/// Span for an upstream request; it ends once response headers arrive, so
+/// its duration covers connecting and waiting for the first byte.
+fn upstream_span(url: &str) -> tracing::Span {
+    tracing::info_span!("upstream_request", url.full = %url, http.status_code = Empty)
+}
+
+/// Span covering a streamed response until the last chunk is relayed.
+/// It keeps the request span open until streaming finishes.
+fn stream_span() -> tracing::Span {
+    tracing::info_span!("stream", ttft_ms = Empty, chunks = Empty, outcome = Empty, error = Empty)
+}
+
 #[allow(clippy::too_many_arguments)]
+#[tracing::instrument(name = "log_write", skip_all)]
 async fn log_request(
     db: &SqlitePool,
+    id: &str,
     token_id: &str,
     route_id: &str,
     target_id: &str,
@@ -761,23 +1400,48 @@ async fn log_request(
     response_headers: Option<&str>,
     request_url: Option<&str>,
     upstream_url: Option<&str>,
-) -> String {
-    let id = uuid::Uuid::new_v4().to_string();
+    normalization_notes: Option<&str>,
+) {
     let now = chrono::Utc::now().to_rfc3339();
     let result = sqlx::query(
-        "INSERT INTO request_logs (id, token_id, route_id, target_id, model, modality, input_format, output_format, status, latency_ms, prompt_tokens, completion_tokens, request_body, response_body, response_body_converted, request_headers, response_headers, request_url, upstream_url, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        "INSERT INTO request_logs (id, token_id, route_id, target_id, model, modality, input_format, output_format, status, latency_ms, prompt_tokens, completion_tokens, request_body, response_body, response_body_converted, request_headers, response_headers, request_url, upstream_url, normalization_notes, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
     )
-    .bind(&id).bind(token_id).bind(route_id).bind(target_id)
+    .bind(id).bind(token_id).bind(route_id).bind(target_id)
     .bind(model).bind(modality).bind(input_format).bind(output_format)
     .bind(status).bind(latency_ms).bind(prompt_tokens).bind(completion_tokens)
     .bind(request_body).bind(response_body).bind(response_body_converted)
     .bind(request_headers).bind(response_headers)
-    .bind(request_url).bind(upstream_url)
+    .bind(request_url).bind(upstream_url).bind(normalization_notes)
     .bind(&now)
     .execute(db).await;
 
     if let Err(e) = result {
         log::error!("Failed to log request: {}", e);
     }
-    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn upstream_without_headers_times_out() {
+        // Accepts the connection but never answers.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1/chat/completions", listener.local_addr().unwrap());
+        let silent = tokio::spawn(async move {
+            let (_socket, _) = listener.accept().await.unwrap();
+            std::future::pending::<()>().await;
+        });
+
+        let request = reqwest::Client::builder().no_proxy().build().unwrap().post(url);
+        let error = send_upstream(request, Duration::from_millis(200), &tracing::Span::none())
+            .await
+            .unwrap_err();
+        assert!(matches!(error, AppError::UpstreamTimeout(_)));
+        let response = error.into_response_as(ChatFormat::Anthropic);
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(response.headers()["x-should-retry"], "true");
+        silent.abort();
+    }
 }
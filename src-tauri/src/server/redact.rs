@@ -0,0 +1,263 @@
+use crate::config::RedactionConfig;
+use axum::http::HeaderMap;
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Headers that carry credentials or session state.
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "x-api-key",
+    "api-key",
+    "x-goog-api-key",
+    "cookie",
+    "set-cookie",
+];
+
+/// Query parameters that carry credentials, e.g. Gemini's `?key=`.
+const SENSITIVE_QUERY_PARAMS: &[&str] = &[
+    "key",
+    "api_key",
+    "api-key",
+    "apikey",
+    "access_token",
+    "token",
+    "signature",
+    "sig",
+];
+
+const MASK: &str = "[REDACTED]";
+
+fn builtin_pii_patterns() -> &'static [(Regex, &'static str)] {
+    static PATTERNS: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        vec![
+            // API-key-like strings: sk-..., sk-ant-..., AIza..., long bearer-ish tokens
+            (
+                Regex::new(r"\b(?:sk|pk|rk)-[A-Za-z0-9_\-]{16,}|\bAIza[0-9A-Za-z_\-]{30,}").unwrap(),
+                "[REDACTED_KEY]",
+            ),
+            (
+                Regex::new(r"[A-Za-z0-9._%+\-]+@[A-Za-z0-9.\-]+\.[A-Za-z]{2,}").unwrap(),
+                "[REDACTED_EMAIL]",
+            ),
+            // Phone numbers with a country code, or North American ones with
+            // grouping; bare digit runs are IDs and timestamps far more often.
+            (
+                Regex::new(
+                    r"\+\d{1,3}[\s.\-]?(?:\(\d{1,4}\)|\d{1,4})(?:[\s.\-]?\d{2,4}){2,4}\b|(?:\(\d{3}\)\s?|\b\d{3}[\s.\-])\d{3}[\s.\-]\d{4}\b",
+                )
+                .unwrap(),
+                "[REDACTED_PHONE]",
+            ),
+        ]
+    })
+}
+
+/// A per-request snapshot of the redaction policy, built from [`RedactionConfig`].
+#[derive(Clone)]
+pub struct Redactor {
+    mask_auth_headers: bool,
+    extra_headers: Vec<String>,
+    mask_body_pii: bool,
+    body_patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    pub fn new(config: &RedactionConfig) -> Self {
+        let body_patterns = config
+            .body_patterns
+            .iter()
+            .filter_map(|p| match Regex::new(p) {
+                Ok(re) => Some(re),
+                Err(e) => {
+                    log::warn!("Ignoring invalid redaction pattern {:?}: {}", p, e);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            mask_auth_headers: config.mask_auth_headers,
+            extra_headers: config.extra_headers.iter().map(|h| h.trim().to_lowercase()).collect(),
+            mask_body_pii: config.mask_body_pii,
+            body_patterns,
+        }
+    }
+
+    /// Also mask the given header names, e.g. header overrides that carry upstream secrets.
+    pub fn with_extra_headers<'a>(mut self, names: impl IntoIterator<Item = &'a str>) -> Self {
+        self.extra_headers
+            .extend(names.into_iter().map(|n| n.trim().to_lowercase()));
+        self
+    }
+
+    fn is_masked_header(&self, name: &str) -> bool {
+        (self.mask_auth_headers && SENSITIVE_HEADERS.contains(&name))
+            || self.extra_headers.iter().any(|h| h == name)
+    }
+
+    /// Serialize headers to a JSON object string, masking sensitive values.
+    pub fn headers_to_json(&self, headers: &HeaderMap) -> Option<String> {
+        let mut map = serde_json::Map::new();
+        for (k, v) in headers.iter() {
+            if let Ok(v_str) = v.to_str() {
+                let name = k.as_str();
+                let value = if self.is_masked_header(name) {
+                    mask_header_value(v_str)
+                } else {
+                    v_str.to_string()
+                };
+                map.insert(name.to_string(), serde_json::Value::String(value));
+            }
+        }
+        if map.is_empty() {
+            None
+        } else {
+            serde_json::to_string(&map).ok()
+        }
+    }
+
+    /// Mask credential query parameters in a logged URL, then apply the body
+    /// rules to it.
+    pub fn url(&self, url: &str) -> String {
+        let Some((path, query)) = url.split_once('?') else {
+            return self.body(url);
+        };
+        let query: Vec<String> = query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((name, _))
+                    if self.mask_auth_headers
+                        && SENSITIVE_QUERY_PARAMS.contains(&name.to_lowercase().as_str()) =>
+                {
+                    format!("{}={}", name, MASK)
+                }
+                _ => pair.to_string(),
+            })
+            .collect();
+        self.body(&format!("{}?{}", path, query.join("&")))
+    }
+
+    /// Apply body masking rules to a logged request/response body.
+    pub fn body(&self, body: &str) -> String {
+        if !self.mask_body_pii && self.body_patterns.is_empty() {
+            return body.to_string();
+        }
+        let mut out = body.to_string();
+        if self.mask_body_pii {
+            for (re, replacement) in builtin_pii_patterns() {
+                out = re.replace_all(&out, *replacement).into_owned();
+            }
+        }
+        for re in &self.body_patterns {
+            out = re.replace_all(&out, MASK).into_owned();
+        }
+        out
+    }
+}
+
+/// Keep the auth scheme (e.g. `Bearer`) and the last 4 characters for correlation.
+fn mask_header_value(value: &str) -> String {
+    let (scheme, secret) = match value.split_once(' ') {
+        Some((s, rest)) if s.len() < 20 => (Some(s), rest),
+        _ => (None, value),
+    };
+    let tail: String = if secret.chars().count() > 12 {
+        let chars: Vec<char> = secret.chars().collect();
+        chars[chars.len() - 4..].iter().collect()
+    } else {
+        String::new()
+    };
+    let masked = if tail.is_empty() {
+        MASK.to_string()
+    } else {
+        format!("{}...{}", MASK, tail)
+    };
+    match scheme {
+        Some(s) => format!("{} {}", s, masked),
+        None => masked,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn masks_auth_headers_and_keeps_others() {
+        let redactor = Redactor::new(&RedactionConfig::default());
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", HeaderValue::from_static("Bearer sk-1234567890abcdef"));
+        headers.insert("x-api-key", HeaderValue::from_static("short"));
+        headers.insert("content-type", HeaderValue::from_static("application/json"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&redactor.headers_to_json(&headers).unwrap()).unwrap();
+        assert_eq!(json["authorization"], "Bearer [REDACTED]...cdef");
+        assert_eq!(json["x-api-key"], "[REDACTED]");
+        assert_eq!(json["content-type"], "application/json");
+    }
+
+    #[test]
+    fn masks_user_defined_headers() {
+        let config = RedactionConfig {
+            extra_headers: vec!["X-Upstream-Secret".into()],
+            ..Default::default()
+        };
+        let redactor = Redactor::new(&config).with_extra_headers(["x-org-id"]);
+        let mut headers = HeaderMap::new();
+        headers.insert("x-upstream-secret", HeaderValue::from_static("abc"));
+        headers.insert("x-org-id", HeaderValue::from_static("org"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&redactor.headers_to_json(&headers).unwrap()).unwrap();
+        assert_eq!(json["x-upstream-secret"], "[REDACTED]");
+        assert_eq!(json["x-org-id"], "[REDACTED]");
+    }
+
+    #[test]
+    fn masks_pii_in_bodies_only_when_enabled() {
+        let body = r#"{"text":"mail me at jane.doe@example.com or +1 (555) 123-4567, key sk-abcdefghijklmnop1234"}"#;
+        assert_eq!(Redactor::new(&RedactionConfig::default()).body(body), body);
+
+        let config = RedactionConfig {
+            mask_body_pii: true,
+            body_patterns: vec![r"secret-\d+".into()],
+            ..Default::default()
+        };
+        let masked = Redactor::new(&config).body(&format!("{} secret-42", body));
+        assert!(masked.contains("[REDACTED_EMAIL]"));
+        assert!(masked.contains("[REDACTED_PHONE]"));
+        assert!(masked.contains("[REDACTED_KEY]"));
+        assert!(masked.ends_with("[REDACTED]"));
+        assert!(!masked.contains("jane.doe"));
+    }
+
+    #[test]
+    fn phone_pattern_skips_timestamps_and_ids() {
+        let config = RedactionConfig { mask_body_pii: true, ..Default::default() };
+        let redactor = Redactor::new(&config);
+        for text in [
+            r#"{"created":"2024-05-01T12:34:56.789+08:00"}"#,
+            "2024-05-01 12:34:56",
+            r#"{"id":1715000000123,"order":"20240501123456789"}"#,
+            "req_01HXYZ 4000123456789010",
+        ] {
+            assert_eq!(redactor.body(text), text);
+        }
+        for phone in ["+1 (555) 123-4567", "+44 20 7946 0958", "+8613812345678", "(555) 123-4567", "555.123.4567"] {
+            assert_eq!(redactor.body(phone), "[REDACTED_PHONE]", "{}", phone);
+        }
+    }
+
+    #[test]
+    fn masks_credential_query_parameters() {
+        let redactor = Redactor::new(&RedactionConfig::default());
+        assert_eq!(
+            redactor.url("/gemini/v1beta/models/x:generateContent?alt=sse&key=AIzaSecret"),
+            "/gemini/v1beta/models/x:generateContent?alt=sse&key=[REDACTED]"
+        );
+        assert_eq!(redactor.url("/v1/models"), "/v1/models");
+    }
+}
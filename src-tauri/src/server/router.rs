@@ -1,6 +1,18 @@
+use super::admin;
+use super::middleware;
+use super::alerts::Alerter;
+use super::metrics::Metrics;
+use super::images::ImageCache;
+use super::model_list::ModelCache;
 use super::proxy::{self, ProxyState};
+use super::traffic::TrafficBus;
+use super::ServerControl;
+use crate::config::AppConfig;
+use crate::outbound::{self, ClientPool};
 use crate::routing::circuit::CircuitBreaker;
 use crate::routing::KeyRotationState;
+use crate::secrets::KeyVault;
+use crate::services::config_watch::ConfigWatcher;
 use axum::body::Body;
 use axum::extract::{Query, State};
 use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
@@ -11,26 +23,57 @@ use serde::Deserialize;
 use serde_json::{json, Value};
 use sqlx::SqlitePool;
 use std::sync::Arc;
+use tokio::sync::RwLock;
 use tower_http::cors::CorsLayer;
 
-pub async fn create_router(pool: SqlitePool) -> Router {
-    let http_client = reqwest::Client::new();
+pub fn create_state(
+    pool: SqlitePool,
+    config: Arc<RwLock<AppConfig>>,
+    vault: Arc<KeyVault>,
+    server: ServerControl,
+    watcher: ConfigWatcher,
+    traffic: TrafficBus,
+    alerts: Alerter,
+) -> ProxyState {
     let circuit = Arc::new(CircuitBreaker::new(5, 60));
     let rotation = Arc::new(KeyRotationState::new());
 
-    let proxy_state = ProxyState {
+    ProxyState {
         db: pool,
-        http_client,
+        clients: Arc::new(ClientPool::new()),
         circuit,
         rotation,
-    };
+        config,
+        vault,
+        server,
+        watcher,
+        metrics: Arc::new(Metrics::new()),
+        traffic,
+        alerts,
+        models: Arc::new(ModelCache::new()),
+        images: Arc::new(ImageCache::new()),
+    }
+}
 
-    Router::new()
+/// Gateway router. The admin API is mounted under `/admin` unless it is
+/// served on its own port (see [`super::admin::router`]); mounted here it
+/// only answers local clients and gets none of the gateway's CORS, as the
+/// gateway may be bound to the LAN. `/metrics` and the desktop app's
+/// `/video-proxy`, which fetches any URL, are likewise local only; metrics
+/// are served remotely as `/admin/metrics`.
+pub fn create_router(proxy_state: ProxyState, with_admin: bool) -> Router {
+    let local_only = || axum::middleware::from_fn(middleware::local_only);
+    let router = Router::new()
         .route("/health", get(health_check))
-        .route("/video-proxy", get(handle_video_proxy))
+        .route("/metrics", get(metrics).layer(local_only()))
+        .route("/video-proxy", get(handle_video_proxy).layer(local_only()))
         .fallback(axum::routing::any(proxy::handle_route_proxy).with_state(proxy_state.clone()))
-        .layer(CorsLayer::permissive())
-        .with_state(proxy_state)
+        .with_state(proxy_state.clone())
+        .layer(CorsLayer::permissive());
+    if !with_admin {
+        return router;
+    }
+    router.merge(admin::router(proxy_state).layer(local_only()))
 }
 
 async fn health_check() -> Json<Value> {
@@ -40,6 +83,14 @@ async fn health_check() -> Json<Value> {
     }))
 }
 
+pub(super) async fn metrics(State(state): State<ProxyState>) -> Response {
+    let body = state.metrics.render(&state.db, &state.circuit).await;
+    Response::builder()
+        .header("Content-Type", "text/plain; version=0.0.4")
+        .body(Body::from(body))
+        .unwrap()
+}
+
 #[derive(Deserialize)]
 struct VideoProxyQuery {
     url: String,
@@ -52,10 +103,13 @@ async fn handle_video_proxy(
 ) -> Result<Response<Body>, crate::error::AppError> {
     let video_url = &query.url;
 
+    let proxy = state.config.read().await.proxy_url.clone();
     let resolved_url = if video_url.contains("aweme.snssdk.com")
         || video_url.contains("api-h2.amemv.com")
     {
-        let no_redirect_client = reqwest::Client::builder()
+        let builder = outbound::with_proxy(reqwest::Client::builder(), proxy.as_deref())
+            .map_err(|e| crate::error::AppError::Internal(format!("Outbound proxy: {}", e)))?;
+        let no_redirect_client = builder
             .user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36")
             .redirect(reqwest::redirect::Policy::none())
             .build()
@@ -78,7 +132,7 @@ async fn handle_video_proxy(
         video_url.clone()
     };
 
-    let mut req = state.http_client.get(&resolved_url)
+    let mut req = state.default_client().await?.get(&resolved_url)
         .header("User-Agent", "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36");
 
     if video_url.contains("bilibili.com") || video_url.contains("bilivideo") {
@@ -0,0 +1,238 @@
+//! Completes the `request_logs` row of a streamed response once the stream
+//! ends, however it ends.
+//!
+//! The row is inserted when upstream headers arrive. Response bodies, usage
+//! and timing (time to first token, total duration, output tokens per
+//! second) are filled in by [`StreamLog::finish`], or by `Drop` when the
+//! client disconnects and the stream generator is dropped mid-way. The usage
+//! is then charged to the API token's quota.
+
+use super::alerts::Alerter;
+use super::redact::Redactor;
+use sqlx::SqlitePool;
+use std::time::{Duration, Instant};
+use tracing::Instrument;
+
+/// How a streamed response ended, stored in `request_logs.stream_outcome`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StreamOutcome {
+    Completed,
+    ClientClosed,
+    UpstreamError,
+}
+
+impl StreamOutcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StreamOutcome::Completed => "completed",
+            StreamOutcome::ClientClosed => "client_closed",
+            StreamOutcome::UpstreamError => "upstream_error",
+        }
+    }
+}
+
+pub struct StreamLog {
+    db: SqlitePool,
+    log_id: String,
+    redactor: Redactor,
+    span: tracing::Span,
+    /// When the proxy received the request.
+    start: Instant,
+    first_chunk: Option<Duration>,
+    chunks: u64,
+    /// Upstream data as received (SSE data lines for converted streams).
+    raw_body: Vec<u8>,
+    /// Events sent to the client after conversion; unused for passthrough.
+    converted_body: String,
+    prompt_tokens: Option<u32>,
+    completion_tokens: Option<u32>,
+    /// API token charged for the usage, with the alerter checking its quota.
+    quota: Option<(String, Alerter)>,
+    finished: bool,
+}
+
+/// Values written when the stream ends.
+#[derive(Debug, PartialEq)]
+struct Timing {
+    ttft_ms: Option<i64>,
+    duration_ms: i64,
+    tokens_per_second: Option<f64>,
+}
+
+impl StreamLog {
+    pub fn new(
+        db: SqlitePool,
+        log_id: String,
+        redactor: Redactor,
+        span: tracing::Span,
+        start: Instant,
+    ) -> Self {
+        Self {
+            db,
+            log_id,
+            redactor,
+            span,
+            start,
+            first_chunk: None,
+            chunks: 0,
+            raw_body: Vec::new(),
+            converted_body: String::new(),
+            prompt_tokens: None,
+            completion_tokens: None,
+            quota: None,
+            finished: false,
+        }
+    }
+
+    /// Charge the stream's usage to `token_id` when it ends.
+    pub fn charge_to(mut self, token_id: &str, alerts: Alerter) -> Self {
+        self.quota = Some((token_id.to_string(), alerts));
+        self
+    }
+
+    /// Record an upstream chunk as it arrives.
+    pub fn chunk(&mut self) {
+        if self.first_chunk.is_none() {
+            let elapsed = self.start.elapsed();
+            self.first_chunk = Some(elapsed);
+            self.span.record("ttft_ms", elapsed.as_millis() as u64);
+        }
+        self.chunks += 1;
+    }
+
+    pub fn error(&self, error: &dyn std::fmt::Display) {
+        self.span.record("error", tracing::field::display(error));
+    }
+
+    pub fn push_raw(&mut self, data: &[u8]) {
+        self.raw_body.extend_from_slice(data);
+    }
+
+    /// Append one newline-delimited data line to the raw body.
+    pub fn push_raw_line(&mut self, line: &str) {
+        if !self.raw_body.is_empty() {
+            self.raw_body.push(b'\n');
+        }
+        self.raw_body.extend_from_slice(line.as_bytes());
+    }
+
+    pub fn push_converted(&mut self, event: &str) {
+        if !self.converted_body.is_empty() {
+            self.converted_body.push('\n');
+        }
+        self.converted_body.push_str(event);
+    }
+
+    /// Keep the largest counts seen; providers may report usage across
+    /// several chunks (e.g. Anthropic's message_start and message_delta).
+    pub fn usage(&mut self, prompt_tokens: u32, completion_tokens: u32) {
+        self.prompt_tokens = self.prompt_tokens.max(Some(prompt_tokens));
+        self.completion_tokens = self.completion_tokens.max(Some(completion_tokens));
+    }
+
+    pub fn prompt_tokens(&self) -> Option<u32> {
+        self.prompt_tokens
+    }
+
+    pub fn completion_tokens(&self) -> Option<u32> {
+        self.completion_tokens
+    }
+
+    /// Write the final log values in the background. Later calls are ignored.
+    pub fn finish(&mut self, outcome: StreamOutcome) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+
+        let timing = timing(self.first_chunk, self.start.elapsed(), self.completion_tokens);
+        self.span.record("chunks", self.chunks);
+        self.span.record("outcome", outcome.as_str());
+
+        let raw_body = (!self.raw_body.is_empty())
+            .then(|| self.redactor.body(&String::from_utf8_lossy(&self.raw_body)));
+        let converted_body = (!self.converted_body.is_empty())
+            .then(|| self.redactor.body(&self.converted_body));
+        let query = sqlx::query(
+            "UPDATE request_logs SET response_body = COALESCE(?, response_body), response_body_converted = COALESCE(?, response_body_converted), prompt_tokens = ?, completion_tokens = ?, ttft_ms = ?, stream_duration_ms = ?, tokens_per_second = ?, stream_outcome = ? WHERE id = ?"
+        )
+        .bind(raw_body)
+        .bind(converted_body)
+        .bind(self.prompt_tokens.map(i64::from))
+        .bind(self.completion_tokens.map(i64::from))
+        .bind(timing.ttft_ms)
+        .bind(timing.duration_ms)
+        .bind(timing.tokens_per_second)
+        .bind(outcome.as_str())
+        .bind(self.log_id.clone());
+
+        let db = self.db.clone();
+        let quota = self.quota.take();
+        let prompt_tokens = self.prompt_tokens.map(i64::from);
+        let completion_tokens = self.completion_tokens.map(i64::from);
+        let write = async move {
+            if let Err(e) = query.execute(&db).await {
+                log::error!("Failed to finalize stream log: {}", e);
+            }
+            if let Some((token_id, alerts)) = quota {
+                super::proxy::charge_quota(&db, &alerts, &token_id, prompt_tokens, completion_tokens).await;
+            }
+        }
+        .instrument(tracing::info_span!(parent: &self.span, "log_write"));
+        // Spawned so the write also completes when the client has gone away
+        // and this generator is being dropped.
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(write);
+            }
+            Err(_) => log::warn!("No runtime to finalize stream log {}", self.log_id),
+        }
+    }
+}
+
+impl Drop for StreamLog {
+    fn drop(&mut self) {
+        self.finish(StreamOutcome::ClientClosed);
+    }
+}
+
+/// Output tokens per second are measured from the first chunk, so they
+/// reflect generation speed rather than queueing or prompt processing.
+fn timing(first_chunk: Option<Duration>, total: Duration, completion_tokens: Option<u32>) -> Timing {
+    let tokens_per_second = match (first_chunk, completion_tokens) {
+        (Some(first), Some(tokens)) if tokens > 0 => {
+            let generating = total.saturating_sub(first).as_secs_f64();
+            (generating > 0.0).then(|| tokens as f64 / generating)
+        }
+        _ => None,
+    };
+    Timing {
+        ttft_ms: first_chunk.map(|d| d.as_millis() as i64),
+        duration_ms: total.as_millis() as i64,
+        tokens_per_second,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn throughput_is_measured_from_first_chunk() {
+        let t = timing(
+            Some(Duration::from_millis(500)),
+            Duration::from_millis(2500),
+            Some(100),
+        );
+        assert_eq!(t.ttft_ms, Some(500));
+        assert_eq!(t.duration_ms, 2500);
+        assert_eq!(t.tokens_per_second, Some(50.0));
+
+        // No content or no usage reported: no throughput.
+        assert_eq!(timing(None, Duration::from_secs(1), Some(10)).tokens_per_second, None);
+        assert_eq!(
+            timing(Some(Duration::from_millis(10)), Duration::from_secs(1), None).tokens_per_second,
+            None
+        );
+    }
+}
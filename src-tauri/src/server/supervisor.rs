@@ -0,0 +1,433 @@
+//! Owns the gateway and admin listeners so listener settings (bind address,
+//! ports, TLS) can change without restarting the process.
+//!
+//! A rebind binds the new sockets before stopping the old ones, so moving to a
+//! free port has no gap. When the new address overlaps the current one, the
+//! old listeners stop accepting first and the bind is retried briefly. The old
+//! listeners drain in-flight requests (including open streams) in the
+//! background. If the new listeners cannot be bound, the previous settings stay
+//! in effect and the error is returned to the caller.
+
+use super::proxy::ProxyState;
+use super::{admin, middleware, router, tls};
+use crate::config::{AppConfig, TlsConfig};
+use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use axum_server::Handle;
+use std::io;
+use std::net::{IpAddr, SocketAddr, TcpListener};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+/// How long stopped listeners keep serving in-flight requests before their
+/// connections are closed.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(60);
+/// How long to retry a bind while the previous listener on the same address
+/// shuts down.
+const REBIND_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The subset of [`AppConfig`] that requires rebinding when it changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListenerSettings {
+    pub ip: IpAddr,
+    pub server_port: u16,
+    pub admin_port: Option<u16>,
+    pub tls: TlsConfig,
+}
+
+impl ListenerSettings {
+    pub fn from_config(config: &AppConfig) -> Result<Self, String> {
+        let ip = config
+            .bind_address
+            .parse()
+            .map_err(|_| format!("Invalid bind address: {}", config.bind_address))?;
+        Ok(Self {
+            ip,
+            server_port: config.server_port,
+            admin_port: config.admin_port,
+            tls: config.tls.clone(),
+        })
+    }
+
+    fn gateway_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.ip, self.server_port)
+    }
+
+    fn admin_addr(&self) -> Option<SocketAddr> {
+        self.admin_port.map(|port| SocketAddr::new(self.ip, port))
+    }
+
+    fn shares_port(&self, other: &ListenerSettings) -> bool {
+        let ports = [Some(self.server_port), self.admin_port];
+        [Some(other.server_port), other.admin_port]
+            .iter()
+            .flatten()
+            .any(|p| ports.contains(&Some(*p)))
+    }
+}
+
+enum Command {
+    Apply(ListenerSettings, oneshot::Sender<Result<(), String>>),
+    Shutdown(oneshot::Sender<()>),
+}
+
+/// Handle to the listener supervisor task. Cheap to clone.
+#[derive(Clone)]
+pub struct ServerControl {
+    tx: mpsc::Sender<Command>,
+}
+
+impl ServerControl {
+    /// Rebind the listeners if `config` changes any listener settings.
+    /// Returns once the new listeners accept connections, or with the bind
+    /// error while the previous listeners keep running.
+    pub async fn apply(&self, config: &AppConfig) -> Result<(), String> {
+        let settings = ListenerSettings::from_config(config)?;
+        let (reply, rx) = oneshot::channel();
+        self.tx
+            .send(Command::Apply(settings, reply))
+            .await
+            .map_err(|_| "Server is not running".to_string())?;
+        rx.await.map_err(|_| "Server is not running".to_string())?
+    }
+
+    /// Stop accepting connections and wait for in-flight requests to finish.
+    pub async fn shutdown(&self) {
+        let (reply, rx) = oneshot::channel();
+        if self.tx.send(Command::Shutdown(reply)).await.is_ok() {
+            let _ = rx.await;
+        }
+    }
+}
+
+/// Create the control handle and the receiver that drives the supervisor.
+/// They are split so the handle can live in [`ProxyState`] before the
+/// supervisor is spawned with that state.
+pub fn channel() -> (ServerControl, Supervisor) {
+    let (tx, rx) = mpsc::channel(8);
+    (ServerControl { tx }, Supervisor { rx })
+}
+
+pub struct Supervisor {
+    rx: mpsc::Receiver<Command>,
+}
+
+struct Running {
+    settings: ListenerSettings,
+    tls: Option<RustlsConfig>,
+    handles: Vec<Handle>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Supervisor {
+    /// Spawn the supervisor task. It starts with no listeners; the first
+    /// [`ServerControl::apply`] binds them.
+    pub fn spawn(self, state: ProxyState, data_dir: PathBuf) {
+        tokio::spawn(self.run(state, data_dir));
+    }
+
+    async fn run(mut self, state: ProxyState, data_dir: PathBuf) {
+        let mut running: Option<Running> = None;
+        while let Some(command) = self.rx.recv().await {
+            match command {
+                Command::Apply(settings, reply) => {
+                    let result = match running.as_mut() {
+                        Some(current) if current.settings == settings && !current.handles.is_empty() => Ok(()),
+                        Some(current) => rebind(&state, current, settings, &data_dir).await,
+                        None => bind_initial(&state, settings, &data_dir).map(|r| {
+                            running = Some(r);
+                        }),
+                    };
+                    let _ = reply.send(result);
+                }
+                Command::Shutdown(reply) => {
+                    if let Some(mut current) = running.take() {
+                        for task in stop(&mut current) {
+                            let _ = task.await;
+                        }
+                    }
+                    let _ = reply.send(());
+                    return;
+                }
+            }
+        }
+    }
+}
+
+fn bind_initial(
+    state: &ProxyState,
+    settings: ListenerSettings,
+    data_dir: &std::path::Path,
+) -> Result<Running, String> {
+    let tls = load_tls(&settings.tls, data_dir)?;
+    let listeners = bind(&settings).map_err(|e| bind_error(&settings, e))?;
+    Ok(spawn(state, settings, tls, listeners))
+}
+
+/// Replace the running listeners. If restoring the previous listeners also
+/// fails, `running` is left with no handles and the next apply binds afresh.
+async fn rebind(
+    state: &ProxyState,
+    running: &mut Running,
+    settings: ListenerSettings,
+    data_dir: &std::path::Path,
+) -> Result<(), String> {
+    let tls = load_tls(&settings.tls, data_dir)?;
+
+    let listeners = match bind(&settings) {
+        Ok(listeners) => listeners,
+        Err(e) if e.kind() == io::ErrorKind::AddrInUse && settings.shares_port(&running.settings) => {
+            // Likely our own listener; stop it and retry while it releases the port.
+            stop(running);
+            match bind_retrying(&settings).await {
+                Ok(listeners) => listeners,
+                Err(e) => {
+                    let error = bind_error(&settings, e);
+                    log::error!("{}; restoring previous listeners", error);
+                    let previous = running.settings.clone();
+                    match bind_retrying(&previous).await {
+                        Ok(listeners) => {
+                            *running = spawn(state, previous, running.tls.take(), listeners);
+                        }
+                        Err(e) => log::error!("{}", bind_error(&previous, e)),
+                    }
+                    return Err(error);
+                }
+            }
+        }
+        Err(e) => return Err(bind_error(&settings, e)),
+    };
+
+    stop(running);
+    *running = spawn(state, settings, tls, listeners);
+    Ok(())
+}
+
+/// Stop accepting on the current listeners. Their connections drain in the
+/// background; the returned tasks finish once draining is done.
+fn stop(running: &mut Running) -> Vec<JoinHandle<()>> {
+    for handle in running.handles.drain(..) {
+        handle.graceful_shutdown(Some(DRAIN_TIMEOUT));
+    }
+    std::mem::take(&mut running.tasks)
+}
+
+fn load_tls(config: &TlsConfig, data_dir: &std::path::Path) -> Result<Option<RustlsConfig>, String> {
+    if config.enabled {
+        tls::load(config, data_dir).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+struct Listeners {
+    gateway: TcpListener,
+    admin: Option<TcpListener>,
+}
+
+/// Bind every listener up front so a failure on one leaves nothing half-started.
+fn bind(settings: &ListenerSettings) -> io::Result<Listeners> {
+    let gateway = bind_one(settings.gateway_addr())?;
+    let admin = settings.admin_addr().map(bind_one).transpose()?;
+    Ok(Listeners { gateway, admin })
+}
+
+fn bind_one(addr: SocketAddr) -> io::Result<TcpListener> {
+    let listener = TcpListener::bind(addr)?;
+    listener.set_nonblocking(true)?;
+    Ok(listener)
+}
+
+async fn bind_retrying(settings: &ListenerSettings) -> io::Result<Listeners> {
+    let deadline = tokio::time::Instant::now() + REBIND_TIMEOUT;
+    loop {
+        match bind(settings) {
+            Err(e) if e.kind() == io::ErrorKind::AddrInUse && tokio::time::Instant::now() < deadline => {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn bind_error(settings: &ListenerSettings, e: io::Error) -> String {
+    match settings.admin_addr() {
+        Some(admin) => format!(
+            "Failed to bind {} (admin {}): {}",
+            settings.gateway_addr(),
+            admin,
+            e
+        ),
+        None => format!("Failed to bind {}: {}", settings.gateway_addr(), e),
+    }
+}
+
+fn spawn(
+    state: &ProxyState,
+    settings: ListenerSettings,
+    tls: Option<RustlsConfig>,
+    listeners: Listeners,
+) -> Running {
+    let mut handles = Vec::new();
+    let mut tasks = Vec::new();
+
+    let app = router::create_router(state.clone(), settings.admin_port.is_none());
+    let (handle, task) = serve(listeners.gateway, with_allow_list(app, state), tls.clone(), "Axum server");
+    handles.push(handle);
+    tasks.push(task);
+
+    if let Some(listener) = listeners.admin {
+        let app = with_allow_list(admin::router(state.clone()), state);
+        let (handle, task) = serve(listener, app, tls.clone(), "Admin API");
+        handles.push(handle);
+        tasks.push(task);
+    }
+
+    Running {
+        settings,
+        tls,
+        handles,
+        tasks,
+    }
+}
+
+fn with_allow_list(app: Router, state: &ProxyState) -> Router {
+    app.layer(axum::middleware::from_fn_with_state(
+        state.clone(),
+        middleware::ip_allow_list,
+    ))
+}
+
+fn serve(
+    listener: TcpListener,
+    app: Router,
+    tls: Option<RustlsConfig>,
+    name: &'static str,
+) -> (Handle, JoinHandle<()>) {
+    let handle = Handle::new();
+    let service = app.into_make_service_with_connect_info::<SocketAddr>();
+    let addr = listener
+        .local_addr()
+        .map(|a| a.to_string())
+        .unwrap_or_default();
+
+    let server_handle = handle.clone();
+    let task = tokio::spawn(async move {
+        let result = match tls {
+            Some(tls) => {
+                log::info!("{} listening on https://{}", name, addr);
+                axum_server::from_tcp_rustls(listener, tls)
+                    .handle(server_handle)
+                    .serve(service)
+                    .await
+            }
+            None => {
+                log::info!("{} listening on http://{}", name, addr);
+                axum_server::from_tcp(listener)
+                    .handle(server_handle)
+                    .serve(service)
+                    .await
+            }
+        };
+        match result {
+            Ok(()) => log::info!("{} on {} stopped", name, addr),
+            Err(e) => log::error!("{} error on {}: {}", name, addr, e),
+        }
+    });
+    (handle, task)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secrets::KeyVault;
+    use crate::server::{alerts::Alerter, traffic::TrafficBus};
+    use crate::services::config::{self as config_service, ConfigUpdate};
+    use crate::services::config_watch::ConfigWatcher;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::SqlitePool;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    async fn start_server(port: u16) -> (ServerControl, SqlitePool, RwLock<AppConfig>) {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = AppConfig {
+            bind_address: "127.0.0.1".into(),
+            server_port: port,
+            ..AppConfig::default()
+        };
+        let vault = Arc::new(KeyVault::from_key(&[7u8; 32]));
+        let traffic = TrafficBus::new();
+        let alerts = Alerter::new(pool.clone(), &traffic);
+        let control = super::super::start(
+            pool.clone(),
+            Arc::new(RwLock::new(config.clone())),
+            vault.clone(),
+            ConfigWatcher::new(pool.clone(), vault),
+            traffic,
+            alerts,
+            std::env::temp_dir(),
+        );
+        control.apply(&config).await.unwrap();
+        (control, pool, RwLock::new(config))
+    }
+
+    fn free_port() -> u16 {
+        TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+    }
+
+    async fn healthy(port: u16) -> bool {
+        reqwest::get(format!("http://127.0.0.1:{}/health", port))
+            .await
+            .is_ok_and(|r| r.status().is_success())
+    }
+
+    #[tokio::test]
+    async fn rebinds_to_a_new_port() {
+        let (old_port, new_port) = (free_port(), free_port());
+        let (control, pool, config) = start_server(old_port).await;
+        assert!(healthy(old_port).await);
+
+        let update = ConfigUpdate {
+            server_port: Some(new_port),
+            ..ConfigUpdate::default()
+        };
+        let updated = config_service::update_config(&pool, &config, &control, update)
+            .await
+            .unwrap();
+        assert_eq!(updated.server_port, new_port);
+        assert!(healthy(new_port).await);
+        assert!(!healthy(old_port).await);
+
+        control.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn bind_failure_keeps_the_old_listener() {
+        let port = free_port();
+        let (control, pool, config) = start_server(port).await;
+        let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+        let taken_port = taken.local_addr().unwrap().port();
+
+        let update = ConfigUpdate {
+            server_port: Some(taken_port),
+            ..ConfigUpdate::default()
+        };
+        let err = config_service::update_config(&pool, &config, &control, update)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, "BIND_FAILED");
+        assert_eq!(config.read().await.server_port, port);
+        assert!(healthy(port).await);
+
+        control.shutdown().await;
+    }
+}
@@ -0,0 +1,72 @@
+use crate::config::TlsConfig;
+use axum_server::tls_rustls::RustlsConfig;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const SELF_SIGNED_CERT: &str = "tls/self-signed.crt";
+const SELF_SIGNED_KEY: &str = "tls/self-signed.key";
+
+/// Build the rustls config for the gateway listener from the user-supplied
+/// cert/key, or from a self-signed pair kept under `data_dir`.
+pub fn load(tls: &TlsConfig, data_dir: &Path) -> Result<RustlsConfig, String> {
+    let (cert_path, key_path) = match (&tls.cert_path, &tls.key_path) {
+        (Some(cert), Some(key)) => (PathBuf::from(cert), PathBuf::from(key)),
+        (None, None) => ensure_self_signed(data_dir)?,
+        _ => return Err("TLS needs both cert_path and key_path, or neither".into()),
+    };
+
+    let cert_pem = std::fs::read(&cert_path)
+        .map_err(|e| format!("Failed to read {}: {}", cert_path.display(), e))?;
+    let key_pem = std::fs::read(&key_path)
+        .map_err(|e| format!("Failed to read {}: {}", key_path.display(), e))?;
+
+    let certs = CertificateDer::pem_slice_iter(&cert_pem)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Invalid certificate {}: {}", cert_path.display(), e))?;
+    if certs.is_empty() {
+        return Err(format!("No certificate found in {}", cert_path.display()));
+    }
+    let key = PrivateKeyDer::from_pem_slice(&key_pem)
+        .map_err(|e| format!("Invalid private key {}: {}", key_path.display(), e))?;
+
+    let mut config = rustls::ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .map_err(|e| e.to_string())?
+    .with_no_client_auth()
+    .with_single_cert(certs, key)
+    .map_err(|e| format!("Certificate and key do not match: {}", e))?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(RustlsConfig::from_config(Arc::new(config)))
+}
+
+fn ensure_self_signed(data_dir: &Path) -> Result<(PathBuf, PathBuf), String> {
+    let cert_path = data_dir.join(SELF_SIGNED_CERT);
+    let key_path = data_dir.join(SELF_SIGNED_KEY);
+    if cert_path.exists() && key_path.exists() {
+        return Ok((cert_path, key_path));
+    }
+
+    let names = vec!["localhost".to_string(), "127.0.0.1".to_string(), "::1".to_string()];
+    let generated = rcgen::generate_simple_self_signed(names)
+        .map_err(|e| format!("Failed to generate self-signed certificate: {}", e))?;
+
+    if let Some(dir) = cert_path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(&cert_path, generated.cert.pem()).map_err(|e| e.to_string())?;
+    std::fs::write(&key_path, generated.key_pair.serialize_pem()).map_err(|e| e.to_string())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&key_path, std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| e.to_string())?;
+    }
+    log::info!("Generated self-signed TLS certificate at {}", cert_path.display());
+
+    Ok((cert_path, key_path))
+}
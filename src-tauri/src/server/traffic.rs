@@ -0,0 +1,279 @@
+//! Live feed of proxied requests for the traffic console.
+//!
+//! Each upstream request publishes `started`, `first_token` (streams only)
+//! and then `completed` or `error` on a broadcast bus. The desktop app
+//! forwards the bus as Tauri events; the admin API serves it as SSE on
+//! `/admin/events`. Requests still in flight are kept so a new subscriber
+//! can be shown them straight away.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+use super::metrics::Outcome;
+
+/// Events buffered per subscriber before a slow one starts missing events.
+const CHANNEL_CAPACITY: usize = 1024;
+
+const CLIENT_CLOSED: &str = "Client closed the connection";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrafficEventKind {
+    Started,
+    FirstToken,
+    Completed,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrafficEvent {
+    /// Request id; also the id of its request log once written.
+    pub id: String,
+    pub kind: TrafficEventKind,
+    pub route_id: String,
+    /// Path prefix of the route.
+    pub route: String,
+    pub target_id: String,
+    pub upstream_format: String,
+    pub model: String,
+    pub token_id: Option<String>,
+    pub status: Option<u16>,
+    /// Time since the upstream request was sent.
+    pub elapsed_ms: u64,
+    pub ttft_ms: Option<u64>,
+    pub prompt_tokens: Option<i64>,
+    pub completion_tokens: Option<i64>,
+    pub error: Option<String>,
+    pub started_at: String,
+    pub timestamp: String,
+}
+
+impl TrafficEvent {
+    /// An `error` event caused by the client going away, not by upstream.
+    pub fn client_closed(&self) -> bool {
+        self.kind == TrafficEventKind::Error && self.error.as_deref() == Some(CLIENT_CLOSED)
+    }
+}
+
+/// Subscription filter; unset fields match everything.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TrafficFilter {
+    pub route_id: Option<String>,
+    pub target_id: Option<String>,
+    pub token_id: Option<String>,
+    pub model: Option<String>,
+    pub kinds: Option<Vec<TrafficEventKind>>,
+}
+
+impl TrafficFilter {
+    pub fn matches(&self, event: &TrafficEvent) -> bool {
+        fn field(filter: &Option<String>, value: &str) -> bool {
+            filter.as_deref().is_none_or(|f| f.is_empty() || f == value)
+        }
+        field(&self.route_id, &event.route_id)
+            && field(&self.target_id, &event.target_id)
+            && field(&self.token_id, event.token_id.as_deref().unwrap_or(""))
+            && field(&self.model, &event.model)
+            && self.kinds.as_ref().is_none_or(|k| k.is_empty() || k.contains(&event.kind))
+    }
+}
+
+#[derive(Clone)]
+pub struct TrafficBus {
+    tx: broadcast::Sender<TrafficEvent>,
+    /// Latest event of each in-flight request.
+    in_flight: Arc<Mutex<HashMap<String, TrafficEvent>>>,
+}
+
+impl Default for TrafficBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TrafficBus {
+    pub fn new() -> Self {
+        Self {
+            tx: broadcast::channel(CHANNEL_CAPACITY).0,
+            in_flight: Arc::default(),
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TrafficEvent> {
+        self.tx.subscribe()
+    }
+
+    /// Requests currently in flight, oldest first.
+    pub fn in_flight(&self) -> Vec<TrafficEvent> {
+        let mut events: Vec<TrafficEvent> = self
+            .in_flight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect();
+        events.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+        events
+    }
+
+    /// Publish `started` for a new request and return its tracker.
+    pub fn start(&self, request: TrafficRequest) -> TrafficTracker {
+        let tracker = TrafficTracker {
+            bus: self.clone(),
+            request,
+            started_at: chrono::Utc::now().to_rfc3339(),
+            ttft_ms: None,
+            prompt_tokens: None,
+            completion_tokens: None,
+            error: None,
+        };
+        tracker.publish(TrafficEventKind::Started, 0, None);
+        tracker
+    }
+
+    fn publish(&self, event: TrafficEvent) {
+        {
+            let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+            match event.kind {
+                TrafficEventKind::Started | TrafficEventKind::FirstToken => {
+                    in_flight.insert(event.id.clone(), event.clone());
+                }
+                TrafficEventKind::Completed | TrafficEventKind::Error => {
+                    in_flight.remove(&event.id);
+                }
+            }
+        }
+        // No receivers is fine: nobody is watching.
+        let _ = self.tx.send(event);
+    }
+}
+
+/// What is known about a request when it is sent upstream.
+pub struct TrafficRequest {
+    pub id: String,
+    pub route_id: String,
+    pub route: String,
+    pub target_id: String,
+    pub upstream_format: String,
+    pub model: String,
+    pub token_id: Option<String>,
+}
+
+/// Publishes the events of one request. Driven by
+/// [`RequestObserver`](super::metrics::RequestObserver).
+pub struct TrafficTracker {
+    bus: TrafficBus,
+    request: TrafficRequest,
+    started_at: String,
+    ttft_ms: Option<u64>,
+    prompt_tokens: Option<i64>,
+    completion_tokens: Option<i64>,
+    error: Option<String>,
+}
+
+impl TrafficTracker {
+    pub fn first_token(&mut self, elapsed_ms: u64) {
+        self.ttft_ms = Some(elapsed_ms);
+        self.publish(TrafficEventKind::FirstToken, elapsed_ms, None);
+    }
+
+    pub fn tokens(&mut self, prompt: Option<i64>, completion: Option<i64>) {
+        self.prompt_tokens = prompt.or(self.prompt_tokens);
+        self.completion_tokens = completion.or(self.completion_tokens);
+    }
+
+    /// Describe why the request failed, for the `error` event.
+    pub fn error(&mut self, message: String) {
+        self.error = Some(message);
+    }
+
+    pub fn finish(&mut self, outcome: Outcome, elapsed_ms: u64) {
+        let (kind, status) = match outcome {
+            Outcome::Status(code) if code < 400 => (TrafficEventKind::Completed, Some(code)),
+            Outcome::Status(code) => (TrafficEventKind::Error, Some(code)),
+            Outcome::UpstreamError => {
+                if self.error.is_none() {
+                    self.error = Some("Upstream error".to_string());
+                }
+                (TrafficEventKind::Error, None)
+            }
+            Outcome::ClientClosed => {
+                self.error = Some(CLIENT_CLOSED.to_string());
+                (TrafficEventKind::Error, None)
+            }
+        };
+        self.publish(kind, elapsed_ms, status);
+    }
+
+    fn publish(&self, kind: TrafficEventKind, elapsed_ms: u64, status: Option<u16>) {
+        let r = &self.request;
+        self.bus.publish(TrafficEvent {
+            id: r.id.clone(),
+            kind,
+            route_id: r.route_id.clone(),
+            route: r.route.clone(),
+            target_id: r.target_id.clone(),
+            upstream_format: r.upstream_format.clone(),
+            model: r.model.clone(),
+            token_id: r.token_id.clone(),
+            status,
+            elapsed_ms,
+            ttft_ms: self.ttft_ms,
+            prompt_tokens: self.prompt_tokens,
+            completion_tokens: self.completion_tokens,
+            error: self.error.clone(),
+            started_at: self.started_at.clone(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(id: &str, model: &str) -> TrafficRequest {
+        TrafficRequest {
+            id: id.to_string(),
+            route_id: "r1".to_string(),
+            route: "/openai".to_string(),
+            target_id: "t1".to_string(),
+            upstream_format: "openai-chat".to_string(),
+            model: model.to_string(),
+            token_id: None,
+        }
+    }
+
+    #[test]
+    fn tracks_in_flight_requests_and_filters_events() {
+        let bus = TrafficBus::new();
+        let mut rx = bus.subscribe();
+
+        let mut a = bus.start(request("a", "gpt-4o"));
+        let _b = bus.start(request("b", "claude"));
+        a.first_token(120);
+        assert_eq!(bus.in_flight().len(), 2);
+
+        a.finish(Outcome::Status(502), 300);
+        let in_flight = bus.in_flight();
+        assert_eq!(in_flight.len(), 1);
+        assert_eq!(in_flight[0].id, "b");
+
+        let filter = TrafficFilter {
+            model: Some("gpt-4o".to_string()),
+            kinds: Some(vec![TrafficEventKind::Error]),
+            ..Default::default()
+        };
+        let mut matched = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if filter.matches(&event) {
+                matched.push(event);
+            }
+        }
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].status, Some(502));
+        assert_eq!(matched[0].ttft_ms, Some(120));
+    }
+}
@@ -0,0 +1,163 @@
+use crate::db::models::AlertRule;
+use crate::error::IpcError;
+use crate::server::alerts::{AlertKind, Alerter};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+#[derive(Debug, Deserialize)]
+pub struct AlertRuleInput {
+    pub name: String,
+    pub kind: String,
+    pub route_id: Option<String>,
+    pub token_id: Option<String>,
+    pub threshold: Option<f64>,
+    #[serde(default = "default_window_secs")]
+    pub window_secs: i64,
+    #[serde(default = "default_min_requests")]
+    pub min_requests: i64,
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: i64,
+    pub webhook_url: Option<String>,
+    #[serde(default = "default_true")]
+    pub notify_desktop: bool,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_window_secs() -> i64 {
+    300
+}
+
+fn default_min_requests() -> i64 {
+    10
+}
+
+fn default_cooldown_secs() -> i64 {
+    900
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl AlertRuleInput {
+    /// Check the input and clear fields the rule's kind does not use.
+    fn validate(mut self) -> Result<Self, IpcError> {
+        self.name = self.name.trim().to_string();
+        if self.name.is_empty() {
+            return Err(IpcError::validation("Alert name must not be empty"));
+        }
+        let kind = AlertKind::parse(&self.kind)
+            .ok_or_else(|| IpcError::validation(format!("Unknown alert kind: {}", self.kind)))?;
+
+        let blank = |v: Option<String>| v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        self.route_id = blank(self.route_id.take());
+        self.token_id = blank(self.token_id.take());
+        self.webhook_url = blank(self.webhook_url.take());
+        match kind {
+            AlertKind::Quota => self.route_id = None,
+            _ => self.token_id = None,
+        }
+
+        match kind {
+            AlertKind::ErrorRate | AlertKind::Quota => match self.threshold {
+                Some(t) if t > 0.0 && t <= 100.0 => {}
+                _ => return Err(IpcError::validation("Threshold must be a percentage between 0 and 100")),
+            },
+            _ => self.threshold = None,
+        }
+        if self.window_secs <= 0 {
+            return Err(IpcError::validation("Window must be positive"));
+        }
+        if self.min_requests <= 0 {
+            return Err(IpcError::validation("Minimum requests must be positive"));
+        }
+        if self.cooldown_secs < 0 {
+            return Err(IpcError::validation("Cooldown must not be negative"));
+        }
+        if let Some(url) = &self.webhook_url {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(IpcError::validation("Webhook URL must start with http:// or https://"));
+            }
+        }
+        Ok(self)
+    }
+}
+
+pub async fn list_alert_rules(db: &SqlitePool) -> Result<Vec<AlertRule>, IpcError> {
+    Ok(sqlx::query_as::<_, AlertRule>("SELECT * FROM alert_rules ORDER BY created_at ASC")
+        .fetch_all(db)
+        .await?)
+}
+
+pub async fn get_alert_rule(db: &SqlitePool, id: &str) -> Result<AlertRule, IpcError> {
+    sqlx::query_as::<_, AlertRule>("SELECT * FROM alert_rules WHERE id = ?")
+        .bind(id)
+        .fetch_optional(db)
+        .await?
+        .ok_or_else(|| IpcError::not_found("Alert rule not found"))
+}
+
+pub async fn create_alert_rule(
+    db: &SqlitePool,
+    alerts: &Alerter,
+    input: AlertRuleInput,
+) -> Result<AlertRule, IpcError> {
+    let input = input.validate()?;
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+
+    sqlx::query(
+        "INSERT INTO alert_rules (id, name, kind, route_id, token_id, threshold, window_secs, min_requests, cooldown_secs, webhook_url, notify_desktop, enabled, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&id).bind(&input.name).bind(&input.kind)
+    .bind(&input.route_id).bind(&input.token_id).bind(input.threshold)
+    .bind(input.window_secs).bind(input.min_requests).bind(input.cooldown_secs)
+    .bind(&input.webhook_url).bind(input.notify_desktop).bind(input.enabled)
+    .bind(&now)
+    .execute(db)
+    .await?;
+
+    alerts.reload().await?;
+    get_alert_rule(db, &id).await
+}
+
+pub async fn update_alert_rule(
+    db: &SqlitePool,
+    alerts: &Alerter,
+    id: &str,
+    input: AlertRuleInput,
+) -> Result<AlertRule, IpcError> {
+    let input = input.validate()?;
+    let updated = sqlx::query(
+        "UPDATE alert_rules SET name = ?, kind = ?, route_id = ?, token_id = ?, threshold = ?, window_secs = ?, min_requests = ?, cooldown_secs = ?, webhook_url = ?, notify_desktop = ?, enabled = ? WHERE id = ?"
+    )
+    .bind(&input.name).bind(&input.kind)
+    .bind(&input.route_id).bind(&input.token_id).bind(input.threshold)
+    .bind(input.window_secs).bind(input.min_requests).bind(input.cooldown_secs)
+    .bind(&input.webhook_url).bind(input.notify_desktop).bind(input.enabled)
+    .bind(id)
+    .execute(db)
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(IpcError::not_found("Alert rule not found"));
+    }
+
+    alerts.reload().await?;
+    get_alert_rule(db, id).await
+}
+
+pub async fn delete_alert_rule(db: &SqlitePool, alerts: &Alerter, id: &str) -> Result<(), IpcError> {
+    sqlx::query("DELETE FROM alert_rules WHERE id = ?")
+        .bind(id)
+        .execute(db)
+        .await?;
+    alerts.reload().await?;
+    Ok(())
+}
+
+/// Send a test alert through the rule's webhook and desktop notification.
+pub async fn test_alert_rule(db: &SqlitePool, alerts: &Alerter, id: &str) -> Result<(), IpcError> {
+    let rule = get_alert_rule(db, id).await?;
+    alerts.send_test(&rule).await.map_err(IpcError::delivery_failed)
+}
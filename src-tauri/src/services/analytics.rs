@@ -0,0 +1,313 @@
+//! Usage analytics over the hourly `usage_rollups` table, which triggers
+//! keep in step with `request_logs` (see migration 015).
+//!
+//! Latency percentiles come from per-bucket request counts, interpolated
+//! within the bucket, so they are estimates with bucket resolution.
+
+use crate::error::IpcError;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::{BTreeMap, HashMap};
+
+/// Upper bounds (ms) of the latency buckets, as assigned by the triggers.
+/// The last one catches everything slower.
+const LATENCY_BUCKETS_MS: &[i64] = &[
+    50, 100, 250, 500, 1000, 2500, 5000, 10000, 30000, 60000, 120000, 86_400_000,
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    Hour,
+    #[default]
+    Day,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AnalyticsQuery {
+    /// Look-back window; defaults to 7 days.
+    pub days: Option<i32>,
+    #[serde(default)]
+    pub granularity: Granularity,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct UsageSummary {
+    pub requests: i64,
+    /// Requests with a 4xx/5xx status or no upstream response.
+    pub errors: i64,
+    pub success_rate: Option<f64>,
+    pub error_rate: Option<f64>,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub avg_latency_ms: Option<f64>,
+    pub p50_latency_ms: Option<f64>,
+    pub p95_latency_ms: Option<f64>,
+    pub p99_latency_ms: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UsagePeriod {
+    /// Start of the hour or day, UTC (`2025-01-31T14:00:00Z` / `2025-01-31`).
+    pub period: String,
+    #[serde(flatten)]
+    pub summary: UsageSummary,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UsageGroup {
+    /// Route, target or token id, or status class. Empty when unknown.
+    pub key: String,
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub summary: UsageSummary,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UsageAnalytics {
+    pub total: UsageSummary,
+    pub series: Vec<UsagePeriod>,
+    pub by_route: Vec<UsageGroup>,
+    pub by_target: Vec<UsageGroup>,
+    pub by_token: Vec<UsageGroup>,
+    pub by_status_class: Vec<UsageGroup>,
+}
+
+#[derive(sqlx::FromRow)]
+struct RollupRow {
+    key: String,
+    status_class: String,
+    latency_bucket: i64,
+    requests: i64,
+    prompt_tokens: i64,
+    completion_tokens: i64,
+    latency_ms_sum: i64,
+    latency_ms_max: i64,
+}
+
+/// Accumulates rollup rows into a [`UsageSummary`].
+#[derive(Default)]
+struct Accumulator {
+    requests: i64,
+    errors: i64,
+    prompt_tokens: i64,
+    completion_tokens: i64,
+    latency_ms_sum: i64,
+    /// Request count and slowest request per latency bucket.
+    buckets: BTreeMap<i64, (i64, i64)>,
+}
+
+impl Accumulator {
+    fn add(&mut self, row: &RollupRow) {
+        self.requests += row.requests;
+        if is_error_class(&row.status_class) {
+            self.errors += row.requests;
+        }
+        self.prompt_tokens += row.prompt_tokens;
+        self.completion_tokens += row.completion_tokens;
+        self.latency_ms_sum += row.latency_ms_sum;
+        let bucket = self.buckets.entry(row.latency_bucket).or_default();
+        bucket.0 += row.requests;
+        bucket.1 = bucket.1.max(row.latency_ms_max);
+    }
+
+    fn summary(&self) -> UsageSummary {
+        let per_request = |n: i64| (self.requests > 0).then(|| n as f64 / self.requests as f64);
+        UsageSummary {
+            requests: self.requests,
+            errors: self.errors,
+            success_rate: per_request(self.requests - self.errors),
+            error_rate: per_request(self.errors),
+            prompt_tokens: self.prompt_tokens,
+            completion_tokens: self.completion_tokens,
+            avg_latency_ms: per_request(self.latency_ms_sum),
+            p50_latency_ms: self.percentile(0.50),
+            p95_latency_ms: self.percentile(0.95),
+            p99_latency_ms: self.percentile(0.99),
+        }
+    }
+
+    /// Estimate the `q` quantile by linear interpolation inside the bucket
+    /// holding it. A bucket's upper bound is capped at its slowest request,
+    /// so the open-ended last bucket still yields a sensible value.
+    fn percentile(&self, q: f64) -> Option<f64> {
+        let total: i64 = self.buckets.values().map(|(count, _)| count).sum();
+        if total == 0 {
+            return None;
+        }
+        let rank = (q * total as f64).ceil().max(1.0);
+        let mut seen = 0i64;
+        for (&upper, &(count, max)) in &self.buckets {
+            if count == 0 {
+                continue;
+            }
+            if (seen + count) as f64 >= rank {
+                let lower = bucket_lower_bound(upper) as f64;
+                let upper = (upper as f64).min(max as f64).max(lower);
+                let within = (rank - seen as f64) / count as f64;
+                return Some(lower + (upper - lower) * within);
+            }
+            seen += count;
+        }
+        None
+    }
+}
+
+fn bucket_lower_bound(upper: i64) -> i64 {
+    LATENCY_BUCKETS_MS
+        .iter()
+        .rev()
+        .find(|&&b| b < upper)
+        .copied()
+        .unwrap_or(0)
+}
+
+fn is_error_class(status_class: &str) -> bool {
+    !matches!(status_class, "2xx" | "3xx")
+}
+
+pub async fn get_usage_analytics(
+    db: &SqlitePool,
+    query: AnalyticsQuery,
+) -> Result<UsageAnalytics, IpcError> {
+    let days = query.days.unwrap_or(7);
+    if days <= 0 {
+        return Err(IpcError::validation("days must be positive"));
+    }
+    let since = rollup_hour(chrono::Utc::now() - chrono::Duration::days(days as i64));
+
+    let period = match query.granularity {
+        Granularity::Hour => "hour",
+        Granularity::Day => "substr(hour, 1, 10)",
+    };
+    let series = grouped(db, period, &since).await?;
+    let mut total = Accumulator::default();
+    for rows in series.values() {
+        for row in rows {
+            total.add(row);
+        }
+    }
+
+    let route_names = names(db, "SELECT id, name FROM routes").await?;
+    let target_names = names(
+        db,
+        "SELECT t.id, r.name || ' → ' || t.base_url FROM route_targets t JOIN routes r ON r.id = t.route_id",
+    )
+    .await?;
+    let token_names = names(db, "SELECT id, name FROM tokens").await?;
+
+    Ok(UsageAnalytics {
+        total: total.summary(),
+        series: series
+            .into_iter()
+            .map(|(period, rows)| UsagePeriod {
+                period,
+                summary: summarize(&rows),
+            })
+            .collect(),
+        by_route: groups(grouped(db, "route_id", &since).await?, &route_names),
+        by_target: groups(grouped(db, "target_id", &since).await?, &target_names),
+        by_token: groups(grouped(db, "token_id", &since).await?, &token_names),
+        by_status_class: groups(grouped(db, "status_class", &since).await?, &HashMap::new()),
+    })
+}
+
+/// Rollup rows since `since`, keyed by `key_expr` (a fixed column or
+/// expression, never user input).
+async fn grouped(
+    db: &SqlitePool,
+    key_expr: &str,
+    since: &str,
+) -> Result<BTreeMap<String, Vec<RollupRow>>, IpcError> {
+    let rows: Vec<RollupRow> = sqlx::query_as(&format!(
+        "SELECT {key_expr} AS key, status_class, latency_bucket, SUM(requests) AS requests, SUM(prompt_tokens) AS prompt_tokens, SUM(completion_tokens) AS completion_tokens, SUM(latency_ms_sum) AS latency_ms_sum, MAX(latency_ms_max) AS latency_ms_max FROM usage_rollups WHERE hour >= ? GROUP BY 1, status_class, latency_bucket"
+    ))
+    .bind(since)
+    .fetch_all(db)
+    .await?;
+
+    let mut map: BTreeMap<String, Vec<RollupRow>> = BTreeMap::new();
+    for row in rows {
+        map.entry(row.key.clone()).or_default().push(row);
+    }
+    Ok(map)
+}
+
+fn summarize(rows: &[RollupRow]) -> UsageSummary {
+    let mut acc = Accumulator::default();
+    for row in rows {
+        acc.add(row);
+    }
+    acc.summary()
+}
+
+/// Groups ordered by request count, busiest first.
+fn groups(
+    grouped: BTreeMap<String, Vec<RollupRow>>,
+    names: &HashMap<String, String>,
+) -> Vec<UsageGroup> {
+    let mut groups: Vec<UsageGroup> = grouped
+        .into_iter()
+        .map(|(key, rows)| UsageGroup {
+            name: names.get(&key).cloned(),
+            summary: summarize(&rows),
+            key,
+        })
+        .collect();
+    groups.sort_by_key(|g| std::cmp::Reverse(g.summary.requests));
+    groups
+}
+
+async fn names(db: &SqlitePool, sql: &str) -> Result<HashMap<String, String>, IpcError> {
+    let rows: Vec<(String, Option<String>)> = sqlx::query_as(sql).fetch_all(db).await?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(id, name)| name.map(|n| (id, n)))
+        .collect())
+}
+
+/// Format a time like the `hour` column of `usage_rollups`.
+pub fn rollup_hour(time: chrono::DateTime<chrono::Utc>) -> String {
+    time.format("%Y-%m-%dT%H:00:00Z").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(status_class: &str, latency_bucket: i64, requests: i64, max: i64) -> RollupRow {
+        RollupRow {
+            key: String::new(),
+            status_class: status_class.to_string(),
+            latency_bucket,
+            requests,
+            prompt_tokens: 0,
+            completion_tokens: 0,
+            latency_ms_sum: 0,
+            latency_ms_max: max,
+        }
+    }
+
+    #[test]
+    fn summary_rates_and_percentiles() {
+        let summary = summarize(&[
+            row("2xx", 100, 90, 100),
+            row("5xx", 1000, 9, 800),
+            row("upstream", 86_400_000, 1, 200_000),
+        ]);
+        assert_eq!(summary.requests, 100);
+        assert_eq!(summary.errors, 10);
+        assert_eq!(summary.success_rate, Some(0.9));
+
+        // p50 falls halfway through the 50-100ms bucket.
+        assert_eq!(summary.p50_latency_ms, Some(50.0 + 50.0 * (50.0 / 90.0)));
+        // p95 is in the 500-1000ms bucket, capped at the slowest request (800ms).
+        assert_eq!(summary.p95_latency_ms, Some(500.0 + 300.0 * (5.0 / 9.0)));
+        // p99 is the last 500-1000ms request; the overflow bucket only holds p100.
+        assert_eq!(summary.p99_latency_ms, Some(800.0));
+
+        let empty = summarize(&[]);
+        assert_eq!(empty.success_rate, None);
+        assert_eq!(empty.p50_latency_ms, None);
+    }
+}
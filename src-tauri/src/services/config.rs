@@ -0,0 +1,230 @@
+use crate::config::{self, AppConfig, ConfigWatch, RedactionConfig, StreamConfig, TlsConfig};
+use crate::error::IpcError;
+use crate::outbound;
+use crate::secrets;
+use crate::server::ServerControl;
+use crate::services::config_watch::ConfigWatcher;
+use crate::telemetry;
+use serde::{Deserialize, Deserializer};
+use sqlx::{SqliteConnection, SqlitePool};
+use tokio::sync::RwLock;
+
+/// Partial config update; omitted fields keep their current value.
+#[derive(Debug, Default, Deserialize)]
+pub struct ConfigUpdate {
+    pub server_port: Option<u16>,
+    pub log_retention_days: Option<u32>,
+    pub bind_address: Option<String>,
+    pub tls: Option<TlsConfig>,
+    pub allowed_ips: Option<Vec<String>>,
+    pub redaction: Option<RedactionConfig>,
+    /// `null` moves the admin API back onto the gateway port.
+    #[serde(default, deserialize_with = "present")]
+    pub admin_port: Option<Option<u16>>,
+    /// `null` stops exporting traces.
+    #[serde(default, deserialize_with = "present")]
+    pub otlp_endpoint: Option<Option<String>>,
+    /// `null` removes the global outbound proxy.
+    #[serde(default, deserialize_with = "present")]
+    pub proxy_url: Option<Option<String>>,
+    pub stream: Option<StreamConfig>,
+}
+
+/// Distinguish an explicit `null` (`Some(None)`) from an omitted field (`None`).
+fn present<'de, D, T>(d: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(d).map(Some)
+}
+
+pub async fn update_config(
+    db: &SqlitePool,
+    config: &RwLock<AppConfig>,
+    server: &ServerControl,
+    update: ConfigUpdate,
+) -> Result<AppConfig, IpcError> {
+    if let Some(redaction) = &update.redaction {
+        for pattern in &redaction.body_patterns {
+            regex::Regex::new(pattern)
+                .map_err(|e| IpcError::validation(format!("Invalid redaction pattern {:?}: {}", pattern, e)))?;
+        }
+    }
+    if let Some(bind_address) = &update.bind_address {
+        bind_address
+            .parse::<std::net::IpAddr>()
+            .map_err(|_| IpcError::validation(format!("Invalid bind address: {}", bind_address)))?;
+    }
+    if let Some(tls) = &update.tls {
+        tls.validate().map_err(IpcError::validation)?;
+    }
+    if let Some(allowed_ips) = &update.allowed_ips {
+        crate::server::middleware::parse_allow_list(allowed_ips).map_err(IpcError::validation)?;
+    }
+    if let Some(Some(endpoint)) = &update.otlp_endpoint {
+        telemetry::validate_endpoint(endpoint).map_err(IpcError::validation)?;
+    }
+    if let Some(Some(proxy_url)) = &update.proxy_url {
+        outbound::validate_proxy_url(proxy_url).map_err(IpcError::validation)?;
+    }
+
+    let previous = config.read().await.clone();
+    let mut next = previous.clone();
+    apply_update(&mut next, &update);
+    if next.admin_port == Some(next.server_port) {
+        return Err(IpcError::validation("admin_port must differ from server_port"));
+    }
+    crate::server::middleware::check_exposure(&next.bind_address, &next.allowed_ips)
+        .map_err(IpcError::validation)?;
+
+    // Rebind inside the transaction, so a port that cannot be bound rolls
+    // every field back and the previous listener settings stay in effect.
+    let mut tx = db.begin().await?;
+    save_update(&mut tx, &update).await?;
+    server.apply(&next).await.map_err(IpcError::bind_failed)?;
+    let tracing_changed = next.otlp_endpoint != previous.otlp_endpoint;
+    if tracing_changed {
+        if let Err(e) = telemetry::set_endpoint(next.otlp_endpoint.as_deref()) {
+            restore_listeners(server, &previous).await;
+            return Err(IpcError::validation(e));
+        }
+    }
+    if let Err(e) = tx.commit().await {
+        restore_listeners(server, &previous).await;
+        if tracing_changed {
+            if let Err(e) = telemetry::set_endpoint(previous.otlp_endpoint.as_deref()) {
+                log::error!("Failed to restore the previous tracing endpoint: {}", e);
+            }
+        }
+        return Err(e.into());
+    }
+
+    let mut config = config.write().await;
+    apply_update(&mut config, &update);
+    Ok(config.clone())
+}
+
+/// Overlay the fields set in `update` onto `config`.
+fn apply_update(config: &mut AppConfig, update: &ConfigUpdate) {
+    if let Some(server_port) = update.server_port {
+        config.server_port = server_port;
+    }
+    if let Some(days) = update.log_retention_days {
+        config.log_retention_days = days;
+    }
+    if let Some(bind_address) = &update.bind_address {
+        config.bind_address = bind_address.clone();
+    }
+    if let Some(tls) = &update.tls {
+        config.tls = tls.clone();
+    }
+    if let Some(allowed_ips) = &update.allowed_ips {
+        config.allowed_ips = allowed_ips.clone();
+    }
+    if let Some(redaction) = &update.redaction {
+        config.redaction = redaction.clone();
+    }
+    if let Some(admin_port) = update.admin_port {
+        config.admin_port = admin_port;
+    }
+    if let Some(otlp_endpoint) = &update.otlp_endpoint {
+        config.otlp_endpoint = otlp_endpoint.clone();
+    }
+    if let Some(proxy_url) = &update.proxy_url {
+        config.proxy_url = proxy_url.clone();
+    }
+    if let Some(stream) = &update.stream {
+        config.stream = stream.clone();
+    }
+}
+
+/// Write the fields set in `update` to `app_config`.
+async fn save_update(tx: &mut SqliteConnection, update: &ConfigUpdate) -> Result<(), IpcError> {
+    if let Some(server_port) = update.server_port {
+        config::save_value(&mut *tx, "server_port", &server_port.to_string()).await?;
+    }
+    if let Some(days) = update.log_retention_days {
+        config::save_value(&mut *tx, "log_retention_days", &days.to_string()).await?;
+    }
+    if let Some(bind_address) = &update.bind_address {
+        config::save_value(&mut *tx, "bind_address", bind_address).await?;
+    }
+    if let Some(tls) = &update.tls {
+        config::save_value(&mut *tx, "tls", &serde_json::to_string(tls)?).await?;
+    }
+    if let Some(allowed_ips) = &update.allowed_ips {
+        config::save_value(&mut *tx, "allowed_ips", &serde_json::to_string(allowed_ips)?).await?;
+    }
+    if let Some(redaction) = &update.redaction {
+        config::save_value(&mut *tx, "redaction", &serde_json::to_string(redaction)?).await?;
+    }
+    match update.admin_port {
+        Some(Some(port)) => config::save_value(&mut *tx, "admin_port", &port.to_string()).await?,
+        Some(None) => config::delete_value(&mut *tx, "admin_port").await?,
+        None => {}
+    }
+    match &update.otlp_endpoint {
+        Some(Some(endpoint)) => config::save_value(&mut *tx, "otlp_endpoint", endpoint).await?,
+        Some(None) => config::delete_value(&mut *tx, "otlp_endpoint").await?,
+        None => {}
+    }
+    match &update.proxy_url {
+        Some(Some(proxy_url)) => config::save_value(&mut *tx, "proxy_url", proxy_url).await?,
+        Some(None) => config::delete_value(&mut *tx, "proxy_url").await?,
+        None => {}
+    }
+    if let Some(stream) = &update.stream {
+        config::save_value(&mut *tx, "stream", &serde_json::to_string(stream)?).await?;
+    }
+    Ok(())
+}
+
+/// Rebind the listeners of `previous` after a failure past the rebind.
+async fn restore_listeners(server: &ServerControl, previous: &AppConfig) {
+    if let Err(e) = server.apply(previous).await {
+        log::error!("Failed to restore the previous listeners: {}", e);
+    }
+}
+
+/// Start watching a declarative config file (applying it right away), or stop
+/// with `None`. The setting persists across restarts.
+pub async fn set_config_watch(
+    db: &SqlitePool,
+    config: &RwLock<AppConfig>,
+    watcher: &ConfigWatcher,
+    watch: Option<ConfigWatch>,
+) -> Result<AppConfig, IpcError> {
+    watcher.set(watch.as_ref()).map_err(IpcError::validation)?;
+    match &watch {
+        Some(watch) => config::save_value(db, "config_watch", &serde_json::to_string(watch)?).await?,
+        None => config::delete_value(db, "config_watch").await?,
+    }
+    let mut config = config.write().await;
+    config.config_watch = watch;
+    Ok(config.clone())
+}
+
+/// Generate a new admin API key, replacing any previous one.
+/// Only its hash is stored, so the returned plaintext is shown once.
+pub async fn rotate_admin_key(
+    db: &SqlitePool,
+    config: &RwLock<AppConfig>,
+) -> Result<String, IpcError> {
+    let key = secrets::generate_token();
+    let hash = secrets::hash_token(&key);
+    config::save_value(db, "admin_key_hash", &hash).await?;
+    config.write().await.admin_key_hash = Some(hash);
+    Ok(key)
+}
+
+/// Remove the admin API key, which disables the admin API.
+#[cfg(feature = "desktop")]
+pub async fn clear_admin_key(
+    db: &SqlitePool,
+    config: &RwLock<AppConfig>,
+) -> Result<(), IpcError> {
+    config::delete_value(db, "admin_key_hash").await?;
+    config.write().await.admin_key_hash = None;
+    Ok(())
+}
@@ -0,0 +1,130 @@
+//! Keeps a declarative config file (see [`super::declarative`]) applied to the
+//! database: once when watching starts and again whenever the file changes.
+
+use super::declarative::{self, ImportOptions};
+use crate::config::ConfigWatch;
+use crate::secrets::KeyVault;
+use notify::{EventKind, RecursiveMode, Watcher};
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Passphrase for watched files whose upstream keys are encrypted.
+const PASSPHRASE_ENV: &str = "OMNIKIT_CONFIG_PASSPHRASE";
+/// Editors often save in several steps; wait for writes to settle.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Clone)]
+pub struct ConfigWatcher {
+    db: SqlitePool,
+    vault: Arc<KeyVault>,
+    current: Arc<Mutex<Option<notify::RecommendedWatcher>>>,
+}
+
+impl ConfigWatcher {
+    pub fn new(db: SqlitePool, vault: Arc<KeyVault>) -> Self {
+        Self {
+            db,
+            vault,
+            current: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Replace the watched file. `None` stops watching.
+    pub fn set(&self, watch: Option<&ConfigWatch>) -> Result<(), String> {
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        // Dropping the previous watcher closes its channel, ending its task.
+        *current = None;
+
+        let Some(watch) = watch else {
+            return Ok(());
+        };
+        let path = PathBuf::from(&watch.path);
+        if !path.is_file() {
+            return Err(format!("File not found: {}", watch.path));
+        }
+        let file_name = path.file_name().map(|n| n.to_os_string());
+        // Watch the directory: editors often replace the file rather than write to it.
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            let Ok(event) = res else { return };
+            if matches!(event.kind, EventKind::Access(_)) {
+                return;
+            }
+            if event
+                .paths
+                .iter()
+                .any(|p| p.file_name().map(|n| n.to_os_string()) == file_name)
+            {
+                let _ = tx.send(());
+            }
+        })
+        .map_err(|e| format!("Failed to watch {}: {}", watch.path, e))?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch {}: {}", watch.path, e))?;
+
+        tokio::spawn(run(
+            self.db.clone(),
+            self.vault.clone(),
+            path,
+            watch.prune,
+            rx,
+        ));
+        *current = Some(watcher);
+        log::info!("Watching config file {}", watch.path);
+        Ok(())
+    }
+}
+
+async fn run(
+    db: SqlitePool,
+    vault: Arc<KeyVault>,
+    path: PathBuf,
+    prune: bool,
+    mut rx: mpsc::UnboundedReceiver<()>,
+) {
+    apply_file(&db, &vault, &path, prune).await;
+    while rx.recv().await.is_some() {
+        tokio::time::sleep(DEBOUNCE).await;
+        while rx.try_recv().is_ok() {}
+        if path.is_file() {
+            apply_file(&db, &vault, &path, prune).await;
+        }
+    }
+}
+
+async fn apply_file(db: &SqlitePool, vault: &KeyVault, path: &Path, prune: bool) {
+    let text = match tokio::fs::read_to_string(path).await {
+        Ok(text) => text,
+        Err(e) => {
+            log::error!("Failed to read {}: {}", path.display(), e);
+            return;
+        }
+    };
+    let options = ImportOptions {
+        passphrase: std::env::var(PASSPHRASE_ENV).ok(),
+        prune,
+        require_token_keys: true,
+    };
+    match declarative::apply_import(db, vault, &text, options).await {
+        Ok(result) => {
+            log::info!("Applied {} ({})", path.display(), result.plan.summary());
+            for token in &result.created_tokens {
+                log::info!(
+                    "Created token {} ({}...)",
+                    token.token.name.as_deref().unwrap_or_default(),
+                    token.token.key_prefix.as_deref().unwrap_or_default()
+                );
+            }
+        }
+        Err(e) => log::error!("Failed to apply {}: {}", path.display(), e.message),
+    }
+}
@@ -0,0 +1,901 @@
+//! Declarative gateway configuration: routes (with targets, keys and
+//! overrides) and tokens as one YAML or JSON document that can be exported,
+//! diffed against the database and applied.
+//!
+//! Routes are matched by `path_prefix` and tokens by `name`. Applying a
+//! document creates and updates what it describes; with `prune` it also
+//! deletes routes and named tokens it does not mention. Unnamed tokens cannot
+//! be described and are never touched.
+
+use super::routes::{self, ModelInput, OverrideInput, RouteInput, RouteWithTargets, TargetInput};
+use super::tokens::{self, CreatedToken, TokenInput};
+use crate::db::models::Token;
+use crate::error::IpcError;
+use crate::secrets::{self, KeyVault};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use std::collections::{HashMap, HashSet};
+
+pub const MANIFEST_VERSION: u32 = 1;
+const KDF_NAME: &str = "pbkdf2-sha256";
+/// Most key-derivation rounds accepted from a document, so a hostile one
+/// can't stall the import.
+const MAX_KDF_ROUNDS: u32 = 10 * secrets::PBKDF2_ROUNDS;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GatewayManifest {
+    pub version: u32,
+    /// Present when upstream keys are encrypted with a passphrase.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<ManifestEncryption>,
+    #[serde(default)]
+    pub routes: Vec<RouteSpec>,
+    #[serde(default)]
+    pub tokens: Vec<TokenSpec>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManifestEncryption {
+    pub kdf: String,
+    pub rounds: u32,
+    /// Base64 salt for the key derivation.
+    pub salt: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteSpec {
+    pub name: String,
+    pub path_prefix: String,
+    pub input_format: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_policy: Option<String>,
+    #[serde(default)]
+    pub targets: Vec<TargetSpec>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TargetSpec {
+    pub upstream_format: String,
+    pub base_url: String,
+    #[serde(default = "default_weight")]
+    pub weight: i32,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub key_rotation: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout_secs: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_byte_timeout_secs: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_timeout_secs: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream_mode: Option<String>,
+    #[serde(default)]
+    pub normalize_messages: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inline_images: Option<String>,
+    /// Omitted keys keep those of the existing target with the same
+    /// `upstream_format` and `base_url`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keys: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overrides: Vec<OverrideSpec>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub models: Vec<ModelSpec>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OverrideSpec {
+    pub scope: String,
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelSpec {
+    pub model: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upstream_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenSpec {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quota_limit: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_models: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Key for a newly created token; a random one is generated when omitted,
+    /// except from a watched file. Existing tokens keep their key. Never
+    /// exported, since only hashes are stored.
+    #[serde(default, skip_serializing)]
+    pub key: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_weight() -> i32 {
+    1
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Yaml,
+    Json,
+}
+
+/// How upstream keys are written on export.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyMode {
+    /// Leave keys out; importing keeps the keys already in the database.
+    #[default]
+    Omit,
+    Plain,
+    /// Encrypt keys with a passphrase-derived key.
+    Encrypt,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ExportOptions {
+    #[serde(default)]
+    pub format: ExportFormat,
+    #[serde(default)]
+    pub keys: KeyMode,
+    pub passphrase: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ImportOptions {
+    /// Needed when the document's keys are encrypted.
+    pub passphrase: Option<String>,
+    #[serde(default)]
+    pub prune: bool,
+    /// Reject new tokens without a `key`. Set for watched files, where a
+    /// generated key could only end up in the log.
+    #[serde(skip)]
+    pub require_token_keys: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeAction {
+    Create,
+    Update,
+    Delete,
+    Unchanged,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlannedChange {
+    pub action: ChangeAction,
+    /// `path_prefix` for routes, `name` for tokens.
+    pub name: String,
+    /// Human-readable field changes; secrets are never included.
+    pub changes: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportPlan {
+    pub routes: Vec<PlannedChange>,
+    pub tokens: Vec<PlannedChange>,
+}
+
+impl ImportPlan {
+    /// e.g. "routes: 1 created, 2 updated; tokens: 1 deleted"
+    pub fn summary(&self) -> String {
+        fn count(changes: &[PlannedChange]) -> String {
+            let parts: Vec<String> = [
+                (ChangeAction::Create, "created"),
+                (ChangeAction::Update, "updated"),
+                (ChangeAction::Delete, "deleted"),
+            ]
+            .iter()
+            .filter_map(|(action, label)| {
+                let n = changes.iter().filter(|c| c.action == *action).count();
+                (n > 0).then(|| format!("{} {}", n, label))
+            })
+            .collect();
+            if parts.is_empty() {
+                "unchanged".to_string()
+            } else {
+                parts.join(", ")
+            }
+        }
+        format!("routes: {}; tokens: {}", count(&self.routes), count(&self.tokens))
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportResult {
+    pub plan: ImportPlan,
+    /// Tokens created by the import; the only time their keys are returned.
+    pub created_tokens: Vec<CreatedToken>,
+}
+
+// === Export ===
+
+pub async fn export_config(
+    db: &SqlitePool,
+    vault: &KeyVault,
+    options: ExportOptions,
+) -> Result<String, IpcError> {
+    let (encryption, key_vault) = match options.keys {
+        KeyMode::Encrypt => {
+            let passphrase = options
+                .passphrase
+                .filter(|p| !p.is_empty())
+                .ok_or_else(|| IpcError::validation("A passphrase is required to encrypt keys"))?;
+            let mut salt = [0u8; 16];
+            rand::rng().fill_bytes(&mut salt);
+            let encryption = ManifestEncryption {
+                kdf: KDF_NAME.to_string(),
+                rounds: secrets::PBKDF2_ROUNDS,
+                salt: BASE64.encode(salt),
+            };
+            let key_vault = KeyVault::from_passphrase(&passphrase, &salt, secrets::PBKDF2_ROUNDS);
+            (Some(encryption), Some(key_vault))
+        }
+        _ => (None, None),
+    };
+
+    let routes = routes::list_routes(db, vault)
+        .await?
+        .iter()
+        .map(|route| {
+            let mut spec = route_spec(route);
+            for target in &mut spec.targets {
+                target.keys = match (options.keys, &key_vault) {
+                    (KeyMode::Omit, _) => None,
+                    (_, Some(key_vault)) => target
+                        .keys
+                        .take()
+                        .map(|keys| keys.iter().map(|k| key_vault.encrypt(k)).collect()),
+                    (_, None) => target.keys.take(),
+                };
+            }
+            spec
+        })
+        .collect();
+
+    let tokens = tokens::list_tokens(db)
+        .await?
+        .iter()
+        .rev()
+        .filter_map(token_spec)
+        .collect();
+
+    let manifest = GatewayManifest {
+        version: MANIFEST_VERSION,
+        encryption,
+        routes,
+        tokens,
+    };
+    match options.format {
+        ExportFormat::Yaml => {
+            serde_yaml::to_string(&manifest).map_err(|e| IpcError::internal(e.to_string()))
+        }
+        ExportFormat::Json => Ok(serde_json::to_string_pretty(&manifest)?),
+    }
+}
+
+fn route_spec(route: &RouteWithTargets) -> RouteSpec {
+    RouteSpec {
+        name: route.route.name.clone(),
+        path_prefix: route.route.path_prefix.clone(),
+        input_format: route.route.input_format.clone(),
+        enabled: route.route.enabled,
+        context_policy: route.route.context_policy.clone(),
+        targets: route
+            .targets
+            .iter()
+            .map(|t| TargetSpec {
+                upstream_format: t.target.upstream_format.clone(),
+                base_url: t.target.base_url.clone(),
+                weight: t.target.weight,
+                enabled: t.target.enabled,
+                key_rotation: t.target.key_rotation,
+                proxy_url: t.target.proxy_url.clone(),
+                connect_timeout_secs: t.target.connect_timeout_secs,
+                first_byte_timeout_secs: t.target.first_byte_timeout_secs,
+                idle_timeout_secs: t.target.idle_timeout_secs,
+                stream_mode: t.target.stream_mode.clone(),
+                normalize_messages: t.target.normalize_messages,
+                inline_images: t.target.inline_images.clone(),
+                keys: Some(t.keys.iter().map(|k| k.key_value.clone()).collect()),
+                overrides: t
+                    .overrides
+                    .iter()
+                    .map(|o| OverrideSpec {
+                        scope: o.scope.clone(),
+                        key: o.key.clone(),
+                        value: o.value.clone(),
+                    })
+                    .collect(),
+                models: t
+                    .models
+                    .iter()
+                    .map(|m| ModelSpec {
+                        model: m.model.clone(),
+                        upstream_model: m.upstream_model.clone(),
+                        context_window: m.context_window,
+                        max_output_tokens: m.max_output_tokens,
+                    })
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+/// Only named tokens can be matched on import, so unnamed ones are skipped.
+fn token_spec(token: &Token) -> Option<TokenSpec> {
+    let name = token.name.clone().filter(|n| !n.trim().is_empty())?;
+    Some(TokenSpec {
+        name,
+        quota_limit: token.quota_limit,
+        expires_at: token.expires_at.clone(),
+        allowed_models: token.allowed_models.clone(),
+        enabled: token.enabled,
+        key: None,
+    })
+}
+
+// === Import ===
+
+/// Parse a YAML or JSON document (JSON is valid YAML).
+pub fn parse_manifest(text: &str) -> Result<GatewayManifest, IpcError> {
+    let manifest: GatewayManifest = serde_yaml::from_str(text)
+        .map_err(|e| IpcError::validation(format!("Invalid config document: {}", e)))?;
+    if manifest.version != MANIFEST_VERSION {
+        return Err(IpcError::validation(format!(
+            "Unsupported config version {} (expected {})",
+            manifest.version, MANIFEST_VERSION
+        )));
+    }
+    Ok(manifest)
+}
+
+enum RouteOp {
+    Create(RouteInput),
+    Update(String, RouteInput),
+    Delete(String),
+}
+
+enum TokenOp {
+    Create(TokenInput, Option<String>),
+    Update(String, TokenInput),
+    Delete(String),
+}
+
+struct Prepared {
+    plan: ImportPlan,
+    route_ops: Vec<RouteOp>,
+    token_ops: Vec<TokenOp>,
+}
+
+/// Diff a document against the database without changing anything.
+pub async fn preview_import(
+    db: &SqlitePool,
+    vault: &KeyVault,
+    text: &str,
+    options: ImportOptions,
+) -> Result<ImportPlan, IpcError> {
+    let manifest = read_manifest(text, &options)?;
+    let mut tx = db.begin().await?;
+    Ok(prepare(&mut tx, vault, manifest, &options).await?.plan)
+}
+
+/// Apply a document. Everything is validated before the first write, and
+/// all writes go in one transaction, so a failure leaves the database as it was.
+pub async fn apply_import(
+    db: &SqlitePool,
+    vault: &KeyVault,
+    text: &str,
+    options: ImportOptions,
+) -> Result<ImportResult, IpcError> {
+    let manifest = read_manifest(text, &options)?;
+    let mut tx = db.begin().await?;
+    let prepared = prepare(&mut tx, vault, manifest, &options).await?;
+
+    for op in prepared.route_ops {
+        match op {
+            RouteOp::Create(input) => {
+                routes::insert_route(&mut tx, vault, &input).await?;
+            }
+            RouteOp::Update(id, input) => routes::replace_route(&mut tx, vault, &id, &input).await?,
+            RouteOp::Delete(id) => routes::delete_route(&mut *tx, &id).await?,
+        }
+    }
+
+    let mut created_tokens = Vec::new();
+    for op in prepared.token_ops {
+        match op {
+            TokenOp::Create(input, key) => {
+                let key = key.unwrap_or_else(secrets::generate_token);
+                created_tokens.push(tokens::insert_token(&mut tx, &input, key).await?);
+            }
+            TokenOp::Update(id, input) => tokens::update_token(&mut *tx, &id, input).await?,
+            TokenOp::Delete(id) => tokens::delete_token(&mut *tx, &id).await?,
+        }
+    }
+
+    tx.commit().await?;
+    Ok(ImportResult {
+        plan: prepared.plan,
+        created_tokens,
+    })
+}
+
+/// Parse a document and decrypt its keys, before any database access.
+fn read_manifest(text: &str, options: &ImportOptions) -> Result<GatewayManifest, IpcError> {
+    let mut manifest = parse_manifest(text)?;
+    decrypt_manifest_keys(&mut manifest, options.passphrase.as_deref())?;
+    Ok(manifest)
+}
+
+/// Plan the changes against the rows read on `conn`. The import applies them
+/// in the same transaction, so nothing can change in between.
+async fn prepare(
+    conn: &mut SqliteConnection,
+    vault: &KeyVault,
+    manifest: GatewayManifest,
+    options: &ImportOptions,
+) -> Result<Prepared, IpcError> {
+    let mut plan = ImportPlan::default();
+    let mut route_ops = Vec::new();
+    let mut token_ops = Vec::new();
+
+    // Routes, matched by path_prefix
+    let existing_routes = routes::read_routes(conn, vault).await?;
+    let existing_by_prefix: HashMap<&str, &RouteWithTargets> = existing_routes
+        .iter()
+        .map(|r| (r.route.path_prefix.as_str(), r))
+        .collect();
+    let mut seen = HashSet::new();
+
+    for mut spec in manifest.routes {
+        if !seen.insert(spec.path_prefix.clone()) {
+            return Err(IpcError::validation(format!(
+                "Duplicate route path_prefix {}",
+                spec.path_prefix
+            )));
+        }
+        let existing = existing_by_prefix.get(spec.path_prefix.as_str()).copied();
+        resolve_omitted_keys(&mut spec, existing);
+        let input = route_input(&spec);
+        routes::validate_input(&input)
+            .map_err(|e| IpcError::validation(format!("Route {}: {}", spec.path_prefix, e.message)))?;
+
+        match existing {
+            None => {
+                plan.routes.push(PlannedChange {
+                    action: ChangeAction::Create,
+                    name: spec.path_prefix.clone(),
+                    changes: vec![format!("{} target(s)", spec.targets.len())],
+                });
+                route_ops.push(RouteOp::Create(input));
+            }
+            Some(existing) => {
+                let changes = diff_route(&route_spec(existing), &spec);
+                if changes.is_empty() {
+                    plan.routes.push(unchanged(&spec.path_prefix));
+                } else {
+                    plan.routes.push(PlannedChange {
+                        action: ChangeAction::Update,
+                        name: spec.path_prefix.clone(),
+                        changes,
+                    });
+                    route_ops.push(RouteOp::Update(existing.route.id.clone(), input));
+                }
+            }
+        }
+    }
+    if options.prune {
+        for route in &existing_routes {
+            if !seen.contains(&route.route.path_prefix) {
+                plan.routes.push(deleted(&route.route.path_prefix));
+                route_ops.push(RouteOp::Delete(route.route.id.clone()));
+            }
+        }
+    }
+
+    // Tokens, matched by name (oldest first when names repeat)
+    let mut existing_tokens = tokens::list_tokens(&mut *conn).await?;
+    existing_tokens.reverse();
+    let mut existing_by_name: HashMap<&str, &Token> = HashMap::new();
+    for token in &existing_tokens {
+        if let Some(name) = token.name.as_deref() {
+            existing_by_name.entry(name).or_insert(token);
+        }
+    }
+    let mut seen = HashSet::new();
+
+    for spec in manifest.tokens {
+        if spec.name.trim().is_empty() {
+            return Err(IpcError::validation("Token name must not be empty"));
+        }
+        if !seen.insert(spec.name.clone()) {
+            return Err(IpcError::validation(format!("Duplicate token name {}", spec.name)));
+        }
+        let input = TokenInput {
+            name: Some(spec.name.clone()),
+            quota_limit: spec.quota_limit,
+            expires_at: spec.expires_at.clone(),
+            allowed_models: spec.allowed_models.clone(),
+            enabled: spec.enabled,
+        };
+        match existing_by_name.get(spec.name.as_str()).copied() {
+            None => {
+                if options.require_token_keys && spec.key.is_none() {
+                    return Err(IpcError::validation(format!(
+                        "Token {}: a key is required, since a generated one couldn't be shown",
+                        spec.name
+                    )));
+                }
+                plan.tokens.push(PlannedChange {
+                    action: ChangeAction::Create,
+                    name: spec.name.clone(),
+                    changes: Vec::new(),
+                });
+                token_ops.push(TokenOp::Create(input, spec.key));
+            }
+            Some(existing) => {
+                let changes = token_spec(existing)
+                    .map(|current| diff_token(&current, &spec))
+                    .unwrap_or_default();
+                if changes.is_empty() {
+                    plan.tokens.push(unchanged(&spec.name));
+                } else {
+                    plan.tokens.push(PlannedChange {
+                        action: ChangeAction::Update,
+                        name: spec.name.clone(),
+                        changes,
+                    });
+                    token_ops.push(TokenOp::Update(existing.id.clone(), input));
+                }
+            }
+        }
+    }
+    if options.prune {
+        for token in &existing_tokens {
+            let Some(name) = token.name.as_deref().filter(|n| !n.trim().is_empty()) else {
+                continue;
+            };
+            if !seen.contains(name) {
+                plan.tokens.push(deleted(name));
+                token_ops.push(TokenOp::Delete(token.id.clone()));
+            }
+        }
+    }
+
+    Ok(Prepared {
+        plan,
+        route_ops,
+        token_ops,
+    })
+}
+
+fn decrypt_manifest_keys(
+    manifest: &mut GatewayManifest,
+    passphrase: Option<&str>,
+) -> Result<(), IpcError> {
+    let Some(encryption) = &manifest.encryption else {
+        return Ok(());
+    };
+    if encryption.kdf != KDF_NAME {
+        return Err(IpcError::validation(format!("Unsupported key derivation {}", encryption.kdf)));
+    }
+    if !(secrets::PBKDF2_ROUNDS..=MAX_KDF_ROUNDS).contains(&encryption.rounds) {
+        return Err(IpcError::validation(format!(
+            "Key derivation rounds must be between {} and {}",
+            secrets::PBKDF2_ROUNDS,
+            MAX_KDF_ROUNDS
+        )));
+    }
+    let passphrase = passphrase
+        .filter(|p| !p.is_empty())
+        .ok_or_else(|| IpcError::validation("This document's keys are encrypted; a passphrase is required"))?;
+    let salt = BASE64
+        .decode(&encryption.salt)
+        .map_err(|e| IpcError::validation(format!("Invalid encryption salt: {}", e)))?;
+    let key_vault = KeyVault::from_passphrase(passphrase, &salt, encryption.rounds);
+
+    for route in &mut manifest.routes {
+        for target in &mut route.targets {
+            for key in target.keys.iter_mut().flatten() {
+                *key = key_vault
+                    .decrypt(key)
+                    .map_err(|_| IpcError::validation("Wrong passphrase for encrypted keys"))?;
+            }
+        }
+    }
+    manifest.encryption = None;
+    Ok(())
+}
+
+fn resolve_omitted_keys(spec: &mut RouteSpec, existing: Option<&RouteWithTargets>) {
+    for target in &mut spec.targets {
+        if target.keys.is_some() {
+            continue;
+        }
+        let keys = existing
+            .and_then(|route| {
+                route.targets.iter().find(|t| {
+                    t.target.upstream_format == target.upstream_format
+                        && t.target.base_url == target.base_url
+                })
+            })
+            .map(|t| t.keys.iter().map(|k| k.key_value.clone()).collect())
+            .unwrap_or_default();
+        target.keys = Some(keys);
+    }
+}
+
+fn route_input(spec: &RouteSpec) -> RouteInput {
+    RouteInput {
+        name: spec.name.clone(),
+        path_prefix: spec.path_prefix.clone(),
+        input_format: spec.input_format.clone(),
+        enabled: spec.enabled,
+        context_policy: spec.context_policy.clone(),
+        targets: spec
+            .targets
+            .iter()
+            .map(|t| TargetInput {
+                upstream_format: t.upstream_format.clone(),
+                base_url: t.base_url.clone(),
+                weight: t.weight,
+                enabled: t.enabled,
+                key_rotation: t.key_rotation,
+                proxy_url: t.proxy_url.clone(),
+                connect_timeout_secs: t.connect_timeout_secs,
+                first_byte_timeout_secs: t.first_byte_timeout_secs,
+                idle_timeout_secs: t.idle_timeout_secs,
+                stream_mode: t.stream_mode.clone(),
+                normalize_messages: t.normalize_messages,
+                inline_images: t.inline_images.clone(),
+                keys: t.keys.clone().unwrap_or_default(),
+                overrides: t
+                    .overrides
+                    .iter()
+                    .map(|o| OverrideInput {
+                        scope: o.scope.clone(),
+                        key: o.key.clone(),
+                        value: o.value.clone(),
+                    })
+                    .collect(),
+                models: t
+                    .models
+                    .iter()
+                    .map(|m| ModelInput {
+                        model: m.model.clone(),
+                        upstream_model: m.upstream_model.clone(),
+                        context_window: m.context_window,
+                        max_output_tokens: m.max_output_tokens,
+                    })
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+fn unchanged(name: &str) -> PlannedChange {
+    PlannedChange {
+        action: ChangeAction::Unchanged,
+        name: name.to_string(),
+        changes: Vec::new(),
+    }
+}
+
+fn deleted(name: &str) -> PlannedChange {
+    PlannedChange {
+        action: ChangeAction::Delete,
+        name: name.to_string(),
+        changes: Vec::new(),
+    }
+}
+
+fn field_change<T: std::fmt::Debug + PartialEq>(
+    changes: &mut Vec<String>,
+    field: &str,
+    old: &T,
+    new: &T,
+) {
+    if old != new {
+        changes.push(format!("{}: {:?} -> {:?}", field, old, new));
+    }
+}
+
+fn diff_route(old: &RouteSpec, new: &RouteSpec) -> Vec<String> {
+    let mut changes = Vec::new();
+    field_change(&mut changes, "name", &old.name, &new.name);
+    field_change(&mut changes, "input_format", &old.input_format, &new.input_format);
+    field_change(&mut changes, "enabled", &old.enabled, &new.enabled);
+    field_change(&mut changes, "context_policy", &old.context_policy, &new.context_policy);
+
+    if old.targets.len() != new.targets.len() {
+        changes.push(format!("targets: {} -> {}", old.targets.len(), new.targets.len()));
+        return changes;
+    }
+    for (i, (a, b)) in old.targets.iter().zip(&new.targets).enumerate() {
+        let mut fields = Vec::new();
+        field_change(&mut fields, "upstream_format", &a.upstream_format, &b.upstream_format);
+        field_change(&mut fields, "base_url", &a.base_url, &b.base_url);
+        field_change(&mut fields, "weight", &a.weight, &b.weight);
+        field_change(&mut fields, "enabled", &a.enabled, &b.enabled);
+        field_change(&mut fields, "key_rotation", &a.key_rotation, &b.key_rotation);
+        field_change(&mut fields, "connect_timeout_secs", &a.connect_timeout_secs, &b.connect_timeout_secs);
+        field_change(&mut fields, "first_byte_timeout_secs", &a.first_byte_timeout_secs, &b.first_byte_timeout_secs);
+        field_change(&mut fields, "idle_timeout_secs", &a.idle_timeout_secs, &b.idle_timeout_secs);
+        field_change(&mut fields, "stream_mode", &a.stream_mode, &b.stream_mode);
+        field_change(&mut fields, "normalize_messages", &a.normalize_messages, &b.normalize_messages);
+        field_change(&mut fields, "inline_images", &a.inline_images, &b.inline_images);
+        // Never print key values, nor proxy URLs, which may hold credentials.
+        if a.proxy_url != b.proxy_url {
+            fields.push("proxy_url changed".to_string());
+        }
+        if a.keys != b.keys {
+            let count = |keys: &Option<Vec<String>>| keys.as_ref().map_or(0, Vec::len);
+            fields.push(format!("keys changed ({} -> {})", count(&a.keys), count(&b.keys)));
+        }
+        if a.overrides != b.overrides {
+            fields.push(format!("overrides: {} -> {}", a.overrides.len(), b.overrides.len()));
+        }
+        if a.models != b.models {
+            fields.push(format!("models: {} -> {}", a.models.len(), b.models.len()));
+        }
+        changes.extend(fields.into_iter().map(|f| format!("target {}: {}", i + 1, f)));
+    }
+    changes
+}
+
+fn diff_token(old: &TokenSpec, new: &TokenSpec) -> Vec<String> {
+    let mut changes = Vec::new();
+    field_change(&mut changes, "quota_limit", &old.quota_limit, &new.quota_limit);
+    field_change(&mut changes, "expires_at", &old.expires_at, &new.expires_at);
+    field_change(&mut changes, "allowed_models", &old.allowed_models, &new.allowed_models);
+    field_change(&mut changes, "enabled", &old.enabled, &new.enabled);
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(base_url: &str, keys: Option<Vec<&str>>) -> TargetSpec {
+        TargetSpec {
+            upstream_format: "openai-chat".into(),
+            base_url: base_url.into(),
+            weight: 1,
+            enabled: true,
+            key_rotation: false,
+            proxy_url: None,
+            connect_timeout_secs: None,
+            first_byte_timeout_secs: None,
+            idle_timeout_secs: None,
+            stream_mode: None,
+            normalize_messages: false,
+            inline_images: None,
+            keys: keys.map(|k| k.into_iter().map(String::from).collect()),
+            overrides: Vec::new(),
+            models: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn parses_yaml_with_defaults() {
+        let manifest = parse_manifest(
+            "version: 1\nroutes:\n  - name: OpenAI\n    path_prefix: /openai\n    input_format: openai-chat\n    targets:\n      - upstream_format: openai-chat\n        base_url: https://api.openai.com\ntokens:\n  - name: ci\n    key: sk-shared\n",
+        )
+        .unwrap();
+        let route = &manifest.routes[0];
+        assert!(route.enabled);
+        assert_eq!(route.targets[0].weight, 1);
+        assert_eq!(route.targets[0].keys, None);
+        assert_eq!(manifest.tokens[0].key.as_deref(), Some("sk-shared"));
+
+        assert!(parse_manifest(r#"{"version": 2}"#).is_err());
+    }
+
+    #[test]
+    fn encrypted_keys_need_the_right_passphrase() {
+        let salt = [3u8; 16];
+        let key_vault = KeyVault::from_passphrase("hunter2", &salt, secrets::PBKDF2_ROUNDS);
+        let encrypted = key_vault.encrypt("sk-upstream");
+        let doc = |keys: &str| GatewayManifest {
+            version: MANIFEST_VERSION,
+            encryption: Some(ManifestEncryption {
+                kdf: KDF_NAME.into(),
+                rounds: secrets::PBKDF2_ROUNDS,
+                salt: BASE64.encode(salt),
+            }),
+            routes: vec![RouteSpec {
+                name: "r".into(),
+                path_prefix: "/r".into(),
+                input_format: "openai-chat".into(),
+                enabled: true,
+                context_policy: None,
+                targets: vec![target("https://x", Some(vec![keys]))],
+            }],
+            tokens: Vec::new(),
+        };
+
+        let mut manifest = doc(&encrypted);
+        assert!(decrypt_manifest_keys(&mut manifest, None).is_err());
+        for rounds in [1, MAX_KDF_ROUNDS + 1] {
+            manifest.encryption.as_mut().unwrap().rounds = rounds;
+            assert!(decrypt_manifest_keys(&mut manifest, Some("hunter2")).is_err());
+        }
+
+        let mut manifest = doc(&encrypted);
+        assert!(decrypt_manifest_keys(&mut manifest, Some("wrong")).is_err());
+
+        let mut manifest = doc(&encrypted);
+        decrypt_manifest_keys(&mut manifest, Some("hunter2")).unwrap();
+        assert_eq!(
+            manifest.routes[0].targets[0].keys,
+            Some(vec!["sk-upstream".to_string()])
+        );
+    }
+
+    #[test]
+    fn route_diff_hides_key_values() {
+        let old = RouteSpec {
+            name: "r".into(),
+            path_prefix: "/r".into(),
+            input_format: "openai-chat".into(),
+            enabled: true,
+            context_policy: None,
+            targets: vec![target("https://x", Some(vec!["sk-old"]))],
+        };
+        let mut new = old.clone();
+        assert!(diff_route(&old, &new).is_empty());
+
+        new.enabled = false;
+        new.targets[0].keys = Some(vec!["sk-new".into()]);
+        let changes = diff_route(&old, &new);
+        assert_eq!(changes[0], "enabled: true -> false");
+        assert_eq!(changes[1], "target 1: keys changed (1 -> 1)");
+        assert!(!changes.iter().any(|c| c.contains("sk-")));
+    }
+
+    #[tokio::test]
+    async fn reimporting_a_document_changes_nothing() {
+        let db = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations").run(&db).await.unwrap();
+        let vault = KeyVault::from_key(&[7u8; 32]);
+        let doc = "version: 1\nroutes:\n  - name: OpenAI\n    path_prefix: /openai\n    input_format: openai-chat\n    targets:\n      - upstream_format: openai-chat\n        base_url: https://api.openai.com\n        keys: [sk-upstream]\ntokens:\n  - name: ci\n    key: sk-shared\n";
+
+        let result = apply_import(&db, &vault, doc, ImportOptions::default()).await.unwrap();
+        assert_eq!(result.plan.routes[0].action, ChangeAction::Create);
+        assert_eq!(result.created_tokens.len(), 1);
+
+        let plan = preview_import(&db, &vault, doc, ImportOptions::default()).await.unwrap();
+        assert_eq!(plan.routes[0].action, ChangeAction::Unchanged);
+        assert_eq!(plan.tokens[0].action, ChangeAction::Unchanged);
+    }
+}
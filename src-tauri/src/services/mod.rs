@@ -0,0 +1,16 @@
+//! Management operations shared by the Tauri commands and the admin HTTP API.
+
+pub mod alerts;
+pub mod analytics;
+pub mod config;
+pub mod config_watch;
+pub mod declarative;
+pub mod request_logs;
+pub mod routes;
+pub mod tokens;
+
+#[derive(serde::Serialize)]
+pub struct PaginatedResult<T: serde::Serialize> {
+    pub items: Vec<T>,
+    pub total: i64,
+}
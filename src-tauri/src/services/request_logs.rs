@@ -0,0 +1,331 @@
+use crate::config::AppConfig;
+use crate::db::models::{RequestLog, Token};
+use crate::error::IpcError;
+use crate::secrets::KeyVault;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+use super::analytics::rollup_hour;
+use super::PaginatedResult;
+
+/// How often logs past `log_retention_days` are deleted.
+const RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+pub async fn list_request_logs(
+    db: &SqlitePool,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    model: Option<String>,
+) -> Result<PaginatedResult<RequestLog>, IpcError> {
+    let limit = limit.unwrap_or(50);
+    let offset = offset.unwrap_or(0);
+
+    let (items, total) = if let Some(model) = model {
+        let items = sqlx::query_as::<_, RequestLog>(
+            "SELECT * FROM request_logs WHERE model = ? ORDER BY created_at DESC LIMIT ? OFFSET ?"
+        )
+        .bind(&model).bind(limit).bind(offset)
+        .fetch_all(db)
+        .await?;
+
+        let (total,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM request_logs WHERE model = ?"
+        )
+        .bind(&model)
+        .fetch_one(db)
+        .await?;
+
+        (items, total)
+    } else {
+        let items = sqlx::query_as::<_, RequestLog>(
+            "SELECT * FROM request_logs ORDER BY created_at DESC LIMIT ? OFFSET ?"
+        )
+        .bind(limit).bind(offset)
+        .fetch_all(db)
+        .await?;
+
+        let (total,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM request_logs"
+        )
+        .fetch_one(db)
+        .await?;
+
+        (items, total)
+    };
+
+    Ok(PaginatedResult { items, total })
+}
+
+pub async fn get_request_log(db: &SqlitePool, id: &str) -> Result<Option<RequestLog>, IpcError> {
+    Ok(sqlx::query_as::<_, RequestLog>("SELECT * FROM request_logs WHERE id = ?")
+        .bind(id)
+        .fetch_optional(db)
+        .await?)
+}
+
+/// Delete all logs, along with the usage rollup built from them.
+pub async fn clear_request_logs(db: &SqlitePool) -> Result<(), IpcError> {
+    let mut tx = db.begin().await?;
+    sqlx::query("DELETE FROM request_logs").execute(&mut *tx).await?;
+    sqlx::query("DELETE FROM usage_rollups").execute(&mut *tx).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Delete logs and rollup hours older than `days`.
+pub async fn prune_request_logs(db: &SqlitePool, days: u32) -> Result<u64, IpcError> {
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(i64::from(days));
+    let mut tx = db.begin().await?;
+    let deleted = sqlx::query("DELETE FROM request_logs WHERE created_at < ?")
+        .bind(cutoff.to_rfc3339())
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    sqlx::query("DELETE FROM usage_rollups WHERE hour < ?")
+        .bind(rollup_hour(cutoff))
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(deleted)
+}
+
+/// Apply `log_retention_days` now and then every [`RETENTION_INTERVAL`].
+pub fn spawn_retention(db: SqlitePool, config: Arc<RwLock<AppConfig>>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(RETENTION_INTERVAL);
+        loop {
+            interval.tick().await;
+            let days = config.read().await.log_retention_days;
+            match prune_request_logs(&db, days).await {
+                Ok(0) => {}
+                Ok(n) => log::info!("Deleted {} request logs older than {} days", n, days),
+                Err(e) => log::error!("Failed to prune request logs: {}", e.message),
+            }
+        }
+    });
+}
+
+pub async fn get_usage_stats(
+    db: &SqlitePool,
+    days: Option<i32>,
+) -> Result<serde_json::Value, IpcError> {
+    let days = days.unwrap_or(7);
+    let since = chrono::Utc::now() - chrono::Duration::days(days as i64);
+    let since_str = since.to_rfc3339();
+
+    let since_hour = super::analytics::rollup_hour(since);
+
+    let daily_stats: Vec<(String, i64, i64, i64)> = sqlx::query_as(
+        "SELECT substr(hour, 1, 10) as date, SUM(requests) as count, SUM(prompt_tokens) as prompt_tokens, SUM(completion_tokens) as completion_tokens FROM usage_rollups WHERE hour >= ? GROUP BY date ORDER BY date ASC"
+    )
+    .bind(&since_hour)
+    .fetch_all(db)
+    .await?;
+
+    let daily: Vec<serde_json::Value> = daily_stats.iter().map(|(date, count, pt, ct)| {
+        serde_json::json!({
+            "date": date,
+            "count": count,
+            "prompt_tokens": pt,
+            "completion_tokens": ct,
+        })
+    }).collect();
+
+    let model_stats: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT CASE WHEN model = '' THEN 'unknown' ELSE model END as model, SUM(requests) as count FROM usage_rollups WHERE hour >= ? GROUP BY 1 ORDER BY count DESC"
+    )
+    .bind(&since_hour)
+    .fetch_all(db)
+    .await?;
+
+    let by_model: Vec<serde_json::Value> = model_stats.iter().map(|(model, count)| {
+        serde_json::json!({
+            "model": model,
+            "count": count,
+        })
+    }).collect();
+
+    // latency_ms of a stream only covers the time until upstream headers
+    // arrive, so streams are summarized by their own timing columns instead.
+    let timings: Vec<TimingRow> = sqlx::query_as(
+        "SELECT CASE WHEN stream_outcome IS NULL THEN latency_ms END AS latency_ms, ttft_ms, stream_duration_ms, tokens_per_second FROM request_logs WHERE created_at >= ?"
+    )
+    .bind(&since_str)
+    .fetch_all(db)
+    .await?;
+
+    let latency: Vec<f64> = timings.iter().filter_map(|t| t.latency_ms).map(|v| v as f64).collect();
+    let ttft: Vec<f64> = timings.iter().filter_map(|t| t.ttft_ms).map(|v| v as f64).collect();
+    let duration: Vec<f64> = timings.iter().filter_map(|t| t.stream_duration_ms).map(|v| v as f64).collect();
+    let tps: Vec<f64> = timings.iter().filter_map(|t| t.tokens_per_second).collect();
+
+    let outcome_stats: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT stream_outcome, COUNT(*) FROM request_logs WHERE created_at >= ? AND stream_outcome IS NOT NULL GROUP BY stream_outcome"
+    )
+    .bind(&since_str)
+    .fetch_all(db)
+    .await?;
+
+    let stream_outcomes: serde_json::Map<String, serde_json::Value> = outcome_stats
+        .into_iter()
+        .map(|(outcome, count)| (outcome, count.into()))
+        .collect();
+
+    Ok(serde_json::json!({
+        "daily": daily,
+        "by_model": by_model,
+        "latency_ms": percentiles(latency),
+        "ttft_ms": percentiles(ttft),
+        "stream_duration_ms": percentiles(duration),
+        "tokens_per_second": percentiles(tps),
+        "stream_outcomes": stream_outcomes,
+    }))
+}
+
+#[derive(sqlx::FromRow)]
+struct TimingRow {
+    latency_ms: Option<i64>,
+    ttft_ms: Option<i64>,
+    stream_duration_ms: Option<i64>,
+    tokens_per_second: Option<f64>,
+}
+
+/// p50/p90/p99 by nearest rank; null percentiles when there are no samples.
+fn percentiles(mut values: Vec<f64>) -> serde_json::Value {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let rank = |p: f64| -> Option<f64> {
+        if values.is_empty() {
+            return None;
+        }
+        let idx = ((p / 100.0) * values.len() as f64).ceil() as usize;
+        Some(values[idx.saturating_sub(1).min(values.len() - 1)])
+    };
+    serde_json::json!({
+        "count": values.len(),
+        "p50": rank(50.0),
+        "p90": rank(90.0),
+        "p99": rank(99.0),
+    })
+}
+
+#[derive(Serialize)]
+pub struct RetryResult {
+    pub status: u16,
+    pub body: String,
+}
+
+/// Replay a logged request through the local gateway on behalf of its token.
+pub async fn retry_request_log(
+    db: &SqlitePool,
+    vault: &KeyVault,
+    base_url: &str,
+    id: &str,
+) -> Result<RetryResult, IpcError> {
+    // 1. Fetch the original log entry
+    let log = get_request_log(db, id)
+        .await?
+        .ok_or_else(|| IpcError::not_found("Log not found"))?;
+
+    let request_body = log.request_body.ok_or_else(|| IpcError::validation("No request body"))?;
+    let input_format = log.input_format.ok_or_else(|| IpcError::validation("No input format"))?;
+    let token_id = log.token_id.ok_or_else(|| IpcError::validation("No token ID"))?;
+
+    // 2. Make sure the token still exists; its plaintext is not stored, so the
+    //    retry authenticates with the vault session key on the token's behalf.
+    let token = sqlx::query_as::<_, Token>("SELECT * FROM tokens WHERE id = ?")
+        .bind(&token_id)
+        .fetch_optional(db)
+        .await?
+        .ok_or_else(|| IpcError::not_found("Token not found"))?;
+
+    // 3. Determine endpoint path from input_format
+    let path = match input_format.as_str() {
+        "openai_chat" => "/v1/chat/completions",
+        "anthropic" => "/v1/messages",
+        "openai_responses" => "/v1/responses",
+        "moonshot" => "/v1/chat/completions",
+        other => return Err(IpcError::validation(format!("Unknown input format: {}", other))),
+    };
+
+    // 4. Send request to local proxy
+    let url = format!("{}{}", base_url, path);
+
+    let resp = crate::server::local_client()
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .header("Authorization", format!("Bearer {}", vault.session_key()))
+        .header(crate::server::middleware::INTERNAL_TOKEN_ID_HEADER, &token.id)
+        .body(request_body)
+        .send()
+        .await?;
+
+    let status = resp.status().as_u16();
+    let body = resp.text().await?;
+
+    Ok(RetryResult { status, body })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        let p = percentiles((1..=100).rev().map(f64::from).collect());
+        assert_eq!(p["count"], 100);
+        assert_eq!(p["p50"], 50.0);
+        assert_eq!(p["p90"], 90.0);
+        assert_eq!(p["p99"], 99.0);
+
+        let single = percentiles(vec![7.0]);
+        assert_eq!(single["p50"], 7.0);
+        assert_eq!(single["p99"], 7.0);
+
+        assert!(percentiles(Vec::new())["p50"].is_null());
+    }
+
+    async fn rollup(db: &SqlitePool) -> Vec<(String, i64, i64)> {
+        sqlx::query_as("SELECT status_class, requests, completion_tokens FROM usage_rollups ORDER BY hour")
+            .fetch_all(db)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn rollups_follow_stream_outcomes_retention_and_clearing() {
+        let db = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations").run(&db).await.unwrap();
+        let old = (chrono::Utc::now() - chrono::Duration::days(40)).to_rfc3339();
+        for (id, created_at) in [("old", old), ("new", chrono::Utc::now().to_rfc3339())] {
+            sqlx::query("INSERT INTO request_logs (id, status, latency_ms, created_at) VALUES (?, 200, 80, ?)")
+                .bind(id)
+                .bind(created_at)
+                .execute(&db)
+                .await
+                .unwrap();
+        }
+
+        // The stream broke after a 200: the request moves to the upstream class.
+        sqlx::query("UPDATE request_logs SET completion_tokens = 12, stream_outcome = 'upstream_error' WHERE id = 'new'")
+            .execute(&db)
+            .await
+            .unwrap();
+        assert_eq!(
+            rollup(&db).await,
+            vec![("2xx".to_string(), 1, 0), ("upstream".to_string(), 1, 12)]
+        );
+
+        assert_eq!(prune_request_logs(&db, 30).await.unwrap(), 1);
+        assert_eq!(rollup(&db).await, vec![("upstream".to_string(), 1, 12)]);
+
+        clear_request_logs(&db).await.unwrap();
+        assert!(rollup(&db).await.is_empty());
+    }
+}
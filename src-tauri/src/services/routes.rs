@@ -0,0 +1,504 @@
+use crate::db::models::{Route, RouteTarget, RouteTargetKey, RouteTargetModel, RouteTargetOverride};
+use crate::error::IpcError;
+use crate::modality::chat::context::ContextPolicy;
+use crate::outbound;
+use crate::secrets::KeyVault;
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqliteExecutor, SqlitePool};
+
+#[derive(Debug, Deserialize)]
+pub struct OverrideInput {
+    pub scope: String,
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ModelInput {
+    pub model: String,
+    /// Name sent upstream instead of `model`; unset sends `model` as is.
+    #[serde(default)]
+    pub upstream_model: Option<String>,
+    #[serde(default)]
+    pub context_window: Option<i64>,
+    #[serde(default)]
+    pub max_output_tokens: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TargetInput {
+    pub upstream_format: String,
+    pub base_url: String,
+    pub weight: i32,
+    pub enabled: bool,
+    pub key_rotation: bool,
+    /// Outbound proxy URL or `direct`; unset uses the global proxy.
+    #[serde(default)]
+    pub proxy_url: Option<String>,
+    /// Upstream timeouts in seconds; unset uses the defaults.
+    #[serde(default)]
+    pub connect_timeout_secs: Option<i64>,
+    #[serde(default)]
+    pub first_byte_timeout_secs: Option<i64>,
+    #[serde(default)]
+    pub idle_timeout_secs: Option<i64>,
+    /// `stream` or `non_stream`; unset follows the client.
+    #[serde(default)]
+    pub stream_mode: Option<String>,
+    #[serde(default)]
+    pub normalize_messages: bool,
+    /// `auto` or `always`; unset sends image URLs as they are.
+    #[serde(default)]
+    pub inline_images: Option<String>,
+    pub keys: Vec<String>,
+    pub overrides: Vec<OverrideInput>,
+    #[serde(default)]
+    pub models: Vec<ModelInput>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RouteInput {
+    pub name: String,
+    pub path_prefix: String,
+    pub input_format: String,
+    pub enabled: bool,
+    /// `reject`, `clamp` or `trim`; unset leaves requests as they are.
+    #[serde(default)]
+    pub context_policy: Option<String>,
+    pub targets: Vec<TargetInput>,
+}
+
+/// Prefixes served by the gateway itself, which a route must not shadow.
+const RESERVED_PREFIXES: &[&str] = &["/admin", "/health", "/metrics", "/video-proxy"];
+
+const VALID_SCOPES: &[&str] = &["body", "header", "query"];
+
+/// Values of `route_targets.stream_mode`.
+const STREAM_MODES: &[&str] = &["stream", "non_stream"];
+
+/// Values of `route_targets.inline_images`.
+const IMAGE_MODES: &[&str] = &["auto", "always"];
+
+fn validate_format(format: &str) -> Result<(), IpcError> {
+    let valid = ["none", "openai-chat", "openai-responses", "anthropic", "gemini", "moonshot"];
+    if valid.contains(&format) {
+        Ok(())
+    } else {
+        Err(IpcError::validation(format!("Unsupported format: {}", format)))
+    }
+}
+
+fn validate_path_prefix(prefix: &str) -> Result<(), IpcError> {
+    if !prefix.starts_with('/') {
+        return Err(IpcError::validation("path_prefix must start with '/'"));
+    }
+    if prefix.len() < 2 {
+        return Err(IpcError::validation("path_prefix must not be empty after '/'"));
+    }
+    if prefix[1..].contains('/') {
+        return Err(IpcError::validation("path_prefix must be a single segment (no '/' after the first)"));
+    }
+    if RESERVED_PREFIXES.contains(&prefix) {
+        return Err(IpcError::validation(format!("path_prefix {} is reserved", prefix)));
+    }
+    Ok(())
+}
+
+/// Check a route and its targets before anything is written.
+pub fn validate_input(input: &RouteInput) -> Result<(), IpcError> {
+    validate_path_prefix(&input.path_prefix)?;
+    validate_format(&input.input_format)?;
+    if let Some(policy) = &input.context_policy {
+        if !ContextPolicy::ALL.contains(&policy.as_str()) {
+            return Err(IpcError::validation(format!("Invalid context_policy: {}", policy)));
+        }
+    }
+    for target in &input.targets {
+        validate_format(&target.upstream_format)?;
+        for (name, secs) in [
+            ("connect_timeout_secs", target.connect_timeout_secs),
+            ("first_byte_timeout_secs", target.first_byte_timeout_secs),
+            ("idle_timeout_secs", target.idle_timeout_secs),
+        ] {
+            if secs.is_some_and(|s| s <= 0) {
+                return Err(IpcError::validation(format!("{} must be positive", name)));
+            }
+        }
+        if let Some(mode) = &target.stream_mode {
+            if !STREAM_MODES.contains(&mode.as_str()) {
+                return Err(IpcError::validation(format!("Invalid stream_mode: {}", mode)));
+            }
+        }
+        if let Some(mode) = &target.inline_images {
+            if !IMAGE_MODES.contains(&mode.as_str()) {
+                return Err(IpcError::validation(format!("Invalid inline_images: {}", mode)));
+            }
+        }
+        for ovr in &target.overrides {
+            if !ovr.key.trim().is_empty() && !VALID_SCOPES.contains(&ovr.scope.as_str()) {
+                return Err(IpcError::validation(format!("Invalid scope: {}", ovr.scope)));
+            }
+        }
+        for mapping in &target.models {
+            for (name, limit) in [
+                ("context_window", mapping.context_window),
+                ("max_output_tokens", mapping.max_output_tokens),
+            ] {
+                if limit.is_some_and(|l| l <= 0) {
+                    return Err(IpcError::validation(format!("{} of {} must be positive", name, mapping.model)));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct RouteWithTargets {
+    #[serde(flatten)]
+    pub route: Route,
+    pub targets: Vec<TargetWithKeys>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TargetWithKeys {
+    #[serde(flatten)]
+    pub target: RouteTarget,
+    pub keys: Vec<RouteTargetKey>,
+    pub overrides: Vec<RouteTargetOverride>,
+    pub models: Vec<RouteTargetModel>,
+}
+
+pub async fn list_routes(
+    db: &SqlitePool,
+    vault: &KeyVault,
+) -> Result<Vec<RouteWithTargets>, IpcError> {
+    read_routes(&mut *db.acquire().await?, vault).await
+}
+
+/// [`list_routes`] on a given connection, so a transaction can read the
+/// rows it is about to change.
+pub async fn read_routes(
+    conn: &mut SqliteConnection,
+    vault: &KeyVault,
+) -> Result<Vec<RouteWithTargets>, IpcError> {
+    let routes = sqlx::query_as::<_, Route>(
+        "SELECT * FROM routes ORDER BY created_at ASC"
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut result = Vec::new();
+    for route in routes {
+        let targets = load_targets(conn, vault, &route.id).await?;
+        result.push(RouteWithTargets { route, targets });
+    }
+    Ok(result)
+}
+
+pub async fn get_route(
+    db: &SqlitePool,
+    vault: &KeyVault,
+    id: &str,
+) -> Result<RouteWithTargets, IpcError> {
+    let route = sqlx::query_as::<_, Route>("SELECT * FROM routes WHERE id = ?")
+        .bind(id)
+        .fetch_optional(db)
+        .await?
+        .ok_or_else(|| IpcError::not_found("Route not found"))?;
+    let targets = load_targets(&mut *db.acquire().await?, vault, id).await?;
+    Ok(RouteWithTargets { route, targets })
+}
+
+pub async fn create_route(
+    db: &SqlitePool,
+    vault: &KeyVault,
+    input: RouteInput,
+) -> Result<RouteWithTargets, IpcError> {
+    let mut tx = db.begin().await?;
+    let route_id = insert_route(&mut tx, vault, &input).await?;
+    tx.commit().await?;
+    get_route(db, vault, &route_id).await
+}
+
+pub async fn update_route(
+    db: &SqlitePool,
+    vault: &KeyVault,
+    id: &str,
+    input: RouteInput,
+) -> Result<RouteWithTargets, IpcError> {
+    let mut tx = db.begin().await?;
+    replace_route(&mut tx, vault, id, &input).await?;
+    tx.commit().await?;
+    get_route(db, vault, id).await
+}
+
+pub async fn delete_route(db: impl SqliteExecutor<'_>, id: &str) -> Result<(), IpcError> {
+    sqlx::query("DELETE FROM routes WHERE id = ?")
+        .bind(id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+/// Write a new route and its targets on `conn`, so callers can group several
+/// writes in one transaction. Returns the route's id.
+pub async fn insert_route(
+    conn: &mut SqliteConnection,
+    vault: &KeyVault,
+    input: &RouteInput,
+) -> Result<String, IpcError> {
+    validate_input(input)?;
+
+    let route_id = uuid::Uuid::new_v4().to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+
+    sqlx::query(
+        "INSERT INTO routes (id, name, path_prefix, input_format, enabled, context_policy, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&route_id).bind(&input.name).bind(&input.path_prefix)
+    .bind(&input.input_format).bind(input.enabled).bind(&input.context_policy).bind(&now).bind(&now)
+    .execute(&mut *conn).await?;
+
+    save_targets(conn, vault, &route_id, &input.targets).await?;
+    Ok(route_id)
+}
+
+/// Overwrite a route and replace its targets on `conn`.
+pub async fn replace_route(
+    conn: &mut SqliteConnection,
+    vault: &KeyVault,
+    id: &str,
+    input: &RouteInput,
+) -> Result<(), IpcError> {
+    validate_input(input)?;
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let updated = sqlx::query(
+        "UPDATE routes SET name=?, path_prefix=?, input_format=?, enabled=?, context_policy=?, updated_at=? WHERE id=?"
+    )
+    .bind(&input.name).bind(&input.path_prefix).bind(&input.input_format)
+    .bind(input.enabled).bind(&input.context_policy).bind(&now).bind(id)
+    .execute(&mut *conn).await?;
+    if updated.rows_affected() == 0 {
+        return Err(IpcError::not_found("Route not found"));
+    }
+
+    sqlx::query("DELETE FROM route_targets WHERE route_id = ?")
+        .bind(id)
+        .execute(&mut *conn).await?;
+
+    save_targets(conn, vault, id, &input.targets).await
+}
+
+async fn save_targets(
+    conn: &mut SqliteConnection,
+    vault: &KeyVault,
+    route_id: &str,
+    targets: &[TargetInput],
+) -> Result<(), IpcError> {
+    let now = chrono::Utc::now().to_rfc3339();
+    for target_input in targets {
+        validate_format(&target_input.upstream_format)?;
+        let proxy_url = target_input
+            .proxy_url
+            .as_deref()
+            .map(str::trim)
+            .filter(|p| !p.is_empty());
+        if let Some(proxy_url) = proxy_url {
+            outbound::validate_proxy_url(proxy_url).map_err(IpcError::validation)?;
+        }
+
+        let target_id = uuid::Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO route_targets (id, route_id, upstream_format, base_url, weight, enabled, key_rotation, proxy_url, connect_timeout_secs, first_byte_timeout_secs, idle_timeout_secs, stream_mode, normalize_messages, inline_images, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&target_id).bind(route_id)
+        .bind(&target_input.upstream_format).bind(&target_input.base_url)
+        .bind(target_input.weight).bind(target_input.enabled)
+        .bind(target_input.key_rotation).bind(proxy_url)
+        .bind(target_input.connect_timeout_secs).bind(target_input.first_byte_timeout_secs)
+        .bind(target_input.idle_timeout_secs).bind(&target_input.stream_mode)
+        .bind(target_input.normalize_messages).bind(&target_input.inline_images).bind(&now)
+        .execute(&mut *conn).await?;
+
+        for key_value in &target_input.keys {
+            if key_value.trim().is_empty() {
+                continue;
+            }
+            let key_id = uuid::Uuid::new_v4().to_string();
+            sqlx::query(
+                "INSERT INTO route_target_keys (id, target_id, key_value, enabled) VALUES (?, ?, ?, 1)"
+            )
+            .bind(&key_id).bind(&target_id).bind(vault.encrypt(key_value.trim()))
+            .execute(&mut *conn).await?;
+        }
+
+        for ovr in &target_input.overrides {
+            if ovr.key.trim().is_empty() {
+                continue;
+            }
+            if !VALID_SCOPES.contains(&ovr.scope.as_str()) {
+                return Err(IpcError::validation(format!("Invalid scope: {}", ovr.scope)));
+            }
+            let ovr_id = uuid::Uuid::new_v4().to_string();
+            sqlx::query(
+                "INSERT INTO route_target_overrides (id, target_id, scope, key, value) VALUES (?, ?, ?, ?, ?)"
+            )
+            .bind(&ovr_id).bind(&target_id)
+            .bind(ovr.scope.trim()).bind(ovr.key.trim()).bind(&ovr.value)
+            .execute(&mut *conn).await?;
+        }
+
+        for mapping in &target_input.models {
+            if mapping.model.trim().is_empty() {
+                continue;
+            }
+            let upstream_model = mapping
+                .upstream_model
+                .as_deref()
+                .map(str::trim)
+                .filter(|m| !m.is_empty());
+            sqlx::query(
+                "INSERT INTO route_target_models (id, target_id, model, upstream_model, context_window, max_output_tokens) VALUES (?, ?, ?, ?, ?, ?)"
+            )
+            .bind(uuid::Uuid::new_v4().to_string()).bind(&target_id)
+            .bind(mapping.model.trim()).bind(upstream_model)
+            .bind(mapping.context_window).bind(mapping.max_output_tokens)
+            .execute(&mut *conn).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn load_targets(
+    conn: &mut SqliteConnection,
+    vault: &KeyVault,
+    route_id: &str,
+) -> Result<Vec<TargetWithKeys>, IpcError> {
+    let targets = sqlx::query_as::<_, RouteTarget>(
+        "SELECT * FROM route_targets WHERE route_id = ? ORDER BY created_at ASC"
+    )
+    .bind(route_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut targets_with_keys = Vec::new();
+    for target in targets {
+        let keys = sqlx::query_as::<_, RouteTargetKey>(
+            "SELECT * FROM route_target_keys WHERE target_id = ? ORDER BY id ASC"
+        )
+        .bind(&target.id)
+        .fetch_all(&mut *conn)
+        .await?;
+        let keys = decrypt_keys(vault, keys)?;
+        let overrides = sqlx::query_as::<_, RouteTargetOverride>(
+            "SELECT * FROM route_target_overrides WHERE target_id = ? ORDER BY id ASC"
+        )
+        .bind(&target.id)
+        .fetch_all(&mut *conn)
+        .await?;
+        let models = load_models(&mut *conn, &target.id).await?;
+        targets_with_keys.push(TargetWithKeys { target, keys, overrides, models });
+    }
+    Ok(targets_with_keys)
+}
+
+/// Model mappings of a target, in the order they were configured.
+pub async fn load_models(db: impl SqliteExecutor<'_>, target_id: &str) -> Result<Vec<RouteTargetModel>, sqlx::Error> {
+    sqlx::query_as::<_, RouteTargetModel>(
+        "SELECT * FROM route_target_models WHERE target_id = ? ORDER BY rowid ASC"
+    )
+    .bind(target_id)
+    .fetch_all(db)
+    .await
+}
+
+/// Keys are encrypted at rest; the route editor works with plaintext values.
+fn decrypt_keys(
+    vault: &KeyVault,
+    keys: Vec<RouteTargetKey>,
+) -> Result<Vec<RouteTargetKey>, IpcError> {
+    keys.into_iter()
+        .map(|mut k| {
+            k.key_value = vault.decrypt(&k.key_value).map_err(IpcError::internal)?;
+            Ok(k)
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize)]
+pub struct TestRouteResult {
+    pub status: u16,
+    pub body: String,
+    pub latency_ms: i64,
+    pub error: Option<String>,
+}
+
+fn test_request_path(input_format: &str) -> &'static str {
+    match input_format {
+        "anthropic" => "/v1/messages",
+        "openai-chat" | "moonshot" => "/v1/chat/completions",
+        "openai-responses" => "/v1/responses",
+        "gemini" => "/v1beta/models/gemini-pro:generateContent",
+        _ => "/v1/chat/completions",
+    }
+}
+
+fn test_request_body(input_format: &str) -> &'static str {
+    match input_format {
+        "anthropic" => r#"{"model":"claude-3-haiku-20240307","max_tokens":10,"messages":[{"role":"user","content":"Hi"}]}"#,
+        "openai-chat" | "moonshot" => r#"{"model":"gpt-3.5-turbo","max_tokens":10,"messages":[{"role":"user","content":"Hi"}]}"#,
+        "openai-responses" => r#"{"model":"gpt-3.5-turbo","max_tokens":10,"input":"Hi"}"#,
+        "gemini" => r#"{"contents":[{"parts":[{"text":"Hi"}]}]}"#,
+        _ => r#"{"model":"gpt-3.5-turbo","max_tokens":10,"messages":[{"role":"user","content":"Hi"}]}"#,
+    }
+}
+
+/// Send a minimal request through the local gateway to check a route end to end.
+pub async fn test_route(
+    db: &SqlitePool,
+    base_url: &str,
+    route_id: &str,
+    token_key: &str,
+) -> Result<TestRouteResult, IpcError> {
+    let route = sqlx::query_as::<_, Route>("SELECT * FROM routes WHERE id = ?")
+        .bind(route_id)
+        .fetch_optional(db)
+        .await?
+        .ok_or_else(|| IpcError::not_found("Route not found"))?;
+
+    let path = test_request_path(&route.input_format);
+    let body = test_request_body(&route.input_format);
+    let url = format!(
+        "{}{}{}",
+        base_url,
+        route.path_prefix.trim_end_matches('/'),
+        path
+    );
+
+    let start = std::time::Instant::now();
+    let resp = crate::server::local_client()
+        .post(&url)
+        .timeout(std::time::Duration::from_secs(30))
+        .header("Content-Type", "application/json")
+        .header("Authorization", format!("Bearer {}", token_key))
+        .body(body)
+        .send()
+        .await;
+    let latency_ms = start.elapsed().as_millis() as i64;
+
+    match resp {
+        Ok(r) => {
+            let status = r.status().as_u16();
+            let body = r.text().await.unwrap_or_default();
+            Ok(TestRouteResult { status, body, latency_ms, error: None })
+        }
+        Err(e) => Ok(TestRouteResult {
+            status: 0,
+            body: String::new(),
+            latency_ms,
+            error: Some(e.to_string()),
+        }),
+    }
+}
@@ -0,0 +1,133 @@
+use crate::db::models::Token;
+use crate::error::IpcError;
+use crate::secrets;
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqliteExecutor, SqlitePool};
+
+/// Returned once on creation or regeneration: the only time the plaintext key
+/// is available.
+#[derive(Debug, Serialize)]
+pub struct CreatedToken {
+    #[serde(flatten)]
+    pub token: Token,
+    pub key_value: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenInput {
+    pub name: Option<String>,
+    pub quota_limit: Option<i64>,
+    pub expires_at: Option<String>,
+    pub allowed_models: Option<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+pub async fn list_tokens(db: impl SqliteExecutor<'_>) -> Result<Vec<Token>, IpcError> {
+    Ok(sqlx::query_as::<_, Token>("SELECT * FROM tokens ORDER BY created_at DESC")
+        .fetch_all(db)
+        .await?)
+}
+
+pub async fn get_token(db: &SqlitePool, id: &str) -> Result<Token, IpcError> {
+    sqlx::query_as::<_, Token>("SELECT * FROM tokens WHERE id = ?")
+        .bind(id)
+        .fetch_optional(db)
+        .await?
+        .ok_or_else(|| IpcError::not_found("Token not found"))
+}
+
+pub async fn create_token(db: &SqlitePool, input: TokenInput) -> Result<CreatedToken, IpcError> {
+    create_token_with_key(db, input, secrets::generate_token()).await
+}
+
+/// Create a token with a caller-chosen key, e.g. one shared through a config file.
+pub async fn create_token_with_key(
+    db: &SqlitePool,
+    input: TokenInput,
+    key_value: String,
+) -> Result<CreatedToken, IpcError> {
+    let mut conn = db.acquire().await?;
+    insert_token(&mut conn, &input, key_value).await
+}
+
+/// Write a new token on `conn`, so callers can group several writes in one
+/// transaction.
+pub async fn insert_token(
+    conn: &mut SqliteConnection,
+    input: &TokenInput,
+    key_value: String,
+) -> Result<CreatedToken, IpcError> {
+    if key_value.trim().is_empty() {
+        return Err(IpcError::validation("Token key must not be empty"));
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+
+    sqlx::query(
+        "INSERT INTO tokens (id, name, key_value, key_prefix, quota_limit, quota_used, expires_at, allowed_models, enabled, created_at) VALUES (?, ?, ?, ?, ?, 0, ?, ?, ?, ?)"
+    )
+    .bind(&id).bind(&input.name)
+    .bind(secrets::hash_token(&key_value)).bind(secrets::token_prefix(&key_value))
+    .bind(input.quota_limit).bind(&input.expires_at).bind(&input.allowed_models)
+    .bind(input.enabled).bind(&now)
+    .execute(&mut *conn)
+    .await?;
+
+    let token = sqlx::query_as::<_, Token>("SELECT * FROM tokens WHERE id = ?")
+        .bind(&id)
+        .fetch_one(&mut *conn)
+        .await?;
+    Ok(CreatedToken { token, key_value })
+}
+
+pub async fn update_token(db: impl SqliteExecutor<'_>, id: &str, input: TokenInput) -> Result<(), IpcError> {
+    let updated = sqlx::query(
+        "UPDATE tokens SET name = ?, quota_limit = ?, expires_at = ?, allowed_models = ?, enabled = ? WHERE id = ?"
+    )
+    .bind(&input.name).bind(input.quota_limit).bind(&input.expires_at)
+    .bind(&input.allowed_models).bind(input.enabled).bind(id)
+    .execute(db)
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(IpcError::not_found("Token not found"));
+    }
+    Ok(())
+}
+
+pub async fn delete_token(db: impl SqliteExecutor<'_>, id: &str) -> Result<(), IpcError> {
+    sqlx::query("DELETE FROM tokens WHERE id = ?")
+        .bind(id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+/// Replace a token's key with a new random one, e.g. when the original was
+/// lost. The old key stops working immediately.
+pub async fn regenerate_token(db: &SqlitePool, id: &str) -> Result<CreatedToken, IpcError> {
+    let key_value = secrets::generate_token();
+    let updated = sqlx::query("UPDATE tokens SET key_value = ?, key_prefix = ? WHERE id = ?")
+        .bind(secrets::hash_token(&key_value))
+        .bind(secrets::token_prefix(&key_value))
+        .bind(id)
+        .execute(db)
+        .await?;
+    if updated.rows_affected() == 0 {
+        return Err(IpcError::not_found("Token not found"));
+    }
+    let token = get_token(db, id).await?;
+    Ok(CreatedToken { token, key_value })
+}
+
+pub async fn reset_token_quota(db: &SqlitePool, id: &str) -> Result<(), IpcError> {
+    sqlx::query("UPDATE tokens SET quota_used = 0 WHERE id = ?")
+        .bind(id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,237 @@
+//! OpenTelemetry tracing for proxied requests.
+//!
+//! Spans are created with `tracing` and bridged to OpenTelemetry. They are
+//! exported over OTLP/HTTP to the collector set in `otlp_endpoint`; the
+//! endpoint can change at runtime because the exporter sits behind a
+//! swappable span processor. Without an endpoint, spans are still created
+//! (so `traceparent` is propagated upstream) but nothing is exported.
+
+use opentelemetry::propagation::{Extractor, Injector, TextMapPropagator};
+use opentelemetry::trace::{TraceContextExt, TracerProvider as _};
+use opentelemetry::Context;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{BatchSpanProcessor, SdkTracerProvider, Span, SpanData, SpanProcessor};
+use opentelemetry_sdk::Resource;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+const TRACE_HEADERS: &[&str] = &["traceparent", "tracestate"];
+
+static EXPORTER: OnceLock<SwitchableProcessor> = OnceLock::new();
+
+/// Install the tracing subscriber. Call once at startup, before
+/// [`set_endpoint`].
+pub fn init() {
+    let processor = EXPORTER.get_or_init(SwitchableProcessor::default).clone();
+    let provider = SdkTracerProvider::builder()
+        .with_span_processor(processor)
+        .with_resource(Resource::builder().with_service_name("omnikit").build())
+        .build();
+    let tracer = provider.tracer("omnikit");
+
+    // Only our own spans; dependencies (sqlx, hyper, ...) have their own.
+    let layer = tracing_opentelemetry::layer()
+        .with_tracer(tracer)
+        .with_filter(tracing_subscriber::filter::filter_fn(|meta| {
+            meta.target().starts_with("omnikit")
+        }));
+    if let Err(e) = tracing_subscriber::registry().with(layer).try_init() {
+        log::warn!("Tracing already initialized: {}", e);
+    }
+}
+
+/// Export spans to the OTLP/HTTP collector at `endpoint` (e.g.
+/// `http://localhost:4318`), or stop exporting with `None`.
+/// If the new exporter can't be built, the current one is kept.
+pub fn set_endpoint(endpoint: Option<&str>) -> Result<(), String> {
+    match EXPORTER.get() {
+        Some(processor) => processor.set_endpoint(endpoint),
+        None => Ok(()),
+    }
+}
+
+/// Flush pending spans before the process exits.
+pub fn shutdown() {
+    if let Some(processor) = EXPORTER.get() {
+        let _ = processor.shutdown_with_timeout(Duration::from_secs(5));
+    }
+}
+
+pub fn validate_endpoint(endpoint: &str) -> Result<(), String> {
+    if endpoint.starts_with("http://") || endpoint.starts_with("https://") {
+        Ok(())
+    } else {
+        Err(format!("OTLP endpoint must be an http(s) URL: {}", endpoint))
+    }
+}
+
+/// The traces URL for a collector base URL; a full `/v1/traces` URL is kept.
+fn traces_url(endpoint: &str) -> String {
+    let base = endpoint.trim_end_matches('/');
+    if base.ends_with("/v1/traces") {
+        base.to_string()
+    } else {
+        format!("{}/v1/traces", base)
+    }
+}
+
+fn build_processor(endpoint: &str) -> Result<BatchSpanProcessor, String> {
+    validate_endpoint(endpoint)?;
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(traces_url(endpoint))
+        .build()
+        .map_err(|e| format!("Failed to create OTLP exporter: {}", e))?;
+    Ok(BatchSpanProcessor::builder(exporter).build())
+}
+
+/// Forwards finished spans to the current exporter, if any.
+#[derive(Debug, Clone, Default)]
+struct SwitchableProcessor {
+    inner: Arc<RwLock<Option<BatchSpanProcessor>>>,
+}
+
+impl SwitchableProcessor {
+    fn set_endpoint(&self, endpoint: Option<&str>) -> Result<(), String> {
+        let next = endpoint.map(build_processor).transpose()?;
+        let previous = std::mem::replace(
+            &mut *self.inner.write().unwrap_or_else(|e| e.into_inner()),
+            next,
+        );
+        if let Some(previous) = previous {
+            if endpoint.is_none() {
+                log::info!("Trace export disabled");
+            }
+            // Flushes pending spans, which may block on the old collector.
+            std::thread::spawn(move || {
+                let _ = previous.shutdown_with_timeout(Duration::from_secs(5));
+            });
+        }
+        if let Some(endpoint) = endpoint {
+            log::info!("Exporting traces to {}", endpoint);
+        }
+        Ok(())
+    }
+}
+
+impl SpanProcessor for SwitchableProcessor {
+    fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+    fn on_end(&self, span: SpanData) {
+        if let Some(processor) = &*self.inner.read().unwrap_or_else(|e| e.into_inner()) {
+            processor.on_end(span);
+        }
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        match &*self.inner.read().unwrap_or_else(|e| e.into_inner()) {
+            Some(processor) => processor.force_flush(),
+            None => Ok(()),
+        }
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        match self.inner.write().unwrap_or_else(|e| e.into_inner()).take() {
+            Some(processor) => processor.shutdown_with_timeout(timeout),
+            None => Ok(()),
+        }
+    }
+}
+
+struct HeaderExtractor<'a>(&'a axum::http::HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|k| k.as_str()).collect()
+    }
+}
+
+struct VecInjector(Vec<(String, String)>);
+
+impl Injector for VecInjector {
+    fn set(&mut self, key: &str, value: String) {
+        self.0.push((key.to_string(), value));
+    }
+}
+
+/// Continue the trace from the client's `traceparent`, if it sent one.
+pub fn set_parent_from(span: &tracing::Span, headers: &axum::http::HeaderMap) {
+    let cx = TraceContextPropagator::new().extract(&HeaderExtractor(headers));
+    if cx.span().span_context().is_valid() {
+        let _ = span.set_parent(cx);
+    }
+}
+
+/// Trace headers for an upstream request made within the current span. When
+/// no span context is available the client's own headers are forwarded.
+pub fn upstream_headers(incoming: &axum::http::HeaderMap) -> Vec<(String, String)> {
+    let cx = tracing::Span::current().context();
+    if cx.span().span_context().is_valid() {
+        let mut injector = VecInjector(Vec::new());
+        TraceContextPropagator::new().inject_context(&cx, &mut injector);
+        return injector.0;
+    }
+    TRACE_HEADERS
+        .iter()
+        .filter_map(|name| {
+            incoming
+                .get(*name)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| (name.to_string(), v.to_string()))
+        })
+        .collect()
+}
+
+/// Whether `name` is a trace header set by [`upstream_headers`].
+pub fn is_trace_header(name: &str) -> bool {
+    TRACE_HEADERS.contains(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoints_resolve_to_the_traces_url() {
+        assert_eq!(traces_url("http://localhost:4318"), "http://localhost:4318/v1/traces");
+        assert_eq!(traces_url("http://localhost:4318/"), "http://localhost:4318/v1/traces");
+        assert_eq!(
+            traces_url("https://otel.example.com/v1/traces"),
+            "https://otel.example.com/v1/traces"
+        );
+        assert!(validate_endpoint("localhost:4318").is_err());
+        assert!(validate_endpoint("grpc://localhost:4317").is_err());
+    }
+
+    fn is_exporting(processor: &SwitchableProcessor) -> bool {
+        processor.inner.read().unwrap().is_some()
+    }
+
+    #[test]
+    fn a_bad_endpoint_keeps_the_current_exporter() {
+        let processor = SwitchableProcessor::default();
+        assert!(!is_exporting(&processor));
+        assert!(processor.force_flush().is_ok());
+
+        processor.set_endpoint(Some("http://127.0.0.1:4318")).unwrap();
+        assert!(is_exporting(&processor));
+
+        assert!(processor.set_endpoint(Some("localhost:4318")).is_err());
+        assert!(is_exporting(&processor));
+
+        processor.set_endpoint(None).unwrap();
+        assert!(!is_exporting(&processor));
+        assert!(processor.set_endpoint(Some("ftp://collector")).is_err());
+        assert!(!is_exporting(&processor));
+    }
+}
@@ -11,16 +11,17 @@ impl VideoParser for BilibiliParser {
         url.contains("bilibili.com") || url.contains("b23.tv")
     }
 
-    async fn parse(&self, url: &str, client: &reqwest::Client) -> Result<VideoInfo, IpcError> {
+    async fn parse(&self, url: &str, proxy: Option<&str>) -> Result<VideoInfo, IpcError> {
         // Resolve short URL
         let resolved_url = if url.contains("b23.tv") {
-            resolve_short_url(url).await?
+            resolve_short_url(url, proxy).await?
         } else {
             url.to_string()
         };
 
         // Extract BV ID
         let bvid = extract_bvid(&resolved_url)?;
+        let client = &super::create_http_client(proxy)?;
 
         // Fetch view info
         let view_url = format!(
@@ -141,12 +142,8 @@ impl VideoParser for BilibiliParser {
     }
 }
 
-async fn resolve_short_url(url: &str) -> Result<String, IpcError> {
-    let no_redirect_client = reqwest::Client::builder()
-        .user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36")
-        .redirect(reqwest::redirect::Policy::none())
-        .build()
-        .map_err(|e| IpcError::internal(e.to_string()))?;
+async fn resolve_short_url(url: &str, proxy: Option<&str>) -> Result<String, IpcError> {
+    let no_redirect_client = super::create_no_redirect_client(proxy)?;
 
     let resp = no_redirect_client.get(url).send().await?;
 
@@ -11,7 +11,7 @@ impl VideoParser for DouyinParser {
         url.contains("douyin.com") || url.contains("douyinvod.com") || url.contains("iesdouyin.com")
     }
 
-    async fn parse(&self, url: &str, client: &reqwest::Client) -> Result<VideoInfo, IpcError> {
+    async fn parse(&self, url: &str, proxy: Option<&str>) -> Result<VideoInfo, IpcError> {
         // Direct CDN URL — return as-is
         if is_direct_cdn_url(url) {
             return Ok(VideoInfo {
@@ -28,6 +28,8 @@ impl VideoParser for DouyinParser {
             });
         }
 
+        let client = &super::create_http_client(proxy)?;
+
         // Resolve short URL (v.douyin.com/xxx) to full page URL to extract video ID
         let page_url = if url.contains("v.douyin.com") {
             resolve_short_url(client, url).await?
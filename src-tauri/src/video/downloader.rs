@@ -15,6 +15,13 @@ struct ActiveTask {
     title: String,
 }
 
+/// Where and how a download is saved, and the proxy it goes through.
+pub struct DownloadOptions {
+    pub save_dir: PathBuf,
+    pub audio_only: bool,
+    pub proxy: Option<String>,
+}
+
 #[derive(Clone)]
 pub struct DownloadManager {
     tasks: Arc<RwLock<HashMap<String, ActiveTask>>>,
@@ -33,9 +40,9 @@ impl DownloadManager {
         task_id: String,
         title: String,
         format: VideoFormat,
-        save_dir: PathBuf,
-        audio_only: bool,
+        options: DownloadOptions,
     ) -> Result<PathBuf, IpcError> {
+        let DownloadOptions { save_dir, audio_only, proxy } = options;
         // Sanitize filename
         let sanitized_title = sanitize_filename(&title);
         let save_path = if audio_only {
@@ -79,6 +86,7 @@ impl DownloadManager {
                 &save_path_clone,
                 &cancel_token,
                 audio_only,
+                proxy.as_deref(),
             )
             .await;
 
@@ -137,14 +145,16 @@ async fn do_download(
     save_path: &PathBuf,
     cancel_token: &CancellationToken,
     audio_only: bool,
+    proxy: Option<&str>,
 ) -> Result<(), IpcError> {
-    let client = super::create_http_client();
+    let client = super::create_http_client(proxy)?;
+    let no_redirect_client = super::create_no_redirect_client(proxy)?;
 
     if audio_only {
         let audio_url = format.audio_url.as_ref().ok_or_else(|| {
             IpcError::validation("Audio-only download is unavailable for this format")
         })?;
-        download_file(app, task_id, &client, audio_url, save_path, cancel_token).await?;
+        download_file(app, task_id, &client, &no_redirect_client, audio_url, save_path, cancel_token).await?;
         return Ok(());
     }
 
@@ -154,9 +164,9 @@ async fn do_download(
         let audio_tmp = save_path.with_extension("audio.tmp");
 
         // Download video stream
-        download_file(app, task_id, &client, &format.url, &video_tmp, cancel_token).await?;
+        download_file(app, task_id, &client, &no_redirect_client, &format.url, &video_tmp, cancel_token).await?;
         // Download audio stream
-        download_file(app, task_id, &client, audio_url, &audio_tmp, cancel_token).await?;
+        download_file(app, task_id, &client, &no_redirect_client, audio_url, &audio_tmp, cancel_token).await?;
 
         // Try muxing with ffmpeg (-c copy = no re-encoding, very fast)
         let mux_result = tokio::process::Command::new("ffmpeg")
@@ -184,15 +194,15 @@ async fn do_download(
             _ => {
                 // ffmpeg not available or failed — fallback: re-download video only
                 // (temp files already cleaned up, so download again to the final path)
-                download_file(app, task_id, &client, &format.url, save_path, cancel_token).await?;
+                download_file(app, task_id, &client, &no_redirect_client, &format.url, save_path, cancel_token).await?;
                 // Save audio alongside as separate file
                 let audio_path = save_path.with_extension("m4a");
-                download_file(app, task_id, &client, audio_url, &audio_path, cancel_token).await?;
+                download_file(app, task_id, &client, &no_redirect_client, audio_url, &audio_path, cancel_token).await?;
             }
         }
     } else {
         // Single-stream format (e.g. Douyin): download directly
-        download_file(app, task_id, &client, &format.url, save_path, cancel_token).await?;
+        download_file(app, task_id, &client, &no_redirect_client, &format.url, save_path, cancel_token).await?;
     }
 
     Ok(())
@@ -201,7 +211,8 @@ async fn do_download(
 async fn download_file(
     app: &AppHandle,
     task_id: &str,
-    _client: &reqwest::Client,
+    client: &reqwest::Client,
+    no_redirect_client: &reqwest::Client,
     url: &str,
     save_path: &PathBuf,
     cancel_token: &CancellationToken,
@@ -215,17 +226,11 @@ async fn download_file(
     } else {
         // For non-bilibili URLs (e.g. Douyin aweme.snssdk.com), manually resolve 302
         // to get the CDN URL, then download the CDN URL directly without extra headers.
-        let resolved = resolve_cdn_url(url).await.unwrap_or_else(|_| url.to_string());
+        let resolved = resolve_cdn_url(no_redirect_client, url).await.unwrap_or_else(|_| url.to_string());
         (resolved, None)
     };
 
-    let dl_client = reqwest::Client::builder()
-        .user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36")
-        .redirect(reqwest::redirect::Policy::limited(10))
-        .build()
-        .map_err(|e| IpcError::internal(e.to_string()))?;
-
-    let mut req = dl_client.get(&final_url);
+    let mut req = client.get(&final_url);
     if let Some(r) = referer {
         req = req.header("Referer", r);
     }
@@ -327,13 +332,7 @@ fn sanitize_filename(name: &str) -> String {
 
 /// Resolve a URL that returns 302 to get the final CDN URL without downloading the body.
 /// This lets us make a clean, header-free request to the CDN directly.
-async fn resolve_cdn_url(url: &str) -> Result<String, IpcError> {
-    let client = reqwest::Client::builder()
-        .user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36")
-        .redirect(reqwest::redirect::Policy::none())
-        .build()
-        .map_err(|e| IpcError::internal(e.to_string()))?;
-
+async fn resolve_cdn_url(client: &reqwest::Client, url: &str) -> Result<String, IpcError> {
     let resp = client.get(url).send().await?;
 
     if resp.status().is_redirection() {
@@ -1,8 +1,10 @@
 pub mod bilibili;
 pub mod douyin;
+#[cfg(feature = "desktop")]
 pub mod downloader;
 
 use crate::error::IpcError;
+use crate::outbound;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +24,20 @@ pub struct VideoFormat {
     pub size: Option<u64>,
 }
 
+/// A download as requested by the frontend.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DownloadRequest {
+    pub task_id: String,
+    pub title: String,
+    pub video_url: String,
+    pub audio_url: Option<String>,
+    pub quality: String,
+    /// Defaults to the user's download directory.
+    pub save_dir: Option<String>,
+    #[serde(default)]
+    pub audio_only: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DownloadProgress {
     pub task_id: String,
@@ -42,21 +58,21 @@ pub enum DownloadStatus {
 #[async_trait::async_trait]
 pub trait VideoParser: Send + Sync {
     fn can_handle(&self, url: &str) -> bool;
-    async fn parse(&self, url: &str, client: &reqwest::Client) -> Result<VideoInfo, IpcError>;
+    /// Parse a share URL, fetching through `proxy` (see [`outbound::with_proxy`]).
+    async fn parse(&self, url: &str, proxy: Option<&str>) -> Result<VideoInfo, IpcError>;
 }
 
-pub async fn parse_video_url(input: &str) -> Result<VideoInfo, IpcError> {
+pub async fn parse_video_url(input: &str, proxy: Option<&str>) -> Result<VideoInfo, IpcError> {
     // Extract URL from share text (e.g. Douyin share messages contain URL mixed with text)
     let url = extract_url_from_text(input).unwrap_or_else(|| input.trim().to_string());
 
-    let client = create_http_client();
     let parsers: Vec<Box<dyn VideoParser>> = vec![
         Box::new(douyin::DouyinParser),
         Box::new(bilibili::BilibiliParser),
     ];
     for parser in &parsers {
         if parser.can_handle(&url) {
-            return parser.parse(&url, &client).await;
+            return parser.parse(&url, proxy).await;
         }
     }
     Err(IpcError::validation(
@@ -70,10 +86,27 @@ fn extract_url_from_text(text: &str) -> Option<String> {
     re.find(text).map(|m| m.as_str().trim_end_matches(|c: char| ",.;:!?，。；：！？)）】》".contains(c)).to_string())
 }
 
-pub fn create_http_client() -> reqwest::Client {
-    reqwest::Client::builder()
-        .user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36")
-        .redirect(reqwest::redirect::Policy::limited(10))
-        .build()
-        .expect("failed to build http client")
+const USER_AGENT: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
+
+/// Browser-like client that follows redirects, going through `proxy`.
+pub fn create_http_client(proxy: Option<&str>) -> Result<reqwest::Client, IpcError> {
+    build_client(proxy, reqwest::redirect::Policy::limited(10))
+}
+
+/// Browser-like client that leaves redirects to the caller, for resolving
+/// short and CDN URLs.
+pub fn create_no_redirect_client(proxy: Option<&str>) -> Result<reqwest::Client, IpcError> {
+    build_client(proxy, reqwest::redirect::Policy::none())
+}
+
+fn build_client(
+    proxy: Option<&str>,
+    redirect: reqwest::redirect::Policy,
+) -> Result<reqwest::Client, IpcError> {
+    let builder = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .redirect(redirect);
+    outbound::with_proxy(builder, proxy)
+        .and_then(|b| b.build())
+        .map_err(|e| IpcError::internal(format!("Failed to build HTTP client: {}", e)))
 }
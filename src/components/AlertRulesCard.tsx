@@ -0,0 +1,416 @@
+import { useState, useEffect } from "react";
+import { BellRing, Plus, Pencil, Trash2, Send, Loader2 } from "lucide-react";
+import { Button } from "@/components/ui/button";
+import { Badge } from "@/components/ui/badge";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import { Switch } from "@/components/ui/switch";
+import {
+  Card,
+  CardHeader,
+  CardTitle,
+  CardDescription,
+  CardContent,
+} from "@/components/ui/card";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
+import {
+  Dialog,
+  DialogContent,
+  DialogDescription,
+  DialogFooter,
+  DialogHeader,
+  DialogTitle,
+} from "@/components/ui/dialog";
+import {
+  AlertDialog,
+  AlertDialogAction,
+  AlertDialogCancel,
+  AlertDialogContent,
+  AlertDialogFooter,
+  AlertDialogHeader,
+  AlertDialogTitle,
+} from "@/components/ui/alert-dialog";
+import {
+  listAlertRules,
+  createAlertRule,
+  updateAlertRule,
+  deleteAlertRule,
+  testAlertRule,
+  listRoutes,
+  listTokens,
+  parseIpcError,
+} from "@/lib/tauri";
+import type { AlertKind, AlertRule, AlertRuleInput, Route, Token } from "@/lib/tauri";
+import { useLanguage } from "@/lib/i18n";
+import { toast } from "sonner";
+
+const KINDS: AlertKind[] = ["circuit_open", "error_rate", "quota", "key_rejected"];
+const ALL = "__all__";
+
+/** Form state; durations are edited in minutes. */
+interface RuleForm {
+  name: string;
+  kind: AlertKind;
+  scope: string;
+  threshold: string;
+  windowMinutes: string;
+  minRequests: string;
+  cooldownMinutes: string;
+  webhookUrl: string;
+  notifyDesktop: boolean;
+  enabled: boolean;
+}
+
+const EMPTY_FORM: RuleForm = {
+  name: "",
+  kind: "circuit_open",
+  scope: ALL,
+  threshold: "",
+  windowMinutes: "5",
+  minRequests: "10",
+  cooldownMinutes: "15",
+  webhookUrl: "",
+  notifyDesktop: true,
+  enabled: true,
+};
+
+function toForm(rule: AlertRule): RuleForm {
+  return {
+    name: rule.name,
+    kind: rule.kind,
+    scope: (rule.kind === "quota" ? rule.token_id : rule.route_id) ?? ALL,
+    threshold: rule.threshold?.toString() ?? "",
+    windowMinutes: (rule.window_secs / 60).toString(),
+    minRequests: rule.min_requests.toString(),
+    cooldownMinutes: (rule.cooldown_secs / 60).toString(),
+    webhookUrl: rule.webhook_url ?? "",
+    notifyDesktop: rule.notify_desktop,
+    enabled: rule.enabled,
+  };
+}
+
+function toInput(form: RuleForm): AlertRuleInput {
+  const scope = form.scope === ALL ? null : form.scope;
+  const hasThreshold = form.kind === "error_rate" || form.kind === "quota";
+  return {
+    name: form.name,
+    kind: form.kind,
+    route_id: form.kind === "quota" ? null : scope,
+    token_id: form.kind === "quota" ? scope : null,
+    threshold: hasThreshold && form.threshold !== "" ? Number(form.threshold) : null,
+    window_secs: Math.round(Number(form.windowMinutes) * 60),
+    min_requests: Math.round(Number(form.minRequests)),
+    cooldown_secs: Math.round(Number(form.cooldownMinutes) * 60),
+    webhook_url: form.webhookUrl.trim() || null,
+    notify_desktop: form.notifyDesktop,
+    enabled: form.enabled,
+  };
+}
+
+/** Alert rules for gateway incidents, delivered by webhook and desktop notification. */
+export default function AlertRulesCard() {
+  const { t } = useLanguage();
+  const [rules, setRules] = useState<AlertRule[]>([]);
+  const [routes, setRoutes] = useState<Route[]>([]);
+  const [tokens, setTokens] = useState<Token[]>([]);
+  const [editing, setEditing] = useState<AlertRule | null>(null);
+  const [dialogOpen, setDialogOpen] = useState(false);
+  const [form, setForm] = useState<RuleForm>(EMPTY_FORM);
+  const [saving, setSaving] = useState(false);
+  const [testing, setTesting] = useState<string | null>(null);
+  const [deleting, setDeleting] = useState<AlertRule | null>(null);
+
+  const load = async () => {
+    try {
+      const [r, routeList, tokenList] = await Promise.all([listAlertRules(), listRoutes(), listTokens()]);
+      setRules(r);
+      setRoutes(routeList);
+      setTokens(tokenList);
+    } catch (err) {
+      toast.error(parseIpcError(err).message);
+    }
+  };
+
+  useEffect(() => {
+    load();
+  }, []);
+
+  const update = (patch: Partial<RuleForm>) => setForm((f) => ({ ...f, ...patch }));
+
+  const openEditor = (rule: AlertRule | null) => {
+    setEditing(rule);
+    setForm(rule ? toForm(rule) : EMPTY_FORM);
+    setDialogOpen(true);
+  };
+
+  const handleSave = async (e: React.FormEvent) => {
+    e.preventDefault();
+    setSaving(true);
+    try {
+      const input = toInput(form);
+      if (editing) {
+        await updateAlertRule(editing.id, input);
+      } else {
+        await createAlertRule(input);
+      }
+      toast.success(t.alerts.saved);
+      setDialogOpen(false);
+      await load();
+    } catch (err) {
+      toast.error(parseIpcError(err).message);
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  const handleToggle = async (rule: AlertRule, enabled: boolean) => {
+    try {
+      await updateAlertRule(rule.id, { ...toInput(toForm(rule)), enabled });
+      await load();
+    } catch (err) {
+      toast.error(parseIpcError(err).message);
+    }
+  };
+
+  const handleTest = async (rule: AlertRule) => {
+    setTesting(rule.id);
+    try {
+      await testAlertRule(rule.id);
+      toast.success(t.alerts.testSent);
+    } catch (err) {
+      toast.error(parseIpcError(err).message);
+    } finally {
+      setTesting(null);
+    }
+  };
+
+  const handleDelete = async () => {
+    if (!deleting) return;
+    try {
+      await deleteAlertRule(deleting.id);
+      await load();
+    } catch (err) {
+      toast.error(parseIpcError(err).message);
+    } finally {
+      setDeleting(null);
+    }
+  };
+
+  const scopeLabel = (rule: AlertRule) => {
+    if (rule.kind === "quota") {
+      const token = tokens.find((tk) => tk.id === rule.token_id);
+      return rule.token_id ? token?.name || token?.key_prefix || rule.token_id : t.alerts.allTokens;
+    }
+    const route = routes.find((r) => r.id === rule.route_id);
+    return rule.route_id ? route?.name ?? rule.route_id : t.alerts.allRoutes;
+  };
+
+  const channels = (rule: AlertRule) => {
+    const list = [];
+    if (rule.webhook_url) list.push("Webhook");
+    if (rule.notify_desktop) list.push(t.alerts.desktopNotification);
+    return list.length ? list.join(" · ") : t.alerts.logOnly;
+  };
+
+  const isQuota = form.kind === "quota";
+  const hasThreshold = form.kind === "error_rate" || isQuota;
+
+  return (
+    <Card className="card-elevated">
+      <CardHeader>
+        <CardTitle className="flex items-center gap-2">
+          <BellRing className="h-5 w-5" />
+          {t.alerts.title}
+        </CardTitle>
+        <CardDescription>{t.alerts.description}</CardDescription>
+      </CardHeader>
+      <CardContent className="space-y-3">
+        {rules.length === 0 && <p className="text-sm text-muted-foreground">{t.alerts.noRules}</p>}
+        {rules.map((rule) => (
+          <div key={rule.id} className="flex items-center gap-3 rounded-md border p-3">
+            <Switch checked={rule.enabled} onCheckedChange={(v) => handleToggle(rule, v)} />
+            <div className="min-w-0 flex-1 space-y-1">
+              <div className="flex items-center gap-2">
+                <span className="truncate text-sm font-medium">{rule.name}</span>
+                <Badge variant="outline">{t.alerts.kinds[rule.kind]}</Badge>
+                {rule.threshold !== null && (
+                  <Badge variant="secondary">≥ {rule.threshold}%</Badge>
+                )}
+              </div>
+              <p className="truncate text-xs text-muted-foreground">
+                {scopeLabel(rule)} · {channels(rule)}
+              </p>
+            </div>
+            <Button
+              size="icon"
+              variant="ghost"
+              title={t.alerts.sendTest}
+              onClick={() => handleTest(rule)}
+              disabled={testing === rule.id}
+            >
+              {testing === rule.id ? <Loader2 className="h-4 w-4 animate-spin" /> : <Send className="h-4 w-4" />}
+            </Button>
+            <Button size="icon" variant="ghost" title={t.common.edit} onClick={() => openEditor(rule)}>
+              <Pencil className="h-4 w-4" />
+            </Button>
+            <Button size="icon" variant="ghost" title={t.common.delete} onClick={() => setDeleting(rule)}>
+              <Trash2 className="h-4 w-4" />
+            </Button>
+          </div>
+        ))}
+        <Button size="sm" variant="outline" onClick={() => openEditor(null)} className="gap-2">
+          <Plus className="h-4 w-4" />
+          {t.alerts.addRule}
+        </Button>
+      </CardContent>
+
+      <Dialog open={dialogOpen} onOpenChange={setDialogOpen}>
+        <DialogContent>
+          <DialogHeader>
+            <DialogTitle>{editing ? t.alerts.editRule : t.alerts.addRule}</DialogTitle>
+            <DialogDescription>{t.alerts.kindHints[form.kind]}</DialogDescription>
+          </DialogHeader>
+          <form onSubmit={handleSave} className="grid gap-4">
+            <div className="grid gap-2">
+              <Label htmlFor="alert-name">{t.common.name}</Label>
+              <Input id="alert-name" value={form.name} onChange={(e) => update({ name: e.target.value })} />
+            </div>
+            <div className="grid grid-cols-2 gap-3">
+              <div className="grid gap-2">
+                <Label>{t.alerts.kind}</Label>
+                <Select
+                  value={form.kind}
+                  onValueChange={(v) => update({ kind: v as AlertKind, scope: ALL })}
+                >
+                  <SelectTrigger>
+                    <SelectValue />
+                  </SelectTrigger>
+                  <SelectContent>
+                    {KINDS.map((kind) => (
+                      <SelectItem key={kind} value={kind}>
+                        {t.alerts.kinds[kind]}
+                      </SelectItem>
+                    ))}
+                  </SelectContent>
+                </Select>
+              </div>
+              <div className="grid gap-2">
+                <Label>{isQuota ? t.alerts.token : t.alerts.route}</Label>
+                <Select value={form.scope} onValueChange={(v) => update({ scope: v })}>
+                  <SelectTrigger>
+                    <SelectValue />
+                  </SelectTrigger>
+                  <SelectContent>
+                    <SelectItem value={ALL}>{isQuota ? t.alerts.allTokens : t.alerts.allRoutes}</SelectItem>
+                    {isQuota
+                      ? tokens.map((tk) => (
+                          <SelectItem key={tk.id} value={tk.id}>
+                            {tk.name || tk.key_prefix || tk.id}
+                          </SelectItem>
+                        ))
+                      : routes.map((r) => (
+                          <SelectItem key={r.id} value={r.id}>
+                            {r.name} ({r.path_prefix})
+                          </SelectItem>
+                        ))}
+                  </SelectContent>
+                </Select>
+              </div>
+            </div>
+            {hasThreshold && (
+              <div className="grid grid-cols-3 gap-3">
+                <div className="grid gap-2">
+                  <Label htmlFor="alert-threshold">{t.alerts.threshold}</Label>
+                  <Input
+                    id="alert-threshold"
+                    type="number"
+                    min={0}
+                    max={100}
+                    value={form.threshold}
+                    onChange={(e) => update({ threshold: e.target.value })}
+                  />
+                </div>
+                {form.kind === "error_rate" && (
+                  <>
+                    <div className="grid gap-2">
+                      <Label htmlFor="alert-window">{t.alerts.windowMinutes}</Label>
+                      <Input
+                        id="alert-window"
+                        type="number"
+                        min={1}
+                        value={form.windowMinutes}
+                        onChange={(e) => update({ windowMinutes: e.target.value })}
+                      />
+                    </div>
+                    <div className="grid gap-2">
+                      <Label htmlFor="alert-min">{t.alerts.minRequests}</Label>
+                      <Input
+                        id="alert-min"
+                        type="number"
+                        min={1}
+                        value={form.minRequests}
+                        onChange={(e) => update({ minRequests: e.target.value })}
+                      />
+                    </div>
+                  </>
+                )}
+              </div>
+            )}
+            <div className="grid gap-2">
+              <Label htmlFor="alert-cooldown">{t.alerts.cooldownMinutes}</Label>
+              <Input
+                id="alert-cooldown"
+                type="number"
+                min={0}
+                value={form.cooldownMinutes}
+                onChange={(e) => update({ cooldownMinutes: e.target.value })}
+              />
+              <p className="text-xs text-muted-foreground">{t.alerts.cooldownHint}</p>
+            </div>
+            <div className="grid gap-2">
+              <Label htmlFor="alert-webhook">{t.alerts.webhookUrl}</Label>
+              <Input
+                id="alert-webhook"
+                placeholder="https://hooks.example.com/omnikit"
+                value={form.webhookUrl}
+                onChange={(e) => update({ webhookUrl: e.target.value })}
+                className="font-mono"
+              />
+            </div>
+            <div className="flex items-center gap-2">
+              <Switch checked={form.notifyDesktop} onCheckedChange={(v) => update({ notifyDesktop: v })} />
+              <span className="text-sm">{t.alerts.desktopNotification}</span>
+            </div>
+            <DialogFooter>
+              <Button type="button" variant="outline" onClick={() => setDialogOpen(false)}>
+                {t.common.cancel}
+              </Button>
+              <Button type="submit" disabled={saving || !form.name.trim()}>
+                {saving && <Loader2 className="animate-spin" />}
+                {t.common.save}
+              </Button>
+            </DialogFooter>
+          </form>
+        </DialogContent>
+      </Dialog>
+
+      <AlertDialog open={!!deleting} onOpenChange={(o) => !o && setDeleting(null)}>
+        <AlertDialogContent>
+          <AlertDialogHeader>
+            <AlertDialogTitle>{deleting && t.alerts.deleteConfirm(deleting.name)}</AlertDialogTitle>
+          </AlertDialogHeader>
+          <AlertDialogFooter>
+            <AlertDialogCancel>{t.common.cancel}</AlertDialogCancel>
+            <AlertDialogAction onClick={handleDelete}>{t.common.delete}</AlertDialogAction>
+          </AlertDialogFooter>
+        </AlertDialogContent>
+      </AlertDialog>
+    </Card>
+  );
+}
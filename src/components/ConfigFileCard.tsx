@@ -0,0 +1,312 @@
+import { useState, useEffect } from "react";
+import { FileCode2, Copy, Download, Upload, Eye, Loader2 } from "lucide-react";
+import { Button } from "@/components/ui/button";
+import { Badge } from "@/components/ui/badge";
+import { Input } from "@/components/ui/input";
+import { Switch } from "@/components/ui/switch";
+import { Textarea } from "@/components/ui/textarea";
+import {
+  Card,
+  CardHeader,
+  CardTitle,
+  CardDescription,
+  CardContent,
+} from "@/components/ui/card";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
+import { Separator } from "@/components/ui/separator";
+import {
+  exportGatewayConfig,
+  previewGatewayImport,
+  importGatewayConfig,
+  setConfigWatch,
+  parseIpcError,
+} from "@/lib/tauri";
+import type { AppConfig, ExportFormat, KeyMode, ImportPlan, PlannedChange, CreatedToken } from "@/lib/tauri";
+import { useLanguage } from "@/lib/i18n";
+import { toast } from "sonner";
+
+const ACTION_STYLES: Record<PlannedChange["action"], string> = {
+  create: "bg-green-500/15 text-green-700 dark:text-green-400 border-green-500/25",
+  update: "bg-amber-500/15 text-amber-700 dark:text-amber-400 border-amber-500/25",
+  delete: "bg-red-500/15 text-red-700 dark:text-red-400 border-red-500/25",
+  unchanged: "bg-muted text-muted-foreground",
+};
+
+interface ConfigFileCardProps {
+  config: AppConfig | null;
+  onConfigChange: (config: AppConfig) => void;
+}
+
+/** Export, import (with a dry-run diff) and watch a routes/tokens config file. */
+export default function ConfigFileCard({ config, onConfigChange }: ConfigFileCardProps) {
+  const { t } = useLanguage();
+
+  const [format, setFormat] = useState<ExportFormat>("yaml");
+  const [keyMode, setKeyMode] = useState<KeyMode>("omit");
+  const [exportPassphrase, setExportPassphrase] = useState("");
+  const [exported, setExported] = useState("");
+
+  const [content, setContent] = useState("");
+  const [importPassphrase, setImportPassphrase] = useState("");
+  const [prune, setPrune] = useState(false);
+  const [plan, setPlan] = useState<ImportPlan | null>(null);
+  const [createdTokens, setCreatedTokens] = useState<CreatedToken[]>([]);
+  const [busy, setBusy] = useState(false);
+
+  const [watchPath, setWatchPath] = useState("");
+  const [watchPrune, setWatchPrune] = useState(false);
+
+  useEffect(() => {
+    setWatchPath(config?.config_watch?.path ?? "");
+    setWatchPrune(config?.config_watch?.prune ?? false);
+  }, [config?.config_watch]);
+
+  const handleExport = async () => {
+    try {
+      setExported(
+        await exportGatewayConfig({
+          format,
+          keys: keyMode,
+          passphrase: keyMode === "encrypt" ? exportPassphrase : null,
+        })
+      );
+    } catch (err) {
+      toast.error(parseIpcError(err).message);
+    }
+  };
+
+  const importOptions = () => ({
+    passphrase: importPassphrase || null,
+    prune,
+  });
+
+  const handlePreview = async () => {
+    setBusy(true);
+    setCreatedTokens([]);
+    try {
+      setPlan(await previewGatewayImport(content, importOptions()));
+    } catch (err) {
+      setPlan(null);
+      toast.error(parseIpcError(err).message);
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const handleApply = async () => {
+    setBusy(true);
+    try {
+      const result = await importGatewayConfig(content, importOptions());
+      setPlan(result.plan);
+      setCreatedTokens(result.created_tokens);
+      toast.success(t.settings.importApplied);
+    } catch (err) {
+      toast.error(parseIpcError(err).message);
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const handleSaveWatch = async (watch: { path: string; prune: boolean } | null) => {
+    try {
+      onConfigChange(await setConfigWatch(watch));
+      toast.success(watch ? t.settings.watchStarted : t.settings.watchStopped);
+    } catch (err) {
+      toast.error(parseIpcError(err).message);
+    }
+  };
+
+  const renderChanges = (label: string, changes: PlannedChange[]) => (
+    <div className="space-y-1">
+      <div className="text-sm font-medium">{label}</div>
+      {changes.length === 0 && <p className="text-xs text-muted-foreground">—</p>}
+      {changes.map((change) => (
+        <div key={`${change.action}-${change.name}`} className="text-sm">
+          <div className="flex items-center gap-2">
+            <Badge variant="outline" className={ACTION_STYLES[change.action]}>
+              {t.settings.importActions[change.action]}
+            </Badge>
+            <span className="font-mono">{change.name}</span>
+          </div>
+          {change.changes.length > 0 && (
+            <ul className="ml-4 mt-1 list-disc text-xs text-muted-foreground">
+              {change.changes.map((c) => (
+                <li key={c} className="font-mono">{c}</li>
+              ))}
+            </ul>
+          )}
+        </div>
+      ))}
+    </div>
+  );
+
+  return (
+    <Card className="card-elevated">
+      <CardHeader>
+        <CardTitle className="flex items-center gap-2">
+          <FileCode2 className="h-5 w-5" />
+          {t.settings.configFile}
+        </CardTitle>
+        <CardDescription>{t.settings.configFileDesc}</CardDescription>
+      </CardHeader>
+      <CardContent className="space-y-4">
+        {/* Export */}
+        <div className="space-y-2">
+          <label className="text-sm font-medium">{t.settings.exportConfig}</label>
+          <div className="flex flex-wrap items-center gap-2">
+            <Select value={format} onValueChange={(v) => setFormat(v as ExportFormat)}>
+              <SelectTrigger className="w-28">
+                <SelectValue />
+              </SelectTrigger>
+              <SelectContent>
+                <SelectItem value="yaml">YAML</SelectItem>
+                <SelectItem value="json">JSON</SelectItem>
+              </SelectContent>
+            </Select>
+            <Select value={keyMode} onValueChange={(v) => setKeyMode(v as KeyMode)}>
+              <SelectTrigger className="w-48">
+                <SelectValue />
+              </SelectTrigger>
+              <SelectContent>
+                <SelectItem value="omit">{t.settings.keysOmit}</SelectItem>
+                <SelectItem value="plain">{t.settings.keysPlain}</SelectItem>
+                <SelectItem value="encrypt">{t.settings.keysEncrypt}</SelectItem>
+              </SelectContent>
+            </Select>
+            {keyMode === "encrypt" && (
+              <Input
+                type="password"
+                value={exportPassphrase}
+                onChange={(e) => setExportPassphrase(e.target.value)}
+                placeholder={t.settings.passphrase}
+                className="w-48"
+              />
+            )}
+            <Button size="sm" variant="outline" onClick={handleExport} className="gap-2">
+              <Download className="h-4 w-4" />
+              {t.settings.exportConfig}
+            </Button>
+          </div>
+          {exported && (
+            <div className="relative">
+              <Textarea readOnly value={exported} className="max-h-64 font-mono text-xs" />
+              <Button
+                size="icon"
+                variant="outline"
+                className="absolute right-2 top-2 h-7 w-7"
+                onClick={() => navigator.clipboard.writeText(exported)}
+              >
+                <Copy className="h-3.5 w-3.5" />
+              </Button>
+            </div>
+          )}
+        </div>
+
+        <Separator />
+
+        {/* Import */}
+        <div className="space-y-2">
+          <label className="text-sm font-medium">{t.settings.importConfig}</label>
+          <Textarea
+            value={content}
+            onChange={(e) => {
+              setContent(e.target.value);
+              setPlan(null);
+            }}
+            placeholder={t.settings.importPlaceholder}
+            className="max-h-64 min-h-24 font-mono text-xs"
+          />
+          <div className="flex flex-wrap items-center gap-3">
+            <Input
+              type="password"
+              value={importPassphrase}
+              onChange={(e) => setImportPassphrase(e.target.value)}
+              placeholder={t.settings.passphraseIfEncrypted}
+              className="w-56"
+            />
+            <div className="flex items-center gap-2">
+              <Switch checked={prune} onCheckedChange={(v) => { setPrune(v); setPlan(null); }} />
+              <span className="text-sm">{t.settings.prune}</span>
+            </div>
+            <Button size="sm" variant="outline" onClick={handlePreview} disabled={busy || !content.trim()} className="gap-2">
+              {busy ? <Loader2 className="h-4 w-4 animate-spin" /> : <Eye className="h-4 w-4" />}
+              {t.settings.previewImport}
+            </Button>
+            <Button size="sm" onClick={handleApply} disabled={busy || plan === null} className="gap-2">
+              <Upload className="h-4 w-4" />
+              {t.settings.applyImport}
+            </Button>
+          </div>
+          <p className="text-xs text-muted-foreground">{t.settings.pruneHint}</p>
+          {plan && (
+            <div className="grid gap-3 rounded-md border p-3 sm:grid-cols-2">
+              {renderChanges(t.settings.routesLabel, plan.routes)}
+              {renderChanges(t.settings.tokensLabel, plan.tokens)}
+            </div>
+          )}
+          {createdTokens.length > 0 && (
+            <div className="space-y-2">
+              <p className="text-xs text-muted-foreground">{t.settings.createdTokensHint}</p>
+              {createdTokens.map((token) => (
+                <div key={token.id} className="flex items-center gap-2">
+                  <span className="w-32 shrink-0 truncate text-sm">{token.name}</span>
+                  <Input readOnly value={token.key_value} className="font-mono" />
+                  <Button
+                    size="icon"
+                    variant="outline"
+                    onClick={() => navigator.clipboard.writeText(token.key_value)}
+                  >
+                    <Copy className="h-4 w-4" />
+                  </Button>
+                </div>
+              ))}
+            </div>
+          )}
+        </div>
+
+        <Separator />
+
+        {/* Watch */}
+        <div className="space-y-2">
+          <label className="text-sm font-medium">{t.settings.watchFile}</label>
+          <div className="flex flex-wrap items-center gap-3">
+            <Input
+              value={watchPath}
+              onChange={(e) => setWatchPath(e.target.value)}
+              placeholder="/path/to/omnikit.yaml"
+              className="min-w-64 flex-1 font-mono"
+            />
+            <div className="flex items-center gap-2">
+              <Switch checked={watchPrune} onCheckedChange={setWatchPrune} />
+              <span className="text-sm">{t.settings.prune}</span>
+            </div>
+            <Button
+              size="sm"
+              variant="outline"
+              onClick={() => handleSaveWatch({ path: watchPath.trim(), prune: watchPrune })}
+              disabled={!watchPath.trim()}
+            >
+              {t.settings.startWatch}
+            </Button>
+            {config?.config_watch && (
+              <Button size="sm" variant="ghost" onClick={() => handleSaveWatch(null)}>
+                {t.settings.stopWatch}
+              </Button>
+            )}
+          </div>
+          <p className="text-xs text-muted-foreground">
+            {config?.config_watch
+              ? `${t.settings.watching} ${config.config_watch.path}`
+              : t.settings.watchHint}
+          </p>
+        </div>
+      </CardContent>
+    </Card>
+  );
+}
@@ -170,6 +170,12 @@ export interface Translations {
     resetQuotaTitle: string;
     resetQuotaDesc: (name: string) => string;
     reset: string;
+    // Regenerate key
+    regenerateKey: string;
+    regenerateTitle: string;
+    regenerateDesc: (name: string) => string;
+    regenerate: string;
+    keyRegenerated: string;
     unlimited: string;
   };
   requestLogs: {
@@ -202,7 +208,16 @@ export interface Translations {
     retry: string;
     retryFailed: string;
     streamingNoBody: string;
-    autoRefresh: string;
+    live: string;
+    inFlight: (n: number) => string;
+    streaming: string;
+    waiting: string;
+    ttft: string;
+    streamDuration: string;
+    tokensPerSecond: string;
+    streamOutcome: string;
+    outcomes: Record<string, string>;
+    normalization: string;
   };
   usageStats: {
     title: string;
@@ -227,6 +242,25 @@ export interface Translations {
     requests: string;
     share: string;
     failedToLoad: string;
+    timing: string;
+    timingDesc: (n: number) => string;
+    noTimingData: string;
+    metric: string;
+    samples: string;
+    latencyNonStream: string;
+    streamOutcomes: string;
+    successRate: string;
+    errorsTotal: (n: string) => string;
+    daily: string;
+    hourly: string;
+    requestTrendHourlyDesc: (n: number) => string;
+    successful: string;
+    errors: string;
+    errorRate: string;
+    breakdown: string;
+    breakdownDesc: (n: number) => string;
+    breakdowns: Record<"by_route" | "by_target" | "by_token" | "by_status_class", string>;
+    unknown: string;
   };
   settings: {
     title: string;
@@ -234,7 +268,7 @@ export interface Translations {
     serverConfig: string;
     serverConfigDesc: string;
     serverPort: string;
-    requiresRestart: string;
+    appliesLive: string;
     logRetention: string;
     days: string;
     serverStatusLabel: string;
@@ -256,11 +290,89 @@ export interface Translations {
     license: string;
     techStack: string;
     saveSuccess: string;
-    restartHint: string;
+    rebindHint: string;
     aboutText: string;
     checkForUpdates: string;
     checkingForUpdates: string;
     alreadyLatest: string;
+    bindAddress: string;
+    bindAddressHint: string;
+    allowedIps: string;
+    allowedIpsHint: string;
+    tls: string;
+    tlsHint: string;
+    certPath: string;
+    keyPath: string;
+    adminApi: string;
+    adminApiDesc: string;
+    adminPort: string;
+    adminPortPlaceholder: string;
+    otlpEndpoint: string;
+    otlpEndpointHint: string;
+    proxyUrl: string;
+    proxyUrlHint: string;
+    streamKeepalive: string;
+    streamKeepaliveHint: string;
+    streamEarlyStart: string;
+    streamEarlyStartHint: string;
+    adminKeySet: string;
+    adminKeyNotSet: string;
+    generateAdminKey: string;
+    regenerateAdminKey: string;
+    disableAdminApi: string;
+    adminKeyCreated: string;
+    configFile: string;
+    configFileDesc: string;
+    exportConfig: string;
+    keysOmit: string;
+    keysPlain: string;
+    keysEncrypt: string;
+    passphrase: string;
+    importConfig: string;
+    importPlaceholder: string;
+    passphraseIfEncrypted: string;
+    prune: string;
+    pruneHint: string;
+    previewImport: string;
+    applyImport: string;
+    importApplied: string;
+    importActions: { create: string; update: string; delete: string; unchanged: string };
+    routesLabel: string;
+    tokensLabel: string;
+    createdTokensHint: string;
+    watchFile: string;
+    watchHint: string;
+    watching: string;
+    startWatch: string;
+    stopWatch: string;
+    watchStarted: string;
+    watchStopped: string;
+  };
+  alerts: {
+    title: string;
+    description: string;
+    addRule: string;
+    editRule: string;
+    noRules: string;
+    kind: string;
+    kinds: { circuit_open: string; error_rate: string; quota: string; key_rejected: string };
+    kindHints: { circuit_open: string; error_rate: string; quota: string; key_rejected: string };
+    route: string;
+    token: string;
+    allRoutes: string;
+    allTokens: string;
+    threshold: string;
+    windowMinutes: string;
+    minRequests: string;
+    cooldownMinutes: string;
+    cooldownHint: string;
+    webhookUrl: string;
+    desktopNotification: string;
+    logOnly: string;
+    sendTest: string;
+    testSent: string;
+    saved: string;
+    deleteConfirm: (name: string) => string;
   };
   updater: {
     newVersion: (version: string) => string;
@@ -911,7 +1023,7 @@ const en: Translations = {
     tokenCreated: "Token Created",
     tokenCreatedDesc: "Copy your API key now. This is the only time the full key will be shown.",
     editTitle: "Edit Token",
-    editDesc: "Update token settings. The key value cannot be changed.",
+    editDesc: "Update token settings. To replace the key, use Regenerate Key.",
     deleteTitle: "Delete Token",
     deleteDesc: (name: string) =>
       `Are you sure you want to delete the token${name ? ` "${name}"` : ""}? This action cannot be undone. Any clients using this key will immediately lose access.`,
@@ -919,6 +1031,12 @@ const en: Translations = {
     resetQuotaDesc: (name: string) =>
       `Reset the used quota for token${name ? ` "${name}"` : ""}? The usage counter will be set back to 0.`,
     reset: "Reset",
+    regenerateKey: "Regenerate Key",
+    regenerateTitle: "Regenerate Key",
+    regenerateDesc: (name: string) =>
+      `Replace the key of token${name ? ` "${name}"` : ""} with a new one? The current key stops working immediately.`,
+    regenerate: "Regenerate",
+    keyRegenerated: "Key Regenerated",
     unlimited: "unlimited",
   },
   requestLogs: {
@@ -951,7 +1069,20 @@ const en: Translations = {
     retry: "Retry",
     retryFailed: "Retry failed",
     streamingNoBody: "Response body is not captured for streaming requests.",
-    autoRefresh: "Auto Refresh",
+    live: "Live",
+    inFlight: (n: number) => `${n} in flight`,
+    streaming: "Streaming",
+    waiting: "Waiting for upstream",
+    ttft: "TTFT",
+    streamDuration: "Duration",
+    tokensPerSecond: "Tokens/s",
+    streamOutcome: "Stream",
+    outcomes: {
+      completed: "Completed",
+      client_closed: "Client closed",
+      upstream_error: "Upstream error",
+    },
+    normalization: "Normalized",
   },
   usageStats: {
     title: "Usage Statistics",
@@ -976,14 +1107,38 @@ const en: Translations = {
     requests: "Requests",
     share: "Share",
     failedToLoad: "Failed to load usage statistics",
+    timing: "Latency & Throughput",
+    timingDesc: (n: number) => `Percentiles over the last ${n} days`,
+    noTimingData: "No timing data for the selected period.",
+    metric: "Metric",
+    samples: "Samples",
+    latencyNonStream: "Latency (non-streaming)",
+    streamOutcomes: "Stream outcomes",
+    successRate: "Success Rate",
+    errorsTotal: (n: string) => `${n} failed requests`,
+    daily: "Daily",
+    hourly: "Hourly",
+    requestTrendHourlyDesc: (n: number) => `Hourly request count over the last ${n} days (UTC)`,
+    successful: "Successful",
+    errors: "Errors",
+    errorRate: "Error Rate",
+    breakdown: "Breakdown",
+    breakdownDesc: (n: number) => `Requests, errors and latency over the last ${n} days`,
+    breakdowns: {
+      by_route: "Route",
+      by_target: "Target",
+      by_token: "Token",
+      by_status_class: "Status",
+    },
+    unknown: "Unknown",
   },
   settings: {
     title: "Settings",
     subtitle: "View server configuration, customize appearance, and application info.",
     serverConfig: "Server Configuration",
-    serverConfigDesc: "Current server settings and status. Changes to the port require a restart.",
+    serverConfigDesc: "Current server settings and status. Listener changes apply without a restart.",
     serverPort: "Server Port",
-    requiresRestart: "Requires restart to change",
+    appliesLive: "Applied without restart",
     logRetention: "Log Retention",
     days: "days",
     serverStatusLabel: "Server Status",
@@ -1006,10 +1161,98 @@ const en: Translations = {
     techStack: "Tech Stack",
     aboutText: "OmniKit is a multi-functional toolkit that integrates LLM API gateway, generic proxy, video download and more utilities in a single desktop app.",
     saveSuccess: "Settings saved",
-    restartHint: "Restart the app to apply port changes.",
+    rebindHint: "Gateway rebound; in-flight requests finish on the previous listener.",
     checkForUpdates: "Check for Updates",
     checkingForUpdates: "Checking...",
     alreadyLatest: "You're on the latest version",
+    bindAddress: "Bind Address",
+    bindAddressHint: "Use 0.0.0.0 to accept connections from the LAN, together with Allowed IPs.",
+    allowedIps: "Allowed IPs",
+    allowedIpsHint: "Comma-separated IPs or CIDR ranges of other machines to let in; 0.0.0.0/0 allows everyone. Localhost is always allowed.",
+    tls: "HTTPS",
+    tlsHint: "Leave the paths empty to use a generated self-signed certificate.",
+    certPath: "Certificate (PEM)",
+    keyPath: "Private Key (PEM)",
+    adminApi: "Admin API",
+    adminApiDesc: "Manage routes, tokens, logs and config over HTTP under /admin. Requests must send the admin key as a Bearer token.",
+    adminPort: "Admin Port",
+    adminPortPlaceholder: "Same as server port (local clients only)",
+    otlpEndpoint: "OTLP Endpoint",
+    otlpEndpointHint: "Export request traces to an OpenTelemetry collector (OTLP/HTTP). Leave empty to disable.",
+    proxyUrl: "Outbound Proxy",
+    proxyUrlHint: "HTTP or SOCKS5 proxy for upstream and video requests. Targets can override it. Leave empty to use the system proxy settings.",
+    streamKeepalive: "Stream Keep-alive (seconds)",
+    streamKeepaliveHint: "Send a keep-alive to streaming clients after this long without data, so slow models don't hit client timeouts. 0 disables it.",
+    streamEarlyStart: "Start streams before the upstream responds",
+    streamEarlyStartHint: "Streaming clients get response headers and keep-alives right away. Upstream errors are then reported inside the stream instead of as an HTTP status.",
+    adminKeySet: "Enabled",
+    adminKeyNotSet: "Disabled",
+    generateAdminKey: "Generate Key",
+    regenerateAdminKey: "Regenerate Key",
+    disableAdminApi: "Disable",
+    adminKeyCreated: "Copy this key now. It will not be shown again.",
+    configFile: "Configuration File",
+    configFileDesc: "Export routes, targets and tokens as YAML or JSON, or apply a file to this gateway. Routes match by path prefix, tokens by name.",
+    exportConfig: "Export",
+    keysOmit: "Omit upstream keys",
+    keysPlain: "Include keys (plain text)",
+    keysEncrypt: "Encrypt keys",
+    passphrase: "Passphrase",
+    importConfig: "Import",
+    importPlaceholder: "Paste a YAML or JSON config document",
+    passphraseIfEncrypted: "Passphrase (if keys are encrypted)",
+    prune: "Delete missing",
+    pruneHint: "Preview shows the changes before anything is written. Targets without keys keep their current keys. \"Delete missing\" removes routes and named tokens not in the file.",
+    previewImport: "Preview",
+    applyImport: "Apply",
+    importApplied: "Configuration applied",
+    importActions: { create: "Create", update: "Update", delete: "Delete", unchanged: "Unchanged" },
+    routesLabel: "Routes",
+    tokensLabel: "Tokens",
+    createdTokensHint: "Keys of the created tokens. Copy them now; they will not be shown again.",
+    watchFile: "Watch File",
+    watchHint: "Apply a config file now and whenever it changes.",
+    watching: "Watching",
+    startWatch: "Watch",
+    stopWatch: "Stop",
+    watchStarted: "Watching config file",
+    watchStopped: "Stopped watching config file",
+  },
+  alerts: {
+    title: "Alerts",
+    description: "Send a webhook and a desktop notification when something goes wrong in the gateway.",
+    addRule: "Add Rule",
+    editRule: "Edit Alert Rule",
+    noRules: "No alert rules yet.",
+    kind: "Trigger",
+    kinds: {
+      circuit_open: "Circuit opened",
+      error_rate: "Error rate",
+      quota: "Token quota",
+      key_rejected: "Key rejected",
+    },
+    kindHints: {
+      circuit_open: "A target stops receiving traffic after repeated failures.",
+      error_rate: "The share of failed requests on a route within the window reaches the threshold.",
+      quota: "A token has used the given percentage of its quota.",
+      key_rejected: "The provider rejects an upstream key with HTTP 401. The key stays enabled.",
+    },
+    route: "Route",
+    token: "Token",
+    allRoutes: "All routes",
+    allTokens: "All tokens",
+    threshold: "Threshold (%)",
+    windowMinutes: "Window (minutes)",
+    minRequests: "Minimum requests",
+    cooldownMinutes: "Cooldown (minutes)",
+    cooldownHint: "The same alert is not repeated within the cooldown; the next one reports how many were suppressed.",
+    webhookUrl: "Webhook URL",
+    desktopNotification: "Desktop notification",
+    logOnly: "Log only",
+    sendTest: "Send test alert",
+    testSent: "Test alert sent",
+    saved: "Alert rule saved",
+    deleteConfirm: (name: string) => `Delete alert rule "${name}"?`,
   },
   updater: {
     newVersion: (version: string) => `A new version ${version} is available`,
@@ -1452,7 +1695,7 @@ const zh: Translations = {
     tokenCreated: "令牌已创建",
     tokenCreatedDesc: "请立即复制您的 API 密钥。这是唯一一次显示完整密钥的机会。",
     editTitle: "编辑令牌",
-    editDesc: "更新令牌设置。密钥值无法更改。",
+    editDesc: "更新令牌设置。如需更换密钥，请使用“重新生成密钥”。",
     deleteTitle: "删除令牌",
     deleteDesc: (name: string) =>
       `确定要删除令牌${name ? ` "${name}"` : ""} 吗？此操作无法撤销。使用此密钥的所有客户端将立即失去访问权限。`,
@@ -1460,6 +1703,12 @@ const zh: Translations = {
     resetQuotaDesc: (name: string) =>
       `重置令牌${name ? ` "${name}"` : ""} 的已用配额？使用计数将重置为 0。`,
     reset: "重置",
+    regenerateKey: "重新生成密钥",
+    regenerateTitle: "重新生成密钥",
+    regenerateDesc: (name: string) =>
+      `为令牌${name ? ` "${name}"` : ""} 生成新密钥？当前密钥将立即失效。`,
+    regenerate: "重新生成",
+    keyRegenerated: "密钥已重新生成",
     unlimited: "无限制",
   },
   requestLogs: {
@@ -1492,7 +1741,20 @@ const zh: Translations = {
     retry: "重试",
     retryFailed: "重试失败",
     streamingNoBody: "流式请求不会捕获响应体。",
-    autoRefresh: "自动刷新",
+    live: "实时",
+    inFlight: (n: number) => `${n} 个进行中`,
+    streaming: "流式输出中",
+    waiting: "等待上游响应",
+    ttft: "首字延迟",
+    streamDuration: "时长",
+    tokensPerSecond: "Token/秒",
+    streamOutcome: "流状态",
+    outcomes: {
+      completed: "已完成",
+      client_closed: "客户端断开",
+      upstream_error: "上游错误",
+    },
+    normalization: "消息规范化",
   },
   usageStats: {
     title: "使用统计",
@@ -1517,14 +1779,38 @@ const zh: Translations = {
     requests: "请求数",
     share: "占比",
     failedToLoad: "加载使用统计失败",
+    timing: "延迟与吞吐",
+    timingDesc: (n: number) => `最近 ${n} 天的分位数`,
+    noTimingData: "选定时间段内无耗时数据。",
+    metric: "指标",
+    samples: "样本数",
+    latencyNonStream: "延迟（非流式）",
+    streamOutcomes: "流结束状态",
+    successRate: "成功率",
+    errorsTotal: (n: string) => `失败请求 ${n} 个`,
+    daily: "按天",
+    hourly: "按小时",
+    requestTrendHourlyDesc: (n: number) => `最近 ${n} 天的每小时请求数（UTC）`,
+    successful: "成功",
+    errors: "错误",
+    errorRate: "错误率",
+    breakdown: "分类统计",
+    breakdownDesc: (n: number) => `最近 ${n} 天的请求数、错误与延迟`,
+    breakdowns: {
+      by_route: "路由",
+      by_target: "目标",
+      by_token: "令牌",
+      by_status_class: "状态",
+    },
+    unknown: "未知",
   },
   settings: {
     title: "设置",
     subtitle: "查看服务器配置、自定义外观和应用信息。",
     serverConfig: "服务器配置",
-    serverConfigDesc: "当前服务器设置和状态。端口更改需要重启应用。",
+    serverConfigDesc: "当前服务器设置和状态。监听设置更改无需重启即可生效。",
     serverPort: "服务器端口",
-    requiresRestart: "更改需要重启",
+    appliesLive: "无需重启即可生效",
     logRetention: "日志保留",
     days: "天",
     serverStatusLabel: "服务器状态",
@@ -1547,10 +1833,98 @@ const zh: Translations = {
     techStack: "技术栈",
     aboutText: "OmniKit 是一个多功能工具箱，集成了 LLM API 网关、通用代理、视频下载等实用工具。",
     saveSuccess: "设置已保存",
-    restartHint: "重启应用以使端口更改生效。",
+    rebindHint: "网关已重新绑定，进行中的请求会在原监听上完成。",
     checkForUpdates: "检查更新",
     checkingForUpdates: "检查中...",
     alreadyLatest: "已是最新版本",
+    bindAddress: "监听地址",
+    bindAddressHint: "使用 0.0.0.0 以接受局域网连接，并填写允许的 IP。",
+    allowedIps: "允许的 IP",
+    allowedIpsHint: "以逗号分隔的其他机器 IP 或 CIDR 网段；0.0.0.0/0 表示允许所有。本机始终允许。",
+    tls: "HTTPS",
+    tlsHint: "路径留空时将使用自动生成的自签名证书。",
+    certPath: "证书 (PEM)",
+    keyPath: "私钥 (PEM)",
+    adminApi: "管理 API",
+    adminApiDesc: "通过 /admin 下的 HTTP 接口管理路由、令牌、日志和配置。请求需以 Bearer 令牌形式携带管理密钥。",
+    adminPort: "管理端口",
+    adminPortPlaceholder: "与服务器端口相同（仅限本机访问）",
+    otlpEndpoint: "OTLP 端点",
+    otlpEndpointHint: "将请求链路追踪导出到 OpenTelemetry 收集器（OTLP/HTTP）。留空则不导出。",
+    proxyUrl: "出站代理",
+    proxyUrlHint: "上游请求和视频下载使用的 HTTP 或 SOCKS5 代理，目标可单独覆盖。留空则使用系统代理设置。",
+    streamKeepalive: "流式保活间隔（秒）",
+    streamKeepaliveHint: "流式响应超过该时长没有数据时向客户端发送保活消息，避免慢速模型触发客户端超时。设为 0 关闭。",
+    streamEarlyStart: "上游响应前即开始流式响应",
+    streamEarlyStartHint: "流式客户端会立即收到响应头和保活消息。此时上游错误将在流中返回，而不是作为 HTTP 状态码。",
+    adminKeySet: "已启用",
+    adminKeyNotSet: "未启用",
+    generateAdminKey: "生成密钥",
+    regenerateAdminKey: "重新生成密钥",
+    disableAdminApi: "停用",
+    adminKeyCreated: "请立即复制此密钥，之后将无法再次查看。",
+    configFile: "配置文件",
+    configFileDesc: "将路由、目标和令牌导出为 YAML 或 JSON，或将文件应用到此网关。路由按路径前缀匹配，令牌按名称匹配。",
+    exportConfig: "导出",
+    keysOmit: "不包含上游密钥",
+    keysPlain: "包含密钥（明文）",
+    keysEncrypt: "加密密钥",
+    passphrase: "口令",
+    importConfig: "导入",
+    importPlaceholder: "粘贴 YAML 或 JSON 配置文档",
+    passphraseIfEncrypted: "口令（密钥已加密时填写）",
+    prune: "删除缺失项",
+    pruneHint: "预览会在写入前列出所有变更。未提供密钥的目标保留现有密钥。“删除缺失项”会删除文件中没有的路由和具名令牌。",
+    previewImport: "预览",
+    applyImport: "应用",
+    importApplied: "配置已应用",
+    importActions: { create: "新建", update: "更新", delete: "删除", unchanged: "未变" },
+    routesLabel: "路由",
+    tokensLabel: "令牌",
+    createdTokensHint: "新建令牌的密钥，请立即复制，之后将无法再次查看。",
+    watchFile: "监听文件",
+    watchHint: "立即应用配置文件，并在文件变更时自动应用。",
+    watching: "正在监听",
+    startWatch: "监听",
+    stopWatch: "停止",
+    watchStarted: "已开始监听配置文件",
+    watchStopped: "已停止监听配置文件",
+  },
+  alerts: {
+    title: "告警",
+    description: "网关出现异常时，通过 Webhook 和桌面通知提醒。",
+    addRule: "添加规则",
+    editRule: "编辑告警规则",
+    noRules: "暂无告警规则。",
+    kind: "触发条件",
+    kinds: {
+      circuit_open: "熔断开启",
+      error_rate: "错误率",
+      quota: "令牌额度",
+      key_rejected: "密钥被拒绝",
+    },
+    kindHints: {
+      circuit_open: "目标连续失败后停止接收流量。",
+      error_rate: "路由在时间窗口内的失败请求占比达到阈值。",
+      quota: "令牌已用额度达到指定百分比。",
+      key_rejected: "上游服务以 HTTP 401 拒绝了密钥。密钥不会被自动禁用。",
+    },
+    route: "路由",
+    token: "令牌",
+    allRoutes: "所有路由",
+    allTokens: "所有令牌",
+    threshold: "阈值 (%)",
+    windowMinutes: "时间窗口（分钟）",
+    minRequests: "最少请求数",
+    cooldownMinutes: "冷却时间（分钟）",
+    cooldownHint: "冷却时间内不会重复发送同一告警，下一次告警会附带被抑制的次数。",
+    webhookUrl: "Webhook URL",
+    desktopNotification: "桌面通知",
+    logOnly: "仅记录日志",
+    sendTest: "发送测试告警",
+    testSent: "测试告警已发送",
+    saved: "告警规则已保存",
+    deleteConfirm: (name: string) => `确定删除告警规则「${name}」？`,
   },
   updater: {
     newVersion: (version: string) => `发现新版本 ${version}`,
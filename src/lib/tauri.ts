@@ -23,9 +23,42 @@ export interface PaginatedResult<T> {
 
 // === Config types ===
 
+export interface RedactionConfig {
+  mask_auth_headers: boolean;
+  extra_headers: string[];
+  mask_body_pii: boolean;
+  body_patterns: string[];
+}
+
+export interface TlsConfig {
+  enabled: boolean;
+  cert_path: string | null;
+  key_path: string | null;
+}
+
+export interface StreamConfig {
+  keepalive_secs: number;
+  early_start: boolean;
+}
+
+export interface ConfigWatch {
+  path: string;
+  prune: boolean;
+}
+
 export interface AppConfig {
   server_port: number;
+  bind_address: string;
+  tls: TlsConfig;
+  allowed_ips: string[];
   log_retention_days: number;
+  redaction: RedactionConfig;
+  admin_port: number | null;
+  admin_key_set: boolean;
+  config_watch: ConfigWatch | null;
+  otlp_endpoint: string | null;
+  proxy_url: string | null;
+  stream: StreamConfig;
 }
 
 export interface ServerStatus {
@@ -51,6 +84,17 @@ export interface RouteTargetOverride {
   value: string;
 }
 
+export interface RouteTargetModel {
+  id: string;
+  target_id: string;
+  model: string;
+  upstream_model: string | null;
+  context_window: number | null;
+  max_output_tokens: number | null;
+}
+
+export type InlineImages = "auto" | "always";
+
 export interface RouteTarget {
   id: string;
   route_id: string;
@@ -59,9 +103,17 @@ export interface RouteTarget {
   weight: number;
   enabled: boolean;
   key_rotation: boolean;
+  proxy_url: string | null;
+  connect_timeout_secs: number | null;
+  first_byte_timeout_secs: number | null;
+  idle_timeout_secs: number | null;
+  stream_mode: "stream" | "non_stream" | null;
+  normalize_messages: boolean;
+  inline_images: InlineImages | null;
   created_at: string;
   keys: RouteTargetKey[];
   overrides: RouteTargetOverride[];
+  models: RouteTargetModel[];
 }
 
 export interface Route {
@@ -70,25 +122,43 @@ export interface Route {
   path_prefix: string;
   input_format: string;
   enabled: boolean;
+  context_policy: ContextPolicy | null;
   created_at: string;
   updated_at: string;
   targets: RouteTarget[];
 }
 
+export type ContextPolicy = "reject" | "clamp" | "trim";
+
 export interface OverrideInput {
   scope: 'body' | 'header' | 'query';
   key: string;
   value: string;
 }
 
+export interface ModelInput {
+  model: string;
+  upstream_model: string | null;
+  context_window: number | null;
+  max_output_tokens: number | null;
+}
+
 export interface TargetInput {
   upstream_format: string;
   base_url: string;
   weight: number;
   enabled: boolean;
   key_rotation: boolean;
+  proxy_url: string | null;
+  connect_timeout_secs: number | null;
+  first_byte_timeout_secs: number | null;
+  idle_timeout_secs: number | null;
+  stream_mode: "stream" | "non_stream" | null;
+  normalize_messages: boolean;
+  inline_images: InlineImages | null;
   keys: string[];
   overrides: OverrideInput[];
+  models: ModelInput[];
 }
 
 export const SUPPORTED_FORMATS = [
@@ -115,15 +185,10 @@ export async function createRoute(data: {
   path_prefix: string;
   input_format: string;
   enabled: boolean;
+  context_policy: ContextPolicy | null;
   targets: TargetInput[];
 }): Promise<Route> {
-  return invoke<Route>("create_route", {
-    name: data.name,
-    pathPrefix: data.path_prefix,
-    inputFormat: data.input_format,
-    enabled: data.enabled,
-    targets: data.targets,
-  });
+  return invoke<Route>("create_route", { input: data });
 }
 
 export async function updateRoute(data: {
@@ -132,16 +197,11 @@ export async function updateRoute(data: {
   path_prefix: string;
   input_format: string;
   enabled: boolean;
+  context_policy: ContextPolicy | null;
   targets: TargetInput[];
 }): Promise<Route> {
-  return invoke<Route>("update_route", {
-    id: data.id,
-    name: data.name,
-    pathPrefix: data.path_prefix,
-    inputFormat: data.input_format,
-    enabled: data.enabled,
-    targets: data.targets,
-  });
+  const { id, ...input } = data;
+  return invoke<Route>("update_route", { id, input });
 }
 
 export async function deleteRoute(id: string): Promise<void> {
@@ -164,7 +224,7 @@ export async function testRoute(routeId: string, tokenKey: string): Promise<Test
 export interface Token {
   id: string;
   name: string | null;
-  key_value: string;
+  key_prefix: string | null;
   quota_limit: number | null;
   quota_used: number;
   expires_at: string | null;
@@ -173,6 +233,11 @@ export interface Token {
   created_at: string;
 }
 
+/** Returned only by createToken: the plaintext key is not stored. */
+export interface CreatedToken extends Token {
+  key_value: string;
+}
+
 // === Request Log types ===
 
 export interface RequestLog {
@@ -196,6 +261,11 @@ export interface RequestLog {
   request_url: string | null;
   upstream_url: string | null;
   created_at: string;
+  ttft_ms: number | null;
+  stream_duration_ms: number | null;
+  tokens_per_second: number | null;
+  stream_outcome: "completed" | "client_closed" | "upstream_error" | null;
+  normalization_notes: string | null;
 }
 
 // === Usage Stats types ===
@@ -212,9 +282,58 @@ export interface ModelStat {
   count: number;
 }
 
+export interface Percentiles {
+  count: number;
+  p50: number | null;
+  p90: number | null;
+  p99: number | null;
+}
+
 export interface UsageStats {
   daily: DailyStat[];
   by_model: ModelStat[];
+  /** Non-streamed requests only. */
+  latency_ms: Percentiles;
+  ttft_ms: Percentiles;
+  stream_duration_ms: Percentiles;
+  tokens_per_second: Percentiles;
+  /** Stream count by outcome (`completed`, `client_closed`, `upstream_error`). */
+  stream_outcomes: Record<string, number>;
+}
+
+export interface UsageSummary {
+  requests: number;
+  errors: number;
+  success_rate: number | null;
+  error_rate: number | null;
+  prompt_tokens: number;
+  completion_tokens: number;
+  avg_latency_ms: number | null;
+  p50_latency_ms: number | null;
+  p95_latency_ms: number | null;
+  p99_latency_ms: number | null;
+}
+
+export interface UsagePeriod extends UsageSummary {
+  /** `YYYY-MM-DDTHH:00:00Z` for hourly, `YYYY-MM-DD` for daily (UTC). */
+  period: string;
+}
+
+export interface UsageGroup extends UsageSummary {
+  /** Route, target or token id, or status class; empty when unknown. */
+  key: string;
+  name: string | null;
+}
+
+export type Granularity = "hour" | "day";
+
+export interface UsageAnalytics {
+  total: UsageSummary;
+  series: UsagePeriod[];
+  by_route: UsageGroup[];
+  by_target: UsageGroup[];
+  by_token: UsageGroup[];
+  by_status_class: UsageGroup[];
 }
 
 // === Test result ===
@@ -241,18 +360,93 @@ export async function getConfig(): Promise<AppConfig> {
   return invoke<AppConfig>("get_config");
 }
 
+/** Base URL the app uses to reach its own gateway (mirrors `AppConfig::local_base_url`). */
+export function gatewayBaseUrl(config: AppConfig): string {
+  const scheme = config.tls.enabled ? "https" : "http";
+  let host = config.bind_address;
+  if (host === "0.0.0.0" || host.startsWith("127.")) host = "127.0.0.1";
+  else if (host === "::" || host === "::1") host = "[::1]";
+  else if (host.includes(":")) host = `[${host}]`;
+  return `${scheme}://${host}:${config.server_port}`;
+}
+
 export async function getServerStatus(): Promise<ServerStatus> {
   return invoke<ServerStatus>("get_server_status");
 }
 
-export async function updateConfig(data: {
-  server_port: number;
-  log_retention_days: number;
+/**
+ * Partial update: omitted fields keep their value, and `null` clears
+ * `admin_port`, `otlp_endpoint` and `proxy_url`.
+ */
+export async function updateConfig(update: {
+  server_port?: number;
+  log_retention_days?: number;
+  bind_address?: string;
+  tls?: TlsConfig;
+  allowed_ips?: string[];
+  redaction?: RedactionConfig;
+  admin_port?: number | null;
+  otlp_endpoint?: string | null;
+  proxy_url?: string | null;
+  stream?: StreamConfig;
 }): Promise<AppConfig> {
-  return invoke<AppConfig>("update_config", {
-    serverPort: data.server_port,
-    logRetentionDays: data.log_retention_days,
-  });
+  return invoke<AppConfig>("update_config", { update });
+}
+
+export async function rotateAdminKey(): Promise<string> {
+  return invoke<string>("rotate_admin_key");
+}
+
+export async function clearAdminKey(): Promise<void> {
+  return invoke<void>("clear_admin_key");
+}
+
+// === Declarative config (routes & tokens as YAML/JSON) ===
+
+export type ExportFormat = "yaml" | "json";
+/** How upstream keys are written: left out, in plain text, or passphrase-encrypted. */
+export type KeyMode = "omit" | "plain" | "encrypt";
+
+export interface PlannedChange {
+  action: "create" | "update" | "delete" | "unchanged";
+  name: string;
+  changes: string[];
+}
+
+export interface ImportPlan {
+  routes: PlannedChange[];
+  tokens: PlannedChange[];
+}
+
+export interface ImportResult {
+  plan: ImportPlan;
+  created_tokens: CreatedToken[];
+}
+
+export interface ImportOptions {
+  passphrase?: string | null;
+  prune: boolean;
+}
+
+export async function exportGatewayConfig(options: {
+  format: ExportFormat;
+  keys: KeyMode;
+  passphrase?: string | null;
+}): Promise<string> {
+  return invoke<string>("export_gateway_config", { options });
+}
+
+export async function previewGatewayImport(content: string, options: ImportOptions): Promise<ImportPlan> {
+  return invoke<ImportPlan>("preview_gateway_import", { content, options });
+}
+
+export async function importGatewayConfig(content: string, options: ImportOptions): Promise<ImportResult> {
+  return invoke<ImportResult>("import_gateway_config", { content, options });
+}
+
+/** Watch a config file and apply it on change; `null` stops watching. */
+export async function setConfigWatch(watch: ConfigWatch | null): Promise<AppConfig> {
+  return invoke<AppConfig>("set_config_watch", { watch });
 }
 
 // === Token commands ===
@@ -266,8 +460,8 @@ export async function createToken(data: {
   quota_limit?: number | null;
   expires_at?: string | null;
   allowed_models?: string | null;
-}): Promise<Token> {
-  return invoke<Token>("create_token", {
+}): Promise<CreatedToken> {
+  return invoke<CreatedToken>("create_token", {
     name: data.name,
     quotaLimit: data.quota_limit,
     expiresAt: data.expires_at,
@@ -297,10 +491,63 @@ export async function deleteToken(id: string): Promise<void> {
   return invoke<void>("delete_token", { id });
 }
 
+/** Replace the token's key; the returned key is the only copy. */
+export async function regenerateToken(id: string): Promise<CreatedToken> {
+  return invoke<CreatedToken>("regenerate_token", { id });
+}
+
+/** The key of the token seeded on first run; returned only once. */
+export async function takeDefaultToken(): Promise<string | null> {
+  return invoke<string | null>("take_default_token");
+}
+
 export async function resetTokenQuota(id: string): Promise<void> {
   return invoke<void>("reset_token_quota", { id });
 }
 
+// === Alert commands ===
+
+export type AlertKind = "circuit_open" | "error_rate" | "quota" | "key_rejected";
+
+export interface AlertRule {
+  id: string;
+  name: string;
+  kind: AlertKind;
+  route_id: string | null;
+  token_id: string | null;
+  /** Percentage: error rate for error_rate, quota used for quota. */
+  threshold: number | null;
+  window_secs: number;
+  min_requests: number;
+  cooldown_secs: number;
+  webhook_url: string | null;
+  notify_desktop: boolean;
+  enabled: boolean;
+  created_at: string;
+}
+
+export type AlertRuleInput = Omit<AlertRule, "id" | "created_at">;
+
+export async function listAlertRules(): Promise<AlertRule[]> {
+  return invoke<AlertRule[]>("list_alert_rules");
+}
+
+export async function createAlertRule(rule: AlertRuleInput): Promise<AlertRule> {
+  return invoke<AlertRule>("create_alert_rule", { rule });
+}
+
+export async function updateAlertRule(id: string, rule: AlertRuleInput): Promise<AlertRule> {
+  return invoke<AlertRule>("update_alert_rule", { id, rule });
+}
+
+export async function deleteAlertRule(id: string): Promise<void> {
+  return invoke<void>("delete_alert_rule", { id });
+}
+
+export async function testAlertRule(id: string): Promise<void> {
+  return invoke<void>("test_alert_rule", { id });
+}
+
 // === Request Log commands ===
 
 export interface RetryResult {
@@ -328,6 +575,64 @@ export async function getUsageStats(days?: number): Promise<UsageStats> {
   return invoke<UsageStats>("get_usage_stats", { days });
 }
 
+export async function getUsageAnalytics(
+  days?: number,
+  granularity?: Granularity,
+): Promise<UsageAnalytics> {
+  return invoke<UsageAnalytics>("get_usage_analytics", { days, granularity });
+}
+
+// === Live request events ===
+
+export type RequestEventKind = "started" | "first_token" | "completed" | "error";
+
+export interface RequestEvent {
+  /** Subscription that delivered the event. */
+  subscription: number;
+  /** Request id; also the id of its request log once written. */
+  id: string;
+  kind: RequestEventKind;
+  route_id: string;
+  route: string;
+  target_id: string;
+  upstream_format: string;
+  model: string;
+  token_id: string | null;
+  status: number | null;
+  elapsed_ms: number;
+  ttft_ms: number | null;
+  prompt_tokens: number | null;
+  completion_tokens: number | null;
+  error: string | null;
+  started_at: string;
+  timestamp: string;
+}
+
+export interface RequestEventFilter {
+  route_id?: string;
+  target_id?: string;
+  token_id?: string;
+  model?: string;
+  kinds?: RequestEventKind[];
+}
+
+export interface RequestEventSubscription {
+  id: number;
+  /** Matching requests already in flight (without `subscription`). */
+  in_flight: Omit<RequestEvent, "subscription">[];
+}
+
+/** Matching events arrive as `request-event` Tauri events tagged with the subscription id. */
+export async function subscribeRequestEvents(
+  filter?: RequestEventFilter,
+): Promise<RequestEventSubscription> {
+  return invoke<RequestEventSubscription>("subscribe_request_events", { filter });
+}
+
+export async function unsubscribeRequestEvents(id: number): Promise<void> {
+  return invoke("unsubscribe_request_events", { id });
+}
+
 export async function retryRequestLog(id: string): Promise<RetryResult> {
   return invoke<RetryResult>("retry_request_log", { id });
 }
@@ -377,13 +682,15 @@ export async function downloadVideo(params: {
   audioOnly?: boolean;
 }): Promise<string> {
   return invoke<string>("download_video", {
-    taskId: params.taskId,
-    title: params.title,
-    videoUrl: params.videoUrl,
-    audioUrl: params.audioUrl,
-    quality: params.quality,
-    saveDir: params.saveDir,
-    audioOnly: params.audioOnly ?? false,
+    request: {
+      task_id: params.taskId,
+      title: params.title,
+      video_url: params.videoUrl,
+      audio_url: params.audioUrl,
+      quality: params.quality,
+      save_dir: params.saveDir,
+      audio_only: params.audioOnly ?? false,
+    },
   });
 }
 
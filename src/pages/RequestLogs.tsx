@@ -1,5 +1,6 @@
 import React, { useCallback, useEffect, useRef, useState } from "react";
-import { Loader2, Search, Trash2, RotateCcw, Copy, Check, RefreshCw, ArrowRight } from "lucide-react";
+import { Loader2, Search, Trash2, RotateCcw, Copy, Check, Radio, ArrowRight } from "lucide-react";
+import { listen } from "@tauri-apps/api/event";
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
 import {
@@ -39,7 +40,11 @@ import {
   clearRequestLogs,
   retryRequestLog,
   getConfig,
+  gatewayBaseUrl,
   listRoutes,
+  subscribeRequestEvents,
+  unsubscribeRequestEvents,
+  type RequestEvent,
   type RequestLog,
 } from "@/lib/tauri";
 import { Pagination } from "@/components/ui/pagination";
@@ -209,14 +214,14 @@ export default function RequestLogs({ embedded = false }: { embedded?: boolean }
     headers: Array<{ key: string; value: string }>;
     body: string;
   }>({ url: "", headers: [], body: "" });
-  const [autoRefresh, setAutoRefresh] = useState(false);
-  const autoRefreshRef = useRef(autoRefresh);
-  autoRefreshRef.current = autoRefresh;
-  const [serverPort, setServerPort] = useState(9000);
+  const [live, setLive] = useState(false);
+  const [inFlight, setInFlight] = useState<Record<string, Omit<RequestEvent, "subscription">>>({});
+  const [now, setNow] = useState(Date.now());
+  const [gatewayUrl, setGatewayUrl] = useState("http://localhost:9000");
   const [routeMap, setRouteMap] = useState<Record<string, { name: string; path_prefix: string }>>({});
 
   useEffect(() => {
-    getConfig().then((c) => setServerPort(c.server_port));
+    getConfig().then((c) => setGatewayUrl(gatewayBaseUrl(c)));
     listRoutes()
       .then((routes) => {
         const map: Record<string, { name: string; path_prefix: string }> = {};
@@ -251,16 +256,66 @@ export default function RequestLogs({ embedded = false }: { embedded?: boolean }
     fetchLogs();
   }, [fetchLogs]);
 
-  // Auto-refresh effect
+  // Live mode: track in-flight requests and reload the list as they finish.
+  const fetchLogsRef = useRef(fetchLogs);
+  fetchLogsRef.current = fetchLogs;
   useEffect(() => {
-    if (!autoRefresh) return;
-    const interval = setInterval(() => {
-      if (autoRefreshRef.current) {
-        fetchLogs();
+    if (!live) {
+      setInFlight({});
+      return;
+    }
+    let subscription: number | null = null;
+    let cancelled = false;
+    let reload: ReturnType<typeof setTimeout> | undefined;
+    // Events can arrive before the subscription id does; hold them until then.
+    let pending: RequestEvent[] = [];
+    const apply = (event: RequestEvent) => {
+      if (event.kind === "started" || event.kind === "first_token") {
+        setInFlight((prev) => ({ ...prev, [event.id]: event }));
+        return;
       }
-    }, 3000);
-    return () => clearInterval(interval);
-  }, [autoRefresh, fetchLogs]);
+      setInFlight((prev) => {
+        const next = { ...prev };
+        delete next[event.id];
+        return next;
+      });
+      // The log row is written right after the request finishes.
+      clearTimeout(reload);
+      reload = setTimeout(() => fetchLogsRef.current(), 500);
+    };
+    const unlisten = listen<RequestEvent>("request-event", ({ payload }) => {
+      if (subscription === null) pending.push(payload);
+      else if (payload.subscription === subscription) apply(payload);
+    });
+    subscribeRequestEvents({ model: appliedFilter.trim() || undefined })
+      .then((sub) => {
+        if (cancelled) {
+          unsubscribeRequestEvents(sub.id);
+          return;
+        }
+        subscription = sub.id;
+        setInFlight(Object.fromEntries(sub.in_flight.map((e) => [e.id, e])));
+        pending.filter((e) => e.subscription === sub.id).forEach(apply);
+        pending = [];
+      })
+      .catch((err) => {
+        toast.error(parseIpcError(err).message);
+        setLive(false);
+      });
+    return () => {
+      cancelled = true;
+      clearTimeout(reload);
+      unlisten.then((fn) => fn());
+      if (subscription !== null) unsubscribeRequestEvents(subscription);
+    };
+  }, [live, appliedFilter]);
+
+  const inFlightList = Object.values(inFlight).sort((a, b) => a.started_at.localeCompare(b.started_at));
+  useEffect(() => {
+    if (inFlightList.length === 0) return;
+    const timer = setInterval(() => setNow(Date.now()), 1000);
+    return () => clearInterval(timer);
+  }, [inFlightList.length]);
 
   function handleSearch() {
     setPage(1);
@@ -334,7 +389,7 @@ export default function RequestLogs({ embedded = false }: { embedded?: boolean }
       ? (FORMAT_SUB_PATH[log.input_format] ?? "/v1/chat/completions")
       : "/v1/chat/completions";
     const prefix = log.route_id ? (routeMap[log.route_id]?.path_prefix ?? "") : "";
-    return `http://localhost:${serverPort}${prefix}${subPath}`;
+    return `${gatewayUrl}${prefix}${subPath}`;
   }
 
   function parseStoredHeaders(headersJson: string | null): Array<{ key: string; value: string }> {
@@ -405,12 +460,12 @@ export default function RequestLogs({ embedded = false }: { embedded?: boolean }
   const actionButtons = (
     <div className="flex items-center gap-2">
       <Button
-        variant={autoRefresh ? "default" : "outline"}
+        variant={live ? "default" : "outline"}
         size="sm"
-        onClick={() => setAutoRefresh((v) => !v)}
+        onClick={() => setLive((v) => !v)}
       >
-        <RefreshCw className={autoRefresh ? "animate-spin" : ""} />
-        {t.requestLogs.autoRefresh}
+        <Radio className={live ? "animate-pulse" : ""} />
+        {t.requestLogs.live}
       </Button>
       <AlertDialog>
         <AlertDialogTrigger asChild>
@@ -489,6 +544,31 @@ export default function RequestLogs({ embedded = false }: { embedded?: boolean }
         </div>
       )}
 
+      {/* In-flight requests (live mode) */}
+      {live && inFlightList.length > 0 && (
+        <div className="rounded-lg border bg-muted/30 text-xs">
+          <div className="px-3 py-1.5 font-medium text-muted-foreground">
+            {t.requestLogs.inFlight(inFlightList.length)}
+          </div>
+          {inFlightList.map((req) => (
+            <div key={req.id} className="flex items-center gap-3 border-t px-3 py-1.5">
+              <Loader2 className="size-3 animate-spin text-muted-foreground" />
+              <span className="font-medium">{req.model || "-"}</span>
+              <span className="text-muted-foreground">{routeMap[req.route_id]?.name ?? req.route}</span>
+              <span className="text-muted-foreground">{req.upstream_format}</span>
+              <span className="ml-auto tabular-nums text-muted-foreground">
+                {req.kind === "first_token"
+                  ? `${t.requestLogs.streaming} · ${t.requestLogs.ttft} ${formatLatency(req.ttft_ms)}`
+                  : t.requestLogs.waiting}
+              </span>
+              <span className="w-14 text-right tabular-nums">
+                {formatLatency(Math.max(0, now - Date.parse(req.started_at)))}
+              </span>
+            </div>
+          ))}
+        </div>
+      )}
+
       {/* Table */}
       {loading ? (
         <div className="flex items-center justify-center py-12">
@@ -672,13 +752,43 @@ export default function RequestLogs({ embedded = false }: { embedded?: boolean }
                           <span className="font-medium tabular-nums">{formatTokens(selectedLog.prompt_tokens, selectedLog.completion_tokens)}</span>
                         </div>
                       </div>
+                      {selectedLog.stream_outcome && (
+                        <div className="flex divide-x divide-border rounded-lg border bg-muted/30 text-xs overflow-hidden">
+                          <div className="flex flex-col gap-0.5 px-3 py-2 min-w-0 flex-1">
+                            <span className="text-muted-foreground text-[10px] uppercase tracking-wide">{t.requestLogs.ttft}</span>
+                            <span className="font-medium tabular-nums">{formatLatency(selectedLog.ttft_ms)}</span>
+                          </div>
+                          <div className="flex flex-col gap-0.5 px-3 py-2 min-w-0 flex-1">
+                            <span className="text-muted-foreground text-[10px] uppercase tracking-wide">{t.requestLogs.streamDuration}</span>
+                            <span className="font-medium tabular-nums">{formatLatency(selectedLog.stream_duration_ms)}</span>
+                          </div>
+                          <div className="flex flex-col gap-0.5 px-3 py-2 min-w-0 flex-1">
+                            <span className="text-muted-foreground text-[10px] uppercase tracking-wide">{t.requestLogs.tokensPerSecond}</span>
+                            <span className="font-medium tabular-nums">{selectedLog.tokens_per_second?.toFixed(1) ?? "-"}</span>
+                          </div>
+                          <div className="flex flex-col gap-0.5 px-3 py-2 min-w-0 flex-1">
+                            <span className="text-muted-foreground text-[10px] uppercase tracking-wide">{t.requestLogs.streamOutcome}</span>
+                            <span className={`font-medium ${selectedLog.stream_outcome === "completed" ? "" : "text-destructive"}`}>
+                              {t.requestLogs.outcomes[selectedLog.stream_outcome] ?? selectedLog.stream_outcome}
+                            </span>
+                          </div>
+                        </div>
+                      )}
+                      {selectedLog.normalization_notes && (
+                        <div className="flex flex-col gap-0.5 rounded-lg border bg-muted/30 px-3 py-2 text-xs">
+                          <span className="text-muted-foreground text-[10px] uppercase tracking-wide">{t.requestLogs.normalization}</span>
+                          {selectedLog.normalization_notes.split("; ").map((note) => (
+                            <span key={note} className="font-medium">{note}</span>
+                          ))}
+                        </div>
+                      )}
                       {/* URL rows */}
                       <div className="flex flex-col gap-1">
                         <div className="flex items-center gap-2 rounded-md border bg-muted/30 px-3 py-1.5 text-xs font-mono">
                           <span className="shrink-0 rounded bg-muted px-1.5 py-0.5 text-[10px] font-sans font-medium text-muted-foreground">IN</span>
                           <span className="flex-1 truncate select-all">
                             {selectedLog.request_url
-                              ? `http://localhost:${serverPort}${selectedLog.request_url}`
+                              ? `${gatewayUrl}${selectedLog.request_url}`
                               : logUrl}
                           </span>
                         </div>
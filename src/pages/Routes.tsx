@@ -33,6 +33,9 @@ import {
   Route,
   TargetInput,
   OverrideInput,
+  ModelInput,
+  ContextPolicy,
+  InlineImages,
   SUPPORTED_FORMATS,
   listRoutes,
   createRoute,
@@ -40,6 +43,7 @@ import {
   deleteRoute,
   parseIpcError,
   getConfig,
+  gatewayBaseUrl,
 } from "@/lib/tauri";
 
 interface OverrideRow {
@@ -48,14 +52,29 @@ interface OverrideRow {
   value: string;
 }
 
+interface ModelRow {
+  model: string;
+  upstream_model: string;
+  context_window: string;
+  max_output_tokens: string;
+}
+
 interface TargetFormState {
   upstream_format: string;
   base_url: string;
   weight: number;
   enabled: boolean;
   key_rotation: boolean;
+  proxy_url: string;
+  connect_timeout_secs: string;
+  first_byte_timeout_secs: string;
+  idle_timeout_secs: string;
+  stream_mode: "auto" | "stream" | "non_stream";
+  normalize_messages: boolean;
+  inline_images: "off" | InlineImages;
   keys: string[];
   overrides: OverrideRow[];
+  models: ModelRow[];
   expanded: boolean;
 }
 
@@ -64,6 +83,7 @@ interface RouteFormState {
   path_prefix: string;
   input_format: string;
   enabled: boolean;
+  context_policy: "none" | ContextPolicy;
   targets: TargetFormState[];
 }
 
@@ -73,8 +93,16 @@ const defaultTarget = (): TargetFormState => ({
   weight: 1,
   enabled: true,
   key_rotation: true,
+  proxy_url: "",
+  connect_timeout_secs: "",
+  first_byte_timeout_secs: "",
+  idle_timeout_secs: "",
+  stream_mode: "auto",
+  normalize_messages: false,
+  inline_images: "off",
   keys: [""],
   overrides: [],
+  models: [],
   expanded: true,
 });
 
@@ -83,6 +111,7 @@ const defaultForm = (): RouteFormState => ({
   path_prefix: "/",
   input_format: "anthropic",
   enabled: true,
+  context_policy: "none",
   targets: [defaultTarget()],
 });
 
@@ -90,6 +119,12 @@ function formatLabel(value: string): string {
   return SUPPORTED_FORMATS.find((f) => f.value === value)?.label ?? value;
 }
 
+/** Empty or invalid input means unset: the default timeout, or no limit. */
+function parsePositiveInt(value: string): number | null {
+  const n = parseInt(value, 10);
+  return isNaN(n) || n < 1 ? null : n;
+}
+
 interface RoutesProps {
   embedded?: boolean;
 }
@@ -126,11 +161,11 @@ export default function Routes({ embedded }: RoutesProps) {
   } | null>(null);
   const [upstreamTestKey, setUpstreamTestKey] = useState(0);
 
-  const [serverPort, setServerPort] = useState<number>(9000);
+  const [gatewayUrl, setGatewayUrl] = useState("http://localhost:9000");
 
   useEffect(() => {
     load();
-    getConfig().then((c) => setServerPort(c.server_port));
+    getConfig().then((c) => setGatewayUrl(gatewayBaseUrl(c)));
   }, []);
 
   async function load() {
@@ -159,14 +194,28 @@ export default function Routes({ embedded }: RoutesProps) {
       path_prefix: route.path_prefix,
       input_format: route.input_format,
       enabled: route.enabled,
+      context_policy: route.context_policy ?? "none",
       targets: route.targets.map((t) => ({
         upstream_format: t.upstream_format,
         base_url: t.base_url,
         weight: t.weight,
         enabled: t.enabled,
         key_rotation: t.key_rotation,
+        proxy_url: t.proxy_url ?? "",
+        connect_timeout_secs: t.connect_timeout_secs?.toString() ?? "",
+        first_byte_timeout_secs: t.first_byte_timeout_secs?.toString() ?? "",
+        idle_timeout_secs: t.idle_timeout_secs?.toString() ?? "",
+        stream_mode: t.stream_mode === "stream" || t.stream_mode === "non_stream" ? t.stream_mode : "auto",
+        normalize_messages: t.normalize_messages,
+        inline_images: t.inline_images ?? "off",
         keys: t.keys.length > 0 ? t.keys.map((k) => k.key_value) : [""],
         overrides: t.overrides.map((o) => ({ scope: o.scope as 'body' | 'header' | 'query', key: o.key, value: o.value })),
+        models: t.models.map((m) => ({
+          model: m.model,
+          upstream_model: m.upstream_model ?? "",
+          context_window: m.context_window?.toString() ?? "",
+          max_output_tokens: m.max_output_tokens?.toString() ?? "",
+        })),
         expanded: true,
       })),
     });
@@ -213,8 +262,21 @@ export default function Routes({ embedded }: RoutesProps) {
       weight: t.weight,
       enabled: t.enabled,
       key_rotation: t.key_rotation,
+      proxy_url: t.proxy_url.trim() || null,
+      connect_timeout_secs: parsePositiveInt(t.connect_timeout_secs),
+      first_byte_timeout_secs: parsePositiveInt(t.first_byte_timeout_secs),
+      idle_timeout_secs: parsePositiveInt(t.idle_timeout_secs),
+      stream_mode: t.stream_mode === "auto" ? null : t.stream_mode,
+      normalize_messages: t.normalize_messages,
+      inline_images: t.inline_images === "off" ? null : t.inline_images,
       keys: t.keys.filter((k) => k.trim()),
       overrides: t.overrides.filter((o) => o.key.trim()).map((o): OverrideInput => ({ scope: o.scope, key: o.key.trim(), value: o.value })),
+      models: t.models.filter((m) => m.model.trim()).map((m): ModelInput => ({
+        model: m.model.trim(),
+        upstream_model: m.upstream_model.trim() || null,
+        context_window: parsePositiveInt(m.context_window),
+        max_output_tokens: parsePositiveInt(m.max_output_tokens),
+      })),
     }));
 
     setSaving(true);
@@ -226,6 +288,7 @@ export default function Routes({ embedded }: RoutesProps) {
           path_prefix: form.path_prefix.trim(),
           input_format: form.input_format,
           enabled: form.enabled,
+          context_policy: form.context_policy === "none" ? null : form.context_policy,
           targets,
         });
       } else {
@@ -234,6 +297,7 @@ export default function Routes({ embedded }: RoutesProps) {
           path_prefix: form.path_prefix.trim(),
           input_format: form.input_format,
           enabled: form.enabled,
+          context_policy: form.context_policy === "none" ? null : form.context_policy,
           targets,
         });
       }
@@ -260,7 +324,7 @@ export default function Routes({ embedded }: RoutesProps) {
   }
 
   function openTest(route: Route) {
-    const url = `http://localhost:${serverPort}${route.path_prefix}${getTestPath(route.input_format)}`;
+    const url = `${gatewayUrl}${route.path_prefix}${getTestPath(route.input_format)}`;
     setTestDialogTitle(`测试路由 "${route.name}"`);
     setTestPanelInfo({
       url,
@@ -420,6 +484,37 @@ export default function Routes({ embedded }: RoutesProps) {
     }));
   }
 
+  function addModel(targetIdx: number) {
+    setForm((prev) => ({
+      ...prev,
+      targets: prev.targets.map((t, i) =>
+        i === targetIdx
+          ? { ...t, models: [...t.models, { model: '', upstream_model: '', context_window: '', max_output_tokens: '' }] }
+          : t
+      ),
+    }));
+  }
+
+  function removeModel(targetIdx: number, modelIdx: number) {
+    setForm((prev) => ({
+      ...prev,
+      targets: prev.targets.map((t, i) =>
+        i === targetIdx ? { ...t, models: t.models.filter((_, j) => j !== modelIdx) } : t
+      ),
+    }));
+  }
+
+  function updateModel(targetIdx: number, modelIdx: number, patch: Partial<ModelRow>) {
+    setForm((prev) => ({
+      ...prev,
+      targets: prev.targets.map((t, i) =>
+        i === targetIdx
+          ? { ...t, models: t.models.map((m, j) => (j === modelIdx ? { ...m, ...patch } : m)) }
+          : t
+      ),
+    }));
+  }
+
   const containerClass = embedded ? "" : "container mx-auto p-4";
 
   return (
@@ -544,6 +639,23 @@ export default function Routes({ embedded }: RoutesProps) {
               <Label className="text-xs text-muted-foreground cursor-pointer select-none">
                 启用此路由
               </Label>
+              <div className="ml-auto flex items-center gap-2">
+                <Label className="text-xs text-muted-foreground">超出模型上下文时</Label>
+                <Select
+                  value={form.context_policy}
+                  onValueChange={(v) => setForm((p) => ({ ...p, context_policy: v as RouteFormState["context_policy"] }))}
+                >
+                  <SelectTrigger className="h-8 w-[150px] text-xs">
+                    <SelectValue />
+                  </SelectTrigger>
+                  <SelectContent>
+                    <SelectItem value="none">不处理</SelectItem>
+                    <SelectItem value="reject">提前拒绝</SelectItem>
+                    <SelectItem value="clamp">压缩 max_tokens</SelectItem>
+                    <SelectItem value="trim">裁剪最早的对话</SelectItem>
+                  </SelectContent>
+                </Select>
+              </div>
             </div>
           </div>
 
@@ -636,8 +748,90 @@ export default function Routes({ embedded }: RoutesProps) {
                         />
                       </div>
 
-                      {/* Format + Weight + Key Rotation — one row */}
-                      <div className="grid grid-cols-3 gap-3 items-end">
+                      {/* Outbound proxy + Image inlining — one row */}
+                      <div className="grid grid-cols-4 gap-3">
+                        <div className="col-span-3 space-y-1.5">
+                          <Label className="text-xs text-muted-foreground">出站代理</Label>
+                          <Input
+                            className="h-8 font-mono text-xs"
+                            placeholder="留空使用全局代理，direct 为直连，如 socks5h://127.0.0.1:1080"
+                            value={target.proxy_url}
+                            onChange={(e) => updateTarget(ti, { proxy_url: e.target.value })}
+                          />
+                        </div>
+                        <div className="space-y-1.5">
+                          <Label className="text-xs text-muted-foreground">图片内联</Label>
+                          <Select
+                            value={target.inline_images}
+                            onValueChange={(v) => updateTarget(ti, { inline_images: v as TargetFormState["inline_images"] })}
+                          >
+                            <SelectTrigger className="h-8 text-sm">
+                              <SelectValue />
+                            </SelectTrigger>
+                            <SelectContent>
+                              <SelectItem value="off">保留 URL</SelectItem>
+                              <SelectItem value="auto">上游不支持时下载</SelectItem>
+                              <SelectItem value="always">始终下载</SelectItem>
+                            </SelectContent>
+                          </Select>
+                        </div>
+                      </div>
+
+                      {/* Timeouts + Stream mode — one row */}
+                      <div className="grid grid-cols-4 gap-3">
+                        <div className="space-y-1.5">
+                          <Label className="text-xs text-muted-foreground">连接超时（秒）</Label>
+                          <Input
+                            type="number"
+                            min={1}
+                            className="h-8 text-sm"
+                            placeholder="10"
+                            value={target.connect_timeout_secs}
+                            onChange={(e) => updateTarget(ti, { connect_timeout_secs: e.target.value })}
+                          />
+                        </div>
+                        <div className="space-y-1.5">
+                          <Label className="text-xs text-muted-foreground">首字节超时（秒）</Label>
+                          <Input
+                            type="number"
+                            min={1}
+                            className="h-8 text-sm"
+                            placeholder="600"
+                            value={target.first_byte_timeout_secs}
+                            onChange={(e) => updateTarget(ti, { first_byte_timeout_secs: e.target.value })}
+                          />
+                        </div>
+                        <div className="space-y-1.5">
+                          <Label className="text-xs text-muted-foreground">流空闲超时（秒）</Label>
+                          <Input
+                            type="number"
+                            min={1}
+                            className="h-8 text-sm"
+                            placeholder="300"
+                            value={target.idle_timeout_secs}
+                            onChange={(e) => updateTarget(ti, { idle_timeout_secs: e.target.value })}
+                          />
+                        </div>
+                        <div className="space-y-1.5">
+                          <Label className="text-xs text-muted-foreground">上游流式</Label>
+                          <Select
+                            value={target.stream_mode}
+                            onValueChange={(v) => updateTarget(ti, { stream_mode: v as TargetFormState["stream_mode"] })}
+                          >
+                            <SelectTrigger className="h-8 text-sm">
+                              <SelectValue />
+                            </SelectTrigger>
+                            <SelectContent>
+                              <SelectItem value="auto">跟随客户端</SelectItem>
+                              <SelectItem value="stream">始终流式</SelectItem>
+                              <SelectItem value="non_stream">始终非流式</SelectItem>
+                            </SelectContent>
+                          </Select>
+                        </div>
+                      </div>
+
+                      {/* Format + Weight + Key Rotation + Normalization — one row */}
+                      <div className="grid grid-cols-4 gap-3 items-end">
                         <div className="space-y-1.5">
                           <Label className="text-xs text-muted-foreground">上游格式</Label>
                           <Select
@@ -671,6 +865,13 @@ export default function Routes({ embedded }: RoutesProps) {
                           />
                           <Label className="text-xs text-muted-foreground">Key 轮询</Label>
                         </div>
+                        <div className="flex items-center gap-2 pb-1" title="合并同角色消息、丢弃空内容、整理工具结果顺序，适用于 Anthropic、Gemini 等严格的上游">
+                          <Switch
+                            checked={target.normalize_messages}
+                            onCheckedChange={(v) => updateTarget(ti, { normalize_messages: v })}
+                          />
+                          <Label className="text-xs text-muted-foreground">规范化消息</Label>
+                        </div>
                       </div>
 
                       {/* API Keys */}
@@ -794,6 +995,69 @@ export default function Routes({ embedded }: RoutesProps) {
                         </Button>
                       </div>
 
+                      {/* Model mappings */}
+                      <div>
+                        <div className="flex items-center gap-2 mb-2.5">
+                          <span className="text-[10px] font-semibold tracking-[0.14em] uppercase text-muted-foreground">
+                            模型映射
+                          </span>
+                          <div className="flex-1 h-px bg-border/40" />
+                        </div>
+                        {target.models.length > 0 && (
+                          <div className="space-y-1.5 mb-1.5">
+                            {target.models.map((m, mi) => (
+                              <div key={mi} className="flex gap-1.5">
+                                <Input
+                                  className="h-8 flex-1 text-xs"
+                                  placeholder="模型名"
+                                  value={m.model}
+                                  onChange={(e) => updateModel(ti, mi, { model: e.target.value })}
+                                />
+                                <Input
+                                  className="h-8 flex-1 text-xs"
+                                  placeholder="上游模型名（可选）"
+                                  value={m.upstream_model}
+                                  onChange={(e) => updateModel(ti, mi, { upstream_model: e.target.value })}
+                                />
+                                <Input
+                                  className="h-8 w-[96px] text-xs shrink-0"
+                                  type="number"
+                                  min={1}
+                                  placeholder="上下文窗口"
+                                  value={m.context_window}
+                                  onChange={(e) => updateModel(ti, mi, { context_window: e.target.value })}
+                                />
+                                <Input
+                                  className="h-8 w-[96px] text-xs shrink-0"
+                                  type="number"
+                                  min={1}
+                                  placeholder="最大输出"
+                                  value={m.max_output_tokens}
+                                  onChange={(e) => updateModel(ti, mi, { max_output_tokens: e.target.value })}
+                                />
+                                <Button
+                                  variant="ghost"
+                                  size="sm"
+                                  className="h-8 w-8 p-0 shrink-0"
+                                  onClick={() => removeModel(ti, mi)}
+                                >
+                                  <Trash2 className="h-3 w-3" />
+                                </Button>
+                              </div>
+                            ))}
+                          </div>
+                        )}
+                        <Button
+                          variant="ghost"
+                          size="sm"
+                          className="h-7 text-xs text-muted-foreground"
+                          onClick={() => addModel(ti)}
+                        >
+                          <Plus className="mr-1 h-3 w-3" />
+                          添加模型
+                        </Button>
+                      </div>
+
                     </div>
                   )}
                 </div>
@@ -1,8 +1,9 @@
 import { useState, useEffect } from "react";
-import { Loader2, Sun, Moon, Monitor, Server, Palette, Info, Languages, Save, CheckCircle2, RefreshCw } from "lucide-react";
+import { Loader2, Sun, Moon, Monitor, Server, Palette, Info, Languages, Save, CheckCircle2, RefreshCw, KeyRound, Copy } from "lucide-react";
 import { Button } from "@/components/ui/button";
 import { Badge } from "@/components/ui/badge";
 import { Input } from "@/components/ui/input";
+import { Switch } from "@/components/ui/switch";
 import {
   Card,
   CardHeader,
@@ -12,14 +13,20 @@ import {
 } from "@/components/ui/card";
 import { Separator } from "@/components/ui/separator";
 import { useTheme } from "@/components/layout/ThemeProvider";
-import { getConfig, getServerStatus, updateConfig } from "@/lib/tauri";
+import { getConfig, getServerStatus, updateConfig, rotateAdminKey, clearAdminKey } from "@/lib/tauri";
 import type { AppConfig, ServerStatus } from "@/lib/tauri";
 import { useLanguage } from "@/lib/i18n";
 import { toast } from "sonner";
 import { parseIpcError } from "@/lib/tauri";
 import { PageHeader } from "@/components/page-header";
+import ConfigFileCard from "@/components/ConfigFileCard";
+import AlertRulesCard from "@/components/AlertRulesCard";
 import { check } from "@tauri-apps/plugin-updater";
 
+function splitList(value: string): string[] {
+  return value.split(",").map((s) => s.trim()).filter(Boolean);
+}
+
 export default function Settings() {
   const { theme, setTheme } = useTheme();
   const { language, setLanguage, t } = useLanguage();
@@ -30,6 +37,17 @@ export default function Settings() {
   // Editable form state
   const [editPort, setEditPort] = useState("");
   const [editRetention, setEditRetention] = useState("");
+  const [editAdminPort, setEditAdminPort] = useState("");
+  const [editBind, setEditBind] = useState("");
+  const [editAllowedIps, setEditAllowedIps] = useState("");
+  const [editTlsEnabled, setEditTlsEnabled] = useState(false);
+  const [editCertPath, setEditCertPath] = useState("");
+  const [editKeyPath, setEditKeyPath] = useState("");
+  const [editOtlpEndpoint, setEditOtlpEndpoint] = useState("");
+  const [editProxyUrl, setEditProxyUrl] = useState("");
+  const [editKeepalive, setEditKeepalive] = useState("");
+  const [editEarlyStart, setEditEarlyStart] = useState(false);
+  const [newAdminKey, setNewAdminKey] = useState<string | null>(null);
   const [saving, setSaving] = useState(false);
   const [saveSuccess, setSaveSuccess] = useState(false);
   const [portChanged, setPortChanged] = useState(false);
@@ -45,6 +63,16 @@ export default function Settings() {
         setConfig(cfg);
         setEditPort(String(cfg.server_port));
         setEditRetention(String(cfg.log_retention_days));
+        setEditAdminPort(cfg.admin_port === null ? "" : String(cfg.admin_port));
+        setEditBind(cfg.bind_address);
+        setEditAllowedIps(cfg.allowed_ips.join(", "));
+        setEditTlsEnabled(cfg.tls.enabled);
+        setEditCertPath(cfg.tls.cert_path ?? "");
+        setEditKeyPath(cfg.tls.key_path ?? "");
+        setEditOtlpEndpoint(cfg.otlp_endpoint ?? "");
+        setEditProxyUrl(cfg.proxy_url ?? "");
+        setEditKeepalive(String(cfg.stream.keepalive_secs));
+        setEditEarlyStart(cfg.stream.early_start);
         setServerStatus(status);
       } catch (err) {
         toast.error(parseIpcError(err).message);
@@ -60,18 +88,42 @@ export default function Settings() {
     const retention = parseInt(editRetention, 10);
     if (isNaN(port) || port < 1 || port > 65535) return;
     if (isNaN(retention) || retention < 1) return;
+    const adminPort = editAdminPort.trim() === "" ? null : parseInt(editAdminPort, 10);
+    if (adminPort !== null && (isNaN(adminPort) || adminPort < 1 || adminPort > 65535)) return;
+    const keepalive = parseInt(editKeepalive, 10);
+    if (isNaN(keepalive) || keepalive < 0) return;
 
     setSaving(true);
     setSaveSuccess(false);
-    const portWillChange = config !== null && config.server_port !== port;
+    const tls = {
+      enabled: editTlsEnabled,
+      cert_path: editCertPath.trim() || null,
+      key_path: editKeyPath.trim() || null,
+    };
+    const portWillChange =
+      config !== null &&
+      (config.server_port !== port ||
+        config.admin_port !== adminPort ||
+        config.bind_address !== editBind.trim() ||
+        JSON.stringify(config.tls) !== JSON.stringify(tls));
     try {
       const updated = await updateConfig({
         server_port: port,
         log_retention_days: retention,
+        bind_address: editBind.trim(),
+        tls,
+        allowed_ips: splitList(editAllowedIps),
+        admin_port: adminPort,
+        otlp_endpoint: editOtlpEndpoint.trim() || null,
+        proxy_url: editProxyUrl.trim() || null,
+        stream: { keepalive_secs: keepalive, early_start: editEarlyStart },
       });
       setConfig(updated);
       setSaveSuccess(true);
       setPortChanged(portWillChange);
+      if (portWillChange) {
+        setServerStatus(await getServerStatus());
+      }
       setTimeout(() => { setSaveSuccess(false); setPortChanged(false); }, 5000);
     } catch (err) {
       toast.error(parseIpcError(err).message);
@@ -83,7 +135,37 @@ export default function Settings() {
   const hasChanges =
     config !== null &&
     (String(config.server_port) !== editPort ||
-      String(config.log_retention_days) !== editRetention);
+      String(config.log_retention_days) !== editRetention ||
+      (config.admin_port === null ? "" : String(config.admin_port)) !== editAdminPort.trim() ||
+      config.bind_address !== editBind.trim() ||
+      config.allowed_ips.join(",") !== splitList(editAllowedIps).join(",") ||
+      config.tls.enabled !== editTlsEnabled ||
+      (config.tls.cert_path ?? "") !== editCertPath.trim() ||
+      (config.tls.key_path ?? "") !== editKeyPath.trim() ||
+      (config.otlp_endpoint ?? "") !== editOtlpEndpoint.trim() ||
+      (config.proxy_url ?? "") !== editProxyUrl.trim() ||
+      String(config.stream.keepalive_secs) !== editKeepalive.trim() ||
+      config.stream.early_start !== editEarlyStart);
+
+  const handleRotateAdminKey = async () => {
+    try {
+      const key = await rotateAdminKey();
+      setNewAdminKey(key);
+      setConfig(await getConfig());
+    } catch (err) {
+      toast.error(parseIpcError(err).message);
+    }
+  };
+
+  const handleClearAdminKey = async () => {
+    try {
+      await clearAdminKey();
+      setNewAdminKey(null);
+      setConfig(await getConfig());
+    } catch (err) {
+      toast.error(parseIpcError(err).message);
+    }
+  };
 
   const handleCheckUpdate = async () => {
     setCheckingUpdate(true);
@@ -161,7 +243,7 @@ export default function Settings() {
                 placeholder="1024-65535"
               />
               <p className="text-xs text-muted-foreground">
-                {t.settings.requiresRestart}
+                {t.settings.appliesLive}
               </p>
             </div>
 
@@ -182,6 +264,142 @@ export default function Settings() {
                 <span className="text-sm text-muted-foreground shrink-0">{t.settings.days}</span>
               </div>
             </div>
+
+            {/* Bind Address */}
+            <div className="space-y-2">
+              <label className="text-sm font-medium">
+                {t.settings.bindAddress}
+              </label>
+              <Input
+                value={editBind}
+                onChange={(e) => setEditBind(e.target.value)}
+                className="font-mono"
+                placeholder="127.0.0.1"
+              />
+              <p className="text-xs text-muted-foreground">
+                {t.settings.bindAddressHint}
+              </p>
+            </div>
+
+            {/* Allowed IPs */}
+            <div className="space-y-2">
+              <label className="text-sm font-medium">
+                {t.settings.allowedIps}
+              </label>
+              <Input
+                value={editAllowedIps}
+                onChange={(e) => setEditAllowedIps(e.target.value)}
+                className="font-mono"
+                placeholder="192.168.1.0/24, 10.0.0.5"
+              />
+              <p className="text-xs text-muted-foreground">
+                {t.settings.allowedIpsHint}
+              </p>
+            </div>
+
+            {/* Admin Port */}
+            <div className="space-y-2">
+              <label className="text-sm font-medium">
+                {t.settings.adminPort}
+              </label>
+              <Input
+                type="number"
+                min={1}
+                max={65535}
+                value={editAdminPort}
+                onChange={(e) => setEditAdminPort(e.target.value)}
+                className="font-mono"
+                placeholder={t.settings.adminPortPlaceholder}
+              />
+              <p className="text-xs text-muted-foreground">
+                {t.settings.appliesLive}
+              </p>
+            </div>
+
+            {/* OTLP Endpoint */}
+            <div className="space-y-2">
+              <label className="text-sm font-medium">
+                {t.settings.otlpEndpoint}
+              </label>
+              <Input
+                value={editOtlpEndpoint}
+                onChange={(e) => setEditOtlpEndpoint(e.target.value)}
+                className="font-mono"
+                placeholder="http://localhost:4318"
+              />
+              <p className="text-xs text-muted-foreground">
+                {t.settings.otlpEndpointHint}
+              </p>
+            </div>
+
+            {/* Outbound Proxy */}
+            <div className="space-y-2">
+              <label className="text-sm font-medium">
+                {t.settings.proxyUrl}
+              </label>
+              <Input
+                value={editProxyUrl}
+                onChange={(e) => setEditProxyUrl(e.target.value)}
+                className="font-mono"
+                placeholder="socks5h://127.0.0.1:1080"
+              />
+              <p className="text-xs text-muted-foreground">
+                {t.settings.proxyUrlHint}
+              </p>
+            </div>
+
+            {/* Stream Keep-alive */}
+            <div className="space-y-2">
+              <label className="text-sm font-medium">
+                {t.settings.streamKeepalive}
+              </label>
+              <Input
+                type="number"
+                min={0}
+                value={editKeepalive}
+                onChange={(e) => setEditKeepalive(e.target.value)}
+                className="font-mono"
+              />
+              <p className="text-xs text-muted-foreground">
+                {t.settings.streamKeepaliveHint}
+              </p>
+            </div>
+          </div>
+
+          {/* Early Stream Start */}
+          <div className="space-y-2">
+            <div className="flex items-center gap-3">
+              <Switch checked={editEarlyStart} onCheckedChange={setEditEarlyStart} />
+              <label className="text-sm font-medium">{t.settings.streamEarlyStart}</label>
+            </div>
+            <p className="text-xs text-muted-foreground">{t.settings.streamEarlyStartHint}</p>
+          </div>
+
+          {/* TLS */}
+          <div className="space-y-2">
+            <div className="flex items-center gap-3">
+              <Switch checked={editTlsEnabled} onCheckedChange={setEditTlsEnabled} />
+              <label className="text-sm font-medium">{t.settings.tls}</label>
+            </div>
+            {editTlsEnabled && (
+              <>
+                <div className="grid gap-3 sm:grid-cols-2">
+                  <Input
+                    value={editCertPath}
+                    onChange={(e) => setEditCertPath(e.target.value)}
+                    className="font-mono"
+                    placeholder={t.settings.certPath}
+                  />
+                  <Input
+                    value={editKeyPath}
+                    onChange={(e) => setEditKeyPath(e.target.value)}
+                    className="font-mono"
+                    placeholder={t.settings.keyPath}
+                  />
+                </div>
+                <p className="text-xs text-muted-foreground">{t.settings.tlsHint}</p>
+              </>
+            )}
           </div>
 
           <Separator />
@@ -243,7 +461,7 @@ export default function Settings() {
                 <CheckCircle2 className="h-4 w-4" />
                 {t.settings.saveSuccess}
                 {portChanged && (
-                  <span className="text-muted-foreground ml-1">— {t.settings.restartHint}</span>
+                  <span className="text-muted-foreground ml-1">— {t.settings.rebindHint}</span>
                 )}
               </span>
             )}
@@ -251,6 +469,60 @@ export default function Settings() {
         </CardContent>
       </Card>
 
+      {/* Admin API */}
+      <Card className="card-elevated">
+        <CardHeader>
+          <CardTitle className="flex items-center gap-2">
+            <KeyRound className="h-5 w-5" />
+            {t.settings.adminApi}
+          </CardTitle>
+          <CardDescription>
+            {t.settings.adminApiDesc}
+          </CardDescription>
+        </CardHeader>
+        <CardContent className="space-y-3">
+          <div className="flex items-center gap-3">
+            <Badge
+              className={
+                config?.admin_key_set
+                  ? "bg-green-500/15 text-green-700 dark:text-green-400 border-green-500/25"
+                  : "bg-muted text-muted-foreground"
+              }
+              variant="outline"
+            >
+              {config?.admin_key_set ? t.settings.adminKeySet : t.settings.adminKeyNotSet}
+            </Badge>
+            <Button size="sm" variant="outline" onClick={handleRotateAdminKey}>
+              {config?.admin_key_set ? t.settings.regenerateAdminKey : t.settings.generateAdminKey}
+            </Button>
+            {config?.admin_key_set && (
+              <Button size="sm" variant="ghost" onClick={handleClearAdminKey}>
+                {t.settings.disableAdminApi}
+              </Button>
+            )}
+          </div>
+          {newAdminKey && (
+            <div className="space-y-2">
+              <div className="flex items-center gap-2">
+                <Input readOnly value={newAdminKey} className="font-mono" />
+                <Button
+                  size="icon"
+                  variant="outline"
+                  onClick={() => navigator.clipboard.writeText(newAdminKey)}
+                >
+                  <Copy className="h-4 w-4" />
+                </Button>
+              </div>
+              <p className="text-xs text-muted-foreground">{t.settings.adminKeyCreated}</p>
+            </div>
+          )}
+        </CardContent>
+      </Card>
+
+      <ConfigFileCard config={config} onConfigChange={setConfig} />
+
+      <AlertRulesCard />
+
       {/* Appearance */}
       <Card className="card-elevated">
         <CardHeader>
@@ -10,14 +10,18 @@ import {
   RotateCcw,
   Loader2,
   KeyRound,
+  RefreshCw,
 } from "lucide-react";
 import {
   type Token,
+  type CreatedToken,
   listTokens,
   createToken,
   updateToken,
   deleteToken,
   resetTokenQuota,
+  regenerateToken,
+  takeDefaultToken,
   parseIpcError,
 } from "@/lib/tauri";
 import { useLanguage } from "@/lib/i18n";
@@ -67,9 +71,9 @@ import {
 // Helpers
 // ---------------------------------------------------------------------------
 
-function maskKey(key: string): string {
-  if (key.length <= 8) return "sk-****";
-  return "sk-****" + key.slice(-4);
+function maskKey(prefix: string | null): string {
+  if (!prefix) return "sk-****";
+  return prefix + "****";
 }
 
 function getTokenStatus(
@@ -116,30 +120,6 @@ function StatusBadge({ status }: { status: "active" | "disabled" | "expired" })
   return <SharedStatusBadge status={s.type}>{s.label}</SharedStatusBadge>;
 }
 
-// ---------------------------------------------------------------------------
-// Copy Button (inline)
-// ---------------------------------------------------------------------------
-
-function CopyButton({ value }: { value: string }) {
-  const [copied, setCopied] = useState(false);
-
-  const handleCopy = async () => {
-    await navigator.clipboard.writeText(value);
-    setCopied(true);
-    setTimeout(() => setCopied(false), 2000);
-  };
-
-  return (
-    <Button variant="ghost" size="icon-xs" onClick={handleCopy}>
-      {copied ? (
-        <Check className="size-3 text-emerald-500" />
-      ) : (
-        <Copy className="size-3" />
-      )}
-    </Button>
-  );
-}
-
 // ---------------------------------------------------------------------------
 // Generate Token Dialog
 // ---------------------------------------------------------------------------
@@ -147,7 +127,7 @@ function CopyButton({ value }: { value: string }) {
 interface GenerateDialogProps {
   open: boolean;
   onOpenChange: (open: boolean) => void;
-  onCreated: (token: Token) => void;
+  onCreated: (token: CreatedToken) => void;
 }
 
 function GenerateTokenDialog({
@@ -264,9 +244,10 @@ interface KeyRevealDialogProps {
   open: boolean;
   onOpenChange: (open: boolean) => void;
   keyValue: string;
+  regenerated?: boolean;
 }
 
-function KeyRevealDialog({ open, onOpenChange, keyValue }: KeyRevealDialogProps) {
+function KeyRevealDialog({ open, onOpenChange, keyValue, regenerated }: KeyRevealDialogProps) {
   const { t } = useLanguage();
   const [copied, setCopied] = useState(false);
 
@@ -280,7 +261,7 @@ function KeyRevealDialog({ open, onOpenChange, keyValue }: KeyRevealDialogProps)
     <Dialog open={open} onOpenChange={onOpenChange}>
       <DialogContent>
         <DialogHeader>
-          <DialogTitle>{t.tokens.tokenCreated}</DialogTitle>
+          <DialogTitle>{regenerated ? t.tokens.keyRegenerated : t.tokens.tokenCreated}</DialogTitle>
           <DialogDescription>
             {t.tokens.tokenCreatedDesc}
           </DialogDescription>
@@ -510,6 +491,44 @@ function ResetQuotaDialog({
   );
 }
 
+// ---------------------------------------------------------------------------
+// Regenerate Key Confirmation
+// ---------------------------------------------------------------------------
+
+interface RegenerateDialogProps {
+  open: boolean;
+  onOpenChange: (open: boolean) => void;
+  token: Token | null;
+  onConfirm: () => void;
+}
+
+function RegenerateKeyDialog({
+  open,
+  onOpenChange,
+  token,
+  onConfirm,
+}: RegenerateDialogProps) {
+  const { t } = useLanguage();
+  return (
+    <AlertDialog open={open} onOpenChange={onOpenChange}>
+      <AlertDialogContent>
+        <AlertDialogHeader>
+          <AlertDialogTitle>{t.tokens.regenerateTitle}</AlertDialogTitle>
+          <AlertDialogDescription>
+            {t.tokens.regenerateDesc(token?.name ?? "")}
+          </AlertDialogDescription>
+        </AlertDialogHeader>
+        <AlertDialogFooter>
+          <AlertDialogCancel>{t.common.cancel}</AlertDialogCancel>
+          <AlertDialogAction variant="destructive" onClick={onConfirm}>
+            {t.tokens.regenerate}
+          </AlertDialogAction>
+        </AlertDialogFooter>
+      </AlertDialogContent>
+    </AlertDialog>
+  );
+}
+
 // ---------------------------------------------------------------------------
 // Main Page
 // ---------------------------------------------------------------------------
@@ -522,9 +541,11 @@ export default function Tokens({ embedded = false }: { embedded?: boolean }) {
   // Dialog state
   const [generateOpen, setGenerateOpen] = useState(false);
   const [revealKey, setRevealKey] = useState<string | null>(null);
+  const [revealRegenerated, setRevealRegenerated] = useState(false);
   const [editToken, setEditToken] = useState<Token | null>(null);
   const [deleteTarget, setDeleteTarget] = useState<Token | null>(null);
   const [resetTarget, setResetTarget] = useState<Token | null>(null);
+  const [regenerateTarget, setRegenerateTarget] = useState<Token | null>(null);
 
   const refresh = useCallback(async () => {
     try {
@@ -541,11 +562,36 @@ export default function Tokens({ embedded = false }: { embedded?: boolean }) {
     refresh();
   }, [refresh]);
 
-  const handleCreated = (token: Token) => {
+  useEffect(() => {
+    takeDefaultToken()
+      .then((key) => {
+        if (key) {
+          setRevealRegenerated(false);
+          setRevealKey(key);
+        }
+      })
+      .catch(() => {});
+  }, []);
+
+  const handleCreated = (token: CreatedToken) => {
+    setRevealRegenerated(false);
     setRevealKey(token.key_value);
     refresh();
   };
 
+  const handleRegenerate = async () => {
+    if (!regenerateTarget) return;
+    try {
+      const token = await regenerateToken(regenerateTarget.id);
+      setRegenerateTarget(null);
+      setRevealRegenerated(true);
+      setRevealKey(token.key_value);
+      refresh();
+    } catch (err) {
+      toast.error(parseIpcError(err).message);
+    }
+  };
+
   const handleDelete = async () => {
     if (!deleteTarget) return;
     try {
@@ -637,9 +683,8 @@ export default function Tokens({ embedded = false }: { embedded?: boolean }) {
                   <TableCell>
                     <div className="flex items-center gap-1">
                       <code className="text-xs text-muted-foreground">
-                        {maskKey(token.key_value)}
+                        {maskKey(token.key_prefix)}
                       </code>
-                      <CopyButton value={token.key_value} />
                     </div>
                   </TableCell>
                   <TableCell>
@@ -674,6 +719,12 @@ export default function Tokens({ embedded = false }: { embedded?: boolean }) {
                           <RotateCcw />
                           {t.tokens.resetQuota}
                         </DropdownMenuItem>
+                        <DropdownMenuItem
+                          onClick={() => setRegenerateTarget(token)}
+                        >
+                          <RefreshCw />
+                          {t.tokens.regenerateKey}
+                        </DropdownMenuItem>
                         <DropdownMenuSeparator />
                         <DropdownMenuItem
                           variant="destructive"
@@ -704,6 +755,7 @@ export default function Tokens({ embedded = false }: { embedded?: boolean }) {
         open={revealKey !== null}
         onOpenChange={(v) => { if (!v) setRevealKey(null); }}
         keyValue={revealKey ?? ""}
+        regenerated={revealRegenerated}
       />
 
       <EditTokenDialog
@@ -726,6 +778,13 @@ export default function Tokens({ embedded = false }: { embedded?: boolean }) {
         token={resetTarget}
         onConfirm={handleResetQuota}
       />
+
+      <RegenerateKeyDialog
+        open={regenerateTarget !== null}
+        onOpenChange={(v) => { if (!v) setRegenerateTarget(null); }}
+        token={regenerateTarget}
+        onConfirm={handleRegenerate}
+      />
     </div>
   );
 }
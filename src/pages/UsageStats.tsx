@@ -1,5 +1,5 @@
 import { useEffect, useState, useCallback } from "react";
-import { Activity, MessageSquare, BrainCircuit } from "lucide-react";
+import { Activity, MessageSquare, BrainCircuit, ShieldCheck } from "lucide-react";
 import {
   Card,
   CardContent,
@@ -28,8 +28,14 @@ import {
   CartesianGrid,
   Legend,
 } from "recharts";
-import { getUsageStats, parseIpcError } from "@/lib/tauri";
-import type { UsageStats as UsageStatsType } from "@/lib/tauri";
+import { Tabs, TabsList, TabsTrigger } from "@/components/ui/tabs";
+import { getUsageAnalytics, getUsageStats, parseIpcError } from "@/lib/tauri";
+import type {
+  Granularity,
+  UsageAnalytics,
+  UsageGroup,
+  UsageStats as UsageStatsType,
+} from "@/lib/tauri";
 import { useLanguage } from "@/lib/i18n";
 import { toast } from "sonner";
 import { PageHeader } from "@/components/page-header";
@@ -44,6 +50,10 @@ const TIME_RANGES = [
   { label: "30 days", value: 30 },
 ] as const;
 
+type BreakdownKey = "by_route" | "by_target" | "by_token" | "by_status_class";
+
+const BREAKDOWNS: BreakdownKey[] = ["by_route", "by_target", "by_token", "by_status_class"];
+
 // ---------------------------------------------------------------------------
 // Skeleton components
 // ---------------------------------------------------------------------------
@@ -104,6 +114,20 @@ function TableSkeleton() {
 // Helpers
 // ---------------------------------------------------------------------------
 
+function formatMs(ms: number | null): string {
+  if (ms === null) return "-";
+  if (ms < 1000) return `${Math.round(ms)}ms`;
+  return `${(ms / 1000).toFixed(1)}s`;
+}
+
+function formatPercent(v: number | null): string {
+  return v === null ? "-" : `${(v * 100).toFixed(1)}%`;
+}
+
+function formatRate(v: number | null): string {
+  return v === null ? "-" : v.toFixed(1);
+}
+
 function formatNumber(n: number): string {
   if (n >= 1_000_000) return (n / 1_000_000).toFixed(1) + "M";
   if (n >= 1_000) return (n / 1_000).toFixed(1) + "K";
@@ -117,16 +141,23 @@ function formatNumber(n: number): string {
 export default function UsageStats() {
   const { t } = useLanguage();
   const [days, setDays] = useState(7);
+  const [granularity, setGranularity] = useState<Granularity>("day");
   const [stats, setStats] = useState<UsageStatsType | null>(null);
+  const [analytics, setAnalytics] = useState<UsageAnalytics | null>(null);
+  const [breakdown, setBreakdown] = useState<BreakdownKey>("by_route");
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
 
-  const fetchStats = useCallback(async (rangeDays: number) => {
+  const fetchStats = useCallback(async (rangeDays: number, period: Granularity) => {
     try {
       setLoading(true);
       setError(null);
-      const data = await getUsageStats(rangeDays);
+      const [data, analyticsData] = await Promise.all([
+        getUsageStats(rangeDays),
+        getUsageAnalytics(rangeDays, period),
+      ]);
       setStats(data);
+      setAnalytics(analyticsData);
     } catch (err) {
       const e = parseIpcError(err);
       setError(e.message);
@@ -137,8 +168,8 @@ export default function UsageStats() {
   }, []);
 
   useEffect(() => {
-    fetchStats(days);
-  }, [days, fetchStats]);
+    fetchStats(days, granularity);
+  }, [days, granularity, fetchStats]);
 
   // --- Computed values ---
   const totalRequests = stats
@@ -160,10 +191,31 @@ export default function UsageStats() {
       }))
     : [];
 
+  const trendData = analytics
+    ? analytics.series.map((p) => ({
+        // "MM-DD" for days, "MM-DD HH:00" for hours
+        date: p.period.length > 10 ? `${p.period.slice(5, 10)} ${p.period.slice(11, 16)}` : p.period.slice(5),
+        requests: p.requests - p.errors,
+        errors: p.errors,
+      }))
+    : [];
+
+  const breakdownRows: UsageGroup[] = analytics ? analytics[breakdown] : [];
+
   const sortedModels = stats
     ? [...stats.by_model].sort((a, b) => b.count - a.count)
     : [];
 
+  const timingRows = stats
+    ? [
+        { label: t.usageStats.latencyNonStream, p: stats.latency_ms, format: formatMs },
+        { label: t.requestLogs.ttft, p: stats.ttft_ms, format: formatMs },
+        { label: t.requestLogs.streamDuration, p: stats.stream_duration_ms, format: formatMs },
+        { label: t.requestLogs.tokensPerSecond, p: stats.tokens_per_second, format: formatRate },
+      ].filter((row) => row.p.count > 0)
+    : [];
+  const streamOutcomes = stats ? Object.entries(stats.stream_outcomes) : [];
+
   const tooltipStyle = {
     borderRadius: "10px",
     border: "1px solid hsl(var(--border))",
@@ -212,9 +264,9 @@ export default function UsageStats() {
       )}
 
       {/* Summary stat cards */}
-      <div className="grid gap-4 md:grid-cols-3">
+      <div className="grid gap-4 md:grid-cols-4">
         {loading ? (
-          Array.from({ length: 3 }).map((_, i) => <StatCardSkeleton key={i} />)
+          Array.from({ length: 4 }).map((_, i) => <StatCardSkeleton key={i} />)
         ) : (
           <>
             <Card className="card-elevated transition-shadow duration-200 hover:shadow-md">
@@ -273,6 +325,25 @@ export default function UsageStats() {
                 </div>
               </CardContent>
             </Card>
+
+            <Card className="card-elevated transition-shadow duration-200 hover:shadow-md">
+              <CardContent className="p-3">
+                <div className="flex items-center justify-between">
+                  <div className="space-y-1">
+                    <p className="text-[13px] font-medium text-muted-foreground">{t.usageStats.successRate}</p>
+                    <p className="text-3xl font-semibold tracking-tight">
+                      {formatPercent(analytics?.total.success_rate ?? null)}
+                    </p>
+                    <p className="text-xs text-muted-foreground/70">
+                      {t.usageStats.errorsTotal((analytics?.total.errors ?? 0).toLocaleString())}
+                    </p>
+                  </div>
+                  <div className="flex h-11 w-11 shrink-0 items-center justify-center rounded-xl bg-amber-50 dark:bg-amber-500/10">
+                    <ShieldCheck className="h-5 w-5 text-amber-600 dark:text-amber-400" />
+                  </div>
+                </div>
+              </CardContent>
+            </Card>
           </>
         )}
       </div>
@@ -283,19 +354,31 @@ export default function UsageStats() {
       ) : (
         <Card className="card-elevated">
           <CardHeader>
-            <CardTitle className="text-base font-semibold">{t.usageStats.requestTrend}</CardTitle>
-            <CardDescription>
-              {t.usageStats.requestTrendDesc(days)}
-            </CardDescription>
+            <div className="flex items-start justify-between gap-4">
+              <div className="space-y-1.5">
+                <CardTitle className="text-base font-semibold">{t.usageStats.requestTrend}</CardTitle>
+                <CardDescription>
+                  {granularity === "hour"
+                    ? t.usageStats.requestTrendHourlyDesc(days)
+                    : t.usageStats.requestTrendDesc(days)}
+                </CardDescription>
+              </div>
+              <Tabs value={granularity} onValueChange={(v) => setGranularity(v as Granularity)}>
+                <TabsList className="h-7">
+                  <TabsTrigger value="day" className="h-6 px-2 text-xs">{t.usageStats.daily}</TabsTrigger>
+                  <TabsTrigger value="hour" className="h-6 px-2 text-xs">{t.usageStats.hourly}</TabsTrigger>
+                </TabsList>
+              </Tabs>
+            </div>
           </CardHeader>
           <CardContent>
-            {chartData.length === 0 ? (
+            {trendData.length === 0 ? (
               <div className="flex h-[300px] items-center justify-center text-muted-foreground">
                 {t.usageStats.noRequestData}
               </div>
             ) : (
               <ResponsiveContainer width="100%" height={300}>
-                <BarChart data={chartData}>
+                <BarChart data={trendData}>
                   <CartesianGrid strokeDasharray="3 3" vertical={false} />
                   <XAxis
                     dataKey="date"
@@ -310,10 +393,18 @@ export default function UsageStats() {
                     allowDecimals={false}
                   />
                   <RechartsTooltip contentStyle={tooltipStyle} />
+                  <Legend />
                   <Bar
                     dataKey="requests"
-                    name={t.usageStats.requests}
+                    name={t.usageStats.successful}
+                    stackId="requests"
                     fill="hsl(var(--chart-1))"
+                  />
+                  <Bar
+                    dataKey="errors"
+                    name={t.usageStats.errors}
+                    stackId="requests"
+                    fill="hsl(var(--destructive))"
                     radius={[4, 4, 0, 0]}
                   />
                 </BarChart>
@@ -412,6 +503,129 @@ export default function UsageStats() {
         </Card>
       )}
 
+      {/* Latency / throughput percentiles */}
+      {loading ? (
+        <TableSkeleton />
+      ) : (
+        <Card className="card-elevated">
+          <CardHeader>
+            <CardTitle className="text-base font-semibold">{t.usageStats.timing}</CardTitle>
+            <CardDescription>
+              {t.usageStats.timingDesc(days)}
+            </CardDescription>
+          </CardHeader>
+          <CardContent>
+            {timingRows.length === 0 ? (
+              <div className="flex items-center justify-center py-8 text-muted-foreground">
+                {t.usageStats.noTimingData}
+              </div>
+            ) : (
+              <>
+                <div className="table-wrapper"><Table>
+                  <TableHeader>
+                    <TableRow>
+                      <TableHead>{t.usageStats.metric}</TableHead>
+                      <TableHead className="text-right">p50</TableHead>
+                      <TableHead className="text-right">p90</TableHead>
+                      <TableHead className="text-right">p99</TableHead>
+                      <TableHead className="text-right">{t.usageStats.samples}</TableHead>
+                    </TableRow>
+                  </TableHeader>
+                  <TableBody>
+                    {timingRows.map((row) => (
+                      <TableRow key={row.label}>
+                        <TableCell className="font-medium">{row.label}</TableCell>
+                        <TableCell className="text-right tabular-nums">{row.format(row.p.p50)}</TableCell>
+                        <TableCell className="text-right tabular-nums">{row.format(row.p.p90)}</TableCell>
+                        <TableCell className="text-right tabular-nums">{row.format(row.p.p99)}</TableCell>
+                        <TableCell className="text-right tabular-nums text-muted-foreground">
+                          {row.p.count.toLocaleString()}
+                        </TableCell>
+                      </TableRow>
+                    ))}
+                  </TableBody>
+                </Table></div>
+                {streamOutcomes.length > 0 && (
+                  <p className="mt-3 text-xs text-muted-foreground">
+                    {t.usageStats.streamOutcomes}:{" "}
+                    {streamOutcomes
+                      .map(([outcome, count]) => `${t.requestLogs.outcomes[outcome] ?? outcome} ${count.toLocaleString()}`)
+                      .join(" · ")}
+                  </p>
+                )}
+              </>
+            )}
+          </CardContent>
+        </Card>
+      )}
+
+      {/* Route / target / token / status breakdown */}
+      {loading ? (
+        <TableSkeleton />
+      ) : (
+        <Card className="card-elevated">
+          <CardHeader>
+            <div className="flex items-start justify-between gap-4">
+              <div className="space-y-1.5">
+                <CardTitle className="text-base font-semibold">{t.usageStats.breakdown}</CardTitle>
+                <CardDescription>
+                  {t.usageStats.breakdownDesc(days)}
+                </CardDescription>
+              </div>
+              <Tabs value={breakdown} onValueChange={(v) => setBreakdown(v as BreakdownKey)}>
+                <TabsList className="h-7">
+                  {BREAKDOWNS.map((key) => (
+                    <TabsTrigger key={key} value={key} className="h-6 px-2 text-xs">
+                      {t.usageStats.breakdowns[key]}
+                    </TabsTrigger>
+                  ))}
+                </TabsList>
+              </Tabs>
+            </div>
+          </CardHeader>
+          <CardContent>
+            {breakdownRows.length === 0 ? (
+              <div className="flex items-center justify-center py-8 text-muted-foreground">
+                {t.usageStats.noRequestData}
+              </div>
+            ) : (
+              <div className="table-wrapper"><Table>
+                <TableHeader>
+                  <TableRow>
+                    <TableHead>{t.usageStats.breakdowns[breakdown]}</TableHead>
+                    <TableHead className="text-right">{t.usageStats.requests}</TableHead>
+                    <TableHead className="text-right">{t.usageStats.errorRate}</TableHead>
+                    <TableHead className="text-right">p50</TableHead>
+                    <TableHead className="text-right">p95</TableHead>
+                    <TableHead className="text-right">p99</TableHead>
+                    <TableHead className="text-right">{t.requestLogs.tokensCol}</TableHead>
+                  </TableRow>
+                </TableHeader>
+                <TableBody>
+                  {breakdownRows.map((row) => (
+                    <TableRow key={row.key}>
+                      <TableCell className="font-medium max-w-[280px] truncate" title={row.key}>
+                        {row.name ?? (row.key || t.usageStats.unknown)}
+                      </TableCell>
+                      <TableCell className="text-right tabular-nums">{row.requests.toLocaleString()}</TableCell>
+                      <TableCell className={`text-right tabular-nums ${row.errors > 0 ? "text-destructive" : ""}`}>
+                        {formatPercent(row.error_rate)}
+                      </TableCell>
+                      <TableCell className="text-right tabular-nums">{formatMs(row.p50_latency_ms)}</TableCell>
+                      <TableCell className="text-right tabular-nums">{formatMs(row.p95_latency_ms)}</TableCell>
+                      <TableCell className="text-right tabular-nums">{formatMs(row.p99_latency_ms)}</TableCell>
+                      <TableCell className="text-right tabular-nums text-muted-foreground">
+                        {formatNumber(row.prompt_tokens + row.completion_tokens)}
+                      </TableCell>
+                    </TableRow>
+                  ))}
+                </TableBody>
+              </Table></div>
+            )}
+          </CardContent>
+        </Card>
+      )}
+
       {/* Model breakdown table */}
       {loading ? (
         <TableSkeleton />
@@ -14,6 +14,7 @@ import { toast } from "sonner";
 import { useLanguage } from "@/lib/i18n";
 import {
   getConfig,
+  gatewayBaseUrl,
   parseVideoUrl,
   downloadVideo,
   cancelVideoDownload,
@@ -70,10 +71,10 @@ export default function VideoDownload() {
   const [videoInfo, setVideoInfo] = useState<VideoInfo | null>(null);
   const [selectedFormat, setSelectedFormat] = useState<string>("");
   const [tasks, setTasks] = useState<DownloadTask[]>([]);
-  const [serverPort, setServerPort] = useState<number | null>(null);
+  const [gatewayUrl, setGatewayUrl] = useState<string | null>(null);
 
   useEffect(() => {
-    getConfig().then((config) => setServerPort(config.server_port));
+    getConfig().then((config) => setGatewayUrl(gatewayBaseUrl(config)));
   }, []);
 
   useEffect(() => {
@@ -209,18 +210,18 @@ export default function VideoDownload() {
   }, []);
 
   const videoProxyUrl = useMemo(() => {
-    if (!videoInfo || !selectedFormat || !serverPort) return null;
+    if (!videoInfo || !selectedFormat || !gatewayUrl) return null;
     const format = videoInfo.formats.find((f) => f.quality === selectedFormat);
     if (!format) return null;
-    return `http://127.0.0.1:${serverPort}/video-proxy?url=${encodeURIComponent(format.url)}`;
-  }, [videoInfo, selectedFormat, serverPort]);
+    return `${gatewayUrl}/video-proxy?url=${encodeURIComponent(format.url)}`;
+  }, [videoInfo, selectedFormat, gatewayUrl]);
 
   const audioProxyUrl = useMemo(() => {
-    if (!videoInfo || !selectedFormat || !serverPort) return null;
+    if (!videoInfo || !selectedFormat || !gatewayUrl) return null;
     const format = videoInfo.formats.find((f) => f.quality === selectedFormat);
     if (!format?.audio_url) return null;
-    return `http://127.0.0.1:${serverPort}/video-proxy?url=${encodeURIComponent(format.audio_url)}`;
-  }, [videoInfo, selectedFormat, serverPort]);
+    return `${gatewayUrl}/video-proxy?url=${encodeURIComponent(format.audio_url)}`;
+  }, [videoInfo, selectedFormat, gatewayUrl]);
 
   return (
     <div className="flex h-full flex-col p-2">
@@ -43,6 +43,7 @@ import { toast } from "sonner";
 import { useLanguage } from "@/lib/i18n";
 import {
   getConfig,
+  gatewayBaseUrl,
   listVideoRecords,
   deleteVideoRecord,
   clearVideoRecords,
@@ -106,7 +107,7 @@ export default function VideoRecords() {
   const [deleteId, setDeleteId] = useState<string | null>(null);
   const [showClearConfirm, setShowClearConfirm] = useState(false);
   const [playerState, setPlayerState] = useState<PlayerState | null>(null);
-  const [serverPort, setServerPort] = useState<number | null>(null);
+  const [gatewayUrl, setGatewayUrl] = useState<string | null>(null);
   const [activeDownloads, setActiveDownloads] = useState<ActiveDownload[]>([]);
 
   const loadRecords = useCallback(async () => {
@@ -122,7 +123,7 @@ export default function VideoRecords() {
 
   useEffect(() => {
     loadRecords();
-    getConfig().then((config) => setServerPort(config.server_port));
+    getConfig().then((config) => setGatewayUrl(gatewayBaseUrl(config)));
   }, [loadRecords]);
 
   useEffect(() => {
@@ -190,14 +191,14 @@ export default function VideoRecords() {
   }, [playerState, playingFormats]);
 
   const playerVideoUrl = useMemo(() => {
-    if (!serverPort || !playingFormat) return null;
-    return `http://127.0.0.1:${serverPort}/video-proxy?url=${encodeURIComponent(playingFormat.url)}`;
-  }, [serverPort, playingFormat]);
+    if (!gatewayUrl || !playingFormat) return null;
+    return `${gatewayUrl}/video-proxy?url=${encodeURIComponent(playingFormat.url)}`;
+  }, [gatewayUrl, playingFormat]);
 
   const playerAudioUrl = useMemo(() => {
-    if (!serverPort || !playingFormat?.audio_url) return null;
-    return `http://127.0.0.1:${serverPort}/video-proxy?url=${encodeURIComponent(playingFormat.audio_url)}`;
-  }, [serverPort, playingFormat]);
+    if (!gatewayUrl || !playingFormat?.audio_url) return null;
+    return `${gatewayUrl}/video-proxy?url=${encodeURIComponent(playingFormat.audio_url)}`;
+  }, [gatewayUrl, playingFormat]);
 
   const playingIndex = useMemo(() => {
     if (!playingRecord) return -1;